@@ -1,6 +1,6 @@
 use std::{
     cmp::max,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fmt::{Debug, Formatter},
     sync::Arc,
     time::Duration,
@@ -19,17 +19,18 @@ use prometheus::IntGauge;
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, instrument, trace};
 
-use super::{blacklist::AddressBlacklist, metadata::AppContextClassifier, pending_message::*};
+use super::{
+    blacklist::AddressBlacklist, message_filter::SharedMessageFilter,
+    metadata::AppContextClassifier, pending_message::*,
+};
 use crate::{db_loader::DbLoaderExt, settings::matching_list::MatchingList};
 
 /// Finds unprocessed messages from an origin and submits then through a channel
 /// for to the appropriate destination.
 #[allow(clippy::too_many_arguments)]
 pub struct MessageDbLoader {
-    /// A matching list of messages that should be whitelisted.
-    message_whitelist: Arc<MatchingList>,
-    /// A matching list of messages that should be blacklisted.
-    message_blacklist: Arc<MatchingList>,
+    /// Matching lists of messages that should be whitelisted and blacklisted.
+    message_filter: SharedMessageFilter,
     /// Addresses that messages may not interact with.
     address_blacklist: Arc<AddressBlacklist>,
     metrics: MessageDbLoaderMetrics,
@@ -41,6 +42,11 @@ pub struct MessageDbLoader {
     metric_app_contexts: Vec<(MatchingList, String)>,
     nonce_iterator: ForwardBackwardIterator,
     max_retries: u32,
+    /// Nonces of messages that were skipped because of the message filter
+    filtered_nonces: BTreeSet<u32>,
+    /// Generation of the message filter when the skipped messages were last
+    /// looked at
+    filter_generation: u64,
 }
 
 #[derive(Debug)]
@@ -231,8 +237,8 @@ impl Debug for MessageDbLoader {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "MessageDbLoader {{ message_whitelist: {:?}, message_blacklist: {:?}, address_blacklist: {:?}, nonce_iterator: {:?}}}",
-            self.message_whitelist, self.message_blacklist, self.address_blacklist, self.nonce_iterator
+            "MessageDbLoader {{ message_filter: {:?}, address_blacklist: {:?}, nonce_iterator: {:?}}}",
+            self.message_filter, self.address_blacklist, self.nonce_iterator
         )
     }
 }
@@ -257,70 +263,14 @@ impl DbLoaderExt for MessageDbLoader {
         // self.tx_msg and then continue the scan at the next highest
         // nonce.
         // Scan until we find next nonce without delivery confirmation.
+        self.reconsider_filtered_messages().await?;
         if let Some(msg) = self.try_get_unprocessed_message().await? {
             trace!(
                 ?msg,
                 cursor = ?self.nonce_iterator,
                 "db_loader working on message"
             );
-            let destination = msg.destination;
-
-            // Skip if not whitelisted.
-            if !self.message_whitelist.msg_matches(&msg, true) {
-                debug!(?msg, "Message not whitelisted, skipping");
-                return Ok(());
-            }
-
-            // Skip if the message is blacklisted
-            if self.message_blacklist.msg_matches(&msg, false) {
-                debug!(?msg, "Message blacklisted, skipping");
-                return Ok(());
-            }
-
-            // Skip if the message involves a blacklisted address
-            if let Some(blacklisted_address) = self.address_blacklist.find_blacklisted_address(&msg)
-            {
-                debug!(
-                    ?msg,
-                    blacklisted_address = hex::encode(blacklisted_address),
-                    "Message involves blacklisted address, skipping"
-                );
-                return Ok(());
-            }
-
-            // Skip if the message is intended for a destination we do not service
-            if !self.send_channels.contains_key(&destination) {
-                debug!(?msg, "Message destined for unknown domain, skipping");
-                return Ok(());
-            }
-
-            // Skip if message is intended for a destination we don't have message context for
-            let destination_msg_ctx = if let Some(ctx) = self.destination_ctxs.get(&destination) {
-                ctx
-            } else {
-                debug!(
-                    ?msg,
-                    "Message destined for unknown message context, skipping",
-                );
-                return Ok(());
-            };
-
-            debug!(%msg, "Sending message to submitter");
-
-            let app_context_classifier =
-                AppContextClassifier::new(self.metric_app_contexts.clone());
-
-            let app_context = app_context_classifier.get_app_context(&msg).await?;
-            // Finally, build the submit arg and dispatch it to the submitter.
-            let pending_msg = PendingMessage::maybe_from_persisted_retries(
-                msg,
-                destination_msg_ctx.clone(),
-                app_context,
-                self.max_retries,
-            );
-            if let Some(pending_msg) = pending_msg {
-                self.send_channels[&destination].send(Box::new(pending_msg) as QueueOperation)?;
-            }
+            self.process_message(msg).await?;
         } else {
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
@@ -332,8 +282,7 @@ impl MessageDbLoader {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         db: HyperlaneRocksDB,
-        message_whitelist: Arc<MatchingList>,
-        message_blacklist: Arc<MatchingList>,
+        message_filter: SharedMessageFilter,
         address_blacklist: Arc<AddressBlacklist>,
         metrics: MessageDbLoaderMetrics,
         send_channels: HashMap<u32, UnboundedSender<QueueOperation>>,
@@ -342,8 +291,7 @@ impl MessageDbLoader {
         max_retries: u32,
     ) -> Self {
         Self {
-            message_whitelist,
-            message_blacklist,
+            message_filter,
             address_blacklist,
             metrics,
             send_channels,
@@ -351,7 +299,97 @@ impl MessageDbLoader {
             metric_app_contexts,
             nonce_iterator: ForwardBackwardIterator::new(Arc::new(db) as Arc<dyn HyperlaneDb>),
             max_retries,
+            filtered_nonces: BTreeSet::new(),
+            filter_generation: 0,
+        }
+    }
+
+    /// Filter a message and dispatch it to the submitter of its destination.
+    async fn process_message(&mut self, msg: HyperlaneMessage) -> Result<()> {
+        let destination = msg.destination;
+
+        {
+            let message_filter = self.message_filter.read().await;
+
+            // Skip if not whitelisted.
+            if !message_filter.whitelist().msg_matches(&msg, true) {
+                debug!(?msg, "Message not whitelisted, skipping");
+                self.filtered_nonces.insert(msg.nonce);
+                return Ok(());
+            }
+
+            // Skip if the message is blacklisted
+            if message_filter.blacklist().msg_matches(&msg, false) {
+                debug!(?msg, "Message blacklisted, skipping");
+                self.filtered_nonces.insert(msg.nonce);
+                return Ok(());
+            }
+        }
+
+        // Skip if the message involves a blacklisted address
+        if let Some(blacklisted_address) = self.address_blacklist.find_blacklisted_address(&msg) {
+            debug!(
+                ?msg,
+                blacklisted_address = hex::encode(blacklisted_address),
+                "Message involves blacklisted address, skipping"
+            );
+            return Ok(());
+        }
+
+        // Skip if the message is intended for a destination we do not service
+        if !self.send_channels.contains_key(&destination) {
+            debug!(?msg, "Message destined for unknown domain, skipping");
+            return Ok(());
+        }
+
+        // Skip if message is intended for a destination we don't have message context for
+        let destination_msg_ctx = if let Some(ctx) = self.destination_ctxs.get(&destination) {
+            ctx
+        } else {
+            debug!(
+                ?msg,
+                "Message destined for unknown message context, skipping",
+            );
+            return Ok(());
+        };
+
+        debug!(%msg, "Sending message to submitter");
+
+        let app_context_classifier = AppContextClassifier::new(self.metric_app_contexts.clone());
+
+        let app_context = app_context_classifier.get_app_context(&msg).await?;
+        // Finally, build the submit arg and dispatch it to the submitter.
+        let pending_msg = PendingMessage::maybe_from_persisted_retries(
+            msg,
+            destination_msg_ctx.clone(),
+            app_context,
+            self.max_retries,
+        );
+        if let Some(pending_msg) = pending_msg {
+            self.send_channels[&destination].send(Box::new(pending_msg) as QueueOperation)?;
+        }
+        Ok(())
+    }
+
+    /// Look at the messages that were skipped because of the message filter
+    /// again if the filter changed since they were last looked at.
+    async fn reconsider_filtered_messages(&mut self) -> Result<()> {
+        let generation = self.message_filter.read().await.generation();
+        if generation == self.filter_generation {
+            return Ok(());
+        }
+        self.filter_generation = generation;
+
+        let db = self.nonce_iterator.high_nonce_iter.db.clone();
+        for nonce in std::mem::take(&mut self.filtered_nonces) {
+            if db.retrieve_processed_by_nonce(&nonce)?.unwrap_or(false) {
+                continue;
+            }
+            if let Some(msg) = db.retrieve_message_by_nonce(nonce)? {
+                self.process_message(msg).await?;
+            }
         }
+        Ok(())
     }
 
     async fn try_get_unprocessed_message(&mut self) -> Result<Option<HyperlaneMessage>> {
@@ -417,8 +455,9 @@ pub mod test {
     use hyperlane_base::{
         cache::{LocalCache, MeteredCache, MeteredCacheConfig, MeteredCacheMetrics, OptionalCache},
         db::{
-            test_utils, DbResult, HyperlaneRocksDB, InterchainGasExpenditureData,
-            InterchainGasPaymentData,
+            test_utils, DbResult, DynamicMessageFilterData, HyperlaneRocksDB,
+            InterchainGasExpenditureData, InterchainGasPaymentData, ProfitabilityLedgerEntry,
+            ProfitabilityRouteAggregate, ShadowSubmission,
         },
     };
     use hyperlane_core::{
@@ -433,6 +472,7 @@ pub mod test {
 
     use crate::{
         db_loader::DbLoader,
+        msg::message_filter::MatchingListKind,
         test_utils::dummy_data::{dummy_message_context, dummy_metadata_builder},
     };
 
@@ -500,7 +540,6 @@ pub mod test {
                 db.clone(),
                 Default::default(),
                 Default::default(),
-                Default::default(),
                 dummy_message_loader_metrics(origin_domain.id()),
                 HashMap::from([(destination_domain.id(), send_channel)]),
                 HashMap::from([(destination_domain.id(), message_context)]),
@@ -736,6 +775,14 @@ pub mod test {
             fn store_payload_uuids_by_message_id(&self, message_id: &H256, payload_uuids: Vec<UniqueIdentifier>) -> DbResult<()>;
 
             fn retrieve_payload_uuids_by_message_id(&self, message_id: &H256) -> DbResult<Option<Vec<UniqueIdentifier>>>;
+            fn store_profitability_ledger_entry_by_message_id(&self, message_id: &H256, entry: &ProfitabilityLedgerEntry) -> DbResult<()>;
+            fn retrieve_profitability_ledger_entry_by_message_id(&self, message_id: &H256) -> DbResult<Option<ProfitabilityLedgerEntry>>;
+            fn store_profitability_route_aggregate_by_destination(&self, destination: &u32, aggregate: &ProfitabilityRouteAggregate) -> DbResult<()>;
+            fn retrieve_profitability_route_aggregate_by_destination(&self, destination: &u32) -> DbResult<Option<ProfitabilityRouteAggregate>>;
+            fn store_dynamic_message_filter(&self, filter: &DynamicMessageFilterData) -> DbResult<()>;
+            fn retrieve_dynamic_message_filter(&self) -> DbResult<Option<DynamicMessageFilterData>>;
+            fn store_shadow_submission_by_message_id(&self, message_id: &H256, submission: &ShadowSubmission) -> DbResult<()>;
+            fn retrieve_shadow_submission_by_message_id(&self, message_id: &H256) -> DbResult<Option<ShadowSubmission>>;
         }
     }
 
@@ -807,6 +854,51 @@ pub mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_filtered_messages_are_reconsidered_when_the_filter_changes() {
+        test_utils::run_test_db(|db| async move {
+            let origin_domain = dummy_domain(0, "dummy_origin_domain");
+            let destination_domain = dummy_domain(1, "dummy_destination_domain");
+            let db = HyperlaneRocksDB::new(&origin_domain, db);
+            persist_retried_messages(&[0], &db, &destination_domain);
+
+            let message_filter = SharedMessageFilter::default();
+            message_filter
+                .write()
+                .await
+                .add_entries(
+                    MatchingListKind::Blacklist,
+                    MatchingList::with_destination_domain(destination_domain.id())
+                        .0
+                        .unwrap(),
+                )
+                .unwrap();
+            let (mut message_db_loader, mut receive_channel) = dummy_message_loader(
+                &origin_domain,
+                &destination_domain,
+                &db,
+                OptionalCache::new(None),
+            );
+            message_db_loader.message_filter = message_filter.clone();
+
+            message_db_loader.tick().await.unwrap();
+            assert!(receive_channel.try_recv().is_err());
+
+            message_filter
+                .write()
+                .await
+                .remove_entry(MatchingListKind::Blacklist, 0)
+                .unwrap();
+            message_db_loader.tick().await.unwrap();
+            let op = receive_channel.try_recv().unwrap();
+            assert_eq!(
+                op.id(),
+                dummy_hyperlane_message(&destination_domain, 0).id()
+            );
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_forward_backward_iterator() {
         let mut mock_db = MockDb::new();
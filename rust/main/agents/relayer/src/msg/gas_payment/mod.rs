@@ -1,8 +1,12 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
 use eyre::Result;
-use hyperlane_base::db::HyperlaneRocksDB;
+use hyperlane_base::db::{HyperlaneDb, HyperlaneRocksDB};
 use hyperlane_core::{
     FixedPointNumber, GasPaymentKey, HyperlaneMessage, InterchainGasExpenditure,
     InterchainGasPayment, TxCostEstimate, TxOutcome, U256,
@@ -11,7 +15,9 @@ use tracing::{debug, error, trace};
 
 use self::policies::{GasPaymentPolicyMinimum, GasPaymentPolicyNone};
 use crate::{
-    msg::gas_payment::policies::GasPaymentPolicyOnChainFeeQuoting,
+    msg::gas_payment::policies::{
+        GasPaymentPolicyOnChainFeeQuoting, GasPaymentPolicyTokenPriceQuoting,
+    },
     settings::{
         matching_list::MatchingList, GasPaymentEnforcementConf, GasPaymentEnforcementPolicy,
     },
@@ -21,6 +27,9 @@ mod policies;
 
 pub const GAS_EXPENDITURE_LOG_MESSAGE: &str = "Recording gas expenditure for message";
 
+/// Prices of the origin and destination native tokens of a message
+pub type NativeTokenPrices = (FixedPointNumber, FixedPointNumber);
+
 #[async_trait]
 pub trait GasPaymentPolicy: Debug + Send + Sync {
     /// Returns Some(gas_limit) if the policy has approved the transaction or
@@ -38,6 +47,12 @@ pub trait GasPaymentPolicy: Debug + Send + Sync {
     }
 
     fn enforcement_type(&self) -> GasPaymentEnforcementPolicy;
+
+    /// Returns the prices of the origin and destination native tokens of the
+    /// message that the policy valued payments with, if it uses any.
+    async fn native_token_prices(&self, _message: &HyperlaneMessage) -> Option<NativeTokenPrices> {
+        None
+    }
 }
 
 #[derive(PartialEq, Debug)]
@@ -56,6 +71,9 @@ pub struct GasPaymentEnforcer {
     /// whitelists, then whichever is first in the list will be used.
     policies: Vec<(Box<dyn GasPaymentPolicy>, MatchingList)>,
     db: HyperlaneRocksDB,
+    /// Serializes profitability ledger updates, which read the route totals
+    /// before writing them
+    ledger_lock: Mutex<()>,
 }
 
 impl GasPaymentEnforcer {
@@ -70,7 +88,11 @@ impl GasPaymentEnforcer {
             .map(|cfg| (Self::create_policy(&cfg.policy), cfg.matching_list))
             .collect();
 
-        Self { policies, db }
+        Self {
+            policies,
+            db,
+            ledger_lock: Mutex::new(()),
+        }
     }
 
     pub fn insert_new_policy(
@@ -92,6 +114,15 @@ impl GasPaymentEnforcer {
                 gas_fraction_numerator: n,
                 gas_fraction_denominator: d,
             } => Box::new(GasPaymentPolicyOnChainFeeQuoting::new(*n, *d)),
+            GasPaymentEnforcementPolicy::TokenPriceQuoting {
+                price_source,
+                cost_fraction_numerator: n,
+                cost_fraction_denominator: d,
+            } => Box::new(GasPaymentPolicyTokenPriceQuoting::new(
+                price_source.clone(),
+                *n,
+                *d,
+            )),
         }
     }
 
@@ -112,6 +143,19 @@ impl GasPaymentEnforcer {
         message: &HyperlaneMessage,
         tx_cost_estimate: &TxCostEstimate,
     ) -> Result<GasPolicyStatus> {
+        self.gas_payment_decision(message, tx_cost_estimate)
+            .await
+            .map(|(status, _)| status)
+    }
+
+    /// Like `message_meets_gas_payment_requirement`, but also returns the
+    /// native token prices the policy valued the payment with if it was met,
+    /// so they can be recorded with the outcome of the transaction.
+    pub async fn gas_payment_decision(
+        &self,
+        message: &HyperlaneMessage,
+        tx_cost_estimate: &TxCostEstimate,
+    ) -> Result<(GasPolicyStatus, Option<NativeTokenPrices>)> {
         let msg_id = message.id();
         let gas_payment_key = GasPaymentKey {
             message_id: msg_id,
@@ -156,10 +200,10 @@ impl GasPaymentEnforcer {
             );
 
             if policy.requires_payment_found() && !payment_found {
-                return Ok(GasPolicyStatus::NoPaymentFound);
+                return Ok((GasPolicyStatus::NoPaymentFound, None));
             }
 
-            return policy
+            let status = policy
                 .message_meets_gas_payment_requirement(
                     message,
                     &current_payment,
@@ -177,7 +221,12 @@ impl GasPaymentEnforcer {
                         // No payment was found and it didn't meet the policy
                         GasPolicyStatus::NoPaymentFound
                     }
-                });
+                })?;
+            let prices = match status {
+                GasPolicyStatus::PolicyMet(_) => policy.native_token_prices(message).await,
+                _ => None,
+            };
+            return Ok((status, prices));
         }
 
         error!(
@@ -185,10 +234,17 @@ impl GasPaymentEnforcer {
             policies=?self.policies,
             "No gas payment policy matched for message; consider adding a default policy to the end of the policies array which uses a wildcard whitelist."
         );
-        Ok(GasPolicyStatus::PolicyNotMet)
+        Ok((GasPolicyStatus::PolicyNotMet, None))
     }
 
-    pub fn record_tx_outcome(&self, message: &HyperlaneMessage, outcome: TxOutcome) -> Result<()> {
+    /// Records the gas spent by `outcome`, along with the native token prices
+    /// the gas payment decision of the message was made with, if any.
+    pub fn record_tx_outcome(
+        &self,
+        message: &HyperlaneMessage,
+        outcome: TxOutcome,
+        native_token_prices: Option<&NativeTokenPrices>,
+    ) -> Result<()> {
         // This log is required in E2E, hence the use of a `const`
         debug!(
             hyp_message=%message,
@@ -199,9 +255,62 @@ impl GasPaymentEnforcer {
         self.db.process_gas_expenditure(InterchainGasExpenditure {
             message_id: message.id(),
             gas_used: outcome.gas_used,
-            tokens_used: (FixedPointNumber::try_from(outcome.gas_used)? * outcome.gas_price)
-                .try_into()?,
+            tokens_used: (FixedPointNumber::try_from(outcome.gas_used)?
+                * outcome.gas_price.clone())
+            .try_into()?,
         })?;
+        self.record_profitability_ledger_entry(message, &outcome, native_token_prices)?;
+        Ok(())
+    }
+
+    /// Updates the profitability ledger entry of the message with its current
+    /// total payment and expenditure, and the transaction of `outcome`.
+    fn record_profitability_ledger_entry(
+        &self,
+        message: &HyperlaneMessage,
+        outcome: &TxOutcome,
+        native_token_prices: Option<&NativeTokenPrices>,
+    ) -> Result<()> {
+        let _guard = self
+            .ledger_lock
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let msg_id = message.id();
+        let payment = self
+            .db
+            .retrieve_gas_payment_by_gas_payment_key(GasPaymentKey {
+                message_id: msg_id,
+                destination: message.destination,
+            })?
+            .unwrap_or_default();
+        let expenditure = self.db.retrieve_gas_expenditure_by_message_id(msg_id)?;
+        let mut entry = self
+            .db
+            .retrieve_profitability_ledger_entry_by_message_id(&msg_id)?
+            .unwrap_or_default();
+
+        // Keep the prices of the most recent decision, if it used any
+        if let Some((origin_price, destination_price)) = native_token_prices {
+            entry.origin_token_price = Some(origin_price.to_string());
+            entry.destination_token_price = Some(destination_price.to_string());
+        }
+
+        entry.origin = message.origin;
+        entry.destination = message.destination;
+        entry.payment = payment.payment;
+        entry.gas_amount = payment.gas_amount;
+        entry.gas_used = expenditure.gas_used;
+        entry.tokens_used = expenditure.tokens_used;
+        if !entry.transaction_ids.contains(&outcome.transaction_id) {
+            entry.transaction_ids.push(outcome.transaction_id);
+        }
+        entry.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        self.db
+            .process_profitability_ledger_entry(&msg_id, &entry)?;
         Ok(())
     }
 }
@@ -210,10 +319,10 @@ impl GasPaymentEnforcer {
 mod test {
     use std::str::FromStr;
 
-    use hyperlane_base::db::{test_utils, HyperlaneRocksDB};
+    use hyperlane_base::db::{test_utils, HyperlaneDb, HyperlaneRocksDB};
     use hyperlane_core::{
-        HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, LogMeta, TxCostEstimate, H160,
-        H256, U256,
+        FixedPointNumber, HyperlaneDomain, HyperlaneMessage, InterchainGasPayment, LogMeta,
+        TxCostEstimate, TxOutcome, H160, H256, H512, U256,
     };
 
     use super::GasPaymentEnforcer;
@@ -755,4 +864,73 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_record_tx_outcome_updates_profitability_ledger() {
+        #[allow(unused_must_use)]
+        test_utils::run_test_db(|db| async move {
+            let msg = HyperlaneMessage {
+                destination: 123,
+                ..HyperlaneMessage::default()
+            };
+
+            let hyperlane_db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("test_record_tx_outcome"),
+                db,
+            );
+            let enforcer = GasPaymentEnforcer::new(
+                vec![GasPaymentEnforcementConf::default()],
+                hyperlane_db.clone(),
+            );
+
+            hyperlane_db.process_gas_payment(
+                InterchainGasPayment {
+                    message_id: msg.id(),
+                    destination: msg.destination,
+                    payment: U256::from(1000),
+                    gas_amount: U256::from(100),
+                },
+                &LogMeta::random(),
+            );
+
+            let outcome = |transaction_id| TxOutcome {
+                transaction_id,
+                executed: true,
+                gas_used: U256::from(40),
+                gas_price: FixedPointNumber::from(2),
+            };
+            enforcer
+                .record_tx_outcome(&msg, outcome(H512::from_low_u64_be(1)), None)
+                .unwrap();
+            let prices = (FixedPointNumber::from(3), FixedPointNumber::from(5));
+            enforcer
+                .record_tx_outcome(&msg, outcome(H512::from_low_u64_be(2)), Some(&prices))
+                .unwrap();
+
+            let entry = hyperlane_db
+                .retrieve_profitability_ledger_entry_by_message_id(&msg.id())
+                .unwrap()
+                .unwrap();
+            assert_eq!(entry.destination, msg.destination);
+            assert_eq!(entry.payment, U256::from(1000));
+            assert_eq!(entry.gas_amount, U256::from(100));
+            assert_eq!(entry.gas_used, U256::from(80));
+            assert_eq!(entry.tokens_used, U256::from(160));
+            assert_eq!(
+                entry.transaction_ids,
+                vec![H512::from_low_u64_be(1), H512::from_low_u64_be(2)]
+            );
+            assert_eq!(entry.origin_token_price, Some("3".to_owned()));
+            assert_eq!(entry.destination_token_price, Some("5".to_owned()));
+
+            let aggregate = hyperlane_db
+                .retrieve_profitability_route_aggregate_by_destination(&msg.destination)
+                .unwrap()
+                .unwrap();
+            assert_eq!(aggregate.messages, 1);
+            assert_eq!(aggregate.payment, U256::from(1000));
+            assert_eq!(aggregate.tokens_used, U256::from(160));
+        })
+        .await;
+    }
 }
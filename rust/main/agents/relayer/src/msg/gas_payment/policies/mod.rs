@@ -1,7 +1,9 @@
 mod minimum;
 mod none;
 mod on_chain_fee_quoting;
+mod token_price_quoting;
 
 pub(crate) use minimum::GasPaymentPolicyMinimum;
 pub(crate) use none::GasPaymentPolicyNone;
 pub(crate) use on_chain_fee_quoting::GasPaymentPolicyOnChainFeeQuoting;
+pub(crate) use token_price_quoting::GasPaymentPolicyTokenPriceQuoting;
@@ -0,0 +1,466 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use eyre::{eyre, Context, Result};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use hyperlane_core::{
+    FixedPointNumber, HyperlaneMessage, InterchainGasExpenditure, InterchainGasPayment,
+    TxCostEstimate, U256,
+};
+
+use crate::{
+    msg::gas_payment::{GasPaymentPolicy, NativeTokenPrices},
+    settings::{GasPaymentEnforcementPolicy, TokenPriceSource},
+};
+
+/// How long fetched token prices are reused before the oracle is queried again.
+const TOKEN_PRICE_CACHE_TTL: Duration = Duration::from_secs(60);
+/// How long to wait for a token price oracle to respond.
+const TOKEN_PRICE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// The largest number of decimals whose scale, `10^decimals`, fits in a U256.
+const MAX_TOKEN_DECIMALS: u8 = 77;
+
+/// The price of a domain's native token, denominated in a common quote
+/// currency (e.g. USD).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPrice {
+    /// Price of one whole native token
+    pub price: FixedPointNumber,
+    /// Number of decimals of the native token
+    pub decimals: u8,
+}
+
+impl TokenPrice {
+    /// Converts an amount of the smallest unit of the native token (e.g. wei)
+    /// into the quote currency.
+    fn quote(&self, amount: U256) -> Result<FixedPointNumber> {
+        let amount = FixedPointNumber::try_from(amount)?;
+        let scale = FixedPointNumber::try_from(U256::exp10(self.decimals as usize))?;
+        Ok(amount * self.price.clone() / scale)
+    }
+}
+
+/// Raw JSON shape served by token price oracles, keyed by domain id:
+/// `{ "1": { "price": 3000.5, "decimals": 18 } }`
+#[derive(Debug, Deserialize)]
+struct RawTokenPrice {
+    price: serde_json::Number,
+    decimals: u8,
+}
+
+fn parse_token_prices(raw: HashMap<u32, RawTokenPrice>) -> Result<HashMap<u32, TokenPrice>> {
+    raw.into_iter()
+        .map(|(domain, raw)| {
+            let price = FixedPointNumber::from_str(&raw.price.to_string())
+                .with_context(|| format!("Invalid token price for domain {domain}"))?;
+            if raw.decimals > MAX_TOKEN_DECIMALS {
+                return Err(eyre!(
+                    "Invalid token decimals for domain {domain}: {} is more than {MAX_TOKEN_DECIMALS}",
+                    raw.decimals
+                ));
+            }
+            Ok((
+                domain,
+                TokenPrice {
+                    price,
+                    decimals: raw.decimals,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// A source of native token prices, keyed by domain id.
+#[async_trait]
+pub trait TokenPriceOracle: Debug + Send + Sync {
+    /// Fetches the latest known native token prices of all domains.
+    async fn token_prices(&self) -> Result<HashMap<u32, TokenPrice>>;
+}
+
+/// Reads token prices from a JSON file on disk, allowing an external process
+/// to keep the file up to date.
+#[derive(Debug)]
+pub struct FileTokenPriceOracle {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl TokenPriceOracle for FileTokenPriceOracle {
+    async fn token_prices(&self) -> Result<HashMap<u32, TokenPrice>> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read token price file {:?}", self.path))?;
+        let raw = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse token price file {:?}", self.path))?;
+        parse_token_prices(raw)
+    }
+}
+
+/// Fetches token prices from a JSON HTTP endpoint, typically a price service
+/// running alongside the relayer.
+#[derive(Debug)]
+pub struct HttpTokenPriceOracle {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl TokenPriceOracle for HttpTokenPriceOracle {
+    async fn token_prices(&self) -> Result<HashMap<u32, TokenPrice>> {
+        let raw = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse token prices from {}", self.url))?;
+        parse_token_prices(raw)
+    }
+}
+
+#[derive(Debug)]
+pub struct GasPaymentPolicyTokenPriceQuoting {
+    price_source: TokenPriceSource,
+    oracle: Box<dyn TokenPriceOracle>,
+    /// Numerator value to modify the estimated cost by.
+    fractional_numerator: u64,
+    /// Denominator value to modify the estimated cost by.
+    fractional_denominator: u64,
+    /// The last fetched prices and when they were fetched.
+    cached_prices: RwLock<Option<(Instant, Arc<HashMap<u32, TokenPrice>>)>>,
+}
+
+impl GasPaymentPolicyTokenPriceQuoting {
+    pub fn new(
+        price_source: TokenPriceSource,
+        fractional_numerator: u64,
+        fractional_denominator: u64,
+    ) -> Self {
+        let oracle: Box<dyn TokenPriceOracle> = match &price_source {
+            TokenPriceSource::File(path) => Box::new(FileTokenPriceOracle { path: path.clone() }),
+            TokenPriceSource::Http(url) => Box::new(HttpTokenPriceOracle {
+                url: url.clone(),
+                client: reqwest::Client::builder()
+                    .timeout(TOKEN_PRICE_REQUEST_TIMEOUT)
+                    .build()
+                    .expect("Failed to build token price HTTP client"),
+            }),
+        };
+        Self::with_oracle(
+            price_source,
+            oracle,
+            fractional_numerator,
+            fractional_denominator,
+        )
+    }
+
+    pub fn with_oracle(
+        price_source: TokenPriceSource,
+        oracle: Box<dyn TokenPriceOracle>,
+        fractional_numerator: u64,
+        fractional_denominator: u64,
+    ) -> Self {
+        Self {
+            price_source,
+            oracle,
+            fractional_numerator,
+            fractional_denominator,
+            cached_prices: RwLock::new(None),
+        }
+    }
+
+    async fn token_prices(&self) -> Result<Arc<HashMap<u32, TokenPrice>>> {
+        if let Some((fetched_at, prices)) = self.cached_prices.read().await.as_ref() {
+            if fetched_at.elapsed() < TOKEN_PRICE_CACHE_TTL {
+                return Ok(prices.clone());
+            }
+        }
+
+        let mut cached_prices = self.cached_prices.write().await;
+        // Another task may have refreshed the prices while we waited for the lock
+        if let Some((fetched_at, prices)) = cached_prices.as_ref() {
+            if fetched_at.elapsed() < TOKEN_PRICE_CACHE_TTL {
+                return Ok(prices.clone());
+            }
+        }
+        let prices = Arc::new(self.oracle.token_prices().await?);
+        *cached_prices = Some((Instant::now(), prices.clone()));
+        Ok(prices)
+    }
+}
+
+#[async_trait]
+impl GasPaymentPolicy for GasPaymentPolicyTokenPriceQuoting {
+    /// TokenPriceQuoting requires the value of the payment made on the origin
+    /// to cover a specified fraction of the value of the tokens spent and
+    /// estimated to be spent on the destination, with both sides converted
+    /// into a common currency using the configured token price oracle. Like
+    /// the Minimum policy, TokenPriceQuoting requires a payment to exist on
+    /// the IGP specified in the config.
+
+    async fn message_meets_gas_payment_requirement(
+        &self,
+        message: &HyperlaneMessage,
+        current_payment: &InterchainGasPayment,
+        current_expenditure: &InterchainGasExpenditure,
+        tx_cost_estimate: &TxCostEstimate,
+    ) -> Result<Option<U256>> {
+        let prices = self.token_prices().await?;
+        let price_of = |domain: u32| {
+            prices
+                .get(&domain)
+                .ok_or_else(|| eyre!("No token price available for domain {domain}"))
+        };
+        let origin_price = price_of(message.origin)?;
+        let destination_price = price_of(message.destination)?;
+
+        let estimated_tokens: U256 = (FixedPointNumber::try_from(tx_cost_estimate.gas_limit)?
+            * tx_cost_estimate.gas_price.clone())
+        .try_into()?;
+        let destination_tokens = current_expenditure
+            .tokens_used
+            .saturating_add(estimated_tokens);
+
+        let paid_value = origin_price.quote(current_payment.payment)?;
+        let required_value = destination_price.quote(destination_tokens)?
+            * self.fractional_numerator
+            / self.fractional_denominator;
+
+        if paid_value >= required_value {
+            Ok(Some(tx_cost_estimate.gas_limit))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn requires_payment_found(&self) -> bool {
+        true
+    }
+
+    fn enforcement_type(&self) -> GasPaymentEnforcementPolicy {
+        GasPaymentEnforcementPolicy::TokenPriceQuoting {
+            price_source: self.price_source.clone(),
+            cost_fraction_numerator: self.fractional_numerator,
+            cost_fraction_denominator: self.fractional_denominator,
+        }
+    }
+
+    async fn native_token_prices(&self, message: &HyperlaneMessage) -> Option<NativeTokenPrices> {
+        let prices = self.token_prices().await.ok()?;
+        let origin_price = prices.get(&message.origin)?;
+        let destination_price = prices.get(&message.destination)?;
+        Some((origin_price.price.clone(), destination_price.price.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use hyperlane_core::H256;
+
+    use super::*;
+
+    const ORIGIN: u32 = 1;
+    const DESTINATION: u32 = 2;
+
+    #[derive(Debug)]
+    struct StaticTokenPriceOracle(HashMap<u32, TokenPrice>);
+
+    #[async_trait]
+    impl TokenPriceOracle for StaticTokenPriceOracle {
+        async fn token_prices(&self) -> Result<HashMap<u32, TokenPrice>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn token_price(price: &str, decimals: u8) -> TokenPrice {
+        TokenPrice {
+            price: FixedPointNumber::from_str(price).unwrap(),
+            decimals,
+        }
+    }
+
+    /// Origin token is worth 2000 and has 18 decimals, destination token is
+    /// worth 1 and has 6 decimals.
+    fn policy(
+        fractional_numerator: u64,
+        fractional_denominator: u64,
+    ) -> GasPaymentPolicyTokenPriceQuoting {
+        let prices = HashMap::from([
+            (ORIGIN, token_price("2000", 18)),
+            (DESTINATION, token_price("1", 6)),
+        ]);
+        GasPaymentPolicyTokenPriceQuoting::with_oracle(
+            TokenPriceSource::Http("http://localhost".to_owned()),
+            Box::new(StaticTokenPriceOracle(prices)),
+            fractional_numerator,
+            fractional_denominator,
+        )
+    }
+
+    fn message() -> HyperlaneMessage {
+        HyperlaneMessage {
+            origin: ORIGIN,
+            destination: DESTINATION,
+            ..HyperlaneMessage::default()
+        }
+    }
+
+    fn current_payment(payment: impl Into<U256>) -> InterchainGasPayment {
+        InterchainGasPayment {
+            message_id: H256::zero(),
+            destination: DESTINATION,
+            payment: payment.into(),
+            gas_amount: U256::zero(),
+        }
+    }
+
+    fn current_expenditure(tokens_used: impl Into<U256>) -> InterchainGasExpenditure {
+        InterchainGasExpenditure {
+            message_id: H256::zero(),
+            gas_used: U256::zero(),
+            tokens_used: tokens_used.into(),
+        }
+    }
+
+    /// Costs 100_000 * 10 = 1_000_000 destination units, i.e. a value of 1.
+    fn cost_estimate() -> TxCostEstimate {
+        TxCostEstimate {
+            gas_limit: U256::from(100_000u32),
+            gas_price: FixedPointNumber::from(10u32),
+            l2_gas_limit: None,
+        }
+    }
+
+    /// A value of 1 is 1 / 2000 of an origin token, i.e. 5 * 10^14 wei.
+    const ONE_UNIT_OF_VALUE: u64 = 500_000_000_000_000;
+
+    #[tokio::test]
+    async fn test_payment_covers_cost() {
+        let policy = policy(1, 1);
+        assert_eq!(
+            policy
+                .message_meets_gas_payment_requirement(
+                    &message(),
+                    &current_payment(ONE_UNIT_OF_VALUE),
+                    &current_expenditure(0),
+                    &cost_estimate(),
+                )
+                .await
+                .unwrap(),
+            Some(cost_estimate().gas_limit)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_payment_less_than_cost() {
+        let policy = policy(1, 1);
+        assert_eq!(
+            policy
+                .message_meets_gas_payment_requirement(
+                    &message(),
+                    &current_payment(ONE_UNIT_OF_VALUE - 1),
+                    &current_expenditure(0),
+                    &cost_estimate(),
+                )
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_applies_cost_fraction() {
+        // Only half of the cost needs to be covered
+        let policy = policy(1, 2);
+        assert_eq!(
+            policy
+                .message_meets_gas_payment_requirement(
+                    &message(),
+                    &current_payment(ONE_UNIT_OF_VALUE / 2),
+                    &current_expenditure(0),
+                    &cost_estimate(),
+                )
+                .await
+                .unwrap(),
+            Some(cost_estimate().gas_limit)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_accounts_for_expenditure() {
+        let policy = policy(1, 1);
+        // Tokens already spent on the destination need to be covered as well
+        assert_eq!(
+            policy
+                .message_meets_gas_payment_requirement(
+                    &message(),
+                    &current_payment(ONE_UNIT_OF_VALUE),
+                    &current_expenditure(1),
+                    &cost_estimate(),
+                )
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_price_errors() {
+        let policy = policy(1, 1);
+        let message = HyperlaneMessage {
+            destination: 3,
+            ..message()
+        };
+        assert!(policy
+            .message_meets_gas_payment_requirement(
+                &message,
+                &current_payment(ONE_UNIT_OF_VALUE),
+                &current_expenditure(0),
+                &cost_estimate(),
+            )
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_token_price_oracle() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"{{"1": {{"price": 2000.5, "decimals": 18}}, "2": {{"price": 0.0001, "decimals": 6}}}}"#
+        )
+        .unwrap();
+
+        let oracle = FileTokenPriceOracle {
+            path: file.path().to_path_buf(),
+        };
+        let prices = oracle.token_prices().await.unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[&1], token_price("2000.5", 18));
+        assert_eq!(prices[&2], token_price("0.0001", 6));
+    }
+
+    #[tokio::test]
+    async fn test_file_token_price_oracle_rejects_too_many_decimals() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, r#"{{"1": {{"price": 1, "decimals": 78}}}}"#).unwrap();
+
+        let oracle = FileTokenPriceOracle {
+            path: file.path().to_path_buf(),
+        };
+        assert!(oracle.token_prices().await.is_err());
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use hyperlane_base::db::{DynamicMessageFilterData, HyperlaneDb, HyperlaneRocksDB};
+use hyperlane_core::{HyperlaneMessage, QueueOperation};
+
+use crate::settings::matching_list::{ListElement, MatchingList};
+
+/// Message filter shared between the db loaders, the message processors and
+/// the relayer API.
+pub type SharedMessageFilter = Arc<RwLock<MessageFilter>>;
+
+/// Which of the matching lists of a `MessageFilter` to operate on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchingListKind {
+    Whitelist,
+    Blacklist,
+}
+
+/// Message whitelist and blacklist of the relayer.
+///
+/// Entries from the relayer settings are fixed, while entries added at runtime
+/// through the relayer API are persisted in the origin dbs so that they survive
+/// restarts. A message is relayed if it matches either the configured or the
+/// dynamic whitelist entries, and matches neither the configured nor the
+/// dynamic blacklist entries. An empty configured whitelist allows every
+/// message, so dynamic whitelist entries only take effect next to configured
+/// ones.
+///
+/// Every change bumps the filter's generation. The db loaders remember the
+/// messages they skipped because of the filter and look at them again when the
+/// generation changes, while the op queues keep disallowed operations parked
+/// until they are allowed again.
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilter {
+    configured_whitelist: MatchingList,
+    configured_blacklist: MatchingList,
+    dynamic_whitelist: Vec<ListElement>,
+    dynamic_blacklist: Vec<ListElement>,
+    /// Configured entries followed by the dynamic ones, rebuilt on every change
+    whitelist: MatchingList,
+    blacklist: MatchingList,
+    /// Incremented every time the effective lists are rebuilt
+    generation: u64,
+    dbs: Vec<HyperlaneRocksDB>,
+}
+
+impl MessageFilter {
+    /// Create a filter from the configured lists, restoring the entries that
+    /// were previously added at runtime from `dbs`.
+    pub fn new(
+        whitelist: MatchingList,
+        blacklist: MatchingList,
+        dbs: Vec<HyperlaneRocksDB>,
+    ) -> Result<Self> {
+        let mut dynamic_whitelist: Vec<ListElement> = vec![];
+        let mut dynamic_blacklist: Vec<ListElement> = vec![];
+        // Every origin db holds the same entries, unless origins were added since
+        // they were last written, so take the union of all of them.
+        for db in &dbs {
+            let Some(data) = db.retrieve_dynamic_message_filter()? else {
+                continue;
+            };
+            for value in data.whitelist {
+                let element = serde_json::from_value(value)?;
+                if !dynamic_whitelist.contains(&element) {
+                    dynamic_whitelist.push(element);
+                }
+            }
+            for value in data.blacklist {
+                let element = serde_json::from_value(value)?;
+                if !dynamic_blacklist.contains(&element) {
+                    dynamic_blacklist.push(element);
+                }
+            }
+        }
+        let mut filter = Self {
+            configured_whitelist: whitelist,
+            configured_blacklist: blacklist,
+            dynamic_whitelist,
+            dynamic_blacklist,
+            dbs,
+            ..Default::default()
+        };
+        filter.rebuild();
+        Ok(filter)
+    }
+
+    /// The effective whitelist
+    pub fn whitelist(&self) -> &MatchingList {
+        &self.whitelist
+    }
+
+    /// The effective blacklist
+    pub fn blacklist(&self) -> &MatchingList {
+        &self.blacklist
+    }
+
+    /// Changes every time entries are added or removed, so that skipped
+    /// messages can be reconsidered
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether a message should be relayed
+    pub fn msg_allowed(&self, msg: &HyperlaneMessage) -> bool {
+        self.whitelist.msg_matches(msg, true) && !self.blacklist.msg_matches(msg, false)
+    }
+
+    /// Whether a queue operation should still be processed
+    pub fn op_allowed(&self, op: &QueueOperation) -> bool {
+        self.whitelist.op_matches_or(op, true) && !self.blacklist.op_matches_or(op, false)
+    }
+
+    /// Entries of a list that come from the relayer settings
+    pub fn configured_entries(&self, kind: MatchingListKind) -> &[ListElement] {
+        let list = match kind {
+            MatchingListKind::Whitelist => &self.configured_whitelist,
+            MatchingListKind::Blacklist => &self.configured_blacklist,
+        };
+        list.0.as_deref().unwrap_or_default()
+    }
+
+    /// Entries of a list that were added at runtime
+    pub fn dynamic_entries(&self, kind: MatchingListKind) -> &[ListElement] {
+        match kind {
+            MatchingListKind::Whitelist => &self.dynamic_whitelist,
+            MatchingListKind::Blacklist => &self.dynamic_blacklist,
+        }
+    }
+
+    /// Add entries to a list and persist them
+    pub fn add_entries(&mut self, kind: MatchingListKind, entries: Vec<ListElement>) -> Result<()> {
+        let mut updated = self.clone();
+        updated.dynamic_entries_mut(kind).extend(entries);
+        updated.persist()?;
+        updated.rebuild();
+        *self = updated;
+        Ok(())
+    }
+
+    /// Remove the dynamic entry at `index` from a list and persist the change.
+    /// Returns the removed entry, or `None` if `index` is out of bounds.
+    pub fn remove_entry(
+        &mut self,
+        kind: MatchingListKind,
+        index: usize,
+    ) -> Result<Option<ListElement>> {
+        if index >= self.dynamic_entries(kind).len() {
+            return Ok(None);
+        }
+        let mut updated = self.clone();
+        let removed = updated.dynamic_entries_mut(kind).remove(index);
+        updated.persist()?;
+        updated.rebuild();
+        *self = updated;
+        Ok(Some(removed))
+    }
+
+    fn dynamic_entries_mut(&mut self, kind: MatchingListKind) -> &mut Vec<ListElement> {
+        match kind {
+            MatchingListKind::Whitelist => &mut self.dynamic_whitelist,
+            MatchingListKind::Blacklist => &mut self.dynamic_blacklist,
+        }
+    }
+
+    fn persist(&self) -> Result<()> {
+        let data = DynamicMessageFilterData {
+            whitelist: self
+                .dynamic_whitelist
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+            blacklist: self
+                .dynamic_blacklist
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+        };
+        for db in &self.dbs {
+            db.store_dynamic_message_filter(&data)?;
+        }
+        Ok(())
+    }
+
+    fn rebuild(&mut self) {
+        // Without configured entries the whitelist is a wildcard, which dynamic
+        // entries must not narrow down
+        self.whitelist = if self.configured_whitelist.0.is_none() {
+            MatchingList(None)
+        } else {
+            Self::combine(&self.configured_whitelist, &self.dynamic_whitelist)
+        };
+        self.blacklist = Self::combine(&self.configured_blacklist, &self.dynamic_blacklist);
+        self.generation += 1;
+    }
+
+    fn combine(configured: &MatchingList, dynamic: &[ListElement]) -> MatchingList {
+        if dynamic.is_empty() {
+            return configured.clone();
+        }
+        let mut elements = configured.0.clone().unwrap_or_default();
+        elements.extend_from_slice(dynamic);
+        MatchingList(Some(elements))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::DB;
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+
+    fn message(origin: u32, destination: u32) -> HyperlaneMessage {
+        HyperlaneMessage {
+            origin,
+            destination,
+            ..Default::default()
+        }
+    }
+
+    fn destination_entries(destination: u32) -> Vec<ListElement> {
+        MatchingList::with_destination_domain(destination)
+            .0
+            .unwrap()
+    }
+
+    #[test]
+    fn test_dynamic_entries_are_applied() {
+        let mut filter = MessageFilter::default();
+        assert!(filter.msg_allowed(&message(1, 2)));
+
+        let generation = filter.generation();
+        filter
+            .add_entries(MatchingListKind::Blacklist, destination_entries(2))
+            .unwrap();
+        assert_ne!(filter.generation(), generation);
+        assert!(!filter.msg_allowed(&message(1, 2)));
+        assert!(filter.msg_allowed(&message(1, 3)));
+
+        // Whitelist entries do not narrow down an empty configured whitelist
+        filter
+            .add_entries(MatchingListKind::Whitelist, destination_entries(4))
+            .unwrap();
+        assert!(filter.msg_allowed(&message(1, 3)));
+        assert!(filter.msg_allowed(&message(1, 4)));
+        assert!(!filter.msg_allowed(&message(1, 2)));
+
+        let removed = filter.remove_entry(MatchingListKind::Whitelist, 0).unwrap();
+        assert_eq!(removed, destination_entries(4).pop());
+        assert!(filter.msg_allowed(&message(1, 3)));
+
+        let removed = filter.remove_entry(MatchingListKind::Blacklist, 0).unwrap();
+        assert_eq!(removed, destination_entries(2).pop());
+        assert!(filter.msg_allowed(&message(1, 2)));
+        let generation = filter.generation();
+        assert!(filter
+            .remove_entry(MatchingListKind::Whitelist, 0)
+            .unwrap()
+            .is_none());
+        assert_eq!(filter.generation(), generation);
+    }
+
+    #[test]
+    fn test_dynamic_entries_extend_configured_whitelist() {
+        let mut filter = MessageFilter::new(
+            MatchingList::with_destination_domain(2),
+            MatchingList::default(),
+            vec![],
+        )
+        .unwrap();
+        assert!(!filter.msg_allowed(&message(1, 3)));
+
+        filter
+            .add_entries(MatchingListKind::Whitelist, destination_entries(3))
+            .unwrap();
+        assert!(filter.msg_allowed(&message(1, 2)));
+        assert!(filter.msg_allowed(&message(1, 3)));
+        assert_eq!(
+            filter.configured_entries(MatchingListKind::Whitelist),
+            destination_entries(2).as_slice()
+        );
+        assert_eq!(
+            filter.dynamic_entries(MatchingListKind::Whitelist),
+            destination_entries(3).as_slice()
+        );
+    }
+
+    #[test]
+    fn test_dynamic_entries_are_restored_from_dbs() {
+        let domains = [
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        ];
+        let temp_dirs: Vec<_> = domains
+            .iter()
+            .map(|_| tempfile::tempdir().unwrap())
+            .collect();
+        let dbs: Vec<_> = domains
+            .iter()
+            .zip(temp_dirs.iter())
+            .map(|(domain, dir)| HyperlaneRocksDB::new(domain, DB::from_path(dir.path()).unwrap()))
+            .collect();
+
+        let mut filter = MessageFilter::new(
+            MatchingList::default(),
+            MatchingList::default(),
+            dbs.clone(),
+        )
+        .unwrap();
+        filter
+            .add_entries(MatchingListKind::Blacklist, destination_entries(2))
+            .unwrap();
+
+        let restored =
+            MessageFilter::new(MatchingList::default(), MatchingList::default(), dbs).unwrap();
+        assert_eq!(
+            restored.dynamic_entries(MatchingListKind::Blacklist),
+            destination_entries(2).as_slice()
+        );
+        assert!(!restored.msg_allowed(&message(1, 2)));
+    }
+}
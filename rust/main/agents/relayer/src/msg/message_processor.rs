@@ -1,9 +1,10 @@
 #![allow(clippy::doc_markdown)] // TODO: `rustc` 1.80.1 clippy issue
 #![allow(clippy::doc_lazy_continuation)] // TODO: `rustc` 1.80.1 clippy issue
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::future::join_all;
 use futures_util::future::try_join_all;
@@ -29,9 +30,11 @@ use lander::{
 use crate::msg::pending_message::CONFIRM_DELAY;
 use crate::server::operations::message_retry::MessageRetryRequest;
 
+use super::message_filter::SharedMessageFilter;
 use super::op_batch::OperationBatch;
 use super::op_queue::OpQueue;
 use super::op_queue::OperationPriorityQueue;
+use super::rate_limiter::RateLimiter;
 
 /// This is needed for logic where we need to allocate
 /// based on how many queues exist in each MessageProcessor.
@@ -91,6 +94,10 @@ pub struct MessageProcessor {
     confirm_queue: OpQueue,
     payload_dispatcher_entrypoint: Option<DispatcherEntrypoint>,
     db: Arc<dyn HyperlaneDb>,
+    /// Limits on how fast operations are prepared, shared across destinations
+    rate_limiter: Arc<RateLimiter>,
+    /// Whether prepared operations are dropped instead of being submitted
+    shadow_mode: bool,
 }
 
 impl MessageProcessor {
@@ -105,17 +112,30 @@ impl MessageProcessor {
         task_monitor: TaskMonitor,
         payload_dispatcher_entrypoint: Option<DispatcherEntrypoint>,
         db: HyperlaneRocksDB,
+        message_filter: SharedMessageFilter,
+        rate_limiter: Arc<RateLimiter>,
+        app_context_weights: Option<HashMap<String, u32>>,
+        shadow_mode: bool,
     ) -> Self {
-        let prepare_queue = OpQueue::new(
+        // The message filter only holds back operations before they are prepared,
+        // so that the ones that are already being submitted can still be confirmed
+        let mut prepare_queue = OpQueue::new(
             metrics.processor_queue_length.clone(),
             "prepare_queue".to_string(),
             Arc::new(Mutex::new(retry_op_transmitter.subscribe())),
-        );
-        let submit_queue = OpQueue::new(
+        )
+        .with_message_filter(message_filter);
+        let mut submit_queue = OpQueue::new(
             metrics.processor_queue_length.clone(),
             "submit_queue".to_string(),
             Arc::new(Mutex::new(retry_op_transmitter.subscribe())),
         );
+        // Confirmation is not competing for the submission slot, so only the
+        // queues before it need to be fair across app contexts
+        if let Some(weights) = app_context_weights {
+            prepare_queue = prepare_queue.with_app_context_weights(weights.clone());
+            submit_queue = submit_queue.with_app_context_weights(weights);
+        }
         let confirm_queue = OpQueue::new(
             metrics.processor_queue_length.clone(),
             "confirm_queue".to_string(),
@@ -135,6 +155,8 @@ impl MessageProcessor {
             confirm_queue,
             payload_dispatcher_entrypoint,
             db: Arc::new(db),
+            rate_limiter,
+            shadow_mode,
         }
     }
 
@@ -163,6 +185,7 @@ impl MessageProcessor {
         let prepare_task = self.create_classic_prepare_task();
 
         let submit_task = match &entrypoint {
+            _ if self.shadow_mode => self.create_shadow_submit_task(),
             None => self.create_classic_submit_task(),
             Some(entrypoint) => self.create_lander_submit_task(entrypoint.clone()),
         };
@@ -213,6 +236,7 @@ impl MessageProcessor {
                     self.max_batch_size,
                     self.max_submit_queue_len,
                     self.metrics.clone(),
+                    self.rate_limiter.clone(),
                 ),
             ))
             .expect("spawning tokio task from Builder is infallible")
@@ -236,6 +260,22 @@ impl MessageProcessor {
             .expect("spawning tokio task from Builder is infallible")
     }
 
+    fn create_shadow_submit_task(&self) -> JoinHandle<()> {
+        let name = Self::task_name("submit_shadow::", &self.domain);
+        tokio::task::Builder::new()
+            .name(&name)
+            .spawn(TaskMonitor::instrument(
+                &self.task_monitor,
+                submit_shadow_task(
+                    self.domain.clone(),
+                    self.submit_queue.clone(),
+                    self.max_batch_size,
+                    self.metrics.clone(),
+                ),
+            ))
+            .expect("spawning tokio task from Builder is infallible")
+    }
+
     fn create_classic_confirm_task(&self) -> JoinHandle<()> {
         let name = Self::task_name("confirm_classic::", &self.domain);
         tokio::task::Builder::new()
@@ -270,6 +310,7 @@ impl MessageProcessor {
                     self.max_submit_queue_len,
                     self.metrics.clone(),
                     self.db.clone(),
+                    self.rate_limiter.clone(),
                 ),
             ))
             .expect("spawning tokio task from Builder is infallible")
@@ -338,6 +379,7 @@ async fn receive_task(
 }
 
 #[instrument(skip_all, fields(%domain))]
+#[allow(clippy::too_many_arguments)]
 async fn prepare_classic_task(
     domain: HyperlaneDomain,
     mut prepare_queue: OpQueue,
@@ -346,6 +388,7 @@ async fn prepare_classic_task(
     max_batch_size: u32,
     max_submit_queue_len: Option<u32>,
     metrics: MessageProcessorMetrics,
+    rate_limiter: Arc<RateLimiter>,
 ) {
     loop {
         if apply_backpressure(&submit_queue, &max_submit_queue_len).await {
@@ -358,9 +401,12 @@ async fn prepare_classic_task(
             continue;
         };
 
+        let batch_to_process =
+            apply_rate_limits(batch, &rate_limiter, &prepare_queue, &metrics).await;
+
         process_batch(
             domain.clone(),
-            batch,
+            batch_to_process,
             &mut prepare_queue,
             &submit_queue,
             &confirm_queue,
@@ -382,6 +428,7 @@ async fn prepare_lander_task(
     max_submit_queue_len: Option<u32>,
     metrics: MessageProcessorMetrics,
     db: Arc<dyn HyperlaneDb>,
+    rate_limiter: Arc<RateLimiter>,
 ) {
     loop {
         if apply_backpressure(&submit_queue, &max_submit_queue_len).await {
@@ -401,6 +448,8 @@ async fn prepare_lander_task(
             batch,
         )
         .await;
+        let batch_to_process =
+            apply_rate_limits(batch_to_process, &rate_limiter, &prepare_queue, &metrics).await;
 
         process_batch(
             domain.clone(),
@@ -483,6 +532,44 @@ async fn apply_backpressure(submit_queue: &OpQueue, max_len: &Option<u32>) -> bo
     false
 }
 
+/// Sends the operations of a batch that exceed a rate limit back to the prepare
+/// queue, to be retried once their token buckets have been refilled.
+async fn apply_rate_limits(
+    batch: Vec<QueueOperation>,
+    rate_limiter: &RateLimiter,
+    prepare_queue: &OpQueue,
+    metrics: &MessageProcessorMetrics,
+) -> Vec<QueueOperation> {
+    use PendingOperationStatus::Retry;
+
+    if rate_limiter.is_empty() {
+        return batch;
+    }
+
+    let now = Instant::now();
+    let mut ops_to_prepare = vec![];
+    for mut op in batch.into_iter() {
+        // Operations that are not due yet are sent back by `prepare` without doing
+        // any work, so they shouldn't consume any tokens
+        if op.next_attempt_after().is_some_and(|after| after > now) {
+            ops_to_prepare.push(op);
+            continue;
+        }
+        match rate_limiter.try_acquire(&op) {
+            Ok(()) => ops_to_prepare.push(op),
+            Err(wait) => {
+                debug!(?op, ?wait, "Operation exceeded rate limit, deferring");
+                metrics.inc_throttled(op.app_context());
+                op.set_next_attempt_after(wait);
+                prepare_queue
+                    .push(op, Some(Retry(ReprepareReason::RateLimited)))
+                    .await;
+            }
+        }
+    }
+    ops_to_prepare
+}
+
 /// Helper method to get a batch from the queue or wait if the queue is empty.
 async fn get_batch_or_wait(queue: &mut OpQueue, batch_size: u32) -> Option<Vec<QueueOperation>> {
     let batch = queue.pop_many(batch_size as usize).await;
@@ -593,6 +680,31 @@ async fn submit_classic_task(
     }
 }
 
+/// In shadow mode, what would have been submitted was already recorded while
+/// preparing, so prepared operations are dropped instead of being submitted.
+#[instrument(skip_all, fields(%domain))]
+async fn submit_shadow_task(
+    domain: HyperlaneDomain,
+    mut submit_queue: OpQueue,
+    max_batch_size: u32,
+    metrics: MessageProcessorMetrics,
+) {
+    let recv_limit = max_batch_size as usize;
+    loop {
+        let batch = submit_queue.pop_many(recv_limit).await;
+        if batch.is_empty() {
+            // The queue is empty, so give some time before checking again to prevent burning CPU
+            sleep(Duration::from_millis(100)).await;
+            continue;
+        }
+        for op in batch {
+            debug!(?op, "Operation prepared in shadow mode, not submitting");
+            metrics.inc_shadow_submitted(op.app_context());
+            op.decrement_metric_if_exists();
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 #[instrument(skip_all, fields(domain=%_domain))]
 async fn submit_lander_task(
@@ -1009,6 +1121,8 @@ pub struct MessageProcessorMetrics {
     pub(crate) ops_confirmed: IntCounterVec,
     pub(crate) ops_failed: IntCounterVec,
     pub(crate) ops_dropped: IntCounterVec,
+    pub(crate) ops_throttled: IntCounterVec,
+    pub(crate) ops_shadow_submitted: IntCounterVec,
 }
 
 impl MessageProcessorMetrics {
@@ -1023,6 +1137,8 @@ impl MessageProcessorMetrics {
             ops_confirmed: metrics.as_ref().operations_processed_count(),
             ops_failed: metrics.as_ref().operations_processed_count(),
             ops_dropped: metrics.as_ref().operations_processed_count(),
+            ops_throttled: metrics.as_ref().operations_processed_count(),
+            ops_shadow_submitted: metrics.as_ref().operations_processed_count(),
         }
     }
 
@@ -1046,6 +1162,14 @@ impl MessageProcessorMetrics {
         self.inc_phase_with_app_context("failed", app_context);
     }
 
+    pub fn inc_throttled(&self, app_context: Option<String>) {
+        self.inc_phase_with_app_context("throttled", app_context);
+    }
+
+    pub fn inc_shadow_submitted(&self, app_context: Option<String>) {
+        self.inc_phase_with_app_context("shadow_submitted", app_context);
+    }
+
     fn inc_phase_with_app_context(&self, phase: &str, app_context: Option<String>) {
         let labels = hashmap! {
             "app_context" => app_context.as_deref().unwrap_or("Unknown"),
@@ -1059,7 +1183,73 @@ impl MessageProcessorMetrics {
             "confirmed" => self.ops_confirmed.with(&labels).inc(),
             "failed" => self.ops_failed.with(&labels).inc(),
             "dropped" => self.ops_dropped.with(&labels).inc(),
+            "throttled" => self.ops_throttled.with(&labels).inc(),
+            "shadow_submitted" => self.ops_shadow_submitted.with(&labels).inc(),
             _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use prometheus::Registry;
+    use tokio::sync::broadcast;
+
+    use hyperlane_core::KnownHyperlaneDomain;
+
+    use crate::msg::op_queue::test::{dummy_metrics_and_label, MockPendingOperation};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn shadow_mode_drops_prepared_operations_without_submitting() {
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let core_metrics = Arc::new(CoreMetrics::new("relayer", 4000, Registry::new()).unwrap());
+        let metrics = MessageProcessorMetrics::new(core_metrics, &domain);
+
+        let (broadcaster, _) = broadcast::channel(1);
+        let (queue_metrics, queue_metrics_label) = dummy_metrics_and_label();
+        let submit_queue = OpQueue::new(
+            queue_metrics,
+            queue_metrics_label,
+            Arc::new(Mutex::new(broadcaster.subscribe())),
+        );
+        // `MockPendingOperation::submit` panics, so the task would fail if it submitted
+        let op_count = 3;
+        for _ in 0..op_count {
+            submit_queue
+                .push(
+                    Box::new(MockPendingOperation::new(0, domain.clone())) as QueueOperation,
+                    Some(PendingOperationStatus::ReadyToSubmit),
+                )
+                .await;
+        }
+
+        let task = tokio::spawn(submit_shadow_task(
+            domain.clone(),
+            submit_queue.clone(),
+            10,
+            metrics.clone(),
+        ));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while submit_queue.len().await > 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Shadow submit task did not drain the submit queue");
+        sleep(Duration::from_millis(100)).await;
+
+        assert!(!task.is_finished());
+        task.abort();
+        let labels = hashmap! {
+            "app_context" => "Unknown",
+            "phase" => "shadow_submitted",
+            "chain" => domain.name(),
+        };
+        assert_eq!(
+            metrics.ops_shadow_submitted.with(&labels).get(),
+            op_count as u64
+        );
+    }
+}
@@ -136,8 +136,7 @@ impl AggregationIsmMetadataBuilder {
         let ism_domain = ism.domain().name();
         let fn_key = "modules_and_threshold";
 
-        // Depending on the cache policy, make use of the message ID
-        let params_cache_key = match self
+        let cache_policy = self
             .base_builder()
             .ism_cache_policy_classifier()
             .get_cache_policy(
@@ -146,8 +145,21 @@ impl AggregationIsmMetadataBuilder {
                 ModuleType::Aggregation,
                 self.base.app_context.as_ref(),
             )
-            .await
-        {
+            .await;
+
+        // The resolved ISM tree ignores the message, like the ISM specific policy
+        if cache_policy == IsmCachePolicy::IsmSpecific {
+            if let Some(result) = self
+                .ism_tree
+                .as_ref()
+                .and_then(|tree| tree.modules_and_threshold(ism.address()))
+            {
+                return Ok(result);
+            }
+        }
+
+        // Depending on the cache policy, make use of the message ID
+        let params_cache_key = match cache_policy {
             // To have the cache key be more succinct, we use the message id
             IsmCachePolicy::MessageSpecific => (ism.address(), message.id()),
             IsmCachePolicy::IsmSpecific => (ism.address(), H256::zero()),
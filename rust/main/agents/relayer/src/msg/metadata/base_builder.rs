@@ -1,7 +1,7 @@
 #![allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
 #![allow(clippy::unnecessary_get_then_check)] // TODO: `rustc` 1.80.1 clippy issue
 
-use std::{collections::HashMap, fmt::Debug, str::FromStr, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, str::FromStr, sync::Arc, time::Duration};
 
 use derive_new::new;
 use eyre::Context;
@@ -15,7 +15,8 @@ use hyperlane_base::{
     cache::{LocalCache, MeteredCache, OptionalCache},
     db::{HyperlaneDb, HyperlaneRocksDB},
     settings::{ChainConf, CheckpointSyncerBuildError, CheckpointSyncerConf},
-    CheckpointSyncer, CoreMetrics, MultisigCheckpointSyncer,
+    CheckpointSyncer, CoreMetrics, MultisigCheckpointSyncer, QuorumSelectionStrategy,
+    ValidatorReliabilityTracker,
 };
 use hyperlane_core::{
     accumulator::merkle::Proof, AggregationIsm, CcipReadIsm, Checkpoint, HyperlaneDomain,
@@ -26,7 +27,10 @@ use hyperlane_core::{
 use crate::merkle_tree::builder::MerkleTreeBuilder;
 use crate::msg::metadata::base_builder::validator_announced_storages::fetch_storage_locations_helper;
 
-use super::{base::IsmCachePolicyClassifier, IsmAwareAppContextClassifier};
+use super::{
+    base::IsmCachePolicyClassifier, ccip_read::DEFAULT_TIMEOUT, multisig::QuorumCheckpointCache,
+    IsmAwareAppContextClassifier,
+};
 
 mod validator_announced_storages;
 
@@ -39,6 +43,13 @@ pub struct IsmBuildMetricsParams {
     pub success: bool,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcipReadGatewayMetricsParams {
+    pub host: String,
+    pub status: &'static str,
+    pub duration: Duration,
+}
+
 /// Base metadata builder with types used by higher level metadata builders.
 #[allow(clippy::too_many_arguments)]
 #[derive(new)]
@@ -55,6 +66,10 @@ pub struct BaseMetadataBuilder {
     ism_cache_policy_classifier: IsmCachePolicyClassifier,
     signer: Option<Signers>,
     ignore_reorg_reports: bool,
+    quorum_selection_strategy: QuorumSelectionStrategy,
+    ccip_read_gateway_timeouts: HashMap<String, Duration>,
+    #[new(default)]
+    quorum_checkpoints: QuorumCheckpointCache,
 }
 
 impl Debug for BaseMetadataBuilder {
@@ -74,9 +89,13 @@ pub trait BuildsBaseMetadata: Send + Sync + Debug {
     fn app_context_classifier(&self) -> &IsmAwareAppContextClassifier;
     fn ism_cache_policy_classifier(&self) -> &IsmCachePolicyClassifier;
     fn cache(&self) -> &OptionalCache<MeteredCache<LocalCache>>;
+    fn quorum_checkpoints(&self) -> &QuorumCheckpointCache;
     fn get_signer(&self) -> Option<&Signers>;
 
     fn update_ism_metric(&self, params: IsmBuildMetricsParams);
+    fn update_ccip_read_gateway_metric(&self, params: CcipReadGatewayMetricsParams);
+    /// Timeout of requests to the CCIP read gateway at `host`
+    fn ccip_read_gateway_timeout(&self, host: &str) -> Duration;
 
     async fn get_proof(&self, leaf_index: u32, checkpoint: Checkpoint) -> eyre::Result<Proof>;
     async fn highest_known_leaf_index(&self) -> Option<u32>;
@@ -116,6 +135,10 @@ impl BuildsBaseMetadata for BaseMetadataBuilder {
         &self.cache
     }
 
+    fn quorum_checkpoints(&self) -> &QuorumCheckpointCache {
+        &self.quorum_checkpoints
+    }
+
     fn update_ism_metric(&self, params: IsmBuildMetricsParams) {
         let labels = hashmap! {
             "app_context" => params.app_context.as_deref().unwrap_or("Unknown"),
@@ -127,6 +150,28 @@ impl BuildsBaseMetadata for BaseMetadataBuilder {
         self.metrics.ism_build_count().with(&labels).inc();
     }
 
+    fn update_ccip_read_gateway_metric(&self, params: CcipReadGatewayMetricsParams) {
+        let labels = hashmap! {
+            "host" => params.host.as_str(),
+            "status" => params.status,
+        };
+        self.metrics
+            .ccip_read_gateway_request_count()
+            .with(&labels)
+            .inc();
+        self.metrics
+            .ccip_read_gateway_request_duration()
+            .with(&labels)
+            .inc_by(params.duration.as_secs_f64());
+    }
+
+    fn ccip_read_gateway_timeout(&self, host: &str) -> Duration {
+        self.ccip_read_gateway_timeouts
+            .get(host)
+            .copied()
+            .unwrap_or(Duration::from_secs(DEFAULT_TIMEOUT))
+    }
+
     async fn get_proof(&self, leaf_index: u32, checkpoint: Checkpoint) -> eyre::Result<Proof> {
         const CTX: &str = "When fetching message proof";
         let proof = self
@@ -278,6 +323,13 @@ impl BuildsBaseMetadata for BaseMetadataBuilder {
         Ok(MultisigCheckpointSyncer::new(
             checkpoint_syncers,
             app_context.map(|ctx| (self.metrics.clone(), ctx)),
+        )
+        .with_quorum_selection(
+            self.quorum_selection_strategy,
+            Some(ValidatorReliabilityTracker::new(
+                self.db.clone(),
+                self.metrics.clone(),
+            )),
         ))
     }
 
@@ -301,7 +353,7 @@ impl BaseMetadataBuilder {
         config: &CheckpointSyncerConf,
         validator: &H256,
     ) -> Result<Option<Box<dyn CheckpointSyncer>>, CheckpointSyncerBuildError> {
-        match config.build_and_validate(None).await {
+        match config.build_and_validate(None, None).await {
             Ok(checkpoint_syncer) => {
                 return Ok(Some(checkpoint_syncer));
             }
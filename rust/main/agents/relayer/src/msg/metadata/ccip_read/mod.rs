@@ -1,19 +1,23 @@
 #![allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use cache_types::SerializedOffchainLookup;
 use derive_more::Deref;
 use derive_new::new;
-use ethers::{abi::AbiDecode, core::utils::hex::decode as hex_decode};
+use ethers::{
+    abi::AbiDecode,
+    core::{types::Bytes, utils::hex::decode as hex_decode},
+};
 use hyperlane_base::cache::FunctionCallCache;
 use regex::{Regex, RegexSet, RegexSetBuilder};
-use reqwest::{header::CONTENT_TYPE, Client};
+use reqwest::{header::CONTENT_TYPE, Client, Response, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha3::{digest::Update, Digest, Keccak256};
-use tracing::{info, instrument, warn};
+use tokio::time::sleep;
+use tracing::{debug, info, instrument, warn};
 
 use hyperlane_core::{
     utils::bytes_to_hex, CcipReadIsm, HyperlaneMessage, HyperlaneSignerExt, ModuleType,
@@ -21,7 +25,9 @@ use hyperlane_core::{
 };
 use hyperlane_ethereum::{OffchainLookup, Signers};
 
-use crate::msg::metadata::base_builder::IsmBuildMetricsParams;
+use crate::msg::metadata::{
+    base_builder::IsmBuildMetricsParams, BuildsBaseMetadata, CcipReadGatewayMetricsParams,
+};
 
 use super::{
     base::{MessageMetadataBuildParams, MetadataBuildError},
@@ -31,8 +37,16 @@ use super::{
 
 mod cache_types;
 
+/// Timeout in seconds of requests to gateways without a configured timeout
 pub const DEFAULT_TIMEOUT: u64 = 30;
 
+/// Number of attempts made to query a gateway while it is unavailable
+const GATEWAY_MAX_ATTEMPTS: u32 = 3;
+/// Delay before retrying an unavailable gateway, doubled after every retry
+const GATEWAY_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Cache key of gateway responses
+const GATEWAY_RESPONSE_FN_KEY: &str = "ccip_read_gateway_response";
+
 #[derive(Serialize, Deserialize)]
 struct OffchainResponse {
     data: String,
@@ -214,13 +228,45 @@ async fn metadata_build(
             let msg = format!("Failed to build CCIP read ISM: {}", err);
             MetadataBuildError::FailedToBuild(msg)
         })?;
+    let ism_domain = ism.domain().name().to_owned();
 
     let info = ism_builder
         .call_get_offchain_verify_info(ism, message)
         .await?;
 
+    // Gateway responses only depend on the ISM and the calldata it asked for
+    let cache = ism_builder.base.base_builder().cache();
+    let response_params = (ism_address, &info.call_data);
+    let cached_response = cache
+        .get_cached_call_result::<Bytes>(&ism_domain, GATEWAY_RESPONSE_FN_KEY, &response_params)
+        .await
+        .map_err(|err| {
+            warn!(error = %err, "Error when getting cached CCIP read gateway response");
+        })
+        .ok()
+        .flatten();
+    if let Some(metadata) = cached_response {
+        let base_builder = ism_builder.base.base_builder().as_ref();
+        if cached_response_verifies(base_builder, ism_address, message, &metadata).await {
+            return Ok(Metadata::new(metadata.to_vec()));
+        }
+        info!(
+            ?ism_address,
+            "Cached CCIP read gateway response no longer verifies, dropping it"
+        );
+        cache
+            .remove_cached_call_result(&ism_domain, GATEWAY_RESPONSE_FN_KEY, &response_params)
+            .await
+            .map_err(|err| {
+                warn!(error = %err, "Error when removing cached CCIP read gateway response");
+            })
+            .ok();
+    }
+
+    let client = Client::new();
     let ccip_url_regex = create_ccip_url_regex();
 
+    // Gateways are tried in the order the ISM listed them, as required by EIP-3668
     for url in info.urls.iter() {
         if ccip_url_regex.is_match(url) {
             tracing::warn!(?ism_address, url, "Suspicious CCIP read url");
@@ -243,7 +289,7 @@ async fn metadata_build(
         let interpolated_url = url
             .replace("{sender}", sender_as_bytes)
             .replace("{data}", data_as_bytes);
-        let res = if !url.contains("{data}") {
+        let request = if !url.contains("{data}") {
             let mut body = json!({
                 "sender": sender_as_bytes,
                 "data": data_as_bytes
@@ -251,46 +297,38 @@ async fn metadata_build(
             if let Some(signature_hex) = &maybe_signature_hex {
                 body["signature"] = json!(signature_hex);
             }
-            Client::new()
-                .post(interpolated_url)
-                .header(CONTENT_TYPE, "application/json")
-                .timeout(Duration::from_secs(DEFAULT_TIMEOUT))
-                .json(&body)
-                .send()
-                .await
-                .map_err(|err| {
-                    let msg = format!(
-                        "Failed to request offchain lookup server with post method: {}",
-                        err
-                    );
-                    MetadataBuildError::FailedToBuild(msg)
-                })?
+            GatewayRequest::Post(interpolated_url, body)
         } else {
-            reqwest::get(interpolated_url).await.map_err(|err| {
-                let msg = format!(
-                    "Failed to request offchain lookup server with get method: {}",
-                    err
-                );
-                MetadataBuildError::FailedToBuild(msg)
-            })?
+            GatewayRequest::Get(interpolated_url)
         };
 
-        let json: Result<OffchainResponse, reqwest::Error> = res.json().await;
-
-        match json {
-            Ok(result) => {
-                // remove leading 0x which hex_decode doesn't like
-                let metadata = hex_decode(&result.data[2..]).map_err(|err| {
-                    let msg = format!(
-                        "Failed to decode hex from offchain lookup server response: err: ({}), data: ({})",
-                        err, result.data
-                    );
-                    MetadataBuildError::FailedToBuild(msg)
-                })?;
+        let base_builder = ism_builder.base.base_builder().as_ref();
+        match fetch_from_gateway(base_builder, &client, &request).await {
+            GatewayResponse::Metadata(metadata) => {
+                cache
+                    .cache_call_result(
+                        &ism_domain,
+                        GATEWAY_RESPONSE_FN_KEY,
+                        &response_params,
+                        &Bytes::from(metadata.clone()),
+                    )
+                    .await
+                    .map_err(|err| {
+                        warn!(error = %err, "Error when caching CCIP read gateway response");
+                    })
+                    .ok();
                 return Ok(Metadata::new(metadata));
             }
-            Err(_err) => {
-                // try the next URL
+            GatewayResponse::ClientError(status) => {
+                // EIP-3668: a 4xx response is an error for the whole lookup
+                info!(?ism_address, url, %status, "CCIP read gateway rejected the request");
+                return Err(MetadataBuildError::CouldNotFetch);
+            }
+            GatewayResponse::InvalidResponse(err) | GatewayResponse::Unavailable(err) => {
+                warn!(
+                    ?ism_address,
+                    url, err, "CCIP read gateway failed, trying the next url"
+                );
             }
         }
     }
@@ -299,6 +337,156 @@ async fn metadata_build(
     Err(MetadataBuildError::CouldNotFetch)
 }
 
+/// Whether the ISM still accepts the metadata of a cached gateway response
+async fn cached_response_verifies(
+    base_builder: &dyn BuildsBaseMetadata,
+    ism_address: H256,
+    message: &HyperlaneMessage,
+    metadata: &[u8],
+) -> bool {
+    let ism = match base_builder.build_ism(ism_address).await {
+        Ok(ism) => ism,
+        Err(err) => {
+            warn!(
+                ?ism_address,
+                error = %err,
+                "Failed to build ISM to verify cached CCIP read gateway response"
+            );
+            return false;
+        }
+    };
+    matches!(ism.dry_run_verify(message, metadata).await, Ok(Some(_)))
+}
+
+/// A request to a CCIP read gateway URL, with `{sender}` and `{data}` interpolated
+#[derive(Clone, Debug)]
+enum GatewayRequest {
+    Get(String),
+    Post(String, serde_json::Value),
+}
+
+impl GatewayRequest {
+    fn url(&self) -> &str {
+        match self {
+            GatewayRequest::Get(url) | GatewayRequest::Post(url, _) => url,
+        }
+    }
+
+    /// Host of the gateway, to label metrics with
+    fn host(&self) -> String {
+        Url::parse(self.url())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+            .unwrap_or_else(|| "unknown".to_owned())
+    }
+
+    async fn send(&self, client: &Client, timeout: Duration) -> reqwest::Result<Response> {
+        let request = match self {
+            GatewayRequest::Get(url) => client.get(url),
+            GatewayRequest::Post(url, body) => client
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .json(body),
+        };
+        request.timeout(timeout).send().await
+    }
+}
+
+/// Outcome of a request to a CCIP read gateway
+#[derive(Clone, Debug, PartialEq)]
+enum GatewayResponse {
+    /// The gateway returned the metadata
+    Metadata(Vec<u8>),
+    /// The gateway rejected the request with a 4xx status
+    ClientError(StatusCode),
+    /// The gateway responded successfully, but not with hex encoded data
+    InvalidResponse(String),
+    /// The gateway could not be reached, timed out or returned a 5xx status
+    Unavailable(String),
+}
+
+impl GatewayResponse {
+    fn status(&self) -> &'static str {
+        match self {
+            GatewayResponse::Metadata(_) => "success",
+            GatewayResponse::ClientError(_) => "client_error",
+            GatewayResponse::InvalidResponse(_) => "invalid_response",
+            GatewayResponse::Unavailable(_) => "unavailable",
+        }
+    }
+}
+
+/// Queries a gateway, retrying with exponential backoff while it is unavailable
+async fn fetch_from_gateway(
+    base_builder: &dyn BuildsBaseMetadata,
+    client: &Client,
+    request: &GatewayRequest,
+) -> GatewayResponse {
+    let host = request.host();
+    let timeout = base_builder.ccip_read_gateway_timeout(&host);
+    let mut backoff = GATEWAY_INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let start = Instant::now();
+        let response = query_gateway(client, request, timeout).await;
+        base_builder.update_ccip_read_gateway_metric(CcipReadGatewayMetricsParams {
+            host: host.clone(),
+            status: response.status(),
+            duration: start.elapsed(),
+        });
+
+        match response {
+            GatewayResponse::Unavailable(err) if attempt < GATEWAY_MAX_ATTEMPTS => {
+                debug!(
+                    url = request.url(),
+                    attempt,
+                    err,
+                    ?backoff,
+                    "Retrying CCIP read gateway"
+                );
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            response => return response,
+        }
+    }
+}
+
+async fn query_gateway(
+    client: &Client,
+    request: &GatewayRequest,
+    timeout: Duration,
+) -> GatewayResponse {
+    let response = match request.send(client, timeout).await {
+        Ok(response) => response,
+        Err(err) => return GatewayResponse::Unavailable(err.to_string()),
+    };
+
+    let status = response.status();
+    // Rate limited requests are worth retrying, unlike other client errors
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        return GatewayResponse::Unavailable(format!("Gateway responded with {}", status));
+    }
+    if status.is_client_error() {
+        return GatewayResponse::ClientError(status);
+    }
+
+    let result = match response.json::<OffchainResponse>().await {
+        Ok(result) => result,
+        Err(err) => return GatewayResponse::InvalidResponse(err.to_string()),
+    };
+    // remove leading 0x which hex_decode doesn't like
+    let data = result.data.strip_prefix("0x").unwrap_or(&result.data);
+    match hex_decode(data) {
+        Ok(metadata) => GatewayResponse::Metadata(metadata),
+        Err(err) => GatewayResponse::InvalidResponse(format!(
+            "Failed to decode hex from offchain lookup server response: err: ({}), data: ({})",
+            err, result.data
+        )),
+    }
+}
+
 fn create_ccip_url_regex() -> RegexSet {
     RegexSetBuilder::new([
         r#"^(https?:\/\/)localhost"#,
@@ -317,10 +505,21 @@ fn create_ccip_url_regex() -> RegexSet {
 
 #[cfg(test)]
 mod test {
-    use std::{str::FromStr, vec};
-
+    use std::{
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        vec,
+    };
+
+    use axum::{extract::State, http::StatusCode as HttpStatusCode, routing::get, Json, Router};
     use ethers::types::H160;
-    use hyperlane_core::SignedType;
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain, SignedType};
+    use tokio::net::TcpListener;
+
+    use crate::test_utils::mock_base_builder::build_mock_base_builder;
 
     use super::*;
 
@@ -452,4 +651,96 @@ mod test {
             assert_eq!(actual, expected);
         }
     }
+
+    /// Serves a gateway that is unavailable for its first request, one that
+    /// rejects every request and one that takes two seconds to respond
+    async fn serve_gateways() -> String {
+        async fn flaky(
+            State(requests): State<Arc<AtomicUsize>>,
+        ) -> (HttpStatusCode, Json<serde_json::Value>) {
+            if requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                (HttpStatusCode::SERVICE_UNAVAILABLE, Json(json!({})))
+            } else {
+                (HttpStatusCode::OK, Json(json!({ "data": "0x1234" })))
+            }
+        }
+
+        let app = Router::new()
+            .route("/flaky", get(flaky))
+            .route("/missing", get(|| async { HttpStatusCode::NOT_FOUND }))
+            .route(
+                "/slow",
+                get(|| async {
+                    sleep(Duration::from_secs(2)).await;
+                    Json(json!({ "data": "0x1234" }))
+                }),
+            )
+            .with_state(Arc::new(AtomicUsize::new(0)));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_gateway() {
+        let base_builder = build_mock_base_builder(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        );
+        let gateway = serve_gateways().await;
+        let client = Client::new();
+
+        // Unavailable gateways are retried
+        let request = GatewayRequest::Get(format!("{}/flaky", gateway));
+        let response = fetch_from_gateway(&base_builder, &client, &request).await;
+        assert_eq!(response, GatewayResponse::Metadata(vec![0x12, 0x34]));
+
+        // Client errors are not
+        let request = GatewayRequest::Get(format!("{}/missing", gateway));
+        let response = fetch_from_gateway(&base_builder, &client, &request).await;
+        assert_eq!(
+            response,
+            GatewayResponse::ClientError(StatusCode::NOT_FOUND)
+        );
+
+        let metrics = base_builder
+            .requests
+            .update_ccip_read_gateway_metrics
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|params| (params.host.clone(), params.status))
+            .collect::<Vec<_>>();
+        let host = "127.0.0.1".to_owned();
+        assert_eq!(
+            metrics,
+            vec![
+                (host.clone(), "unavailable"),
+                (host.clone(), "success"),
+                (host, "client_error"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_gateway_uses_gateway_timeout() {
+        let mut base_builder = build_mock_base_builder(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        );
+        let gateway = serve_gateways().await;
+        let client = Client::new();
+        let request = GatewayRequest::Get(format!("{}/slow", gateway));
+
+        // The default timeout leaves slow gateways enough time to respond
+        let response = fetch_from_gateway(&base_builder, &client, &request).await;
+        assert_eq!(response, GatewayResponse::Metadata(vec![0x12, 0x34]));
+
+        base_builder
+            .ccip_read_gateway_timeouts
+            .insert("127.0.0.1".to_owned(), Duration::from_millis(100));
+        let response = fetch_from_gateway(&base_builder, &client, &request).await;
+        assert!(matches!(response, GatewayResponse::Unavailable(_)));
+    }
 }
@@ -0,0 +1,674 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::future::BoxFuture;
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use hyperlane_core::{HyperlaneMessage, ModuleType, H256};
+
+use crate::msg::pending_message::{ISM_MAX_COUNT, ISM_MAX_DEPTH};
+
+use super::BuildsBaseMetadata;
+
+/// The ISM tree of a recipient, resolved ahead of time so that building
+/// metadata for its messages does not need to query every ISM again.
+///
+/// The tree is resolved one origin at a time, as messages from each origin
+/// come in, using probe messages that only differ in their origin.
+/// ISMs whose config depends on more than the origin are left unresolved,
+/// or are only used for messages when their cache policy is ISM specific.
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolvedIsmTree {
+    pub destination: u32,
+    pub recipient: H256,
+    /// Origins the tree was resolved for
+    pub origins: Vec<u32>,
+    pub root: ResolvedIsmNode,
+    /// Unix timestamp of when the tree was resolved
+    pub resolved_at: u64,
+    #[serde(skip)]
+    expires_at: Instant,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ResolvedIsmNode {
+    pub address: H256,
+    pub module_type: Option<ModuleType>,
+    #[serde(flatten)]
+    pub config: ResolvedIsmConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "config", rename_all = "snake_case")]
+pub enum ResolvedIsmConfig {
+    /// The ISM routed to for each origin
+    Routing {
+        routes: BTreeMap<u32, ResolvedIsmNode>,
+    },
+    Aggregation {
+        modules: Vec<ResolvedIsmNode>,
+        threshold: u8,
+    },
+    Multisig {
+        validators: Vec<H256>,
+        threshold: u8,
+    },
+    /// The ISM has no config that metadata building depends on
+    Leaf,
+    /// The ISM could not be resolved, so it is queried for every message
+    Unresolved { error: String },
+}
+
+impl ResolvedIsmNode {
+    fn new(address: H256, module_type: Option<ModuleType>, config: ResolvedIsmConfig) -> Self {
+        Self {
+            address,
+            module_type,
+            config,
+        }
+    }
+
+    fn unresolved(address: H256, module_type: Option<ModuleType>, error: String) -> Self {
+        Self::new(
+            address,
+            module_type,
+            ResolvedIsmConfig::Unresolved { error },
+        )
+    }
+
+    fn children(&self) -> Vec<&ResolvedIsmNode> {
+        match &self.config {
+            ResolvedIsmConfig::Routing { routes } => routes.values().collect(),
+            ResolvedIsmConfig::Aggregation { modules, .. } => modules.iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Depth-first search for the first node `f` returns a value for
+    fn find_map<T>(&self, f: &impl Fn(&ResolvedIsmNode) -> Option<T>) -> Option<T> {
+        f(self).or_else(|| {
+            self.children()
+                .into_iter()
+                .find_map(|child| child.find_map(f))
+        })
+    }
+
+    /// Merge `other`, the same ISM resolved for other origins, into this node.
+    /// Configs that differ between origins cannot be looked up by ISM alone,
+    /// so they are left unresolved.
+    fn merge(self, other: ResolvedIsmNode) -> ResolvedIsmNode {
+        use ResolvedIsmConfig::*;
+
+        let module_type = self.module_type.or(other.module_type);
+        let config = match (self.config, other.config) {
+            (Unresolved { error }, _) | (_, Unresolved { error }) => Unresolved { error },
+            (
+                Routing { mut routes },
+                Routing {
+                    routes: other_routes,
+                },
+            ) => {
+                for (origin, route) in other_routes {
+                    let route = match routes.remove(&origin) {
+                        Some(existing) => existing.merge(route),
+                        None => route,
+                    };
+                    routes.insert(origin, route);
+                }
+                Routing { routes }
+            }
+            (
+                Aggregation { modules, threshold },
+                Aggregation {
+                    modules: other_modules,
+                    threshold: other_threshold,
+                },
+            ) if threshold == other_threshold
+                && modules
+                    .iter()
+                    .map(|m| m.address)
+                    .eq(other_modules.iter().map(|m| m.address)) =>
+            {
+                let modules = modules
+                    .into_iter()
+                    .zip(other_modules)
+                    .map(|(module, other)| module.merge(other))
+                    .collect();
+                Aggregation { modules, threshold }
+            }
+            (config, other_config) if config == other_config => config,
+            _ => Unresolved {
+                error: "Config differs between origins".to_string(),
+            },
+        };
+        Self::new(self.address, module_type, config)
+    }
+}
+
+impl ResolvedIsmTree {
+    /// Resolve the ISM tree of `recipient` on `destination`, whose root ISM is `root`
+    #[instrument(skip(base))]
+    pub async fn resolve(
+        base: &dyn BuildsBaseMetadata,
+        destination: u32,
+        recipient: H256,
+        root: H256,
+        origins: Vec<u32>,
+        ttl: Duration,
+    ) -> Self {
+        let resolved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let tree = Self {
+            destination,
+            recipient,
+            origins: vec![],
+            root: ResolvedIsmNode::unresolved(
+                root,
+                None,
+                "No origin to resolve the ISM for".to_string(),
+            ),
+            resolved_at,
+            expires_at: Instant::now() + ttl,
+        };
+        tree.extended(base, &origins).await
+    }
+
+    /// A copy of the tree that is also resolved for `origins`.
+    /// Each origin gets its own ISM count budget, like the metadata of a single message.
+    #[instrument(skip(self, base), fields(destination = self.destination, recipient = ?self.recipient))]
+    pub async fn extended(&self, base: &dyn BuildsBaseMetadata, origins: &[u32]) -> Self {
+        let mut tree = self.clone();
+        for &origin in origins {
+            if tree.origins.contains(&origin) {
+                continue;
+            }
+            let resolver = IsmTreeResolver {
+                base,
+                destination: self.destination,
+                recipient: self.recipient,
+                origin,
+                ism_count: AtomicU32::new(0),
+            };
+            let node = resolver.resolve_node(self.root.address, 0).await;
+            tree.root = if tree.origins.is_empty() {
+                node
+            } else {
+                tree.root.merge(node)
+            };
+            tree.origins.push(origin);
+        }
+        tree.origins.sort_unstable();
+        tree
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+
+    fn is_resolved_for(&self, origins: &[u32]) -> bool {
+        origins.iter().all(|origin| self.origins.contains(origin))
+    }
+
+    pub fn module_type(&self, address: H256) -> Option<ModuleType> {
+        self.root
+            .find_map(&|node| node.module_type.filter(|_| node.address == address))
+    }
+
+    pub fn route(&self, address: H256, origin: u32) -> Option<H256> {
+        self.root.find_map(&|node| match &node.config {
+            ResolvedIsmConfig::Routing { routes } if node.address == address => {
+                routes.get(&origin).map(|route| route.address)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn modules_and_threshold(&self, address: H256) -> Option<(Vec<H256>, u8)> {
+        self.root.find_map(&|node| match &node.config {
+            ResolvedIsmConfig::Aggregation { modules, threshold } if node.address == address => {
+                Some((modules.iter().map(|m| m.address).collect(), *threshold))
+            }
+            _ => None,
+        })
+    }
+
+    pub fn validators_and_threshold(&self, address: H256) -> Option<(Vec<H256>, u8)> {
+        self.root.find_map(&|node| match &node.config {
+            ResolvedIsmConfig::Multisig {
+                validators,
+                threshold,
+            } if node.address == address => Some((validators.clone(), *threshold)),
+            _ => None,
+        })
+    }
+}
+
+/// Resolves the branch of an ISM tree that messages from `origin` go through
+struct IsmTreeResolver<'a> {
+    base: &'a dyn BuildsBaseMetadata,
+    destination: u32,
+    recipient: H256,
+    origin: u32,
+    ism_count: AtomicU32,
+}
+
+impl IsmTreeResolver<'_> {
+    /// A message from the origin, with a body shaped like a token transfer of nothing
+    fn probe_message(&self) -> HyperlaneMessage {
+        HyperlaneMessage {
+            origin: self.origin,
+            destination: self.destination,
+            recipient: self.recipient,
+            body: vec![0; 64],
+            ..Default::default()
+        }
+    }
+
+    /// The probe message with every field but the origin, destination and
+    /// recipient maxed out, e.g. a token transfer of the max amount
+    fn maxed_probe_message(&self) -> HyperlaneMessage {
+        HyperlaneMessage {
+            nonce: u32::MAX,
+            sender: H256::repeat_byte(0xff),
+            body: vec![0xff; 64],
+            ..self.probe_message()
+        }
+    }
+
+    /// Resolve the ISM at `address`
+    fn resolve_node(&self, address: H256, depth: u32) -> BoxFuture<'_, ResolvedIsmNode> {
+        Box::pin(async move {
+            if depth >= ISM_MAX_DEPTH {
+                let error = format!("Max ISM depth of {ISM_MAX_DEPTH} reached");
+                return ResolvedIsmNode::unresolved(address, None, error);
+            }
+            if self.ism_count.fetch_add(1, Ordering::Relaxed) >= ISM_MAX_COUNT {
+                let error = format!("Max ISM count of {ISM_MAX_COUNT} reached");
+                return ResolvedIsmNode::unresolved(address, None, error);
+            }
+            let module_type = match self.module_type(address).await {
+                Ok(module_type) => module_type,
+                Err(err) => return ResolvedIsmNode::unresolved(address, None, err.to_string()),
+            };
+            match self.resolve_config(address, module_type, depth).await {
+                Ok(config) => ResolvedIsmNode::new(address, Some(module_type), config),
+                Err(err) => {
+                    ResolvedIsmNode::unresolved(address, Some(module_type), err.to_string())
+                }
+            }
+        })
+    }
+
+    async fn module_type(&self, address: H256) -> eyre::Result<ModuleType> {
+        Ok(self.base.build_ism(address).await?.module_type().await?)
+    }
+
+    async fn resolve_config(
+        &self,
+        address: H256,
+        module_type: ModuleType,
+        depth: u32,
+    ) -> eyre::Result<ResolvedIsmConfig> {
+        let config = match module_type {
+            ModuleType::Routing => {
+                let ism = self.base.build_routing_ism(address).await?;
+                let mut routes = BTreeMap::new();
+                // Origins the ISM does not route are left to be queried per message
+                match ism.route(&self.probe_message()).await {
+                    Ok(route) => {
+                        // Routers that also route on e.g. the amount transferred
+                        // would be cached with the route of the probe message
+                        if ism.route(&self.maxed_probe_message()).await? != route {
+                            eyre::bail!("Route depends on more than the message origin");
+                        }
+                        let node = self.resolve_node(route, depth + 1).await;
+                        routes.insert(self.origin, node);
+                    }
+                    Err(err) => {
+                        debug!(
+                            ?address,
+                            origin = self.origin,
+                            ?err,
+                            "Failed to resolve route"
+                        );
+                    }
+                }
+                ResolvedIsmConfig::Routing { routes }
+            }
+            ModuleType::Aggregation => {
+                let (addresses, threshold) = self
+                    .base
+                    .build_aggregation_ism(address)
+                    .await?
+                    .modules_and_threshold(&self.probe_message())
+                    .await?;
+                let mut modules = Vec::with_capacity(addresses.len());
+                for module in addresses {
+                    modules.push(self.resolve_node(module, depth + 1).await);
+                }
+                ResolvedIsmConfig::Aggregation { modules, threshold }
+            }
+            ModuleType::MerkleRootMultisig | ModuleType::MessageIdMultisig => {
+                let (validators, threshold) = self
+                    .base
+                    .build_multisig_ism(address)
+                    .await?
+                    .validators_and_threshold(&self.probe_message())
+                    .await?;
+                ResolvedIsmConfig::Multisig {
+                    validators,
+                    threshold,
+                }
+            }
+            _ => ResolvedIsmConfig::Leaf,
+        };
+        Ok(config)
+    }
+}
+
+/// Resolved ISM trees keyed by (destination domain id, recipient)
+#[derive(Debug)]
+pub struct IsmTreeCache {
+    ttl: Duration,
+    trees: RwLock<HashMap<(u32, H256), Arc<ResolvedIsmTree>>>,
+    /// Held while a tree is resolved, so that concurrent misses resolve it once
+    resolving: Mutex<HashMap<(u32, H256), Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl IsmTreeCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            trees: Default::default(),
+            resolving: Default::default(),
+        }
+    }
+
+    /// The cached tree of `recipient`, if it has not expired
+    pub fn get(&self, destination: u32, recipient: H256) -> Option<Arc<ResolvedIsmTree>> {
+        self.trees
+            .read()
+            .expect("ISM tree cache lock poisoned")
+            .get(&(destination, recipient))
+            .filter(|tree| !tree.is_expired())
+            .cloned()
+    }
+
+    /// The cached tree of `recipient`, if it has not expired and still has root ISM `root`
+    fn get_with_root(
+        &self,
+        destination: u32,
+        recipient: H256,
+        root: H256,
+    ) -> Option<Arc<ResolvedIsmTree>> {
+        self.get(destination, recipient)
+            .filter(|tree| tree.root.address == root)
+    }
+
+    /// The cached tree of `recipient`, resolving it for any of `origins` it
+    /// was not resolved for yet. The tree is resolved again if it expired or
+    /// the recipient now uses a different root ISM.
+    pub async fn get_or_resolve(
+        &self,
+        base: &dyn BuildsBaseMetadata,
+        destination: u32,
+        recipient: H256,
+        root: H256,
+        origins: &[u32],
+    ) -> Arc<ResolvedIsmTree> {
+        let key = (destination, recipient);
+        if let Some(tree) = self
+            .get_with_root(destination, recipient, root)
+            .filter(|tree| tree.is_resolved_for(origins))
+        {
+            return tree;
+        }
+
+        let lock = self
+            .resolving
+            .lock()
+            .expect("ISM tree cache lock poisoned")
+            .entry(key)
+            .or_default()
+            .clone();
+        let tree = {
+            let _guard = lock.lock().await;
+            // The tree may have been resolved while waiting for the lock
+            match self.get_with_root(destination, recipient, root) {
+                Some(tree) if tree.is_resolved_for(origins) => tree,
+                cached => {
+                    let tree = match cached {
+                        Some(tree) => tree.extended(base, origins).await,
+                        None => {
+                            ResolvedIsmTree::resolve(
+                                base,
+                                destination,
+                                recipient,
+                                root,
+                                origins.to_vec(),
+                                self.ttl,
+                            )
+                            .await
+                        }
+                    };
+                    let tree = Arc::new(tree);
+                    self.trees
+                        .write()
+                        .expect("ISM tree cache lock poisoned")
+                        .insert(key, tree.clone());
+                    tree
+                }
+            }
+        };
+
+        let mut resolving = self.resolving.lock().expect("ISM tree cache lock poisoned");
+        // Only drop the lock if no other task is waiting on it
+        if Arc::strong_count(&lock) == 2 {
+            resolving.remove(&key);
+        }
+        tree
+    }
+
+    /// Drop the cached tree of `recipient`. Returns whether a tree was cached.
+    pub fn invalidate(&self, destination: u32, recipient: H256) -> bool {
+        self.trees
+            .write()
+            .expect("ISM tree cache lock poisoned")
+            .remove(&(destination, recipient))
+            .is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain, U256};
+
+    use super::*;
+    use crate::test_utils::{
+        mock_aggregation_ism::MockAggregationIsm,
+        mock_base_builder::{build_mock_base_builder, MockBaseMetadataBuilder},
+        mock_ism::MockInterchainSecurityModule,
+        mock_routing_ism::MockRoutingIsm,
+    };
+
+    const TEST_DOMAIN: HyperlaneDomain = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn insert_ism(base_builder: &MockBaseMetadataBuilder, address: H256, module_type: ModuleType) {
+        let mock_ism = MockInterchainSecurityModule::new(address, TEST_DOMAIN.clone(), module_type);
+        mock_ism
+            .responses
+            .dry_run_verify
+            .lock()
+            .unwrap()
+            .push_back(Ok(Some(U256::zero())));
+        base_builder
+            .responses
+            .push_build_ism_response(address, Ok(Box::new(mock_ism)));
+    }
+
+    /// Insert a routing ISM that routes both probe messages to `route` and `maxed_route`
+    fn insert_routing_ism(
+        base_builder: &MockBaseMetadataBuilder,
+        address: H256,
+        route: H256,
+        maxed_route: H256,
+    ) {
+        insert_ism(base_builder, address, ModuleType::Routing);
+        let routing_ism = MockRoutingIsm::new(address, TEST_DOMAIN.clone());
+        routing_ism
+            .responses
+            .route
+            .lock()
+            .unwrap()
+            .extend([Ok(route), Ok(maxed_route)]);
+        base_builder
+            .responses
+            .push_build_routing_ism_response(address, Ok(Box::new(routing_ism)));
+    }
+
+    /// The branch of origin 1:
+    ///
+    /// routing 0 --> aggregation 10 --> null 100, null 101
+    fn insert_origin_1_branch(base_builder: &MockBaseMetadataBuilder) {
+        let addr = H256::from_low_u64_be;
+        insert_routing_ism(base_builder, addr(0), addr(10), addr(10));
+
+        insert_ism(base_builder, addr(10), ModuleType::Aggregation);
+        let agg_ism = MockAggregationIsm::new(addr(10), TEST_DOMAIN.clone());
+        agg_ism
+            .responses
+            .modules_and_threshold
+            .lock()
+            .unwrap()
+            .push_back(Ok((vec![addr(100), addr(101)], 1)));
+        base_builder
+            .responses
+            .push_build_aggregation_ism_response(addr(10), Ok(Box::new(agg_ism)));
+
+        for null_ism in [100, 101] {
+            insert_ism(base_builder, addr(null_ism), ModuleType::Null);
+        }
+    }
+
+    /// The branch of origin 2:
+    ///
+    /// routing 0 --> null 20
+    fn insert_origin_2_branch(base_builder: &MockBaseMetadataBuilder) {
+        let addr = H256::from_low_u64_be;
+        insert_routing_ism(base_builder, addr(0), addr(20), addr(20));
+        insert_ism(base_builder, addr(20), ModuleType::Null);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ism_tree() {
+        let addr = H256::from_low_u64_be;
+        let base_builder = build_mock_base_builder(TEST_DOMAIN.clone(), TEST_DOMAIN.clone());
+        insert_origin_1_branch(&base_builder);
+        insert_origin_2_branch(&base_builder);
+
+        let tree =
+            ResolvedIsmTree::resolve(&base_builder, 1, addr(0xff), addr(0), vec![1, 2], TTL).await;
+
+        assert_eq!(tree.origins, vec![1, 2]);
+        assert_eq!(tree.module_type(addr(0)), Some(ModuleType::Routing));
+        assert_eq!(tree.module_type(addr(101)), Some(ModuleType::Null));
+        assert_eq!(tree.route(addr(0), 1), Some(addr(10)));
+        assert_eq!(tree.route(addr(0), 2), Some(addr(20)));
+        assert_eq!(tree.route(addr(0), 3), None);
+        assert_eq!(
+            tree.modules_and_threshold(addr(10)),
+            Some((vec![addr(100), addr(101)], 1))
+        );
+        assert_eq!(tree.validators_and_threshold(addr(10)), None);
+        assert!(!tree.is_expired());
+
+        let json = serde_json::to_value(&tree).unwrap();
+        assert_eq!(json["root"]["config"], "routing");
+        assert_eq!(json["root"]["routes"]["1"]["config"], "aggregation");
+        assert_eq!(json["root"]["routes"]["2"]["config"], "leaf");
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_ism() {
+        let addr = H256::from_low_u64_be;
+        let base_builder = build_mock_base_builder(TEST_DOMAIN.clone(), TEST_DOMAIN.clone());
+        base_builder
+            .responses
+            .push_build_ism_response(addr(0), Err(eyre::eyre!("rpc error")));
+
+        let tree =
+            ResolvedIsmTree::resolve(&base_builder, 1, addr(0xff), addr(0), vec![1], TTL).await;
+
+        assert_eq!(
+            tree.root,
+            ResolvedIsmNode::unresolved(addr(0), None, "rpc error".to_string())
+        );
+        assert_eq!(tree.module_type(addr(0)), None);
+    }
+
+    #[tokio::test]
+    async fn test_body_dependent_routing_ism_is_unresolved() {
+        let addr = H256::from_low_u64_be;
+        let base_builder = build_mock_base_builder(TEST_DOMAIN.clone(), TEST_DOMAIN.clone());
+        // Like an amount routing ISM, which routes large transfers elsewhere
+        insert_routing_ism(&base_builder, addr(0), addr(10), addr(11));
+
+        let tree =
+            ResolvedIsmTree::resolve(&base_builder, 1, addr(0xff), addr(0), vec![1], TTL).await;
+
+        assert_eq!(
+            tree.root,
+            ResolvedIsmNode::unresolved(
+                addr(0),
+                Some(ModuleType::Routing),
+                "Route depends on more than the message origin".to_string()
+            )
+        );
+        assert_eq!(tree.route(addr(0), 1), None);
+    }
+
+    #[tokio::test]
+    async fn test_ism_tree_cache() {
+        let addr = H256::from_low_u64_be;
+        let base_builder = build_mock_base_builder(TEST_DOMAIN.clone(), TEST_DOMAIN.clone());
+        // Only one set of responses per origin, so resolving an origin twice would panic
+        insert_origin_1_branch(&base_builder);
+        insert_origin_2_branch(&base_builder);
+        let cache = IsmTreeCache::new(TTL);
+
+        assert!(cache.get(1, addr(0xff)).is_none());
+        let tree = cache
+            .get_or_resolve(&base_builder, 1, addr(0xff), addr(0), &[1])
+            .await;
+        assert_eq!(tree.origins, vec![1]);
+        assert_eq!(tree.route(addr(0), 2), None);
+        let cached = cache
+            .get_or_resolve(&base_builder, 1, addr(0xff), addr(0), &[1])
+            .await;
+        assert!(Arc::ptr_eq(&tree, &cached));
+
+        // Only origin 2 is resolved when it is first needed
+        let extended = cache
+            .get_or_resolve(&base_builder, 1, addr(0xff), addr(0), &[2])
+            .await;
+        assert_eq!(extended.origins, vec![1, 2]);
+        assert_eq!(extended.route(addr(0), 1), Some(addr(10)));
+        assert_eq!(extended.route(addr(0), 2), Some(addr(20)));
+
+        assert!(cache.invalidate(1, addr(0xff)));
+        assert!(!cache.invalidate(1, addr(0xff)));
+        assert!(cache.get(1, addr(0xff)).is_none());
+    }
+}
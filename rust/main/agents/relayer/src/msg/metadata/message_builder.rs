@@ -21,7 +21,9 @@ use super::{
     aggregation::AggregationIsmMetadataBuilder,
     base::{IsmWithMetadataAndType, MessageMetadataBuildParams, MetadataBuildError},
     ccip_read::CcipReadIsmMetadataBuilder,
+    ism_tree::ResolvedIsmTree,
     multisig::{MerkleRootMultisigMetadataBuilder, MessageIdMultisigMetadataBuilder},
+    native_bridge::NativeBridgeIsmMetadataBuilder,
     null_metadata::NullMetadataBuilder,
     routing::RoutingIsmMetadataBuilder,
     Metadata, MetadataBuilder,
@@ -35,6 +37,8 @@ pub struct MessageMetadataBuilder {
     pub root_ism: H256,
     pub max_ism_depth: u32,
     pub max_ism_count: u32,
+    /// The resolved ISM tree of the recipient, consulted before querying ISMs
+    pub ism_tree: Option<Arc<ResolvedIsmTree>>,
 }
 
 /// This is the entry point for recursively building ISM metadata.
@@ -82,9 +86,15 @@ impl MessageMetadataBuilder {
             root_ism: ism_address,
             max_ism_depth: ISM_MAX_DEPTH,
             max_ism_count: ISM_MAX_COUNT,
+            ism_tree: None,
         })
     }
 
+    pub fn with_ism_tree(mut self, ism_tree: Arc<ResolvedIsmTree>) -> Self {
+        self.ism_tree = Some(ism_tree);
+        self
+    }
+
     pub fn base_builder(&self) -> &Arc<dyn BuildsBaseMetadata> {
         &self.base
     }
@@ -99,6 +109,13 @@ impl MessageMetadataBuilder {
         &self,
         ism: &dyn InterchainSecurityModule,
     ) -> Result<ModuleType, MetadataBuildError> {
+        if let Some(module_type) = self
+            .ism_tree
+            .as_ref()
+            .and_then(|tree| tree.module_type(ism.address()))
+        {
+            return Ok(module_type);
+        }
         let ism_domain = ism.domain().name();
         let fn_key = "module_type";
         let call_params = (ism.address(), NoParams);
@@ -201,6 +218,9 @@ pub async fn build_message_metadata(
         ModuleType::Aggregation => Box::new(AggregationIsmMetadataBuilder::new(message_builder)),
         ModuleType::Null => Box::new(NullMetadataBuilder::new()),
         ModuleType::CcipRead => Box::new(CcipReadIsmMetadataBuilder::new(message_builder)),
+        ModuleType::ArbL2ToL1 | ModuleType::OpL2ToL1 => {
+            Box::new(NativeBridgeIsmMetadataBuilder::new(message_builder))
+        }
         _ => return Err(MetadataBuildError::UnsupportedModuleType(module_type)),
     };
     let metadata = metadata_builder.build(ism_address, message, params).await?;
@@ -515,4 +535,72 @@ mod test {
         assert_eq!(*(params.ism_count.lock().await), 5);
         assert!(logs_contain("Max ISM count reached ism_count=5"));
     }
+
+    fn insert_native_bridge_ism(
+        base_builder: &MockBaseMetadataBuilder,
+        ism_address: H256,
+        module_type: ModuleType,
+        verified: bool,
+    ) {
+        // the ISM is built once to fetch its module type and once by the builder
+        for _ in 0..2 {
+            let mock_ism =
+                MockInterchainSecurityModule::new(ism_address, TEST_DOMAIN.clone(), module_type);
+            mock_ism
+                .responses
+                .dry_run_verify
+                .lock()
+                .unwrap()
+                .push_back(Ok(verified.then(U256::zero)));
+            base_builder
+                .responses
+                .push_build_ism_response(ism_address, Ok(Box::new(mock_ism)));
+        }
+    }
+
+    #[tokio::test]
+    async fn native_bridge_ism_metadata() {
+        for module_type in [ModuleType::ArbL2ToL1, ModuleType::OpL2ToL1] {
+            let base_builder = Arc::new(build_mock_base_builder(
+                HyperlaneDomain::Known(KnownHyperlaneDomain::Optimism),
+                HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+            ));
+            let verified_ism = H256::from_low_u64_be(1);
+            let pending_ism = H256::from_low_u64_be(2);
+            insert_native_bridge_ism(&base_builder, verified_ism, module_type, true);
+            insert_native_bridge_ism(&base_builder, pending_ism, module_type, false);
+
+            let message = HyperlaneMessage::default();
+
+            let message_builder =
+                MessageMetadataBuilder::new(base_builder.clone(), verified_ism, &message)
+                    .await
+                    .expect("Failed to build MessageMetadataBuilder");
+            let res = build_message_metadata(
+                message_builder,
+                verified_ism,
+                &message,
+                MessageMetadataBuildParams::default(),
+                None,
+            )
+            .await
+            .expect("Native bridge metadata should build once verified");
+            assert!(res.metadata.to_vec().is_empty());
+
+            let message_builder =
+                MessageMetadataBuilder::new(base_builder.clone(), pending_ism, &message)
+                    .await
+                    .expect("Failed to build MessageMetadataBuilder");
+            let err = build_message_metadata(
+                message_builder,
+                pending_ism,
+                &message,
+                MessageMetadataBuildParams::default(),
+                None,
+            )
+            .await
+            .expect_err("Metadata found before the native bridge verified the message");
+            assert_eq!(err, MetadataBuildError::CouldNotFetch);
+        }
+    }
 }
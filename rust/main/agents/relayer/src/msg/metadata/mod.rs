@@ -2,8 +2,10 @@ mod aggregation;
 mod base;
 mod base_builder;
 mod ccip_read;
+mod ism_tree;
 mod message_builder;
 mod multisig;
+mod native_bridge;
 mod null_metadata;
 mod routing;
 
@@ -13,5 +15,10 @@ pub(crate) use base::{
     MetadataBuildError, MetadataBuilder,
 };
 #[allow(unused_imports)]
-pub(crate) use base_builder::{BaseMetadataBuilder, BuildsBaseMetadata, IsmBuildMetricsParams};
+pub(crate) use base_builder::{
+    BaseMetadataBuilder, BuildsBaseMetadata, CcipReadGatewayMetricsParams, IsmBuildMetricsParams,
+};
+pub(crate) use ccip_read::DEFAULT_TIMEOUT as CCIP_READ_DEFAULT_TIMEOUT;
+pub(crate) use ism_tree::{IsmTreeCache, ResolvedIsmTree};
 pub(crate) use message_builder::MessageMetadataBuilder;
+pub(crate) use multisig::QuorumCheckpointCache;
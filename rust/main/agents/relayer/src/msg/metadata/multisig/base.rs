@@ -111,8 +111,7 @@ pub trait MultisigIsmMetadataBuilder: AsRef<MessageMetadataBuilder> + Send + Syn
         let ism_domain = multisig_ism.domain().name();
         let fn_key = "validators_and_threshold";
 
-        // Depending on the cache policy, make use of the message ID
-        let params_cache_key = match self
+        let cache_policy = self
             .as_ref()
             .base_builder()
             .ism_cache_policy_classifier()
@@ -122,8 +121,22 @@ pub trait MultisigIsmMetadataBuilder: AsRef<MessageMetadataBuilder> + Send + Syn
                 self.module_type(),
                 self.as_ref().app_context.as_ref(),
             )
-            .await
-        {
+            .await;
+
+        // The resolved ISM tree ignores the message, like the ISM specific policy
+        if cache_policy == IsmCachePolicy::IsmSpecific {
+            if let Some(result) = self
+                .as_ref()
+                .ism_tree
+                .as_ref()
+                .and_then(|tree| tree.validators_and_threshold(multisig_ism.address()))
+            {
+                return Ok(result);
+            }
+        }
+
+        // Depending on the cache policy, make use of the message ID
+        let params_cache_key = match cache_policy {
             // To have the cache key be more succinct, we use the message id
             IsmCachePolicy::MessageSpecific => (multisig_ism.address(), message.id()),
             IsmCachePolicy::IsmSpecific => (multisig_ism.address(), H256::zero()),
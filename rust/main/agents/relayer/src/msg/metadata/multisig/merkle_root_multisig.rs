@@ -55,6 +55,31 @@ impl MultisigIsmMetadataBuilder for MerkleRootMultisigMetadataBuilder {
                 "No merkle leaf found for message id, must have not been enqueued in the tree"
             )
         );
+        let quorum_checkpoints = self.base_builder().quorum_checkpoints();
+        if let Some(quorum_checkpoint) = quorum_checkpoints
+            .get(validators, threshold, leaf_index)
+            .filter(|quorum_checkpoint| quorum_checkpoint.checkpoint.index <= highest_leaf_index)
+        {
+            let proof = self
+                .base_builder()
+                .get_proof(leaf_index, quorum_checkpoint.checkpoint.checkpoint)
+                .await
+                .context(CTX)?;
+            if proof.root() == quorum_checkpoint.checkpoint.root {
+                debug!(
+                    leaf_index,
+                    checkpoint_index = quorum_checkpoint.checkpoint.index,
+                    "Reusing quorum checkpoint"
+                );
+                return Ok(Some(MultisigMetadata::new(
+                    quorum_checkpoint,
+                    leaf_index,
+                    Some(proof),
+                )));
+            }
+            // The checkpoint no longer matches the canonical tree, e.g. after a reorg
+            quorum_checkpoints.remove(validators, threshold);
+        }
         let quorum_checkpoint = unwrap_or_none_result!(
             checkpoint_syncer
                 .fetch_checkpoint_in_range(
@@ -77,6 +102,9 @@ impl MultisigIsmMetadataBuilder for MerkleRootMultisigMetadataBuilder {
             .get_proof(leaf_index, quorum_checkpoint.checkpoint.checkpoint)
             .await
             .context(CTX)?;
+        if proof.root() == quorum_checkpoint.checkpoint.root {
+            quorum_checkpoints.insert(validators, threshold, quorum_checkpoint.clone());
+        }
         Ok(Some(MultisigMetadata::new(
             quorum_checkpoint,
             leaf_index,
@@ -197,4 +225,97 @@ mod tests {
         assert_eq!(resp.checkpoint, expected.checkpoint);
         assert_eq!(resp.signatures, expected.signatures);
     }
+
+    #[tokio::test]
+    async fn test_fetch_metadata_reuses_quorum_checkpoint() {
+        let proof = Proof {
+            leaf: H256::zero(),
+            index: 100,
+            path: [H256::zero(); TREE_DEPTH],
+        };
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                mailbox_domain: 100,
+                merkle_tree_hook_address: H256::zero(),
+                root: proof.root(),
+                index: 1000,
+            },
+            message_id: H256::zero(),
+        };
+
+        let mut validators: Vec<_> = dummy_validators().drain(..).take(3).collect();
+        for validator in validators.iter_mut() {
+            validator.latest_index = Some(1000);
+            validator.fetch_checkpoint = Some(checkpoint);
+        }
+        let syncers: HashMap<_, _> = build_mock_checkpoint_syncs(&validators)
+            .await
+            .into_iter()
+            .map(|(k, v)| (k, Arc::new(v) as Arc<dyn CheckpointSyncer>))
+            .collect();
+        let validator_addresses = validators
+            .iter()
+            .map(|validator| validator.public_key.parse::<H160>().unwrap().into())
+            .collect::<Vec<_>>();
+        let signed_checkpoint = generate_multisig_signed_checkpoint(&validators, checkpoint).await;
+
+        let base_builder = build_mock_base_builder(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Optimism),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        );
+        for leaf_index in [100, 101] {
+            base_builder
+                .responses
+                .highest_known_leaf_index
+                .lock()
+                .unwrap()
+                .push_back(Some(1000));
+            base_builder
+                .responses
+                .get_merkle_leaf_id_by_message_id
+                .lock()
+                .unwrap()
+                .push_back(Ok(Some(leaf_index)));
+            base_builder
+                .responses
+                .get_proof
+                .lock()
+                .unwrap()
+                .push_back(Ok(proof.clone()));
+        }
+
+        let message = HyperlaneMessage::default();
+        let message_builder =
+            MessageMetadataBuilder::new(Arc::new(base_builder), H256::zero(), &message)
+                .await
+                .expect("Failed to build MessageMetadataBuilder");
+        let builder = MerkleRootMultisigMetadataBuilder::new(message_builder);
+
+        let threshold = 2;
+        let first = builder
+            .fetch_metadata(
+                &validator_addresses,
+                threshold,
+                &message,
+                &MultisigCheckpointSyncer::new(syncers, None),
+            )
+            .await
+            .expect("Failed to fetch metadata")
+            .expect("Expected MultisigMetadata");
+        assert_eq!(first.checkpoint, signed_checkpoint.checkpoint);
+
+        // Without any checkpoint syncers, the metadata can only come from the cached checkpoint
+        let second = builder
+            .fetch_metadata(
+                &validator_addresses,
+                threshold,
+                &message,
+                &MultisigCheckpointSyncer::new(HashMap::new(), None),
+            )
+            .await
+            .expect("Failed to fetch metadata")
+            .expect("Expected MultisigMetadata");
+        assert_eq!(second.checkpoint, first.checkpoint);
+        assert_eq!(second.signatures, first.signatures);
+    }
 }
@@ -0,0 +1,58 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use hyperlane_core::{MultisigSignedCheckpoint, H256};
+
+/// The latest quorum checkpoint fetched for each merkle root multisig
+/// validator set and threshold.
+///
+/// A signed checkpoint at index `i` proves every message with a leaf index
+/// up to `i`, so during a backlog the signatures fetched for one message are
+/// reused for the following ones instead of being fetched again.
+#[derive(Debug, Default)]
+pub struct QuorumCheckpointCache {
+    checkpoints: Mutex<HashMap<(Vec<H256>, u8), MultisigSignedCheckpoint>>,
+}
+
+impl QuorumCheckpointCache {
+    /// The cached checkpoint of the validator set, if it covers `leaf_index`
+    pub fn get(
+        &self,
+        validators: &[H256],
+        threshold: u8,
+        leaf_index: u32,
+    ) -> Option<MultisigSignedCheckpoint> {
+        self.checkpoints
+            .lock()
+            .expect("Quorum checkpoint cache lock poisoned")
+            .get(&(validators.to_vec(), threshold))
+            .filter(|quorum_checkpoint| quorum_checkpoint.checkpoint.index >= leaf_index)
+            .cloned()
+    }
+
+    /// Cache `quorum_checkpoint`, unless a higher checkpoint is already cached
+    pub fn insert(
+        &self,
+        validators: &[H256],
+        threshold: u8,
+        quorum_checkpoint: MultisigSignedCheckpoint,
+    ) {
+        let mut checkpoints = self
+            .checkpoints
+            .lock()
+            .expect("Quorum checkpoint cache lock poisoned");
+        let key = (validators.to_vec(), threshold);
+        match checkpoints.get(&key) {
+            Some(cached) if cached.checkpoint.index > quorum_checkpoint.checkpoint.index => {}
+            _ => {
+                checkpoints.insert(key, quorum_checkpoint);
+            }
+        }
+    }
+
+    pub fn remove(&self, validators: &[H256], threshold: u8) {
+        self.checkpoints
+            .lock()
+            .expect("Quorum checkpoint cache lock poisoned")
+            .remove(&(validators.to_vec(), threshold));
+    }
+}
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use derive_more::Deref;
+use derive_new::new;
+
+use hyperlane_core::{HyperlaneMessage, H256};
+use tracing::{debug, instrument};
+
+use super::{
+    base::MessageMetadataBuildParams, MessageMetadataBuilder, Metadata, MetadataBuildError,
+    MetadataBuilder,
+};
+
+/// Builds metadata for ISMs that are verified by a native bridge
+/// (`ARB_L2_TO_L1` and `OP_L2_TO_L1`).
+///
+/// These ISMs accept a message once the native bridge has delivered the
+/// message id to them, after which `verify` succeeds with empty metadata.
+/// Until then the message is retried, rather than treated as unsupported.
+/// Executing the bridge withdrawal ourselves is not supported, so messages
+/// rely on the bridge (or a third party) to relay the message id first.
+#[derive(Clone, Debug, new, Deref)]
+pub struct NativeBridgeIsmMetadataBuilder {
+    base: MessageMetadataBuilder,
+}
+
+#[async_trait]
+impl MetadataBuilder for NativeBridgeIsmMetadataBuilder {
+    #[allow(clippy::blocks_in_conditions)] // TODO: `rustc` 1.80.1 clippy issue
+    #[instrument(err, skip(self, message, _params))]
+    async fn build(
+        &self,
+        ism_address: H256,
+        message: &HyperlaneMessage,
+        _params: MessageMetadataBuildParams,
+    ) -> Result<Metadata, MetadataBuildError> {
+        let ism = self
+            .base_builder()
+            .build_ism(ism_address)
+            .await
+            .map_err(|err| MetadataBuildError::FailedToBuild(err.to_string()))?;
+
+        match ism.dry_run_verify(message, &[]).await {
+            Ok(Some(_)) => Ok(Metadata::new(vec![])),
+            Ok(None) => {
+                debug!(
+                    ?ism_address,
+                    "Message not yet verified by the native bridge, will retry"
+                );
+                Err(MetadataBuildError::CouldNotFetch)
+            }
+            Err(err) => Err(MetadataBuildError::FailedToBuild(err.to_string())),
+        }
+    }
+}
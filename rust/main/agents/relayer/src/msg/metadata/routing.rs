@@ -47,6 +47,17 @@ impl MetadataBuilder for RoutingIsmMetadataBuilder {
             )
             .await;
 
+        // Like the ISM specific cache, the resolved ISM tree routes by origin only
+        if cache_policy == IsmCachePolicy::IsmSpecific {
+            if let Some(module) = self
+                .ism_tree
+                .as_ref()
+                .and_then(|tree| tree.route(ism_address, message.origin))
+            {
+                return self.base.build(module, message, params).await;
+            }
+        }
+
         let cache_result: Option<H256> = match cache_policy {
             // if cache is ISM specific, we use the message origin for caching
             IsmCachePolicy::IsmSpecific => {
@@ -19,10 +19,14 @@
 pub(crate) mod blacklist;
 pub(crate) mod db_loader;
 pub(crate) mod gas_payment;
+pub(crate) mod message_filter;
 pub(crate) mod message_processor;
 pub(crate) mod metadata;
 pub(crate) mod op_batch;
+pub(crate) mod op_heap;
 pub(crate) mod op_queue;
+pub(crate) mod rate_limiter;
+pub(crate) mod webhooks;
 mod utils;
 
 pub mod pending_message;
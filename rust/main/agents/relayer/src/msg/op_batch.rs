@@ -398,6 +398,8 @@ mod tests {
             IsmCachePolicyClassifier::new(default_ism_getter, Default::default()),
             None,
             false,
+            Default::default(),
+            Default::default(),
         );
         let message_context = Arc::new(MessageContext {
             destination_mailbox: arb_mailbox,
@@ -414,6 +416,9 @@ mod tests {
             transaction_gas_limit: Default::default(),
             metrics: dummy_submission_metrics(),
             application_operation_verifier: Some(Arc::new(DummyApplicationOperationVerifier {})),
+            shadow_mode: false,
+            webhook_notifier: None,
+            ism_trees: None,
         });
 
         let attempts = 2;
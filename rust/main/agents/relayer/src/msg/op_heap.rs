@@ -0,0 +1,277 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    time::Instant,
+};
+
+use hyperlane_core::QueueOperation;
+
+/// Weight of the app contexts that are not configured explicitly, and of the
+/// operations that have no app context.
+pub const DEFAULT_APP_CONTEXT_WEIGHT: u32 = 1;
+
+/// The operations held by an `OpQueue`.
+///
+/// By default this is a single priority heap, ordered by the `Ord` impl of
+/// `PendingOperation`. With weighted fair queuing enabled, operations are
+/// partitioned by `app_context()` instead, each partition being a priority heap
+/// of its own. Partitions are then served with smooth weighted round robin, so
+/// that a backlog in one app context only delays the others in proportion to
+/// its weight.
+///
+/// Operations can also be bumped ahead of all others, in which case they are
+/// popped in the order they were bumped, before any other operation.
+#[derive(Debug, Default)]
+pub struct OperationHeap {
+    /// Weight of each app context, if weighted fair queuing is enabled
+    app_context_weights: Option<HashMap<String, u32>>,
+    partitions: HashMap<Option<String>, Partition>,
+    bumped: VecDeque<QueueOperation>,
+    len: usize,
+}
+
+#[derive(Debug, Default)]
+struct Partition {
+    heap: BinaryHeap<Reverse<QueueOperation>>,
+    weight: u32,
+    /// Credit accumulated by the weighted round robin
+    current_weight: i64,
+}
+
+impl Partition {
+    fn head_is_ready(&self) -> bool {
+        self.heap.peek().is_some_and(|Reverse(op)| {
+            op.next_attempt_after()
+                .map_or(true, |after| after <= Instant::now())
+        })
+    }
+}
+
+impl OperationHeap {
+    /// Partition operations by app context and serve the partitions according
+    /// to `weights`. App contexts missing from `weights` get
+    /// `DEFAULT_APP_CONTEXT_WEIGHT`.
+    pub fn with_app_context_weights(weights: HashMap<String, u32>) -> Self {
+        Self {
+            app_context_weights: Some(weights),
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, op: QueueOperation) {
+        let key = self.partition_key(&op);
+        let weight = self.weight(&key);
+        self.partitions
+            .entry(key)
+            .or_insert_with(|| Partition {
+                weight,
+                ..Default::default()
+            })
+            .heap
+            .push(Reverse(op));
+        self.len += 1;
+    }
+
+    /// Push an operation that is popped before all the operations that were
+    /// not bumped
+    pub fn push_bumped(&mut self, op: QueueOperation) {
+        self.bumped.push_back(op);
+        self.len += 1;
+    }
+
+    /// Pop the next operation to process. Without weighted fair queuing this
+    /// is the highest priority operation. Otherwise the partitions whose highest
+    /// priority operation is ready to be attempted take turns according to their
+    /// weights. If none of them is ready, the highest priority operation
+    /// across all partitions is popped.
+    pub fn pop(&mut self) -> Option<QueueOperation> {
+        if let Some(op) = self.bumped.pop_front() {
+            self.len -= 1;
+            return Some(op);
+        }
+        let key = if self.app_context_weights.is_some() {
+            self.next_weighted_partition()
+                .or_else(|| self.highest_priority_partition())?
+        } else {
+            self.partitions.keys().next()?.clone()
+        };
+        let partition = self.partitions.get_mut(&key)?;
+        let Reverse(op) = partition.heap.pop()?;
+        if partition.heap.is_empty() {
+            self.partitions.remove(&key);
+        }
+        self.len -= 1;
+        Some(op)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Iterate over all operations, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &QueueOperation> {
+        self.bumped.iter().chain(
+            self.partitions
+                .values()
+                .flat_map(|partition| partition.heap.iter().map(|Reverse(op)| op)),
+        )
+    }
+
+    /// Remove the bumped operations, in the order they would be popped
+    pub fn drain_bumped(&mut self) -> Vec<QueueOperation> {
+        self.len -= self.bumped.len();
+        self.bumped.drain(..).collect()
+    }
+
+    /// Remove all operations, in no particular order
+    pub fn drain(&mut self) -> Vec<QueueOperation> {
+        self.len = 0;
+        self.bumped
+            .drain(..)
+            .chain(
+                self.partitions
+                    .drain()
+                    .flat_map(|(_, partition)| partition.heap.into_iter().map(|Reverse(op)| op)),
+            )
+            .collect()
+    }
+
+    fn partition_key(&self, op: &QueueOperation) -> Option<String> {
+        if self.app_context_weights.is_some() {
+            op.app_context()
+        } else {
+            None
+        }
+    }
+
+    fn weight(&self, key: &Option<String>) -> u32 {
+        self.app_context_weights
+            .as_ref()
+            .zip(key.as_ref())
+            .and_then(|(weights, app_context)| weights.get(app_context).copied())
+            .unwrap_or(DEFAULT_APP_CONTEXT_WEIGHT)
+    }
+
+    /// Pick among the partitions with a ready operation using smooth weighted
+    /// round robin
+    fn next_weighted_partition(&mut self) -> Option<Option<String>> {
+        let mut ready: Vec<_> = self
+            .partitions
+            .iter_mut()
+            .filter(|(_, partition)| partition.head_is_ready())
+            .collect();
+        let total_weight: i64 = ready
+            .iter()
+            .map(|(_, partition)| partition.weight as i64)
+            .sum();
+        for (_, partition) in ready.iter_mut() {
+            partition.current_weight += partition.weight as i64;
+        }
+        // Ties are broken by the priority of the partitions' next operation, so
+        // that the order does not depend on the iteration order of the map
+        let (key, partition) = ready.into_iter().max_by(|(_, a), (_, b)| {
+            a.current_weight
+                .cmp(&b.current_weight)
+                .then_with(|| a.heap.peek().cmp(&b.heap.peek()))
+        })?;
+        partition.current_weight -= total_weight;
+        Some(key.clone())
+    }
+
+    fn highest_priority_partition(&self) -> Option<Option<String>> {
+        self.partitions
+            .iter()
+            .filter_map(|(key, partition)| Some((key, partition.heap.peek()?)))
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(key, _)| key.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::msg::op_queue::test::MockPendingOperation;
+
+    fn op(seconds_to_next_attempt: u64, app_context: &str) -> QueueOperation {
+        let destination = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        Box::new(
+            MockPendingOperation::new(seconds_to_next_attempt, destination)
+                .with_app_context(app_context),
+        )
+    }
+
+    fn pop_app_contexts(heap: &mut OperationHeap, count: usize) -> Vec<String> {
+        (0..count)
+            .filter_map(|_| heap.pop())
+            .map(|op| op.app_context().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_partitions_take_turns() {
+        let weights = HashMap::from([("governance".to_owned(), 2)]);
+        let mut heap = OperationHeap::with_app_context_weights(weights);
+        for _ in 0..10 {
+            heap.push(op(0, "warp_route"));
+        }
+        for _ in 0..3 {
+            heap.push(op(0, "governance"));
+        }
+        assert_eq!(heap.len(), 13);
+
+        let popped = pop_app_contexts(&mut heap, 6);
+        let governance = popped.iter().filter(|c| *c == "governance").count();
+        assert_eq!(governance, 3);
+        assert!(popped[..3].contains(&"governance".to_owned()));
+
+        // once governance is exhausted, the backlog is served on its own
+        let popped = pop_app_contexts(&mut heap, 10);
+        assert_eq!(popped.len(), 7);
+        assert!(popped.iter().all(|c| c == "warp_route"));
+        assert_eq!(heap.len(), 0);
+    }
+
+    #[test]
+    fn test_partitions_without_ready_ops_are_skipped() {
+        let mut heap = OperationHeap::with_app_context_weights(HashMap::new());
+        heap.push(op(100, "governance"));
+        heap.push(op(0, "warp_route"));
+        heap.push(op(0, "warp_route"));
+
+        assert_eq!(
+            pop_app_contexts(&mut heap, 3),
+            vec!["warp_route", "warp_route", "governance"]
+        );
+    }
+
+    #[test]
+    fn test_without_weights_ops_are_popped_in_priority_order() {
+        let mut heap = OperationHeap::default();
+        heap.push(op(3, "warp_route"));
+        heap.push(op(1, "governance"));
+        heap.push(op(2, "warp_route"));
+
+        assert_eq!(
+            pop_app_contexts(&mut heap, 3),
+            vec!["governance", "warp_route", "warp_route"]
+        );
+        assert!(heap.pop().is_none());
+    }
+
+    #[test]
+    fn test_bumped_ops_are_popped_first_in_order() {
+        let mut heap = OperationHeap::with_app_context_weights(HashMap::new());
+        heap.push(op(0, "governance"));
+        heap.push_bumped(op(10, "warp_route"));
+        heap.push_bumped(op(5, "bumped_second"));
+        assert_eq!(heap.len(), 3);
+
+        assert_eq!(
+            pop_app_contexts(&mut heap, 3),
+            vec!["warp_route", "bumped_second", "governance"]
+        );
+        assert_eq!(heap.len(), 0);
+    }
+}
@@ -1,14 +1,23 @@
-use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 use derive_new::new;
 use hyperlane_core::{PendingOperation, PendingOperationStatus, QueueOperation};
 use prometheus::{IntGauge, IntGaugeVec};
 use tokio::sync::{broadcast::Receiver, Mutex};
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, trace};
 
-use crate::server::operations::message_retry::{MessageRetryQueueResponse, MessageRetryRequest};
+use crate::{
+    msg::{message_filter::SharedMessageFilter, op_heap::OperationHeap},
+    server::operations::message_retry::{
+        MessageRetryAction, MessageRetryQueueResponse, MessageRetryRequest,
+    },
+};
 
-pub type OperationPriorityQueue = Arc<Mutex<BinaryHeap<Reverse<QueueOperation>>>>;
+pub type OperationPriorityQueue = Arc<Mutex<OperationHeap>>;
+
+/// Retry count persisted for operations dropped through a retry request. It is
+/// above any max retries, so that they are skipped when loading messages again.
+pub const DROPPED_OPERATION_RETRIES: u32 = u32::MAX;
 
 /// Queue of generic operations that can be submitted to a destination chain.
 /// Includes logic for maintaining queue metrics by the destination and `app_context` of an operation
@@ -19,9 +28,26 @@ pub struct OpQueue {
     retry_receiver: Arc<Mutex<Receiver<MessageRetryRequest>>>,
     #[new(default)]
     pub queue: OperationPriorityQueue,
+    /// If set, operations that are not allowed by the message filter stay in the
+    /// queue instead of being popped
+    #[new(default)]
+    message_filter: Option<SharedMessageFilter>,
 }
 
 impl OpQueue {
+    /// Keep operations that are not allowed by `message_filter` in the queue when popping
+    pub fn with_message_filter(mut self, message_filter: SharedMessageFilter) -> Self {
+        self.message_filter = Some(message_filter);
+        self
+    }
+
+    /// Serve operations with weighted fair queuing across app contexts, using
+    /// the given weights. Must be called before the queue is shared.
+    pub fn with_app_context_weights(mut self, weights: HashMap<String, u32>) -> Self {
+        self.queue = Arc::new(Mutex::new(OperationHeap::with_app_context_weights(weights)));
+        self
+    }
+
     /// Push an element onto the queue and update metrics
     /// Arguments:
     /// - `op`: the operation to push onto the queue
@@ -32,7 +58,7 @@ impl OpQueue {
         let new_metric = Arc::new(self.get_new_operation_metric(op.as_ref(), new_status.clone()));
         op.set_status_and_update_metrics(new_status, new_metric);
 
-        self.queue.lock().await.push(Reverse(op));
+        self.queue.lock().await.push(op);
     }
 
     /// Pop an element from the queue and update metrics
@@ -46,14 +72,35 @@ impl OpQueue {
     #[instrument(skip(self), fields(queue_label=%self.queue_metrics_label), level = "debug")]
     pub async fn pop_many(&mut self, limit: usize) -> Vec<QueueOperation> {
         self.process_retry_requests().await;
+        let message_filter = match &self.message_filter {
+            Some(message_filter) => Some(message_filter.read().await),
+            None => None,
+        };
         let mut queue = self.queue.lock().await;
         let mut popped = vec![];
-        while let Some(Reverse(op)) = queue.pop() {
+        // Operations the message filter disallows stay in the queue, so that they
+        // are picked up again once the filter allows them
+        let mut parked = vec![];
+        while let Some(op) = queue.pop() {
+            if let Some(message_filter) = &message_filter {
+                if !message_filter.op_allowed(&op) {
+                    trace!(
+                        queue_label = %self.queue_metrics_label,
+                        ?op,
+                        "Skipping operation not allowed by the message filter"
+                    );
+                    parked.push(op);
+                    continue;
+                }
+            }
             popped.push(op);
             if popped.len() >= limit {
                 break;
             }
         }
+        for op in parked {
+            queue.push(op);
+        }
 
         // This function is called very often by the message processor tasks, so only log when there are operations to pop
         // to avoid spamming the logs
@@ -123,35 +170,52 @@ impl OpQueue {
     }
 
     fn reprioritize_matching(
-        queue: &mut BinaryHeap<Reverse<Box<dyn PendingOperation>>>,
+        queue: &mut OperationHeap,
         retry_requests: &[MessageRetryRequest],
     ) -> Vec<MessageRetryQueueResponse> {
         let mut retry_responses: Vec<_> = (0..retry_requests.len())
             .map(|_| MessageRetryQueueResponse::default())
             .collect();
-        let mut reprioritized_queue: BinaryHeap<_> = queue
-            .drain()
-            .map(|Reverse(mut op)| {
-                let mut matched = false;
-                retry_responses
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(i, retry_response)| {
-                        let retry_req = &retry_requests[i];
-                        if !retry_req.pattern.op_matches(&op) {
-                            return;
-                        }
-                        // update retry metrics
-                        retry_response.matched += 1;
-                        matched = true;
-                    });
-                if matched {
+        // bumped operations stay ahead of the others unless dropped
+        let bumped = queue.drain_bumped().into_iter().map(|op| (op, true));
+        let others = queue.drain().into_iter().map(|op| (op, false));
+        for (mut op, was_bumped) in bumped.chain(others).collect::<Vec<_>>() {
+            let mut action = None;
+            for (retry_req, retry_response) in retry_requests.iter().zip(retry_responses.iter_mut())
+            {
+                if !retry_req.op_matches(&op) {
+                    continue;
+                }
+                // update retry metrics
+                retry_response.matched += 1;
+                if !retry_req.options.try_take() {
+                    continue;
+                }
+                retry_response.message_ids.push(op.id());
+                action = action.max(Some(retry_req.options.action));
+            }
+            match action {
+                None if was_bumped => queue.push_bumped(op),
+                None => queue.push(op),
+                Some(MessageRetryAction::Retry) => {
                     op.reset_attempts();
+                    if was_bumped {
+                        queue.push_bumped(op);
+                    } else {
+                        queue.push(op);
+                    }
                 }
-                Reverse(op)
-            })
-            .collect();
-        queue.append(&mut reprioritized_queue);
+                Some(MessageRetryAction::BumpToFront) => {
+                    op.reset_attempts();
+                    queue.push_bumped(op);
+                }
+                Some(MessageRetryAction::Drop) => {
+                    debug!(?op, "Dropping operation on request");
+                    op.drop_on_request();
+                    op.decrement_metric_if_exists();
+                }
+            }
+        }
         retry_responses
     }
 
@@ -166,6 +230,7 @@ pub mod test {
     use std::{
         collections::VecDeque,
         str::FromStr,
+        sync::atomic::AtomicUsize,
         time::{Duration, Instant},
     };
 
@@ -179,7 +244,8 @@ pub mod test {
     };
 
     use crate::{
-        server::ENDPOINT_MESSAGES_QUEUE_SIZE,
+        msg::message_filter::{MatchingListKind, MessageFilter},
+        server::{operations::message_retry::MessageRetryOptions, ENDPOINT_MESSAGES_QUEUE_SIZE},
         settings::matching_list::{Filter, ListElement, MatchingList},
     };
 
@@ -196,6 +262,8 @@ pub mod test {
         destination_domain: HyperlaneDomain,
         retry_count: u32,
         #[serde(skip)]
+        app_context: Option<String>,
+        #[serde(skip)]
         pub mailbox: Option<Arc<dyn Mailbox>>,
     }
 
@@ -210,6 +278,7 @@ pub mod test {
                 recipient_address: H256::random(),
                 origin_domain_id: 0,
                 retry_count: 0,
+                app_context: None,
                 mailbox: None,
             }
         }
@@ -230,6 +299,7 @@ pub mod test {
                     domain_protocol: HyperlaneDomainProtocol::Ethereum,
                     domain_technical_stack: HyperlaneDomainTechnicalStack::Other,
                 },
+                app_context: None,
                 mailbox: None,
             }
         }
@@ -266,6 +336,13 @@ pub mod test {
             }
         }
 
+        pub fn with_app_context(self, app_context: &str) -> Self {
+            Self {
+                app_context: Some(app_context.to_owned()),
+                ..self
+            }
+        }
+
         pub fn with_retry_count(mut self, retry_count: u32) -> Self {
             self.set_retries(retry_count);
             self
@@ -330,7 +407,7 @@ pub mod test {
         }
 
         fn app_context(&self) -> Option<String> {
-            todo!()
+            self.app_context.clone()
         }
 
         async fn prepare(&mut self) -> PendingOperationResult {
@@ -385,6 +462,10 @@ pub mod test {
             self.retry_count
         }
 
+        fn drop_on_request(&mut self) {
+            self.set_retries(DROPPED_OPERATION_RETRIES);
+        }
+
         async fn payload(&self) -> ChainResult<Vec<u8>> {
             todo!()
         }
@@ -483,6 +564,7 @@ pub mod test {
             .send(MessageRetryRequest {
                 uuid: "59400966-e7fa-4fb9-9372-9a671d4392c3".to_string(),
                 pattern: MatchingList::with_message_id(op_ids[1]),
+                options: Default::default(),
                 transmitter: transmitter.clone(),
             })
             .unwrap();
@@ -490,6 +572,7 @@ pub mod test {
             .send(MessageRetryRequest {
                 uuid: "59400966-e7fa-4fb9-9372-9a671d4392c3".to_string(),
                 pattern: MatchingList::with_message_id(op_ids[2]),
+                options: Default::default(),
                 transmitter,
             })
             .unwrap();
@@ -549,6 +632,7 @@ pub mod test {
             .send(MessageRetryRequest {
                 uuid: "a5b39473-7cc5-48a1-8bed-565454ba1037".to_string(),
                 pattern: MatchingList::with_destination_domain(destination_domain_2.id()),
+                options: Default::default(),
                 transmitter,
             })
             .unwrap();
@@ -574,6 +658,63 @@ pub mod test {
         assert_eq!(retry_response.matched, 3);
     }
 
+    #[tokio::test]
+    async fn test_message_filter_parks_disallowed_ops() {
+        let broadcaster = sync::broadcast::Sender::new(100);
+        let message_filter = Arc::new(sync::RwLock::new(MessageFilter::default()));
+        let mut op_queue =
+            initialize_queue(&broadcaster).with_message_filter(message_filter.clone());
+
+        let destination_domain_1: HyperlaneDomain = KnownHyperlaneDomain::Injective.into();
+        let destination_domain_2: HyperlaneDomain = KnownHyperlaneDomain::Ethereum.into();
+        let ops = vec![
+            Box::new(MockPendingOperation::new(1, destination_domain_1.clone())) as QueueOperation,
+            Box::new(MockPendingOperation::new(2, destination_domain_2.clone())) as QueueOperation,
+            Box::new(MockPendingOperation::new(3, destination_domain_1.clone())) as QueueOperation,
+        ];
+        let op_ids: Vec<_> = ops.iter().map(|op| op.id()).collect();
+        for op in ops {
+            op_queue
+                .push(op, Some(PendingOperationStatus::FirstPrepareAttempt))
+                .await;
+        }
+
+        // Blacklist one of the destinations after the operations were queued
+        let blacklist = MatchingList::with_destination_domain(destination_domain_1.id());
+        message_filter
+            .write()
+            .await
+            .add_entries(MatchingListKind::Blacklist, blacklist.0.unwrap())
+            .unwrap();
+
+        let popped: Vec<_> = op_queue
+            .pop_many(3)
+            .await
+            .into_iter()
+            .map(|op| op.id())
+            .collect();
+        assert_eq!(popped, vec![op_ids[1]]);
+        assert_eq!(op_queue.len().await, 2);
+
+        // The parked operations are popped once the blacklist entry is removed
+        message_filter
+            .write()
+            .await
+            .remove_entry(MatchingListKind::Blacklist, 0)
+            .unwrap();
+        let mut popped: Vec<_> = op_queue
+            .pop_many(3)
+            .await
+            .into_iter()
+            .map(|op| op.id())
+            .collect();
+        popped.sort();
+        let mut expected = vec![op_ids[0], op_ids[2]];
+        expected.sort();
+        assert_eq!(popped, expected);
+        assert_eq!(op_queue.len().await, 0);
+    }
+
     #[tracing_test::traced_test]
     #[tokio::test]
     async fn test_process_retry_requests_by_id() {
@@ -600,6 +741,7 @@ pub mod test {
             .send(MessageRetryRequest {
                 uuid: "0e92ace7-ba5d-4a1f-8501-51b6d9d500cf".to_string(),
                 pattern: MatchingList::with_message_id(op_ids[1]),
+                options: Default::default(),
                 transmitter: transmitter.clone(),
             })
             .unwrap();
@@ -626,6 +768,7 @@ pub mod test {
                 pattern: MatchingList::with_destination_domain(
                     KnownHyperlaneDomain::Arbitrum as u32,
                 ),
+                options: Default::default(),
                 transmitter: transmitter.clone(),
             })
             .unwrap();
@@ -669,6 +812,7 @@ pub mod test {
                     Filter::Wildcard,
                     None,
                 )])),
+                options: Default::default(),
                 transmitter: transmitter.clone(),
             })
             .unwrap();
@@ -726,6 +870,7 @@ pub mod test {
                     Filter::Wildcard,
                     None,
                 )])),
+                options: Default::default(),
                 transmitter: transmitter.clone(),
             })
             .unwrap();
@@ -793,6 +938,7 @@ pub mod test {
                         None,
                     ),
                 ])),
+                options: Default::default(),
                 transmitter: transmitter.clone(),
             })
             .unwrap();
@@ -859,6 +1005,7 @@ pub mod test {
                     None,
                 ),
             ])),
+            options: Default::default(),
             transmitter: transmitter.clone(),
         };
 
@@ -881,4 +1028,129 @@ pub mod test {
         assert_eq!(retry_response.evaluated, 7);
         assert_eq!(retry_response.matched, 2);
     }
+
+    #[tokio::test]
+    async fn test_retry_request_actions() {
+        let broadcaster = sync::broadcast::Sender::new(100);
+        let mut op_queue = initialize_queue(&broadcaster);
+
+        let destination_domain: HyperlaneDomain = KnownHyperlaneDomain::Injective.into();
+        let ops = generate_test_messages(destination_domain, 4);
+        let op_ids: Vec<_> = ops.iter().map(|op| op.id()).collect();
+        for op in ops {
+            op_queue
+                .push(op, Some(PendingOperationStatus::FirstPrepareAttempt))
+                .await;
+        }
+
+        let (transmitter, mut receiver) = mpsc::channel(ENDPOINT_MESSAGES_QUEUE_SIZE);
+        let request = |id, action| MessageRetryRequest {
+            uuid: "c2a4f2c5-6bb5-4d52-8f0b-2a3f7a1f0f4e".to_string(),
+            pattern: MatchingList::with_message_id(id),
+            options: MessageRetryOptions {
+                action,
+                ..Default::default()
+            },
+            transmitter: transmitter.clone(),
+        };
+        broadcaster
+            .send(request(op_ids[3], MessageRetryAction::BumpToFront))
+            .unwrap();
+        broadcaster
+            .send(request(op_ids[1], MessageRetryAction::Drop))
+            .unwrap();
+        // the most drastic action wins
+        broadcaster
+            .send(request(op_ids[1], MessageRetryAction::Retry))
+            .unwrap();
+
+        let popped: Vec<_> = op_queue
+            .pop_many(4)
+            .await
+            .into_iter()
+            .map(|op| op.id())
+            .collect();
+        assert_eq!(popped, vec![op_ids[3], op_ids[0], op_ids[2]]);
+
+        let message_ids: Vec<_> = (0..3)
+            .map(|_| receiver.try_recv().unwrap().message_ids)
+            .collect();
+        assert_eq!(
+            message_ids,
+            vec![vec![op_ids[3]], vec![op_ids[1]], vec![op_ids[1]]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_request_status_and_limit() {
+        let broadcaster = sync::broadcast::Sender::new(100);
+        let mut op_queue_1 = initialize_queue(&broadcaster);
+        let mut op_queue_2 = initialize_queue(&broadcaster);
+
+        let destination_domain: HyperlaneDomain = KnownHyperlaneDomain::Injective.into();
+        let mut ops = generate_test_messages(destination_domain, 4);
+        for _ in 0..2 {
+            op_queue_1
+                .push(
+                    ops.pop_front().unwrap(),
+                    Some(PendingOperationStatus::FirstPrepareAttempt),
+                )
+                .await;
+            op_queue_2
+                .push(
+                    ops.pop_front().unwrap(),
+                    Some(PendingOperationStatus::FirstPrepareAttempt),
+                )
+                .await;
+        }
+
+        let (transmitter, mut receiver) = mpsc::channel(ENDPOINT_MESSAGES_QUEUE_SIZE);
+        let all = MatchingList(Some(vec![ListElement::new(
+            Filter::Wildcard,
+            Filter::Wildcard,
+            Filter::Wildcard,
+            Filter::Wildcard,
+            Filter::Wildcard,
+            None,
+        )]));
+        broadcaster
+            .send(MessageRetryRequest {
+                uuid: "0b5e0c1e-5d5c-4f0e-9a55-0d9e4c3e0a6a".to_string(),
+                pattern: all.clone(),
+                options: MessageRetryOptions {
+                    status: Some("Retry".parse().unwrap()),
+                    ..Default::default()
+                },
+                transmitter: transmitter.clone(),
+            })
+            .unwrap();
+        broadcaster
+            .send(MessageRetryRequest {
+                uuid: "4c8f7d5e-2b8f-4a57-a1c1-3b1a2e0f9d77".to_string(),
+                pattern: all,
+                options: MessageRetryOptions {
+                    action: MessageRetryAction::Drop,
+                    status: Some("FirstPrepareAttempt".parse().unwrap()),
+                    remaining: Some(Arc::new(AtomicUsize::new(3))),
+                },
+                transmitter,
+            })
+            .unwrap();
+
+        op_queue_1.process_retry_requests().await;
+        op_queue_2.process_retry_requests().await;
+
+        let responses: Vec<_> = (0..4).map(|_| receiver.try_recv().unwrap()).collect();
+        // no operation is waiting to be retried
+        assert!(responses.iter().step_by(2).all(|r| r.matched == 0));
+        // all operations match, but only 3 of them are dropped
+        let dropped: usize = responses
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|r| r.message_ids.len())
+            .sum();
+        assert_eq!(dropped, 3);
+        assert_eq!(op_queue_1.len().await + op_queue_2.len().await, 1);
+    }
 }
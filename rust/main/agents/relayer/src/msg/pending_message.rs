@@ -3,7 +3,7 @@
 use std::{
     fmt::{Debug, Formatter},
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use async_trait::async_trait;
@@ -16,24 +16,29 @@ use tracing::{debug, error, info, info_span, instrument, trace, warn, Instrument
 
 use hyperlane_base::{
     cache::{FunctionCallCache, LocalCache, MeteredCache, OptionalCache},
-    db::HyperlaneDb,
+    db::{HyperlaneDb, ShadowSubmission},
 };
 use hyperlane_core::{
     gas_used_by_operation, BatchItem, ChainCommunicationError, ChainResult, ConfirmReason,
-    FixedPointNumber, HyperlaneChain, HyperlaneDomain, HyperlaneMessage, Mailbox,
-    MessageSubmissionData, PendingOperation, PendingOperationResult, PendingOperationStatus,
-    ReprepareReason, TryBatchAs, TxCostEstimate, TxOutcome, H256, U256,
+    FixedPointNumber, HyperlaneChain, HyperlaneDomain, HyperlaneMessage, InterchainSecurityModule,
+    Mailbox, MessageSubmissionData, PendingOperation, PendingOperationResult,
+    PendingOperationStatus, ReprepareReason, TryBatchAs, TxCostEstimate, TxOutcome, H256, U256,
 };
 use hyperlane_operation_verifier::ApplicationOperationVerifier;
 
 use crate::{
     metrics::message_submission::{MessageSubmissionMetrics, MetadataBuildMetric},
     msg::metadata::{MessageMetadataBuildParams, MetadataBuildError},
+    settings::WebhookEvent,
 };
 
 use super::{
-    gas_payment::{GasPaymentEnforcer, GasPolicyStatus},
-    metadata::{BuildsBaseMetadata, MessageMetadataBuilder, Metadata, MetadataBuilder},
+    gas_payment::{GasPaymentEnforcer, GasPolicyStatus, NativeTokenPrices},
+    metadata::{
+        BuildsBaseMetadata, IsmTreeCache, MessageMetadataBuilder, Metadata, MetadataBuilder,
+    },
+    op_queue::DROPPED_OPERATION_RETRIES,
+    webhooks::WebhookNotifier,
 };
 
 /// a default of 66 is picked, so messages are retried for 2 weeks (period confirmed by @nambrot) before being skipped.
@@ -81,6 +86,12 @@ pub struct MessageContext {
     pub metrics: MessageSubmissionMetrics,
     /// Application operation verifier
     pub application_operation_verifier: Option<Arc<dyn ApplicationOperationVerifier>>,
+    /// Whether messages are only prepared and recorded, without being submitted
+    pub shadow_mode: bool,
+    /// Notifies webhooks of message lifecycle events, if any are configured
+    pub webhook_notifier: Option<Arc<WebhookNotifier>>,
+    /// Resolved ISM trees of recipients, if contract call caching is enabled
+    pub ism_trees: Option<Arc<IsmTreeCache>>,
 }
 
 /// A message that is pending processing and submission.
@@ -109,6 +120,10 @@ pub struct PendingMessage {
     #[new(default)]
     #[serde(skip_serializing)]
     submission_outcome: Option<TxOutcome>,
+    /// Native token prices the latest gas payment decision was made with
+    #[new(default)]
+    #[serde(skip_serializing)]
+    native_token_prices: Option<NativeTokenPrices>,
     #[new(default)]
     #[serde(skip_serializing)]
     metadata: Option<Vec<u8>>,
@@ -182,7 +197,10 @@ impl PendingOperation for PendingMessage {
         {
             warn!(message_id = ?self.message.id(), err = %e, status = %status, "Persisting `status` failed for message");
         }
-        self.status = status;
+        let previous_status = std::mem::replace(&mut self.status, status);
+        if previous_status != self.status {
+            self.notify_webhooks(WebhookEvent::Transition, Some(&previous_status));
+        }
     }
 
     fn priority(&self) -> u32 {
@@ -261,6 +279,7 @@ impl PendingOperation for PendingMessage {
                 recipient=?self.message.recipient,
                 "Dropping message because recipient is not a contract"
             );
+            self.on_drop();
             return PendingOperationResult::Drop;
         }
 
@@ -333,6 +352,8 @@ impl PendingOperation for PendingMessage {
                         .clarify_reason(ReprepareReason::ErrorEstimatingGas)
                         .await
                         .unwrap_or(ReprepareReason::ErrorEstimatingGas);
+                    // The metadata may have been built from a stale ISM tree
+                    self.invalidate_ism_tree_if_rejected(&metadata_bytes).await;
                     self.clear_metadata();
                     return self.on_reprepare(Some(err), reason);
                 }
@@ -364,6 +385,11 @@ impl PendingOperation for PendingMessage {
             }
         }
 
+        if self.ctx.shadow_mode {
+            self.record_shadow_submission(&metadata_bytes, gas_limit, &tx_cost_estimate)
+                .await;
+        }
+
         self.submission_data = Some(Box::new(MessageSubmissionData {
             metadata: metadata_bytes,
             gas_limit,
@@ -500,7 +526,11 @@ impl PendingOperation for PendingMessage {
             .origin_gas_payment_enforcer
             .read()
             .await
-            .record_tx_outcome(&self.message, operation_outcome.clone())
+            .record_tx_outcome(
+                &self.message,
+                operation_outcome.clone(),
+                self.native_token_prices.as_ref(),
+            )
         {
             error!(error=?e, "Error when recording tx outcome");
         }
@@ -535,6 +565,12 @@ impl PendingOperation for PendingMessage {
         self.num_retries
     }
 
+    fn drop_on_request(&mut self) {
+        info!("Dropping message on request");
+        self.set_retries(DROPPED_OPERATION_RETRIES);
+        self.on_drop();
+    }
+
     fn try_get_mailbox(&self) -> Option<Arc<dyn Mailbox>> {
         Some(self.ctx.destination_mailbox.clone())
     }
@@ -800,16 +836,19 @@ impl PendingMessage {
         &mut self,
         tx_cost_estimate: &TxCostEstimate,
     ) -> GasPaymentRequirementOutcome {
-        let gas_limit = self
+        let decision = self
             .ctx
             .origin_gas_payment_enforcer
             .read()
             .await
-            .message_meets_gas_payment_requirement(&self.message, tx_cost_estimate)
+            .gas_payment_decision(&self.message, tx_cost_estimate)
             .await;
 
-        let gas_limit = match gas_limit {
-            Ok(gas_limit) => gas_limit,
+        let gas_limit = match decision {
+            Ok((gas_limit, native_token_prices)) => {
+                self.native_token_prices = native_token_prices;
+                gas_limit
+            }
             Err(err) => {
                 return GasPaymentRequirementOutcome::RequirementNotMet(
                     self.on_reprepare(Some(err), ReprepareReason::ErrorCheckingGasRequirement),
@@ -878,16 +917,44 @@ impl PendingMessage {
             .store_processed_by_nonce(&self.message.nonce, &true)?;
         self.ctx.metrics.update_nonce(&self.message);
         self.ctx.metrics.messages_processed.inc();
+        self.notify_webhooks(WebhookEvent::Delivered, None);
         Ok(())
     }
 
+    /// Let the webhook sinks know that the message is given up on
+    fn on_drop(&self) {
+        self.notify_webhooks(WebhookEvent::Dropped, None);
+    }
+
+    fn notify_webhooks(
+        &self,
+        event: WebhookEvent,
+        previous_status: Option<&PendingOperationStatus>,
+    ) {
+        if let Some(notifier) = &self.ctx.webhook_notifier {
+            notifier.notify(
+                event,
+                &self.message,
+                &self.status,
+                previous_status,
+                self.num_retries,
+            );
+        }
+    }
+
     fn reset_attempts(&mut self) {
         self.next_attempt_after = None;
         self.last_attempted_at = Instant::now();
     }
 
     fn inc_attempts(&mut self) {
+        let previous_retries = self.num_retries;
         self.set_retries(self.num_retries + 1);
+        if self.ctx.webhook_notifier.as_ref().is_some_and(|notifier| {
+            notifier.crosses_stuck_threshold(previous_retries, self.num_retries)
+        }) {
+            self.notify_webhooks(WebhookEvent::Stuck, None);
+        }
         self.last_attempted_at = Instant::now();
         self.next_attempt_after = PendingMessage::calculate_msg_backoff(
             self.num_retries,
@@ -977,11 +1044,41 @@ impl PendingMessage {
         }
     }
 
+    /// Drop the cached ISM tree of the recipient if its ISM rejects `metadata`.
+    /// Other failures, like the recipient reverting, leave the tree cached.
+    async fn invalidate_ism_tree_if_rejected(&self, metadata: &[u8]) {
+        let Some(ism_trees) = &self.ctx.ism_trees else {
+            return;
+        };
+        let Some(ism_tree) = ism_trees.get(self.message.destination, self.message.recipient) else {
+            return;
+        };
+        let verified = match self
+            .ctx
+            .metadata_builder
+            .build_ism(ism_tree.root.address)
+            .await
+        {
+            Ok(ism) => ism.dry_run_verify(&self.message, metadata).await,
+            Err(err) => {
+                debug!(?err, "Failed to build recipient ISM to check metadata with");
+                return;
+            }
+        };
+        if !matches!(verified, Ok(Some(_))) {
+            debug!(
+                ?verified,
+                "Recipient ISM rejected metadata, invalidating its ISM tree"
+            );
+            ism_trees.invalidate(self.message.destination, self.message.recipient);
+        }
+    }
+
     /// Builds metadata
     async fn build_metadata(&mut self) -> Result<Metadata, PendingOperationResult> {
         let ism_address = self.recipient_ism_address().await?;
 
-        let message_metadata_builder = match MessageMetadataBuilder::new(
+        let mut message_metadata_builder = match MessageMetadataBuilder::new(
             self.ctx.metadata_builder.clone(),
             ism_address,
             &self.message,
@@ -995,6 +1092,18 @@ impl PendingMessage {
                 );
             }
         };
+        if let Some(ism_trees) = &self.ctx.ism_trees {
+            let ism_tree = ism_trees
+                .get_or_resolve(
+                    self.ctx.metadata_builder.as_ref(),
+                    self.message.destination,
+                    self.message.recipient,
+                    ism_address,
+                    &[self.message.origin],
+                )
+                .await;
+            message_metadata_builder = message_metadata_builder.with_ism_tree(ism_tree);
+        }
 
         let params = MessageMetadataBuildParams::default();
 
@@ -1054,6 +1163,51 @@ impl PendingMessage {
         metadata_res
     }
 
+    /// Record what would have been submitted for this message, in place of
+    /// submitting it. Failing to do so is logged but does not fail preparation.
+    async fn record_shadow_submission(
+        &self,
+        metadata: &[u8],
+        gas_limit: U256,
+        tx_cost_estimate: &TxCostEstimate,
+    ) {
+        let calldata = match self
+            .ctx
+            .destination_mailbox
+            .process_calldata(&self.message, metadata)
+            .await
+        {
+            Ok(calldata) => Some(calldata),
+            Err(err) => {
+                warn!(
+                    ?err,
+                    "Failed to build process calldata for shadow submission"
+                );
+                None
+            }
+        };
+        let submission = ShadowSubmission {
+            destination: self.message.destination,
+            metadata: metadata.to_vec(),
+            calldata,
+            gas_limit,
+            gas_price: tx_cost_estimate.gas_price.to_string(),
+            l2_gas_limit: tx_cost_estimate.l2_gas_limit,
+            prepared_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        if let Err(err) = self
+            .ctx
+            .origin_db
+            .store_shadow_submission_by_message_id(&self.message.id(), &submission)
+        {
+            warn!(?err, "Failed to store shadow submission");
+        }
+        debug!(?gas_limit, ?tx_cost_estimate, "Recorded shadow submission");
+    }
+
     /// clear metadata cache
     fn clear_metadata(&mut self) {
         tracing::debug!(id=?self.message.id(), INVALIDATE_CACHE_METADATA_LOG);
@@ -1072,6 +1226,7 @@ mod test {
     use chrono::TimeDelta;
     use hyperlane_base::{cache::OptionalCache, db::*};
     use hyperlane_core::{identifiers::UniqueIdentifier, *};
+    use hyperlane_test::mocks::MockMailboxContract;
 
     use crate::test_utils::dummy_data::{dummy_message_context, dummy_metadata_builder};
 
@@ -1190,6 +1345,14 @@ mod test {
             fn retrieve_highest_seen_message_nonce_number(&self) -> DbResult<Option<u32>>;
             fn store_payload_uuids_by_message_id(&self, message_id: &H256, payload_uuids: Vec<UniqueIdentifier>) -> DbResult<()>;
             fn retrieve_payload_uuids_by_message_id(&self, message_id: &H256) -> DbResult<Option<Vec<UniqueIdentifier>>>;
+            fn store_profitability_ledger_entry_by_message_id(&self, message_id: &H256, entry: &ProfitabilityLedgerEntry) -> DbResult<()>;
+            fn retrieve_profitability_ledger_entry_by_message_id(&self, message_id: &H256) -> DbResult<Option<ProfitabilityLedgerEntry>>;
+            fn store_profitability_route_aggregate_by_destination(&self, destination: &u32, aggregate: &ProfitabilityRouteAggregate) -> DbResult<()>;
+            fn retrieve_profitability_route_aggregate_by_destination(&self, destination: &u32) -> DbResult<Option<ProfitabilityRouteAggregate>>;
+            fn store_dynamic_message_filter(&self, filter: &DynamicMessageFilterData) -> DbResult<()>;
+            fn retrieve_dynamic_message_filter(&self) -> DbResult<Option<DynamicMessageFilterData>>;
+            fn store_shadow_submission_by_message_id(&self, message_id: &H256, submission: &ShadowSubmission) -> DbResult<()>;
+            fn retrieve_shadow_submission_by_message_id(&self, message_id: &H256) -> DbResult<Option<ShadowSubmission>>;
         }
     }
 
@@ -1401,6 +1564,66 @@ mod test {
         assert_eq!(db_status, expected_status);
     }
 
+    #[tokio::test]
+    async fn shadow_submission_is_recorded_without_submitting() {
+        let origin_domain = HyperlaneDomain::Known(hyperlane_core::KnownHyperlaneDomain::Arbitrum);
+        let destination_domain =
+            HyperlaneDomain::Known(hyperlane_core::KnownHyperlaneDomain::Arbitrum);
+        let cache = OptionalCache::new(None);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = DB::from_path(temp_dir.path()).unwrap();
+        let base_db = HyperlaneRocksDB::new(&origin_domain, db);
+
+        let message = HyperlaneMessage {
+            nonce: 0,
+            origin: KnownHyperlaneDomain::Arbitrum as u32,
+            destination: KnownHyperlaneDomain::Arbitrum as u32,
+            ..Default::default()
+        };
+
+        let mut mailbox = MockMailboxContract::new_with_default_ism(H256::zero());
+        mailbox
+            .expect_process_calldata()
+            .returning(|_, _| vec![1, 2, 3]);
+        // in shadow mode, the transaction is never sent
+        mailbox.expect_process().never();
+
+        let base_metadata_builder =
+            dummy_metadata_builder(&origin_domain, &destination_domain, &base_db, cache.clone());
+        let mut message_context =
+            dummy_message_context(Arc::new(base_metadata_builder), &base_db, cache);
+        message_context.destination_mailbox = Arc::new(mailbox);
+        message_context.shadow_mode = true;
+
+        let pending_message = PendingMessage::new(
+            message.clone(),
+            Arc::new(message_context),
+            PendingOperationStatus::FirstPrepareAttempt,
+            Some(format!("test-{}", 0)),
+            2,
+        );
+
+        let tx_cost_estimate = TxCostEstimate {
+            gas_limit: U256::from(100),
+            gas_price: 10u32.into(),
+            l2_gas_limit: None,
+        };
+        pending_message
+            .record_shadow_submission(&[4, 5], U256::from(120), &tx_cost_estimate)
+            .await;
+
+        let submission = base_db
+            .retrieve_shadow_submission_by_message_id(&message.id())
+            .expect("Failed to fetch shadow submission")
+            .expect("Shadow submission not found");
+        assert_eq!(submission.destination, message.destination);
+        assert_eq!(submission.metadata, vec![4, 5]);
+        assert_eq!(submission.calldata, Some(vec![1, 2, 3]));
+        assert_eq!(submission.gas_limit, U256::from(120));
+        assert_eq!(submission.gas_price, tx_cost_estimate.gas_price.to_string());
+    }
+
     #[test]
     fn check_debug_print() {
         let origin_domain = HyperlaneDomain::Known(hyperlane_core::KnownHyperlaneDomain::Arbitrum);
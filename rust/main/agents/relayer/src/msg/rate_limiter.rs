@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hyperlane_core::{QueueOperation, H256};
+
+use crate::settings::{RateLimitConf, RateLimitScope};
+
+/// Once a limit tracks more buckets than this, the ones that are full again are
+/// dropped, since they behave the same as a freshly created bucket.
+const MAX_BUCKETS_PER_LIMIT: usize = 10_000;
+
+/// Token bucket limits on how fast operations are prepared, shared by the
+/// message processors of all destinations.
+///
+/// An operation that matches several limits needs a token from each of them,
+/// and only consumes tokens if all of them have one available.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    limits: Vec<RateLimitConf>,
+    /// The buckets of each limit, in the same order as `limits`
+    buckets: Mutex<Vec<HashMap<BucketKey, TokenBucket>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Shared,
+    Sender { origin: u32, sender: H256 },
+    Recipient { destination: u32, recipient: H256 },
+    Route { origin: u32, destination: u32 },
+}
+
+impl BucketKey {
+    fn new(scope: RateLimitScope, op: &QueueOperation) -> Self {
+        match scope {
+            RateLimitScope::Shared => Self::Shared,
+            RateLimitScope::Sender => Self::Sender {
+                origin: op.origin_domain_id(),
+                sender: *op.sender_address(),
+            },
+            RateLimitScope::Recipient => Self::Recipient {
+                destination: op.destination_domain().id(),
+                recipient: *op.recipient_address(),
+            },
+            RateLimitScope::Route => Self::Route {
+                origin: op.origin_domain_id(),
+                destination: op.destination_domain().id(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(capacity: u32, now: Instant) -> Self {
+        Self {
+            tokens: capacity as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, conf: &RateLimitConf, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * conf.refill_rate).min(conf.capacity as f64);
+        self.last_refill = now;
+    }
+
+    /// How long to wait until a token is available
+    fn wait_time(&self, conf: &RateLimitConf) -> Duration {
+        if self.tokens >= 1. {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1. - self.tokens) / conf.refill_rate)
+    }
+
+    fn is_full(&self, conf: &RateLimitConf) -> bool {
+        self.tokens >= conf.capacity as f64
+    }
+}
+
+impl RateLimiter {
+    pub fn new(limits: Vec<RateLimitConf>) -> Self {
+        let buckets = Mutex::new(vec![HashMap::new(); limits.len()]);
+        Self { limits, buckets }
+    }
+
+    /// Whether no limits are configured
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Take a token for the operation from every limit it matches.
+    /// If any of them has no token available, nothing is consumed and the
+    /// time until all of them will have one is returned instead.
+    pub fn try_acquire(&self, op: &QueueOperation) -> Result<(), Duration> {
+        self.try_acquire_at(op, Instant::now())
+    }
+
+    fn try_acquire_at(&self, op: &QueueOperation, now: Instant) -> Result<(), Duration> {
+        if self.limits.is_empty() {
+            return Ok(());
+        }
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+
+        let mut matched = vec![];
+        let mut wait = Duration::ZERO;
+        let limits = self.limits.iter().zip(buckets.iter_mut()).enumerate();
+        for (index, (conf, limit_buckets)) in limits {
+            if !conf.matching_list.op_matches_or(op, true) {
+                continue;
+            }
+            let key = BucketKey::new(conf.scope, op);
+            let bucket = limit_buckets
+                .entry(key)
+                .or_insert_with(|| TokenBucket::full(conf.capacity, now));
+            bucket.refill(conf, now);
+            wait = wait.max(bucket.wait_time(conf));
+            matched.push((index, key));
+        }
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for (index, key) in matched {
+            if let Some(bucket) = buckets[index].get_mut(&key) {
+                bucket.tokens -= 1.;
+            }
+            Self::prune(&self.limits[index], &mut buckets[index], now);
+        }
+        Ok(())
+    }
+
+    fn prune(conf: &RateLimitConf, buckets: &mut HashMap<BucketKey, TokenBucket>, now: Instant) {
+        if buckets.len() <= MAX_BUCKETS_PER_LIMIT {
+            return;
+        }
+        buckets.retain(|_, bucket| {
+            bucket.refill(conf, now);
+            !bucket.is_full(conf)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::{msg::op_queue::test::MockPendingOperation, settings::matching_list::MatchingList};
+
+    fn conf(matching_list: MatchingList, capacity: u32, scope: RateLimitScope) -> RateLimitConf {
+        RateLimitConf {
+            matching_list,
+            capacity,
+            refill_rate: 1.,
+            scope,
+        }
+    }
+
+    fn op(origin: u32, sender: H256, destination: &HyperlaneDomain) -> QueueOperation {
+        let message = HyperlaneMessage {
+            origin,
+            sender,
+            destination: destination.id(),
+            ..Default::default()
+        };
+        Box::new(MockPendingOperation::with_message_data(message))
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let destination = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let limiter = RateLimiter::new(vec![conf(
+            MatchingList::default(),
+            2,
+            RateLimitScope::Shared,
+        )]);
+        let op = op(1, H256::zero(), &destination);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(&op, start).is_ok());
+        assert!(limiter.try_acquire_at(&op, start).is_ok());
+        assert_eq!(
+            limiter.try_acquire_at(&op, start),
+            Err(Duration::from_secs(1))
+        );
+        assert_eq!(
+            limiter.try_acquire_at(&op, start + Duration::from_millis(500)),
+            Err(Duration::from_millis(500))
+        );
+        assert!(limiter
+            .try_acquire_at(&op, start + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_sender_scope_uses_separate_buckets() {
+        let destination = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let limiter = RateLimiter::new(vec![conf(
+            MatchingList::default(),
+            1,
+            RateLimitScope::Sender,
+        )]);
+        let noisy = op(1, H256::from_low_u64_be(1), &destination);
+        let quiet = op(1, H256::from_low_u64_be(2), &destination);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(&noisy, start).is_ok());
+        assert!(limiter.try_acquire_at(&noisy, start).is_err());
+        assert!(limiter.try_acquire_at(&quiet, start).is_ok());
+    }
+
+    #[test]
+    fn test_tokens_are_only_taken_if_all_limits_allow() {
+        let destination = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let limiter = RateLimiter::new(vec![
+            conf(MatchingList::default(), 2, RateLimitScope::Shared),
+            conf(
+                MatchingList::with_destination_domain(destination.id()),
+                1,
+                RateLimitScope::Sender,
+            ),
+        ]);
+        let first = op(1, H256::from_low_u64_be(1), &destination);
+        let second = op(1, H256::from_low_u64_be(2), &destination);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(&first, start).is_ok());
+        // the sender bucket of `first` is empty, so the shared bucket is left untouched
+        assert!(limiter.try_acquire_at(&first, start).is_err());
+        assert!(limiter.try_acquire_at(&second, start).is_ok());
+        assert!(limiter.try_acquire_at(&second, start).is_err());
+    }
+
+    #[test]
+    fn test_unmatched_ops_are_not_limited() {
+        let destination = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let limiter = RateLimiter::new(vec![conf(
+            MatchingList::with_destination_domain(destination.id() + 1),
+            1,
+            RateLimitScope::Shared,
+        )]);
+        let op = op(1, H256::zero(), &destination);
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            assert!(limiter.try_acquire_at(&op, start).is_ok());
+        }
+    }
+}
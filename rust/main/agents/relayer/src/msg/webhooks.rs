@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ethers::utils::hex;
+use eyre::Result;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info_span, warn, Instrument};
+
+use hyperlane_base::db::{HyperlaneRocksDB, WebhookOutboxEntry};
+use hyperlane_core::{HyperlaneMessage, PendingOperationStatus, H256};
+
+use crate::settings::{WebhookConf, WebhookEvent};
+
+/// Header carrying the HMAC-SHA256 signature of the request body
+pub const SIGNATURE_HEADER: &str = "X-Hyperlane-Signature";
+/// How long a dispatcher waits when no outbox entry is due
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const BASE_RETRY_DELAY_SECS: u64 = 5;
+const MAX_RETRY_DELAY_SECS: u64 = 60 * 60;
+/// Entries that failed this many times are dropped
+const MAX_DELIVERY_ATTEMPTS: u32 = 20;
+
+/// Notifies the configured webhooks of message lifecycle events.
+///
+/// Events are first written to an outbox per webhook in the database of the
+/// message's origin, and then delivered by a dispatcher task per origin and
+/// webhook, so that they survive restarts and are retried with exponential
+/// backoff when the webhook cannot be reached, without holding back the
+/// events of other webhooks.
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    sinks: Vec<WebhookConf>,
+    /// Databases holding the outboxes, keyed by origin domain id
+    dbs: HashMap<u32, HyperlaneRocksDB>,
+    /// Serializes access to the outboxes, which are shared by the message
+    /// processors and the dispatchers
+    outbox_lock: Mutex<()>,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    event: WebhookEvent,
+    message_id: H256,
+    origin: u32,
+    destination: u32,
+    nonce: u32,
+    sender: H256,
+    recipient: H256,
+    status: &'a PendingOperationStatus,
+    previous_status: Option<&'a PendingOperationStatus>,
+    retries: u32,
+    timestamp: u64,
+}
+
+impl WebhookNotifier {
+    pub fn new(sinks: Vec<WebhookConf>, dbs: HashMap<u32, HyperlaneRocksDB>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build webhook HTTP client");
+        Self {
+            sinks,
+            dbs,
+            outbox_lock: Mutex::new(()),
+            client,
+        }
+    }
+
+    /// Queue `event` for delivery to every webhook that subscribed to it and
+    /// whose matching list matches the message.
+    /// `Stuck` events are only sent to the webhooks whose configured number
+    /// of retries the message just reached.
+    pub fn notify(
+        &self,
+        event: WebhookEvent,
+        message: &HyperlaneMessage,
+        status: &PendingOperationStatus,
+        previous_status: Option<&PendingOperationStatus>,
+        retries: u32,
+    ) {
+        let mut sinks = self
+            .sinks
+            .iter()
+            .filter(|sink| sink.events.contains(&event))
+            .filter(|sink| event != WebhookEvent::Stuck || retries == sink.stuck_after_retries)
+            .filter(|sink| sink.matching_list.msg_matches(message, true))
+            .peekable();
+        if sinks.peek().is_none() {
+            return;
+        }
+        let Some(db) = self.dbs.get(&message.origin) else {
+            warn!(
+                origin = message.origin,
+                "No database to queue webhook events in"
+            );
+            return;
+        };
+
+        let payload = WebhookPayload {
+            event,
+            message_id: message.id(),
+            origin: message.origin,
+            destination: message.destination,
+            nonce: message.nonce,
+            sender: message.sender,
+            recipient: message.recipient,
+            status,
+            previous_status,
+            retries,
+            timestamp: unix_timestamp(),
+        };
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!(?err, ?event, "Failed to serialize webhook payload");
+                return;
+            }
+        };
+
+        let _guard = self
+            .outbox_lock
+            .lock()
+            .expect("webhook outbox lock poisoned");
+        for sink in sinks {
+            let entry = WebhookOutboxEntry {
+                sink: sink.id().to_owned(),
+                body: body.clone(),
+                attempts: 0,
+                next_attempt_at: 0,
+            };
+            if let Err(err) = db.push_webhook_outbox_entry(sink.id(), &entry) {
+                warn!(
+                    ?err,
+                    sink = sink.id(),
+                    ?event,
+                    "Failed to queue webhook event"
+                );
+            }
+        }
+    }
+
+    /// Whether going from `previous_retries` to `retries` makes a message
+    /// stuck for any of the webhooks notified of `Stuck` events
+    pub fn crosses_stuck_threshold(&self, previous_retries: u32, retries: u32) -> bool {
+        self.sinks.iter().any(|sink| {
+            sink.events.contains(&WebhookEvent::Stuck)
+                && previous_retries < sink.stuck_after_retries
+                && retries >= sink.stuck_after_retries
+        })
+    }
+
+    /// Spawn a task per origin and webhook delivering the events of its outbox
+    pub fn spawn(self: &Arc<Self>) -> Vec<JoinHandle<()>> {
+        self.dbs
+            .keys()
+            .flat_map(|&origin| {
+                self.sinks.iter().map(move |sink| {
+                    let notifier = self.clone();
+                    let sink_id = sink.id().to_owned();
+                    let span = info_span!("WebhookDispatcher", origin, sink = %sink_id);
+                    tokio::spawn(
+                        async move { notifier.run_dispatcher(origin, &sink_id).await }
+                            .instrument(span),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    async fn run_dispatcher(&self, origin: u32, sink_id: &str) {
+        let Some(db) = self.dbs.get(&origin) else {
+            return;
+        };
+        loop {
+            let next = {
+                let _guard = self
+                    .outbox_lock
+                    .lock()
+                    .expect("webhook outbox lock poisoned");
+                db.peek_webhook_outbox_entry(sink_id)
+            };
+            let (sequence, entry) = match next {
+                Ok(Some((sequence, entry))) if entry.next_attempt_at <= unix_timestamp() => {
+                    (sequence, entry)
+                }
+                Ok(_) => {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+                Err(err) => {
+                    error!(?err, "Failed to read webhook outbox");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+
+            // secrets are not persisted, so they are looked up from the current config
+            let Some(sink) = self.sinks.iter().find(|sink| sink.id() == entry.sink) else {
+                warn!(sink = %entry.sink, "Dropping webhook event of a webhook that is no longer configured");
+                if let Err(err) = self.pop(db, sink_id) {
+                    error!(?err, "Failed to update webhook outbox");
+                }
+                continue;
+            };
+
+            // the entry is only removed once the delivery was attempted, so
+            // that it is retried if the relayer stops in the meantime
+            let result = self.deliver(sink, &entry).await;
+            let updated = match result {
+                Ok(()) => {
+                    debug!(sink = %entry.sink, "Delivered webhook event");
+                    self.pop(db, sink_id).map(|_| ())
+                }
+                Err(err) if entry.attempts + 1 >= MAX_DELIVERY_ATTEMPTS => {
+                    error!(?err, sink = %entry.sink, body = %entry.body, "Dropping webhook event after too many failed attempts");
+                    self.pop(db, sink_id).map(|_| ())
+                }
+                Err(err) => {
+                    let attempts = entry.attempts + 1;
+                    warn!(?err, sink = %entry.sink, attempts, "Failed to deliver webhook event");
+                    // later events of the same webhook wait for this one, so
+                    // that they are delivered in order
+                    self.reschedule(
+                        db,
+                        sink_id,
+                        sequence,
+                        WebhookOutboxEntry {
+                            attempts,
+                            next_attempt_at: unix_timestamp() + retry_delay_secs(attempts),
+                            ..entry
+                        },
+                    )
+                }
+            };
+            if let Err(err) = updated {
+                error!(?err, "Failed to update webhook outbox");
+            }
+        }
+    }
+
+    fn pop(&self, db: &HyperlaneRocksDB, sink_id: &str) -> Result<Option<WebhookOutboxEntry>> {
+        let _guard = self
+            .outbox_lock
+            .lock()
+            .expect("webhook outbox lock poisoned");
+        Ok(db.pop_webhook_outbox_entry(sink_id)?)
+    }
+
+    /// Replace the oldest entry of the outbox of `sink_id` with `entry`
+    fn reschedule(
+        &self,
+        db: &HyperlaneRocksDB,
+        sink_id: &str,
+        sequence: u64,
+        entry: WebhookOutboxEntry,
+    ) -> Result<()> {
+        let _guard = self
+            .outbox_lock
+            .lock()
+            .expect("webhook outbox lock poisoned");
+        db.update_webhook_outbox_entry(sink_id, sequence, &entry)?;
+        Ok(())
+    }
+
+    async fn deliver(&self, sink: &WebhookConf, entry: &WebhookOutboxEntry) -> Result<()> {
+        self.client
+            .post(&sink.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature(&sink.secret, &entry.body))
+            .body(entry.body.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// `sha256=` followed by the hex encoded HMAC-SHA256 of `body`
+fn signature(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn retry_delay_secs(attempts: u32) -> u64 {
+    BASE_RETRY_DELAY_SECS
+        .saturating_mul(2u64.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY_SECS)
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_base::db::DB;
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::settings::matching_list::MatchingList;
+
+    fn sink(url: &str, matching_list: MatchingList, events: Vec<WebhookEvent>) -> WebhookConf {
+        WebhookConf {
+            name: None,
+            url: url.to_owned(),
+            secret: "secret".to_owned(),
+            matching_list,
+            events,
+            stuck_after_retries: 3,
+        }
+    }
+
+    fn outbox_bodies(db: &HyperlaneRocksDB, sink: &str) -> Vec<String> {
+        let mut bodies = vec![];
+        while let Some(entry) = db.pop_webhook_outbox_entry(sink).unwrap() {
+            bodies.push(entry.body);
+        }
+        bodies
+    }
+
+    #[test]
+    fn test_notify_queues_matching_sinks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let db = HyperlaneRocksDB::new(&domain, DB::from_path(temp_dir.path()).unwrap());
+        let message = HyperlaneMessage {
+            origin: domain.id(),
+            destination: 1,
+            ..Default::default()
+        };
+        let notifier = WebhookNotifier::new(
+            vec![
+                sink(
+                    "https://example.com/all",
+                    MatchingList::default(),
+                    vec![WebhookEvent::Delivered, WebhookEvent::Stuck],
+                ),
+                sink(
+                    "https://example.com/other",
+                    MatchingList::with_destination_domain(2),
+                    vec![WebhookEvent::Delivered],
+                ),
+            ],
+            HashMap::from([(domain.id(), db.clone())]),
+        );
+        let status = PendingOperationStatus::FirstPrepareAttempt;
+
+        notifier.notify(WebhookEvent::Delivered, &message, &status, None, 0);
+        notifier.notify(WebhookEvent::Transition, &message, &status, None, 0);
+        notifier.notify(WebhookEvent::Stuck, &message, &status, None, 2);
+        notifier.notify(WebhookEvent::Stuck, &message, &status, None, 3);
+        notifier.notify(WebhookEvent::Stuck, &message, &status, None, 4);
+
+        assert_eq!(db.webhook_outbox_len("https://example.com/all").unwrap(), 2);
+        assert_eq!(
+            db.webhook_outbox_len("https://example.com/other").unwrap(),
+            0
+        );
+        let (_, entry) = db
+            .peek_webhook_outbox_entry("https://example.com/all")
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.sink, "https://example.com/all");
+        let body: serde_json::Value = serde_json::from_str(&entry.body).unwrap();
+        assert_eq!(body["event"], "delivered");
+        assert_eq!(body["destination"], 1);
+        let events: Vec<_> = outbox_bodies(&db, "https://example.com/all")
+            .iter()
+            .map(|body| serde_json::from_str::<serde_json::Value>(body).unwrap()["event"].clone())
+            .collect();
+        assert_eq!(events, vec!["delivered", "stuck"]);
+    }
+
+    #[test]
+    fn test_crosses_stuck_threshold() {
+        let notifier = WebhookNotifier::new(
+            vec![
+                sink(
+                    "https://example.com/stuck",
+                    MatchingList::default(),
+                    vec![WebhookEvent::Stuck],
+                ),
+                sink(
+                    "https://example.com/delivered",
+                    MatchingList::default(),
+                    vec![WebhookEvent::Delivered],
+                ),
+            ],
+            HashMap::new(),
+        );
+        assert!(!notifier.crosses_stuck_threshold(1, 2));
+        assert!(notifier.crosses_stuck_threshold(2, 3));
+        assert!(!notifier.crosses_stuck_threshold(3, 4));
+    }
+
+    #[test]
+    fn test_outboxes_are_kept_per_sink() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let db = HyperlaneRocksDB::new(&domain, DB::from_path(temp_dir.path()).unwrap());
+        for (sink, body) in [("a", "first"), ("ab", "other"), ("a", "second")] {
+            let entry = WebhookOutboxEntry {
+                sink: sink.to_owned(),
+                body: body.to_owned(),
+                ..Default::default()
+            };
+            db.push_webhook_outbox_entry(sink, &entry).unwrap();
+        }
+
+        // a failed delivery is rescheduled in place, ahead of later events
+        let notifier = WebhookNotifier::new(vec![], HashMap::new());
+        let (sequence, first) = db.peek_webhook_outbox_entry("a").unwrap().unwrap();
+        notifier
+            .reschedule(
+                &db,
+                "a",
+                sequence,
+                WebhookOutboxEntry {
+                    attempts: 1,
+                    ..first
+                },
+            )
+            .unwrap();
+        let (_, first) = db.peek_webhook_outbox_entry("a").unwrap().unwrap();
+        assert_eq!(first.attempts, 1);
+
+        assert_eq!(db.webhook_outbox_len("a").unwrap(), 2);
+        assert_eq!(outbox_bodies(&db, "a"), vec!["first", "second"]);
+        assert_eq!(outbox_bodies(&db, "ab"), vec!["other"]);
+        assert_eq!(db.webhook_outbox_len("a").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_signature() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        assert_eq!(retry_delay_secs(1), 5);
+        assert_eq!(retry_delay_secs(2), 10);
+        assert_eq!(retry_delay_secs(4), 40);
+        assert_eq!(retry_delay_secs(30), MAX_RETRY_DELAY_SECS);
+    }
+}
@@ -24,7 +24,7 @@ use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use hyperlane_base::{
     broadcast::BroadcastMpscSender,
-    cache::{LocalCache, MeteredCache, MeteredCacheConfig, OptionalCache},
+    cache::{default_expiration, LocalCache, MeteredCache, MeteredCacheConfig, OptionalCache},
     cursors::Indexable,
     db::{HyperlaneRocksDB, DB},
     metrics::{AgentMetrics, ChainSpecificMetricsUpdater},
@@ -52,12 +52,15 @@ use crate::{
         blacklist::AddressBlacklist,
         db_loader::{MessageDbLoader, MessageDbLoaderMetrics},
         gas_payment::GasPaymentEnforcer,
+        message_filter::{MessageFilter, SharedMessageFilter},
         message_processor::{MessageProcessor, MessageProcessorMetrics},
         metadata::{
             BaseMetadataBuilder, DefaultIsmCache, IsmAwareAppContextClassifier,
-            IsmCachePolicyClassifier,
+            IsmCachePolicyClassifier, IsmTreeCache,
         },
         pending_message::MessageContext,
+        rate_limiter::RateLimiter,
+        webhooks::WebhookNotifier,
     },
     server::{self as relayer_server},
     settings::{matching_list::MatchingList, RelayerSettings},
@@ -96,8 +99,11 @@ pub struct Relayer {
     dbs: HashMap<HyperlaneDomain, HyperlaneRocksDB>,
     /// The original reference to the relayer cache
     _cache: OptionalCache<MeteredCache<LocalCache>>,
-    message_whitelist: Arc<MatchingList>,
-    message_blacklist: Arc<MatchingList>,
+    message_filter: SharedMessageFilter,
+    rate_limiter: Arc<RateLimiter>,
+    app_context_weights: Option<HashMap<String, u32>>,
+    shadow_mode: bool,
+    webhook_notifier: Option<Arc<WebhookNotifier>>,
     address_blacklist: Arc<AddressBlacklist>,
     transaction_gas_limit: Option<U256>,
     skip_transaction_gas_limit_for: HashSet<u32>,
@@ -120,11 +126,10 @@ impl Debug for Relayer {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Relayer {{ origin_chains: {:?}, destination_chains: {:?}, message_whitelist: {:?}, message_blacklist: {:?}, address_blacklist: {:?}, transaction_gas_limit: {:?}, skip_transaction_gas_limit_for: {:?}, allow_local_checkpoint_syncers: {:?} }}",
+            "Relayer {{ origin_chains: {:?}, destination_chains: {:?}, message_filter: {:?}, address_blacklist: {:?}, transaction_gas_limit: {:?}, skip_transaction_gas_limit_for: {:?}, allow_local_checkpoint_syncers: {:?} }}",
             self.origin_chains,
             self.destination_chains,
-            self.message_whitelist,
-            self.message_blacklist,
+            self.message_filter,
             self.address_blacklist,
             self.transaction_gas_limit,
             self.skip_transaction_gas_limit_for,
@@ -185,6 +190,14 @@ impl BaseAgent for Relayer {
             .collect::<HashMap<_, _>>();
         debug!(elapsed = ?start_entity_init.elapsed(), event = "initialized databases", "Relayer startup duration measurement");
 
+        let webhook_notifier = (!settings.webhooks.is_empty()).then(|| {
+            let outbox_dbs = dbs
+                .iter()
+                .map(|(origin, db)| (origin.id(), db.clone()))
+                .collect();
+            Arc::new(WebhookNotifier::new(settings.webhooks.clone(), outbox_dbs))
+        });
+
         start_entity_init = Instant::now();
         let application_operation_verifiers =
             Self::build_application_operation_verifiers(&settings, &core_metrics, &chain_metrics)
@@ -260,20 +273,27 @@ impl BaseAgent for Relayer {
 
         debug!(elapsed = ?start_entity_init.elapsed(), event = "initialized merkle tree hook syncs", "Relayer startup duration measurement");
 
-        let message_whitelist = Arc::new(settings.whitelist);
-        let message_blacklist = Arc::new(settings.blacklist);
+        let message_filter = MessageFilter::new(
+            settings.whitelist,
+            settings.blacklist,
+            dbs.values().cloned().collect(),
+        )?;
+        let rate_limiter = Arc::new(RateLimiter::new(settings.rate_limits));
         let address_blacklist = Arc::new(AddressBlacklist::new(settings.address_blacklist));
         let skip_transaction_gas_limit_for = settings.skip_transaction_gas_limit_for;
         let transaction_gas_limit = settings.transaction_gas_limit;
 
         info!(
-            %message_whitelist,
-            %message_blacklist,
+            message_whitelist = %message_filter.whitelist(),
+            message_blacklist = %message_filter.blacklist(),
             ?address_blacklist,
             ?transaction_gas_limit,
             ?skip_transaction_gas_limit_for,
             "Whitelist configuration"
         );
+        if settings.shadow_mode {
+            warn!("Shadow mode is enabled, messages will be prepared but never submitted");
+        }
 
         // provers by origin chain
         start_entity_init = Instant::now();
@@ -354,6 +374,10 @@ impl BaseAgent for Relayer {
         debug!(elapsed = ?start_entity_init.elapsed(), event = "initialized ccip signers", "Relayer startup duration measurement");
 
         start_entity_init = Instant::now();
+        // Resolved ISM trees are cached like contract call results
+        let ism_trees = settings
+            .allow_contract_call_caching
+            .then(|| Arc::new(IsmTreeCache::new(default_expiration())));
         let mut msg_ctxs = HashMap::new();
         let mut destination_chains = HashMap::new();
         for (destination, dest_mailbox) in mailboxes.iter() {
@@ -426,6 +450,8 @@ impl BaseAgent for Relayer {
                     ),
                     ccip_signers.get(destination).cloned().flatten(),
                     origin_chain_setup.ignore_reorg_reports,
+                    settings.quorum_selection_strategy,
+                    settings.ccip_read_gateway_timeouts.clone(),
                 );
 
                 msg_ctxs.insert(
@@ -442,6 +468,9 @@ impl BaseAgent for Relayer {
                         transaction_gas_limit,
                         metrics: MessageSubmissionMetrics::new(&core_metrics, origin, destination),
                         application_operation_verifier: application_operation_verifier.cloned(),
+                        shadow_mode: settings.shadow_mode,
+                        webhook_notifier: webhook_notifier.clone(),
+                        ism_trees: ism_trees.clone(),
                     }),
                 );
             }
@@ -461,8 +490,11 @@ impl BaseAgent for Relayer {
             interchain_gas_payment_syncs,
             prover_syncs,
             merkle_tree_hook_syncs,
-            message_whitelist,
-            message_blacklist,
+            message_filter: Arc::new(RwLock::new(message_filter)),
+            rate_limiter,
+            app_context_weights: settings.app_context_weights,
+            shadow_mode: settings.shadow_mode,
+            webhook_notifier,
             address_blacklist,
             transaction_gas_limit,
             skip_transaction_gas_limit_for,
@@ -513,9 +545,11 @@ impl BaseAgent for Relayer {
             let (send_channel, receive_channel) = mpsc::unbounded_channel::<QueueOperation>();
             send_channels.insert(dest_domain.id(), send_channel);
 
+            // In shadow mode nothing is submitted, so Lander is not used at all
             let dispatcher_entrypoint = self
                 .destinations
                 .get(dest_domain)
+                .filter(|_| !self.shadow_mode)
                 .and_then(|d| d.dispatcher_entrypoint.clone());
 
             let db = match self.dbs.get(dest_domain) {
@@ -560,6 +594,10 @@ impl BaseAgent for Relayer {
                 task_monitor.clone(),
                 dispatcher_entrypoint,
                 db,
+                self.message_filter.clone(),
+                self.rate_limiter.clone(),
+                self.app_context_weights.clone(),
+                self.shadow_mode,
             );
             prep_queues.insert(dest_domain.id(), message_processor.prepare_queue().await);
 
@@ -572,6 +610,7 @@ impl BaseAgent for Relayer {
             let dispatcher = self
                 .destinations
                 .get(dest_domain)
+                .filter(|_| !self.shadow_mode)
                 .and_then(|d| d.dispatcher.clone());
             if let Some(dispatcher) = dispatcher {
                 tasks.push(dispatcher.spawn().await);
@@ -601,6 +640,10 @@ impl BaseAgent for Relayer {
         }
         debug!(elapsed = ?start_entity_init.elapsed(), event = "started processors", "Relayer startup duration measurement");
 
+        if let Some(webhook_notifier) = &self.webhook_notifier {
+            tasks.extend(webhook_notifier.spawn());
+        }
+
         start_entity_init = Instant::now();
         for origin in &self.origin_chains {
             let maybe_broadcaster = self
@@ -714,11 +757,19 @@ impl BaseAgent for Relayer {
             .iter()
             .map(|(key, ctx)| (key.origin.clone(), ctx.origin_gas_payment_enforcer.clone()))
             .collect();
+        let msg_ctxs: HashMap<_, _> = self
+            .msg_ctxs
+            .iter()
+            .map(|(key, ctx)| ((key.origin.id(), key.destination.id()), ctx.clone()))
+            .collect();
         let relayer_router = relayer_server::Server::new(self.destination_chains.len())
             .with_op_retry(sender.clone())
             .with_message_queue(prep_queues)
             .with_dbs(dbs)
             .with_gas_enforcers(gas_enforcers)
+            .with_message_filter(self.message_filter.clone())
+            .with_address_blacklist(self.address_blacklist.clone())
+            .with_message_contexts(msg_ctxs)
             .router();
 
         let server = self
@@ -1014,8 +1065,7 @@ impl Relayer {
 
         let message_db_loader = MessageDbLoader::new(
             db,
-            self.message_whitelist.clone(),
-            self.message_blacklist.clone(),
+            self.message_filter.clone(),
             self.address_blacklist.clone(),
             metrics,
             send_channels,
@@ -134,6 +134,12 @@ fn generate_test_relayer_settings(
         max_retries: 1,
         tx_id_indexing_enabled: true,
         igp_indexing_enabled: true,
+        rate_limits: Vec::new(),
+        app_context_weights: None,
+        shadow_mode: false,
+        webhooks: Vec::new(),
+        quorum_selection_strategy: Default::default(),
+        ccip_read_gateway_timeouts: HashMap::new(),
     }
 }
 
@@ -0,0 +1,713 @@
+use std::{collections::HashMap, time::Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use ethers::utils::hex;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::server::utils::{
+    ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse,
+};
+use hyperlane_core::{
+    FixedPointNumber, GasPaymentKey, HyperlaneMessage, ModuleType, PendingOperationStatus,
+    ReprepareReason, TxCostEstimate, H160, H256, U256,
+};
+
+use crate::{
+    msg::{
+        gas_payment::GasPolicyStatus,
+        metadata::{
+            BuildsBaseMetadata, MessageMetadataBuildParams, MessageMetadataBuilder, MetadataBuilder,
+        },
+        pending_message::{MessageContext, ISM_MAX_DEPTH},
+    },
+    server::explain::ServerState,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathParams {
+    pub message_id: H256,
+}
+
+/// The outcome of the checks a message goes through before being submitted
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub message_id: H256,
+    pub origin: u32,
+    pub destination: u32,
+    pub nonce: u32,
+    pub filter: FilterReport,
+    pub delivery: DeliveryReport,
+    pub gas_policy: GasPolicyReport,
+    pub ism: IsmReport,
+    pub operation: OperationReport,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FilterReport {
+    /// Whether the message matches the whitelist
+    pub whitelisted: bool,
+    /// Whether the message matches the blacklist
+    pub blacklisted: bool,
+    /// The entry of the address blacklist found in the message, if any
+    pub blacklisted_address: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DeliveryReport {
+    /// Whether the message is marked as processed in the origin db
+    pub processed: bool,
+    /// Whether the destination mailbox reports the message as delivered
+    pub delivered: Option<bool>,
+    /// Whether the recipient is a contract. Messages to other recipients are dropped.
+    pub recipient_is_contract: Option<bool>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GasPolicyReport {
+    /// Total amount paid to the IGP for the message
+    pub payment: U256,
+    /// Total gas amount paid for
+    pub gas_amount: U256,
+    /// Tokens already spent on delivery attempts
+    pub tokens_used: U256,
+    /// Gas already spent on delivery attempts
+    pub gas_used: U256,
+    /// Verdict for a transaction that costs nothing, as in the preflight check
+    pub preflight: Option<GasPolicyVerdict>,
+    /// Estimated cost of the `process` transaction, if metadata could be built
+    pub tx_cost_estimate: Option<TxCostEstimateReport>,
+    /// Verdict for the estimated cost of the `process` transaction
+    pub verdict: Option<GasPolicyVerdict>,
+    /// Hard limit on transaction gas for the destination, if any
+    pub transaction_gas_limit: Option<U256>,
+    /// Whether the gas limit allowed by the policy exceeds `transaction_gas_limit`
+    pub exceeds_transaction_gas_limit: bool,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasPolicyVerdict {
+    NoPaymentFound,
+    PolicyNotMet,
+    PolicyMet { gas_limit: U256 },
+}
+
+impl From<GasPolicyStatus> for GasPolicyVerdict {
+    fn from(status: GasPolicyStatus) -> Self {
+        match status {
+            GasPolicyStatus::NoPaymentFound => Self::NoPaymentFound,
+            GasPolicyStatus::PolicyNotMet => Self::PolicyNotMet,
+            GasPolicyStatus::PolicyMet(gas_limit) => Self::PolicyMet { gas_limit },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TxCostEstimateReport {
+    pub gas_limit: U256,
+    pub gas_price: String,
+    pub l2_gas_limit: Option<U256>,
+}
+
+impl From<&TxCostEstimate> for TxCostEstimateReport {
+    fn from(estimate: &TxCostEstimate) -> Self {
+        Self {
+            gas_limit: estimate.gas_limit,
+            gas_price: estimate.gas_price.to_string(),
+            l2_gas_limit: estimate.l2_gas_limit,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IsmReport {
+    /// Index of the message in the origin merkle tree, if it was indexed
+    pub merkle_leaf_index: Option<u32>,
+    /// The recipient ISM, along with the ISMs it defers to for this message
+    pub root: Option<IsmNode>,
+    /// Whether metadata could be built. Metadata is only built if the message
+    /// passes the gas policy preflight check.
+    pub metadata_built: bool,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IsmNode {
+    pub address: H256,
+    pub module_type: Option<ModuleType>,
+    /// Threshold of aggregation and multisig ISMs
+    pub threshold: Option<u8>,
+    /// The route taken by a routing ISM, or the modules of an aggregation ISM
+    pub children: Vec<IsmNode>,
+    /// The validators of a multisig ISM
+    pub validators: Vec<ValidatorReport>,
+    pub error: Option<String>,
+}
+
+impl IsmNode {
+    fn new(address: H256) -> Self {
+        Self {
+            address,
+            module_type: None,
+            threshold: None,
+            children: vec![],
+            validators: vec![],
+            error: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ValidatorReport {
+    pub validator: H256,
+    /// Latest checkpoint index the validator has published, if it could be fetched
+    pub latest_index: Option<u32>,
+    /// Whether the latest checkpoint covers the message, if its leaf index is known
+    pub has_checkpoint: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OperationReport {
+    /// Whether the message is currently in the prepare queue
+    pub in_prepare_queue: bool,
+    pub status: Option<PendingOperationStatus>,
+    pub last_reprepare_reason: Option<ReprepareReason>,
+    pub retries: u32,
+    /// Seconds until the next prepare attempt, if the message is in the prepare queue
+    pub next_attempt_in_secs: Option<u64>,
+    pub errors: Vec<String>,
+}
+
+/// Run the checks a message goes through before being submitted, and report
+/// their outcome
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let PathParams { message_id } = path_params;
+
+    tracing::debug!(?message_id, "Explaining message");
+
+    let Some(message) = find_message(&state, &message_id)? else {
+        return Err(ServerErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            ServerErrorBody {
+                message: "Message not found".to_string(),
+            },
+        ));
+    };
+    let Some(ctx) = state.msg_ctxs.get(&(message.origin, message.destination)) else {
+        return Err(ServerErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            ServerErrorBody {
+                message: "Message is not on a route served by this relayer".to_string(),
+            },
+        ));
+    };
+
+    let filter = explain_filter(&state, &message).await;
+    let delivery = explain_delivery(ctx, &message).await;
+    let (ism, gas_policy) = explain_ism_and_gas_policy(ctx, &message).await;
+    let operation = explain_operation(&state, ctx, &message).await;
+
+    let resp = ResponseBody {
+        message_id,
+        origin: message.origin,
+        destination: message.destination,
+        nonce: message.nonce,
+        filter,
+        delivery,
+        gas_policy,
+        ism,
+        operation,
+    };
+    Ok(ServerSuccessResponse::new(resp))
+}
+
+fn find_message(state: &ServerState, message_id: &H256) -> ServerResult<Option<HyperlaneMessage>> {
+    for ctx in state.msg_ctxs.values() {
+        let message = ctx
+            .origin_db
+            .retrieve_message_by_id(message_id)
+            .map_err(|err| {
+                let error_msg = "Failed to fetch message";
+                tracing::debug!(?message_id, ?err, "{error_msg}");
+                ServerErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ServerErrorBody {
+                        message: error_msg.to_string(),
+                    },
+                )
+            })?;
+        if message.is_some() {
+            return Ok(message);
+        }
+    }
+    Ok(None)
+}
+
+async fn explain_filter(state: &ServerState, message: &HyperlaneMessage) -> FilterReport {
+    let (whitelisted, blacklisted) = match &state.message_filter {
+        Some(filter) => {
+            let filter = filter.read().await;
+            (
+                filter.whitelist().msg_matches(message, true),
+                filter.blacklist().msg_matches(message, false),
+            )
+        }
+        None => (true, false),
+    };
+    let blacklisted_address = state
+        .address_blacklist
+        .as_ref()
+        .and_then(|blacklist| blacklist.find_blacklisted_address(message))
+        .map(|address| format!("0x{}", hex::encode(address)));
+    FilterReport {
+        whitelisted,
+        blacklisted,
+        blacklisted_address,
+    }
+}
+
+async fn explain_delivery(ctx: &MessageContext, message: &HyperlaneMessage) -> DeliveryReport {
+    let mut report = DeliveryReport::default();
+    match ctx.origin_db.retrieve_processed_by_nonce(&message.nonce) {
+        Ok(processed) => report.processed = processed.unwrap_or_default(),
+        Err(err) => report
+            .errors
+            .push(format!("Failed to fetch processed status: {err}")),
+    }
+    match ctx.destination_mailbox.delivered(message.id()).await {
+        Ok(delivered) => report.delivered = Some(delivered),
+        Err(err) => report
+            .errors
+            .push(format!("Failed to check delivery status: {err}")),
+    }
+    let provider = ctx.destination_mailbox.provider();
+    match provider.is_contract(&message.recipient).await {
+        Ok(is_contract) => report.recipient_is_contract = Some(is_contract),
+        Err(err) => report
+            .errors
+            .push(format!("Failed to check if recipient is a contract: {err}")),
+    }
+    report
+}
+
+/// The ISM tree and the gas policy are explained together, since the cost of
+/// the transaction can only be estimated once metadata has been built.
+async fn explain_ism_and_gas_policy(
+    ctx: &MessageContext,
+    message: &HyperlaneMessage,
+) -> (IsmReport, GasPolicyReport) {
+    let mut ism = IsmReport::default();
+    let mut gas_policy = GasPolicyReport {
+        transaction_gas_limit: ctx.transaction_gas_limit,
+        ..Default::default()
+    };
+
+    let gas_payment_key = GasPaymentKey {
+        message_id: message.id(),
+        destination: message.destination,
+    };
+    match ctx
+        .origin_db
+        .retrieve_interchain_gas_payment_data_by_gas_payment_key(&gas_payment_key)
+    {
+        Ok(payment) => {
+            let payment = payment.unwrap_or_default();
+            gas_policy.payment = payment.payment;
+            gas_policy.gas_amount = payment.gas_amount;
+        }
+        Err(err) => gas_policy
+            .errors
+            .push(format!("Failed to fetch gas payment: {err}")),
+    }
+    match ctx
+        .origin_db
+        .retrieve_interchain_gas_expenditure_data_by_message_id(&message.id())
+    {
+        Ok(expenditure) => {
+            let expenditure = expenditure.unwrap_or_default();
+            gas_policy.tokens_used = expenditure.tokens_used;
+            gas_policy.gas_used = expenditure.gas_used;
+        }
+        Err(err) => gas_policy
+            .errors
+            .push(format!("Failed to fetch gas expenditure: {err}")),
+    }
+
+    let zero_cost = TxCostEstimate {
+        gas_limit: U256::zero(),
+        gas_price: FixedPointNumber::zero(),
+        l2_gas_limit: None,
+    };
+    match gas_policy_verdict(ctx, message, &zero_cost).await {
+        Ok(verdict) => gas_policy.preflight = Some(verdict),
+        Err(err) => gas_policy.errors.push(err),
+    }
+
+    match ctx
+        .metadata_builder
+        .get_merkle_leaf_id_by_message_id(message.id())
+        .await
+    {
+        Ok(leaf_index) => ism.merkle_leaf_index = leaf_index,
+        Err(err) => ism
+            .errors
+            .push(format!("Failed to fetch merkle leaf index: {err}")),
+    }
+    let ism_address = match ctx
+        .destination_mailbox
+        .recipient_ism(message.recipient)
+        .await
+    {
+        Ok(ism_address) => ism_address,
+        Err(err) => {
+            ism.errors
+                .push(format!("Failed to fetch recipient ISM: {err}"));
+            return (ism, gas_policy);
+        }
+    };
+    ism.root = Some(
+        explain_ism_node(
+            ctx.metadata_builder.as_ref(),
+            ism_address,
+            message,
+            ism.merkle_leaf_index,
+            0,
+        )
+        .await,
+    );
+
+    // Like `prepare`, only build metadata if the message may meet the gas policy
+    if !matches!(
+        gas_policy.preflight,
+        Some(GasPolicyVerdict::PolicyMet { .. })
+    ) {
+        return (ism, gas_policy);
+    }
+    let metadata = match build_metadata(ctx, ism_address, message).await {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            ism.errors.push(format!("Failed to build metadata: {err}"));
+            return (ism, gas_policy);
+        }
+    };
+    ism.metadata_built = true;
+
+    let tx_cost_estimate = match ctx
+        .destination_mailbox
+        .process_estimate_costs(message, &metadata)
+        .await
+    {
+        Ok(tx_cost_estimate) => tx_cost_estimate,
+        Err(err) => {
+            gas_policy
+                .errors
+                .push(format!("Failed to estimate transaction cost: {err}"));
+            return (ism, gas_policy);
+        }
+    };
+    gas_policy.tx_cost_estimate = Some((&tx_cost_estimate).into());
+    match gas_policy_verdict(ctx, message, &tx_cost_estimate).await {
+        Ok(verdict) => {
+            if let (GasPolicyVerdict::PolicyMet { gas_limit }, Some(max_limit)) =
+                (&verdict, ctx.transaction_gas_limit)
+            {
+                gas_policy.exceeds_transaction_gas_limit = *gas_limit > max_limit;
+            }
+            gas_policy.verdict = Some(verdict);
+        }
+        Err(err) => gas_policy.errors.push(err),
+    }
+    (ism, gas_policy)
+}
+
+async fn gas_policy_verdict(
+    ctx: &MessageContext,
+    message: &HyperlaneMessage,
+    tx_cost_estimate: &TxCostEstimate,
+) -> Result<GasPolicyVerdict, String> {
+    ctx.origin_gas_payment_enforcer
+        .read()
+        .await
+        .message_meets_gas_payment_requirement(message, tx_cost_estimate)
+        .await
+        .map(Into::into)
+        .map_err(|err| format!("Failed to evaluate gas policy: {err}"))
+}
+
+async fn build_metadata(
+    ctx: &MessageContext,
+    ism_address: H256,
+    message: &HyperlaneMessage,
+) -> Result<Vec<u8>, String> {
+    let builder = MessageMetadataBuilder::new(ctx.metadata_builder.clone(), ism_address, message)
+        .await
+        .map_err(|err| err.to_string())?;
+    builder
+        .build(ism_address, message, MessageMetadataBuildParams::default())
+        .await
+        .map(|metadata| metadata.to_vec())
+        .map_err(|err| err.to_string())
+}
+
+/// Resolve the ISM at `address` and the ISMs it defers to for `message`
+fn explain_ism_node<'a>(
+    base: &'a dyn BuildsBaseMetadata,
+    address: H256,
+    message: &'a HyperlaneMessage,
+    leaf_index: Option<u32>,
+    depth: u32,
+) -> BoxFuture<'a, IsmNode> {
+    Box::pin(async move {
+        let mut node = IsmNode::new(address);
+        if depth >= ISM_MAX_DEPTH {
+            node.error = Some(format!("Max ISM depth of {ISM_MAX_DEPTH} reached"));
+            return node;
+        }
+        if let Err(err) = expand_ism_node(base, &mut node, message, leaf_index, depth).await {
+            node.error = Some(err.to_string());
+        }
+        node
+    })
+}
+
+async fn expand_ism_node(
+    base: &dyn BuildsBaseMetadata,
+    node: &mut IsmNode,
+    message: &HyperlaneMessage,
+    leaf_index: Option<u32>,
+    depth: u32,
+) -> eyre::Result<()> {
+    let module_type = base.build_ism(node.address).await?.module_type().await?;
+    node.module_type = Some(module_type);
+    match module_type {
+        ModuleType::Routing => {
+            let route = base
+                .build_routing_ism(node.address)
+                .await?
+                .route(message)
+                .await?;
+            let child = explain_ism_node(base, route, message, leaf_index, depth + 1).await;
+            node.children.push(child);
+        }
+        ModuleType::Aggregation => {
+            let (modules, threshold) = base
+                .build_aggregation_ism(node.address)
+                .await?
+                .modules_and_threshold(message)
+                .await?;
+            node.threshold = Some(threshold);
+            for module in modules {
+                let child = explain_ism_node(base, module, message, leaf_index, depth + 1).await;
+                node.children.push(child);
+            }
+        }
+        ModuleType::MerkleRootMultisig | ModuleType::MessageIdMultisig => {
+            let (validators, threshold) = base
+                .build_multisig_ism(node.address)
+                .await?
+                .validators_and_threshold(message)
+                .await?;
+            node.threshold = Some(threshold);
+            node.validators = explain_validators(base, message, &validators, leaf_index).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn explain_validators(
+    base: &dyn BuildsBaseMetadata,
+    message: &HyperlaneMessage,
+    validators: &[H256],
+    leaf_index: Option<u32>,
+) -> eyre::Result<Vec<ValidatorReport>> {
+    let checkpoint_syncer = base
+        .build_checkpoint_syncer(message, validators, None)
+        .await?;
+    let latest_indices: HashMap<H160, u32> = checkpoint_syncer
+        .get_validator_latest_checkpoints_and_update_metrics(
+            validators,
+            base.origin_domain(),
+            base.destination_domain(),
+        )
+        .await?
+        .into_iter()
+        .collect();
+    let reports = validators
+        .iter()
+        .map(|validator| {
+            let latest_index = latest_indices.get(&H160::from(*validator)).copied();
+            ValidatorReport {
+                validator: *validator,
+                latest_index,
+                has_checkpoint: leaf_index
+                    .map(|leaf_index| latest_index.is_some_and(|index| index >= leaf_index)),
+            }
+        })
+        .collect();
+    Ok(reports)
+}
+
+async fn explain_operation(
+    state: &ServerState,
+    ctx: &MessageContext,
+    message: &HyperlaneMessage,
+) -> OperationReport {
+    let message_id = message.id();
+    if let Some(queue) = state.op_queues.get(&message.destination) {
+        let queue = queue.lock().await;
+        if let Some(op) = queue.iter().find(|op| op.id() == message_id) {
+            let status = op.status();
+            return OperationReport {
+                in_prepare_queue: true,
+                last_reprepare_reason: reprepare_reason(&status),
+                status: Some(status),
+                retries: op.get_retries(),
+                next_attempt_in_secs: op
+                    .next_attempt_after()
+                    .map(|after| after.saturating_duration_since(Instant::now()).as_secs()),
+                errors: vec![],
+            };
+        }
+    }
+
+    // The operation is being submitted or confirmed, or was dropped, so only
+    // what was persisted is known
+    let mut report = OperationReport::default();
+    match ctx.origin_db.retrieve_status_by_message_id(&message_id) {
+        Ok(status) => {
+            report.last_reprepare_reason = status.as_ref().and_then(reprepare_reason);
+            report.status = status;
+        }
+        Err(err) => report.errors.push(format!("Failed to fetch status: {err}")),
+    }
+    match ctx
+        .origin_db
+        .retrieve_pending_message_retry_count_by_message_id(&message_id)
+    {
+        Ok(retries) => report.retries = retries.unwrap_or_default(),
+        Err(err) => report
+            .errors
+            .push(format!("Failed to fetch retry count: {err}")),
+    }
+    report
+}
+
+fn reprepare_reason(status: &PendingOperationStatus) -> Option<ReprepareReason> {
+    match status {
+        PendingOperationStatus::Retry(reason) => Some(reason.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{self, Body},
+        http::{header::CONTENT_TYPE, Request, Response},
+    };
+    use tower::ServiceExt;
+
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use crate::test_utils::{
+        mock_aggregation_ism::MockAggregationIsm, mock_base_builder::build_mock_base_builder,
+        mock_ism::MockInterchainSecurityModule, mock_routing_ism::MockRoutingIsm,
+    };
+
+    use super::*;
+
+    async fn send_request(state: ServerState, message_id: H256) -> Response<Body> {
+        let api_url = format!("/messages/{message_id:?}/explain");
+        let request = Request::builder()
+            .uri(api_url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body::Body::empty())
+            .expect("Failed to build request");
+        state
+            .router()
+            .oneshot(request)
+            .await
+            .expect("Failed to send request")
+    }
+
+    #[tokio::test]
+    async fn test_explain_unknown_message() {
+        let state = ServerState::new(HashMap::new(), None, None, HashMap::new());
+
+        let response = send_request(state, H256::from_low_u64_be(1)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_explain_ism_tree() {
+        let origin = HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum);
+        let destination = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let base_builder = build_mock_base_builder(origin, destination.clone());
+
+        let routing_address = H256::from_low_u64_be(1);
+        let aggregation_address = H256::from_low_u64_be(2);
+        let null_address = H256::from_low_u64_be(3);
+        let unreachable_address = H256::from_low_u64_be(4);
+        for (address, module_type) in [
+            (routing_address, ModuleType::Routing),
+            (aggregation_address, ModuleType::Aggregation),
+            (null_address, ModuleType::Null),
+        ] {
+            let ism = MockInterchainSecurityModule::new(address, destination.clone(), module_type);
+            base_builder
+                .responses
+                .push_build_ism_response(address, Ok(Box::new(ism)));
+        }
+        base_builder
+            .responses
+            .push_build_ism_response(unreachable_address, Err(eyre::eyre!("RPC error")));
+
+        let routing_ism = MockRoutingIsm::new(routing_address, destination.clone());
+        routing_ism
+            .responses
+            .route
+            .lock()
+            .unwrap()
+            .push_back(Ok(aggregation_address));
+        base_builder
+            .responses
+            .push_build_routing_ism_response(routing_address, Ok(Box::new(routing_ism)));
+
+        let aggregation_ism = MockAggregationIsm::new(aggregation_address, destination.clone());
+        aggregation_ism
+            .responses
+            .modules_and_threshold
+            .lock()
+            .unwrap()
+            .push_back(Ok((vec![null_address, unreachable_address], 1)));
+        base_builder.responses.push_build_aggregation_ism_response(
+            aggregation_address,
+            Ok(Box::new(aggregation_ism)),
+        );
+
+        let message = HyperlaneMessage::default();
+        let root = explain_ism_node(&base_builder, routing_address, &message, None, 0).await;
+
+        assert_eq!(root.module_type, Some(ModuleType::Routing));
+        assert_eq!(root.children.len(), 1);
+        let aggregation = &root.children[0];
+        assert_eq!(aggregation.address, aggregation_address);
+        assert_eq!(aggregation.module_type, Some(ModuleType::Aggregation));
+        assert_eq!(aggregation.threshold, Some(1));
+        assert_eq!(aggregation.children.len(), 2);
+        assert_eq!(aggregation.children[0].module_type, Some(ModuleType::Null));
+        assert!(aggregation.children[0].error.is_none());
+        assert_eq!(aggregation.children[1].module_type, None);
+        assert!(aggregation.children[1].error.is_some());
+    }
+}
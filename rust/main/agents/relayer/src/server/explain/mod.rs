@@ -0,0 +1,32 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{routing::get, Router};
+use derive_new::new;
+
+use crate::msg::{
+    blacklist::AddressBlacklist, message_filter::SharedMessageFilter,
+    op_queue::OperationPriorityQueue, pending_message::MessageContext,
+};
+
+pub mod explain_message;
+
+#[derive(Clone, new)]
+pub struct ServerState {
+    /// Message contexts keyed by (origin domain id, destination domain id)
+    pub msg_ctxs: HashMap<(u32, u32), Arc<MessageContext>>,
+    pub message_filter: Option<SharedMessageFilter>,
+    pub address_blacklist: Option<Arc<AddressBlacklist>>,
+    /// Prepare queues keyed by destination domain id
+    pub op_queues: HashMap<u32, OperationPriorityQueue>,
+}
+
+impl ServerState {
+    pub fn router(self) -> Router {
+        Router::new()
+            .route(
+                "/messages/{message_id}/explain",
+                get(explain_message::handler),
+            )
+            .with_state(self)
+    }
+}
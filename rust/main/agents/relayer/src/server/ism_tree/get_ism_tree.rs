@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+
+use hyperlane_base::server::utils::{
+    ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse,
+};
+use hyperlane_core::H256;
+
+use crate::{msg::metadata::ResolvedIsmTree, server::ism_tree::ServerState};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathParams {
+    pub destination: u32,
+    pub recipient: H256,
+}
+
+/// Return the resolved ISM tree of a recipient, resolving it if it is not cached
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+) -> ServerResult<ServerSuccessResponse<ResolvedIsmTree>> {
+    let PathParams {
+        destination,
+        recipient,
+    } = path_params;
+
+    tracing::debug!(destination, ?recipient, "Fetching ISM tree");
+
+    let Some(ctx) = state.destination_context(destination) else {
+        return Err(ServerErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            ServerErrorBody {
+                message: "Destination is not served by this relayer".to_string(),
+            },
+        ));
+    };
+    let root = ctx
+        .destination_mailbox
+        .recipient_ism(recipient)
+        .await
+        .map_err(|err| {
+            let error_msg = "Failed to fetch recipient ISM";
+            tracing::debug!(?recipient, ?err, "{error_msg}");
+            ServerErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ServerErrorBody {
+                    message: error_msg.to_string(),
+                },
+            )
+        })?;
+
+    let tree = match &ctx.ism_trees {
+        Some(ism_trees) => ism_trees
+            .get_or_resolve(
+                ctx.metadata_builder.as_ref(),
+                destination,
+                recipient,
+                root,
+                &state.origins(destination),
+            )
+            .await
+            .as_ref()
+            .clone(),
+        // Without caching, the tree is resolved for this request only
+        None => {
+            ResolvedIsmTree::resolve(
+                ctx.metadata_builder.as_ref(),
+                destination,
+                recipient,
+                root,
+                state.origins(destination),
+                Duration::ZERO,
+            )
+            .await
+        }
+    };
+    Ok(ServerSuccessResponse::new(tree))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{
+        body::{self, Body},
+        http::{header::CONTENT_TYPE, Request, Response},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send_request(state: ServerState, destination: u32, recipient: H256) -> Response<Body> {
+        let api_url = format!("/ism/{destination}/{recipient:?}");
+        let request = Request::builder()
+            .uri(api_url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body::Body::empty())
+            .expect("Failed to build request");
+        state
+            .router()
+            .oneshot(request)
+            .await
+            .expect("Failed to send request")
+    }
+
+    #[tokio::test]
+    async fn test_get_ism_tree_unknown_destination() {
+        let state = ServerState::new(HashMap::new());
+
+        let response = send_request(state, 1, H256::from_low_u64_be(1)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
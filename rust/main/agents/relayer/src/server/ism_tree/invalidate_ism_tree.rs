@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::server::utils::{
+    ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse,
+};
+
+use crate::server::ism_tree::{get_ism_tree::PathParams, ServerState};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResponseBody {
+    /// Whether a resolved tree was cached for the recipient
+    pub invalidated: bool,
+}
+
+/// Drop the cached ISM tree of a recipient, so it is resolved again for its next message
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let PathParams {
+        destination,
+        recipient,
+    } = path_params;
+
+    let Some(ctx) = state.destination_context(destination) else {
+        return Err(ServerErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            ServerErrorBody {
+                message: "Destination is not served by this relayer".to_string(),
+            },
+        ));
+    };
+    let invalidated = ctx
+        .ism_trees
+        .as_ref()
+        .map(|ism_trees| ism_trees.invalidate(destination, recipient))
+        .unwrap_or_default();
+
+    tracing::debug!(destination, ?recipient, invalidated, "Invalidated ISM tree");
+
+    let resp = ResponseBody { invalidated };
+    Ok(ServerSuccessResponse::new(resp))
+}
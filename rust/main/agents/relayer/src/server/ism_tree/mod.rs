@@ -0,0 +1,51 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+use derive_new::new;
+
+use crate::msg::pending_message::MessageContext;
+
+pub mod get_ism_tree;
+pub mod invalidate_ism_tree;
+
+#[derive(Clone, new)]
+pub struct ServerState {
+    /// Message contexts keyed by (origin domain id, destination domain id)
+    pub msg_ctxs: HashMap<(u32, u32), Arc<MessageContext>>,
+}
+
+impl ServerState {
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/ism/{destination}/{recipient}", get(get_ism_tree::handler))
+            .route(
+                "/ism/{destination}/{recipient}",
+                delete(invalidate_ism_tree::handler),
+            )
+            .with_state(self)
+    }
+
+    /// A message context of `destination`, to query its ISMs with
+    fn destination_context(&self, destination: u32) -> Option<&Arc<MessageContext>> {
+        self.msg_ctxs
+            .iter()
+            .filter(|((_, dest), _)| *dest == destination)
+            .min_by_key(|((origin, _), _)| *origin)
+            .map(|(_, ctx)| ctx)
+    }
+
+    /// Origins relayed to `destination`
+    fn origins(&self, destination: u32) -> Vec<u32> {
+        let mut origins: Vec<_> = self
+            .msg_ctxs
+            .keys()
+            .filter(|(_, dest)| *dest == destination)
+            .map(|(origin, _)| *origin)
+            .collect();
+        origins.sort_unstable();
+        origins
+    }
+}
@@ -0,0 +1,220 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::server::utils::{
+    ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse,
+};
+
+use crate::{
+    msg::message_filter::MatchingListKind, server::message_filter::ServerState,
+    settings::matching_list::MatchingList,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathParams {
+    pub list: MatchingListKind,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RequestBody {
+    pub matching_list: MatchingList,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResponseBody {}
+
+/// Add entries to the message whitelist or blacklist. The entries are
+/// persisted and take effect immediately, including for operations that are
+/// already waiting to be prepared and for messages that were skipped before.
+/// Whitelist entries have no effect while the relayer settings configure no
+/// whitelist, since every message is then allowed.
+///
+/// Example request to `/message_filter/blacklist`
+/// ```json
+/// {
+///     "matching_list": [
+///         {
+///             "destinationdomain": 100
+///         }
+///     ]
+/// }
+/// ```
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+    Json(payload): Json<RequestBody>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let PathParams { list } = path_params;
+    let RequestBody { matching_list } = payload;
+
+    let Some(entries) = matching_list.0 else {
+        return Err(ServerErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            ServerErrorBody {
+                message: "matching_list must not be empty".to_string(),
+            },
+        ));
+    };
+
+    tracing::debug!(?list, ?entries, "Adding message filter entries");
+
+    state
+        .message_filter
+        .write()
+        .await
+        .add_entries(list, entries)
+        .map_err(|err| {
+            let error_msg = "Failed to add message filter entries";
+            tracing::debug!(?list, ?err, "{error_msg}");
+            ServerErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ServerErrorBody {
+                    message: error_msg.to_string(),
+                },
+            )
+        })?;
+
+    let resp = ResponseBody {};
+    Ok(ServerSuccessResponse::new(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, Method, Request, Response},
+        Router,
+    };
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use hyperlane_base::db::{HyperlaneRocksDB, DB};
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::msg::message_filter::{MessageFilter, SharedMessageFilter};
+
+    #[derive(Debug)]
+    struct TestServerSetup {
+        pub app: Router,
+        pub message_filter: SharedMessageFilter,
+        pub db: HyperlaneRocksDB,
+        _temp_dir: tempfile::TempDir,
+    }
+
+    fn setup_test_server() -> TestServerSetup {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = DB::from_path(temp_dir.path()).unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let db = HyperlaneRocksDB::new(&domain, db);
+
+        let message_filter =
+            MessageFilter::new(Default::default(), Default::default(), vec![db.clone()]).unwrap();
+        let message_filter = Arc::new(RwLock::new(message_filter));
+
+        let server_state = ServerState::new(message_filter.clone());
+        let app = server_state.router();
+
+        TestServerSetup {
+            app,
+            message_filter,
+            db,
+            _temp_dir: temp_dir,
+        }
+    }
+
+    async fn send_request(app: Router, list: &str, body: &str) -> Response<Body> {
+        let api_url = format!("/message_filter/{list}");
+        let request = Request::builder()
+            .uri(api_url)
+            .method(Method::POST)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to send request");
+        response
+    }
+
+    #[tokio::test]
+    async fn test_add_message_filter_entries_happy_path() {
+        let TestServerSetup {
+            app,
+            message_filter,
+            db,
+            _temp_dir,
+        } = setup_test_server();
+
+        let body = r#"{
+            "matching_list": [
+                {
+                    "destinationdomain": 100
+                }
+            ]
+        }"#;
+        let response = send_request(app, "blacklist", body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let expected = MatchingList::with_destination_domain(100).0.unwrap();
+        assert_eq!(
+            message_filter
+                .read()
+                .await
+                .dynamic_entries(MatchingListKind::Blacklist),
+            expected.as_slice()
+        );
+        assert!(message_filter
+            .read()
+            .await
+            .dynamic_entries(MatchingListKind::Whitelist)
+            .is_empty());
+
+        // entries are restored from the db
+        let restored =
+            MessageFilter::new(Default::default(), Default::default(), vec![db]).unwrap();
+        assert_eq!(
+            restored.dynamic_entries(MatchingListKind::Blacklist),
+            expected.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_message_filter_entries_empty_list() {
+        let TestServerSetup {
+            app,
+            message_filter,
+            ..
+        } = setup_test_server();
+
+        let body = r#"{
+            "matching_list": []
+        }"#;
+        let response = send_request(app, "whitelist", body).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(message_filter
+            .read()
+            .await
+            .dynamic_entries(MatchingListKind::Whitelist)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_message_filter_entries_unknown_list() {
+        let TestServerSetup { app, .. } = setup_test_server();
+
+        let body = r#"{
+            "matching_list": [
+                {
+                    "destinationdomain": 100
+                }
+            ]
+        }"#;
+        let response = send_request(app, "greylist", body).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
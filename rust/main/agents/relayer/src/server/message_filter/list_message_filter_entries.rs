@@ -0,0 +1,113 @@
+use axum::extract::{Path, State};
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::server::utils::{ServerResult, ServerSuccessResponse};
+
+use crate::{
+    msg::message_filter::MatchingListKind, server::message_filter::ServerState,
+    settings::matching_list::ListElement,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathParams {
+    pub list: MatchingListKind,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ResponseBody {
+    /// Entries from the relayer settings, which cannot be removed at runtime
+    pub configured: Vec<ListElement>,
+    /// Entries added at runtime, indexed the same way as for removal
+    pub dynamic: Vec<ListElement>,
+}
+
+/// Get the entries of the message whitelist or blacklist
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let PathParams { list } = path_params;
+
+    let message_filter = state.message_filter.read().await;
+    let resp = ResponseBody {
+        configured: message_filter.configured_entries(list).to_vec(),
+        dynamic: message_filter.dynamic_entries(list).to_vec(),
+    };
+    Ok(ServerSuccessResponse::new(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, Method, Request, Response, StatusCode},
+        Router,
+    };
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        msg::message_filter::MessageFilter, settings::matching_list::MatchingList,
+        test_utils::request::parse_body_to_json,
+    };
+
+    fn setup_test_server() -> Router {
+        let mut message_filter = MessageFilter::new(
+            MatchingList::with_destination_domain(100),
+            Default::default(),
+            vec![],
+        )
+        .unwrap();
+        message_filter
+            .add_entries(
+                MatchingListKind::Whitelist,
+                MatchingList::with_destination_domain(200).0.unwrap(),
+            )
+            .unwrap();
+
+        let server_state = ServerState::new(Arc::new(RwLock::new(message_filter)));
+        server_state.router()
+    }
+
+    async fn send_request(app: Router, list: &str) -> Response<Body> {
+        let api_url = format!("/message_filter/{list}");
+        let request = Request::builder()
+            .uri(api_url)
+            .method(Method::GET)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to send request");
+        response
+    }
+
+    #[tokio::test]
+    async fn test_list_message_filter_entries_happy_path() {
+        let app = setup_test_server();
+
+        let response = send_request(app.clone(), "whitelist").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let resp_body: ResponseBody = parse_body_to_json(response.into_body()).await;
+        assert_eq!(
+            resp_body,
+            ResponseBody {
+                configured: MatchingList::with_destination_domain(100).0.unwrap(),
+                dynamic: MatchingList::with_destination_domain(200).0.unwrap(),
+            }
+        );
+
+        let response = send_request(app, "blacklist").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let resp_body: ResponseBody = parse_body_to_json(response.into_body()).await;
+        assert_eq!(
+            resp_body,
+            ResponseBody {
+                configured: vec![],
+                dynamic: vec![],
+            }
+        );
+    }
+}
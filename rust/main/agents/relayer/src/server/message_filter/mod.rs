@@ -0,0 +1,35 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use derive_new::new;
+
+use crate::msg::message_filter::SharedMessageFilter;
+
+pub mod add_message_filter_entries;
+pub mod list_message_filter_entries;
+pub mod remove_message_filter_entry;
+
+#[derive(Clone, Debug, new)]
+pub struct ServerState {
+    pub message_filter: SharedMessageFilter,
+}
+
+impl ServerState {
+    pub fn router(self) -> Router {
+        Router::new()
+            .route(
+                "/message_filter/{list}",
+                post(add_message_filter_entries::handler),
+            )
+            .route(
+                "/message_filter/{list}",
+                get(list_message_filter_entries::handler),
+            )
+            .route(
+                "/message_filter/{list}/{index}",
+                delete(remove_message_filter_entry::handler),
+            )
+            .with_state(self)
+    }
+}
@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::server::utils::{
+    ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse,
+};
+
+use crate::{msg::message_filter::MatchingListKind, server::message_filter::ServerState};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathParams {
+    pub list: MatchingListKind,
+    pub index: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResponseBody {}
+
+/// Remove an entry that was added at runtime from the message whitelist or
+/// blacklist, based on its index. Entries from the relayer settings cannot be
+/// removed. Messages that were skipped because of the entry are picked up
+/// again without a restart.
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let PathParams { list, index } = path_params;
+
+    tracing::debug!(?list, index, "Removing message filter entry");
+
+    let removed = state
+        .message_filter
+        .write()
+        .await
+        .remove_entry(list, index)
+        .map_err(|err| {
+            let error_msg = "Failed to remove message filter entry";
+            tracing::debug!(?list, index, ?err, "{error_msg}");
+            ServerErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ServerErrorBody {
+                    message: error_msg.to_string(),
+                },
+            )
+        })?;
+
+    if removed.is_none() {
+        return Err(ServerErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            ServerErrorBody {
+                message: "No message filter entry found at index".to_string(),
+            },
+        ));
+    }
+
+    let resp = ResponseBody {};
+    Ok(ServerSuccessResponse::new(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, Method, Request, Response},
+        Router,
+    };
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use hyperlane_base::db::{HyperlaneRocksDB, DB};
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain};
+
+    use super::*;
+    use crate::{
+        msg::message_filter::{MessageFilter, SharedMessageFilter},
+        settings::matching_list::MatchingList,
+    };
+
+    #[derive(Debug)]
+    struct TestServerSetup {
+        pub app: Router,
+        pub message_filter: SharedMessageFilter,
+        pub db: HyperlaneRocksDB,
+        _temp_dir: tempfile::TempDir,
+    }
+
+    fn setup_test_server() -> TestServerSetup {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = DB::from_path(temp_dir.path()).unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum);
+        let db = HyperlaneRocksDB::new(&domain, db);
+
+        let mut message_filter =
+            MessageFilter::new(Default::default(), Default::default(), vec![db.clone()]).unwrap();
+        for destination in [100, 200] {
+            message_filter
+                .add_entries(
+                    MatchingListKind::Blacklist,
+                    MatchingList::with_destination_domain(destination)
+                        .0
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+        let message_filter = Arc::new(RwLock::new(message_filter));
+
+        let server_state = ServerState::new(message_filter.clone());
+        let app = server_state.router();
+
+        TestServerSetup {
+            app,
+            message_filter,
+            db,
+            _temp_dir: temp_dir,
+        }
+    }
+
+    async fn send_request(app: Router, list: &str, index: usize) -> Response<Body> {
+        let api_url = format!("/message_filter/{list}/{index}");
+        let request = Request::builder()
+            .uri(api_url)
+            .method(Method::DELETE)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to send request");
+        response
+    }
+
+    #[tokio::test]
+    async fn test_remove_message_filter_entry_happy_path() {
+        let TestServerSetup {
+            app,
+            message_filter,
+            db,
+            _temp_dir,
+        } = setup_test_server();
+
+        let response = send_request(app, "blacklist", 0).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let expected = MatchingList::with_destination_domain(200).0.unwrap();
+        assert_eq!(
+            message_filter
+                .read()
+                .await
+                .dynamic_entries(MatchingListKind::Blacklist),
+            expected.as_slice()
+        );
+
+        // the removal is persisted
+        let restored =
+            MessageFilter::new(Default::default(), Default::default(), vec![db]).unwrap();
+        assert_eq!(
+            restored.dynamic_entries(MatchingListKind::Blacklist),
+            expected.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_message_filter_entry_out_of_bounds() {
+        let TestServerSetup {
+            app,
+            message_filter,
+            ..
+        } = setup_test_server();
+
+        let response = send_request(app.clone(), "blacklist", 2).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = send_request(app, "whitelist", 0).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        assert_eq!(
+            message_filter
+                .read()
+                .await
+                .dynamic_entries(MatchingListKind::Blacklist)
+                .len(),
+            2
+        );
+    }
+}
@@ -10,17 +10,24 @@ use tokio::sync::broadcast::Sender;
 use hyperlane_base::db::HyperlaneRocksDB;
 use tokio::sync::RwLock;
 
+use crate::msg::blacklist::AddressBlacklist;
 use crate::msg::gas_payment::GasPaymentEnforcer;
+use crate::msg::message_filter::SharedMessageFilter;
 use crate::msg::op_queue::OperationPriorityQueue;
+use crate::msg::pending_message::MessageContext;
 use crate::server::environment_variable::EnvironmentVariableApi;
 
 pub const ENDPOINT_MESSAGES_QUEUE_SIZE: usize = 100;
 
 pub mod environment_variable;
+pub mod explain;
 pub mod igp;
+pub mod ism_tree;
 pub mod merkle_tree_insertions;
+pub mod message_filter;
 pub mod messages;
 pub mod operations;
+pub mod profitability;
 
 #[derive(new)]
 pub struct Server {
@@ -33,6 +40,12 @@ pub struct Server {
     dbs: Option<HashMap<u32, HyperlaneRocksDB>>,
     #[new(default)]
     gas_enforcers: Option<HashMap<HyperlaneDomain, Arc<RwLock<GasPaymentEnforcer>>>>,
+    #[new(default)]
+    message_filter: Option<SharedMessageFilter>,
+    #[new(default)]
+    address_blacklist: Option<Arc<AddressBlacklist>>,
+    #[new(default)]
+    msg_ctxs: Option<HashMap<(u32, u32), Arc<MessageContext>>>,
 }
 
 impl Server {
@@ -62,10 +75,43 @@ impl Server {
         self
     }
 
+    pub fn with_message_filter(mut self, message_filter: SharedMessageFilter) -> Self {
+        self.message_filter = Some(message_filter);
+        self
+    }
+
+    pub fn with_address_blacklist(mut self, address_blacklist: Arc<AddressBlacklist>) -> Self {
+        self.address_blacklist = Some(address_blacklist);
+        self
+    }
+
+    /// Message contexts keyed by (origin domain id, destination domain id)
+    pub fn with_message_contexts(
+        mut self,
+        msg_ctxs: HashMap<(u32, u32), Arc<MessageContext>>,
+    ) -> Self {
+        self.msg_ctxs = Some(msg_ctxs);
+        self
+    }
+
     // return a custom router that can be used in combination with other routers
     pub fn router(self) -> Router {
         let mut router = Router::new();
 
+        if let Some(msg_ctxs) = self.msg_ctxs {
+            router = router
+                .merge(
+                    explain::ServerState::new(
+                        msg_ctxs.clone(),
+                        self.message_filter.clone(),
+                        self.address_blacklist,
+                        self.op_queues.clone().unwrap_or_default(),
+                    )
+                    .router(),
+                )
+                .merge(ism_tree::ServerState::new(msg_ctxs).router())
+        }
+
         if let Some(tx) = self.retry_transmitter {
             router = router.merge(
                 operations::message_retry::ServerState::new(tx, self.destination_chains).router(),
@@ -78,10 +124,14 @@ impl Server {
             router = router
                 .merge(messages::ServerState::new(dbs.clone()).router())
                 .merge(merkle_tree_insertions::ServerState::new(dbs.clone()).router())
+                .merge(profitability::ServerState::new(dbs.clone()).router())
         }
         if let Some(gas_enforcers) = self.gas_enforcers {
             router = router.merge(igp::ServerState::new(gas_enforcers.clone()).router())
         }
+        if let Some(message_filter) = self.message_filter {
+            router = router.merge(message_filter::ServerState::new(message_filter).router())
+        }
 
         let expose_environment_variable_endpoint =
             env::var("HYPERLANE_RELAYER_ENVIRONMENT_VARIABLE_ENDPOINT_ENABLED")
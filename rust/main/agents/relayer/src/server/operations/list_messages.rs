@@ -71,10 +71,10 @@ pub async fn format_queue(queue: OperationPriorityQueue) -> String {
         .lock()
         .await
         .iter()
-        .map(|reverse| {
+        .map(|op| {
             (
-                reverse.0.get_retries(),
-                serde_json::to_value(OperationWithId::new(&reverse.0)),
+                op.get_retries(),
+                serde_json::to_value(OperationWithId::new(op)),
             )
         })
         .collect();
@@ -102,7 +102,7 @@ mod tests {
         body::Body,
         http::{Method, Request, StatusCode},
     };
-    use std::{cmp::Reverse, sync::Arc};
+    use std::sync::Arc;
     use tokio::sync::{self, Mutex};
     use tower::ServiceExt;
 
@@ -218,8 +218,8 @@ mod tests {
   }}
 ]"#
         );
-        op_queue.lock().await.push(Reverse(dummy_operation_1));
-        op_queue.lock().await.push(Reverse(dummy_operation_2));
+        op_queue.lock().await.push(dummy_operation_1);
+        op_queue.lock().await.push(dummy_operation_2);
 
         let api_url = format!(
             "{LIST_OPERATIONS_API_BASE}?destination_domain={}",
@@ -285,8 +285,8 @@ mod tests {
   }}
 ]"#
         );
-        op_queue.lock().await.push(Reverse(dummy_operation_1));
-        op_queue.lock().await.push(Reverse(dummy_operation_2));
+        op_queue.lock().await.push(dummy_operation_1);
+        op_queue.lock().await.push(dummy_operation_2);
 
         let api_url = format!(
             "{LIST_OPERATIONS_API_BASE}?destination_domain={}",
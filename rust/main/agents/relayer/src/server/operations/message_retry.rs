@@ -1,5 +1,17 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
 use axum::{extract::State, routing, Json, Router};
 use derive_new::new;
+use eyre::eyre;
+use hyperlane_core::{
+    ConfirmReason, PendingOperationStatus, QueueOperation, ReprepareReason, H256,
+};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast::Sender, mpsc};
 
@@ -26,15 +38,132 @@ impl ServerState {
 pub struct MessageRetryRequest {
     pub uuid: String,
     pub pattern: MatchingList,
+    pub options: MessageRetryOptions,
     pub transmitter: mpsc::Sender<MessageRetryQueueResponse>,
 }
 
+impl MessageRetryRequest {
+    /// Whether the operation matches both the pattern and the status selector
+    pub fn op_matches(&self, op: &QueueOperation) -> bool {
+        self.pattern.op_matches(op)
+            && self
+                .options
+                .status
+                .as_ref()
+                .map_or(true, |status| status.matches(&op.status()))
+    }
+}
+
+/// What is done with the operations matching a retry request.
+/// Ordered from the least to the most drastic, so that the most drastic action
+/// wins when several requests match the same operation.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageRetryAction {
+    /// Attempt the operations again as soon as possible
+    #[default]
+    Retry,
+    /// Attempt the operations again before any other operation in their queue
+    BumpToFront,
+    /// Remove the operations from their queue. They are not loaded again when
+    /// the relayer restarts.
+    Drop,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MessageRetryOptions {
+    pub action: MessageRetryAction,
+    /// If set, only operations whose status matches are affected
+    pub status: Option<StatusSelector>,
+    /// If set, how many more operations can be affected, shared by all the
+    /// queues processing the request
+    pub remaining: Option<Arc<AtomicUsize>>,
+}
+
+impl MessageRetryOptions {
+    /// Take one operation from the `remaining` budget, if any is left
+    pub fn try_take(&self) -> bool {
+        self.remaining.as_ref().map_or(true, |remaining| {
+            remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        })
+    }
+}
+
+/// Selects operations by their status, either by the full status, formatted
+/// like `Retry(GasPaymentRequirementNotMet)`, or only by its variant, like
+/// `Retry`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum StatusSelector {
+    FirstPrepareAttempt,
+    /// Any retry reason if `None`
+    Retry(Option<ReprepareReason>),
+    ReadyToSubmit,
+    /// Any confirm reason if `None`
+    Confirm(Option<ConfirmReason>),
+}
+
+impl StatusSelector {
+    /// Whether `status` is selected
+    pub fn matches(&self, status: &PendingOperationStatus) -> bool {
+        match (self, status) {
+            (Self::FirstPrepareAttempt, PendingOperationStatus::FirstPrepareAttempt)
+            | (Self::ReadyToSubmit, PendingOperationStatus::ReadyToSubmit) => true,
+            (Self::Retry(reason), PendingOperationStatus::Retry(status_reason)) => reason
+                .as_ref()
+                .map_or(true, |reason| reason == status_reason),
+            (Self::Confirm(reason), PendingOperationStatus::Confirm(status_reason)) => reason
+                .as_ref()
+                .map_or(true, |reason| reason == status_reason),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for StatusSelector {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (variant, reason) = match s.split_once('(') {
+            Some((variant, rest)) => {
+                let reason = rest
+                    .strip_suffix(')')
+                    .ok_or_else(|| eyre!("Invalid status `{s}`"))?;
+                (variant, Some(serde_json::Value::String(reason.to_owned())))
+            }
+            None => (s, None),
+        };
+        match (variant, reason) {
+            ("FirstPrepareAttempt", None) => Ok(Self::FirstPrepareAttempt),
+            ("ReadyToSubmit", None) => Ok(Self::ReadyToSubmit),
+            ("Retry", reason) => Ok(Self::Retry(reason.map(serde_json::from_value).transpose()?)),
+            ("Confirm", reason) => Ok(Self::Confirm(
+                reason.map(serde_json::from_value).transpose()?,
+            )),
+            _ => Err(eyre!("Invalid status `{s}`")),
+        }
+    }
+}
+
+impl TryFrom<String> for StatusSelector {
+    type Error = eyre::Report;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, new)]
 pub struct MessageRetryQueueResponse {
     /// how many pending operations were evaluated
     pub evaluated: usize,
     /// how many of the pending operations matched the retry request pattern
     pub matched: u64,
+    /// ids of the messages the retry request was applied to
+    #[new(default)]
+    pub message_ids: Vec<H256>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -45,16 +174,68 @@ pub struct MessageRetryResponse {
     pub evaluated: usize,
     /// how many of the pending operations matched the retry request pattern
     pub matched: u64,
+    /// ids of the messages the retry request was applied to
+    #[serde(default)]
+    pub message_ids: Vec<H256>,
+}
+
+/// Either a bare matching list, which retries all matching operations, or a
+/// request with additional selectors and a different action.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RequestBody {
+    Pattern(MatchingList),
+    Extended(ExtendedRequestBody),
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExtendedRequestBody {
+    pub matching_list: MatchingList,
+    #[serde(default)]
+    pub action: MessageRetryAction,
+    /// Only affect operations with this status, e.g.
+    /// `Retry(GasPaymentRequirementNotMet)`, or `Retry` for any retry reason
+    #[serde(default)]
+    pub status: Option<StatusSelector>,
+    /// Maximum number of operations affected, across all queues
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Retry, bump or drop the operations matching a request, across the queues
+/// of all destinations.
+///
+/// Example request, dropping at most 10 messages to domain 42 that are waiting
+/// for a gas payment
+/// ```json
+/// {
+///     "matching_list": [{ "destinationdomain": 42 }],
+///     "action": "drop",
+///     "status": "Retry(GasPaymentRequirementNotMet)",
+///     "limit": 10
+/// }
+/// ```
+/// `action` is one of `retry` (default), `bump_to_front` and `drop`.
+/// A bare matching list is also accepted, and retries all matching messages.
 async fn handler(
     State(state): State<ServerState>,
-    Json(payload): Json<MatchingList>,
+    Json(payload): Json<RequestBody>,
 ) -> Result<Json<MessageRetryResponse>, String> {
     let uuid = uuid::Uuid::new_v4();
     let uuid_string = uuid.to_string();
 
     tracing::debug!(?payload);
+    let (pattern, options) = match payload {
+        RequestBody::Pattern(pattern) => (pattern, MessageRetryOptions::default()),
+        RequestBody::Extended(body) => (
+            body.matching_list,
+            MessageRetryOptions {
+                action: body.action,
+                status: body.status,
+                remaining: body.limit.map(|limit| Arc::new(AtomicUsize::new(limit))),
+            },
+        ),
+    };
     tracing::debug!(uuid = uuid_string, "Sending message retry request");
 
     // Create a channel that can hold each chain's MessageProcessor
@@ -66,7 +247,8 @@ async fn handler(
         .retry_request_transmitter
         .send(MessageRetryRequest {
             uuid: uuid_string.clone(),
-            pattern: payload,
+            pattern,
+            options,
             transmitter,
         })
         .map_err(|err| {
@@ -79,6 +261,7 @@ async fn handler(
         uuid: uuid_string,
         evaluated: 0,
         matched: 0,
+        message_ids: vec![],
     };
 
     // Wait for responses from relayer
@@ -91,6 +274,7 @@ async fn handler(
         );
         resp.evaluated += relayer_resp.evaluated;
         resp.matched += relayer_resp.matched;
+        resp.message_ids.extend(relayer_resp.message_ids);
     }
 
     Ok(Json(resp))
@@ -139,8 +323,8 @@ mod tests {
         if let Ok(req) = retry_request_receiver.recv().await {
             for (op, (evaluated, matched)) in pending_operations.iter().zip(metrics) {
                 // Check that the list received by the server matches the pending operation
-                assert!(req.pattern.op_matches(op));
-                let resp = MessageRetryQueueResponse { evaluated, matched };
+                assert!(req.op_matches(op));
+                let resp = MessageRetryQueueResponse::new(evaluated, matched);
                 req.transmitter.send(resp).await.unwrap();
             }
         }
@@ -369,4 +553,77 @@ mod tests {
         assert_eq!(resp_json.evaluated, 1);
         assert_eq!(resp_json.matched, 1);
     }
+
+    #[tokio::test]
+    async fn test_extended_retry_request() {
+        let TestServerSetup {
+            app,
+            mut retry_req_rx,
+        } = setup_test_server();
+
+        let message = HyperlaneMessage::default();
+        tokio::task::spawn(async move {
+            let req = retry_req_rx.recv().await.unwrap();
+            assert_eq!(req.options.action, MessageRetryAction::BumpToFront);
+            assert_eq!(
+                req.options.status,
+                Some(StatusSelector::Retry(Some(
+                    ReprepareReason::GasPaymentRequirementNotMet
+                )))
+            );
+            assert!(req.options.try_take());
+            assert!(!req.options.try_take());
+            let mut resp = MessageRetryQueueResponse::new(2, 1);
+            resp.message_ids.push(message.id());
+            req.transmitter.send(resp).await.unwrap();
+        });
+
+        let body = json!({
+            "matching_list": [{ "messageid": message.id() }],
+            "action": "bump_to_front",
+            "status": "Retry(GasPaymentRequirementNotMet)",
+            "limit": 1
+        });
+        let response = send_retry_request(app, &body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let resp_json: MessageRetryResponse = parse_body_to_json(response.into_body()).await;
+        assert_eq!(resp_json.evaluated, 2);
+        assert_eq!(resp_json.matched, 1);
+        assert_eq!(resp_json.message_ids, vec![message.id()]);
+    }
+
+    #[test]
+    fn test_status_selector() {
+        let status = PendingOperationStatus::Retry(ReprepareReason::GasPaymentRequirementNotMet);
+        let cases = [
+            ("Retry(GasPaymentRequirementNotMet)", &status, true),
+            ("Retry", &status, true),
+            ("Retry(ErrorSubmitting)", &status, false),
+            ("Confirm", &status, false),
+            (
+                "FirstPrepareAttempt",
+                &PendingOperationStatus::FirstPrepareAttempt,
+                true,
+            ),
+            (
+                "Confirm(SubmittedBySelf)",
+                &PendingOperationStatus::Confirm(ConfirmReason::SubmittedBySelf),
+                true,
+            ),
+        ];
+        for (selector, status, expected) in cases {
+            let selector: StatusSelector = selector.parse().unwrap();
+            assert_eq!(selector.matches(status), expected, "{selector:?}");
+        }
+
+        for invalid in [
+            "Ret",
+            "Retry(Unknown)",
+            "Retry(ErrorSubmitting",
+            "ReadyToSubmit(x)",
+        ] {
+            assert!(invalid.parse::<StatusSelector>().is_err(), "{invalid}");
+        }
+    }
 }
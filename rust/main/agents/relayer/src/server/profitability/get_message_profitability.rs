@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::{
+    db::{HyperlaneDb, ProfitabilityLedgerEntry},
+    server::utils::{ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse},
+};
+use hyperlane_core::H256;
+
+use crate::server::profitability::ServerState;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathParams {
+    pub message_id: H256,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub message_id: H256,
+    #[serde(flatten)]
+    pub entry: ProfitabilityLedgerEntry,
+}
+
+/// Fetch the profitability ledger entry of a message
+pub async fn handler(
+    State(state): State<ServerState>,
+    Path(path_params): Path<PathParams>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let PathParams { message_id } = path_params;
+
+    tracing::debug!(?message_id, "Fetching message profitability");
+
+    for db in state.dbs.values() {
+        let entry = db
+            .retrieve_profitability_ledger_entry_by_message_id(&message_id)
+            .map_err(|err| {
+                let error_msg = "Failed to fetch message profitability";
+                tracing::debug!(?message_id, ?err, "{error_msg}");
+                ServerErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ServerErrorBody {
+                        message: error_msg.to_string(),
+                    },
+                )
+            })?;
+        if let Some(entry) = entry {
+            let resp = ResponseBody { message_id, entry };
+            return Ok(ServerSuccessResponse::new(resp));
+        }
+    }
+
+    Err(ServerErrorResponse::new(
+        StatusCode::NOT_FOUND,
+        ServerErrorBody {
+            message: "No profitability ledger entry found for message".to_string(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{
+        body::{self, Body},
+        http::{header::CONTENT_TYPE, Request, Response},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use hyperlane_base::db::{HyperlaneRocksDB, DB};
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain, H512, U256};
+
+    use crate::test_utils::request::parse_body_to_json;
+
+    use super::*;
+
+    fn setup_test_server(domains: &[HyperlaneDomain]) -> (Router, HashMap<u32, HyperlaneRocksDB>) {
+        let dbs: HashMap<_, _> = domains
+            .iter()
+            .map(|domain| {
+                let temp_dir = tempfile::tempdir().unwrap();
+                let db = DB::from_path(temp_dir.path()).unwrap();
+                let base_db = HyperlaneRocksDB::new(domain, db);
+                (domain.id(), base_db)
+            })
+            .collect();
+
+        let app = ServerState::new(dbs.clone()).router();
+        (app, dbs)
+    }
+
+    async fn send_request(app: Router, message_id: H256) -> Response<Body> {
+        let api_url = format!("/profitability/{message_id:?}");
+        let request = Request::builder()
+            .uri(api_url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body::Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to send request");
+        response
+    }
+
+    #[tokio::test]
+    async fn test_get_message_profitability_not_found() {
+        let domains = &[HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum)];
+        let (app, _) = setup_test_server(domains);
+
+        let response = send_request(app, H256::from_low_u64_be(1)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_message_profitability_happy_path() {
+        let domains = &[
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        ];
+        let (app, dbs) = setup_test_server(domains);
+
+        let message_id = H256::from_low_u64_be(1);
+        let entry = ProfitabilityLedgerEntry {
+            origin: domains[1].id(),
+            destination: domains[0].id(),
+            payment: U256::from(1000u32),
+            gas_amount: U256::from(100u32),
+            gas_used: U256::from(90u32),
+            tokens_used: U256::from(900u32),
+            transaction_ids: vec![H512::from_low_u64_be(5)],
+            origin_token_price: Some("2000".to_owned()),
+            destination_token_price: Some("1".to_owned()),
+            updated_at: 1_700_000_000,
+        };
+        dbs[&domains[1].id()]
+            .process_profitability_ledger_entry(&message_id, &entry)
+            .unwrap();
+
+        let response = send_request(app, message_id).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let resp_body: ResponseBody = parse_body_to_json(response.into_body()).await;
+        assert_eq!(resp_body.message_id, message_id);
+        assert_eq!(resp_body.entry, entry);
+    }
+}
@@ -0,0 +1,246 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use hyperlane_base::{
+    db::{HyperlaneDb, ProfitabilityRouteAggregate},
+    server::utils::{ServerErrorBody, ServerErrorResponse, ServerResult, ServerSuccessResponse},
+};
+
+use crate::server::profitability::ServerState;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct QueryParams {
+    pub origin: Option<u32>,
+    pub destination: Option<u32>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RouteProfitability {
+    pub origin: u32,
+    pub destination: u32,
+    #[serde(flatten)]
+    pub aggregate: ProfitabilityRouteAggregate,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ResponseBody {
+    pub routes: Vec<RouteProfitability>,
+}
+
+/// List the profitability totals of every route messages were relayed on
+pub async fn handler(
+    State(state): State<ServerState>,
+    Query(query_params): Query<QueryParams>,
+) -> ServerResult<ServerSuccessResponse<ResponseBody>> {
+    let QueryParams {
+        origin,
+        destination,
+    } = query_params;
+
+    tracing::debug!(?origin, ?destination, "Fetching route profitability");
+
+    let mut routes = Vec::new();
+    for (origin_id, db) in state.dbs.iter() {
+        if origin.is_some_and(|origin| origin != *origin_id) {
+            continue;
+        }
+        let destination_ids = db
+            .retrieve_profitability_route_destinations()
+            .map_err(|err| {
+                let error_msg = "Failed to fetch route profitability destinations";
+                tracing::debug!(origin_id, ?err, "{error_msg}");
+                ServerErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ServerErrorBody {
+                        message: error_msg.to_string(),
+                    },
+                )
+            })?;
+        for destination_id in destination_ids.iter() {
+            if destination.is_some_and(|destination| destination != *destination_id) {
+                continue;
+            }
+            let aggregate = db
+                .retrieve_profitability_route_aggregate_by_destination(destination_id)
+                .map_err(|err| {
+                    let error_msg = "Failed to fetch route profitability";
+                    tracing::debug!(origin_id, destination_id, ?err, "{error_msg}");
+                    ServerErrorResponse::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        ServerErrorBody {
+                            message: error_msg.to_string(),
+                        },
+                    )
+                })?;
+            if let Some(aggregate) = aggregate {
+                routes.push(RouteProfitability {
+                    origin: *origin_id,
+                    destination: *destination_id,
+                    aggregate,
+                });
+            }
+        }
+    }
+    routes.sort_by_key(|route| (route.origin, route.destination));
+
+    let resp = ResponseBody { routes };
+    Ok(ServerSuccessResponse::new(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{
+        body::{self, Body},
+        http::{header::CONTENT_TYPE, Request, Response},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use hyperlane_base::db::{HyperlaneRocksDB, ProfitabilityLedgerEntry, DB};
+    use hyperlane_core::{HyperlaneDomain, KnownHyperlaneDomain, H256, U256};
+
+    use crate::test_utils::request::parse_body_to_json;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestServerSetup {
+        pub app: Router,
+        pub dbs: HashMap<u32, HyperlaneRocksDB>,
+    }
+
+    fn setup_test_server(domains: &[HyperlaneDomain]) -> TestServerSetup {
+        let dbs: HashMap<_, _> = domains
+            .iter()
+            .map(|domain| {
+                let temp_dir = tempfile::tempdir().unwrap();
+                let db = DB::from_path(temp_dir.path()).unwrap();
+                let base_db = HyperlaneRocksDB::new(domain, db);
+                (domain.id(), base_db)
+            })
+            .collect();
+
+        let server_state = ServerState::new(dbs.clone());
+        let app = server_state.router();
+
+        TestServerSetup { app, dbs }
+    }
+
+    async fn send_request(app: Router, query: &str) -> Response<Body> {
+        let api_url = format!("/profitability{query}");
+        let request = Request::builder()
+            .uri(api_url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body::Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to send request");
+        response
+    }
+
+    fn ledger_entry(origin: u32, destination: u32, payment: u32) -> ProfitabilityLedgerEntry {
+        ProfitabilityLedgerEntry {
+            origin,
+            destination,
+            payment: U256::from(payment),
+            gas_used: U256::from(10u32),
+            tokens_used: U256::from(100u32),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_route_profitability_empty_db() {
+        let domains = &[
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        ];
+        let TestServerSetup { app, .. } = setup_test_server(domains);
+
+        let response = send_request(app, "").await;
+        let resp_status = response.status();
+        let resp_body: ResponseBody = parse_body_to_json(response.into_body()).await;
+
+        assert_eq!(resp_status, StatusCode::OK);
+        assert!(resp_body.routes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_route_profitability_happy_path() {
+        // Destinations are not necessarily origins themselves
+        let domains = &[
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Arbitrum),
+            HyperlaneDomain::Known(KnownHyperlaneDomain::Ethereum),
+        ];
+        let TestServerSetup { app, dbs } = setup_test_server(domains);
+        let (arbitrum, ethereum) = (domains[0].id(), domains[1].id());
+        let optimism = KnownHyperlaneDomain::Optimism as u32;
+
+        let origin_db = &dbs[&arbitrum];
+        origin_db
+            .process_profitability_ledger_entry(
+                &H256::from_low_u64_be(1),
+                &ledger_entry(arbitrum, ethereum, 1000),
+            )
+            .unwrap();
+        origin_db
+            .process_profitability_ledger_entry(
+                &H256::from_low_u64_be(2),
+                &ledger_entry(arbitrum, ethereum, 2000),
+            )
+            .unwrap();
+        // Updating an existing entry replaces its contribution to the totals
+        origin_db
+            .process_profitability_ledger_entry(
+                &H256::from_low_u64_be(2),
+                &ledger_entry(arbitrum, ethereum, 3000),
+            )
+            .unwrap();
+        origin_db
+            .process_profitability_ledger_entry(
+                &H256::from_low_u64_be(3),
+                &ledger_entry(arbitrum, optimism, 500),
+            )
+            .unwrap();
+
+        let response = send_request(app.clone(), "").await;
+        let resp_status = response.status();
+        let resp_body: ResponseBody = parse_body_to_json(response.into_body()).await;
+
+        assert_eq!(resp_status, StatusCode::OK);
+        let mut expected = vec![
+            RouteProfitability {
+                origin: arbitrum,
+                destination: ethereum,
+                aggregate: ProfitabilityRouteAggregate {
+                    messages: 2,
+                    payment: U256::from(4000u32),
+                    gas_used: U256::from(20u32),
+                    tokens_used: U256::from(200u32),
+                },
+            },
+            RouteProfitability {
+                origin: arbitrum,
+                destination: optimism,
+                aggregate: ProfitabilityRouteAggregate {
+                    messages: 1,
+                    payment: U256::from(500u32),
+                    gas_used: U256::from(10u32),
+                    tokens_used: U256::from(100u32),
+                },
+            },
+        ];
+        expected.sort_by_key(|route| (route.origin, route.destination));
+        assert_eq!(resp_body.routes, expected);
+
+        // Filter by destination
+        let response = send_request(app, &format!("?destination={optimism}")).await;
+        let resp_body: ResponseBody = parse_body_to_json(response.into_body()).await;
+        assert_eq!(resp_body.routes.len(), 1);
+        assert_eq!(resp_body.routes[0].destination, optimism);
+    }
+}
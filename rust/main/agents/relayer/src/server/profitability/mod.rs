@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+use axum::{routing::get, Router};
+use derive_new::new;
+use hyperlane_base::db::HyperlaneRocksDB;
+
+pub mod get_message_profitability;
+pub mod list_route_profitability;
+
+#[derive(Clone, Debug, new)]
+pub struct ServerState {
+    pub dbs: HashMap<u32, HyperlaneRocksDB>,
+}
+
+impl ServerState {
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/profitability", get(list_route_profitability::handler))
+            .route(
+                "/profitability/{message_id}",
+                get(get_message_profitability::handler),
+            )
+            .with_state(self)
+    }
+}
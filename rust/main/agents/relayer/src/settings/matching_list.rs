@@ -11,12 +11,15 @@ use std::{
 use derive_new::new;
 use ethers::utils::hex;
 use hyperlane_core::{
-    config::StrOrInt, utils::hex_or_base58_to_h256, HyperlaneMessage, QueueOperation, H256,
+    config::StrOrInt, utils::hex_or_base58_to_h256, Decode, HyperlaneMessage, QueueOperation, H256,
+    U256,
 };
+use hyperlane_warp_route::TokenMessage;
 use regex::Regex;
 use serde::{
     de::{Error, SeqAccess, Visitor},
-    Deserialize, Deserializer,
+    ser::{SerializeSeq, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
 /// Defines a set of patterns for determining if a message should or should not
@@ -27,7 +30,16 @@ use serde::{
 /// - wildcard "*"
 /// - single value in decimal or hex (must start with `0x`) format
 /// - list of values in decimal or hex format
-#[derive(Debug, Default, Clone)]
+///
+/// Rules can additionally match on the message body with
+/// - `bodyregex`: a regex applied to the hex encoded body
+/// - `bodyprefix`: hex encoded bytes the body must start with
+/// - `bodyselector`: a function selector the body must start with, either as 4
+///   hex encoded bytes or as a function signature like `transfer(address,uint256)`
+/// - `tokenamount`: an inclusive `{"min": .., "max": ..}` range the amount of a
+///   warp route `TokenMessage` body must be in. Only bodies with the length of
+///   a `TokenMessage` without metadata (64 bytes) can match it
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct MatchingList(pub Option<Vec<ListElement>>);
 
 #[derive(Debug, Clone, PartialEq)]
@@ -242,6 +254,24 @@ impl<'de> Deserialize<'de> for Filter<H256> {
     }
 }
 
+impl<T: Serialize> Serialize for Filter<T> {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Wildcard => s.serialize_str("*"),
+            Self::Enumerated(values) => {
+                let mut seq = s.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
 /// Wrapper around Regex so we can impl traits for it
 #[derive(Clone, Debug)]
 pub struct RegexWrapper(pub Regex);
@@ -255,13 +285,143 @@ impl<'de> Deserialize<'de> for RegexWrapper {
     }
 }
 
+impl Serialize for RegexWrapper {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(self.0.as_str())
+    }
+}
+
 impl PartialEq for RegexWrapper {
     fn eq(&self, other: &Self) -> bool {
         self.0.as_str() == other.0.as_str()
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, new)]
+/// Bytes the message body must start with
+#[derive(Clone, Debug, PartialEq)]
+pub struct BodyPrefix(pub Vec<u8>);
+
+impl<'de> Deserialize<'de> for BodyPrefix {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let prefix = String::deserialize(d)?;
+        parse_hex_bytes(&prefix).map(Self)
+    }
+}
+
+impl Serialize for BodyPrefix {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(&format!("0x{}", hex::encode(&self.0)))
+    }
+}
+
+/// Function selector the message body must start with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FunctionSelector(pub [u8; 4]);
+
+impl<'de> Deserialize<'de> for FunctionSelector {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let selector = String::deserialize(d)?;
+        if selector.contains('(') {
+            return Ok(Self(ethers::utils::id(selector)));
+        }
+        let bytes = parse_hex_bytes::<D::Error>(&selector)?;
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| D::Error::custom("Function selector must be 4 bytes long"))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for FunctionSelector {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(&format!("0x{}", hex::encode(self.0)))
+    }
+}
+
+/// Length of a `TokenMessage` body without metadata
+const TOKEN_MESSAGE_LEN: usize = 64;
+
+/// Inclusive range the amount of a warp route `TokenMessage` must be in.
+/// The body shape is otherwise not validated, so only bodies with exactly the
+/// length of an encoded `TokenMessage` without metadata are read as one; the
+/// amount is then read from bytes 32..64. Bodies of any other length never
+/// match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenAmountRange {
+    pub min: Option<U256>,
+    pub max: Option<U256>,
+}
+
+impl TokenAmountRange {
+    fn matches(&self, body: &[u8]) -> bool {
+        if body.len() != TOKEN_MESSAGE_LEN {
+            return false;
+        }
+        let Ok(token_message) = TokenMessage::read_from(&mut &body[..]) else {
+            return false;
+        };
+        let amount = token_message.amount();
+        self.min.map_or(true, |min| amount >= min) && self.max.map_or(true, |max| amount <= max)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawTokenAmountRange {
+    #[serde(default)]
+    min: Option<StrOrInt>,
+    #[serde(default)]
+    max: Option<StrOrInt>,
+}
+
+impl<'de> Deserialize<'de> for TokenAmountRange {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = RawTokenAmountRange::deserialize(d)?;
+        let range = Self {
+            min: raw.min.as_ref().map(parse_amount::<D::Error>).transpose()?,
+            max: raw.max.as_ref().map(parse_amount::<D::Error>).transpose()?,
+        };
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min > max {
+                return Err(D::Error::custom(
+                    "Token amount range minimum must not exceed its maximum",
+                ));
+            }
+        }
+        Ok(range)
+    }
+}
+
+impl Serialize for TokenAmountRange {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut range = s.serialize_struct("TokenAmountRange", 2)?;
+        range.serialize_field("min", &self.min.map(|min| min.to_string()))?;
+        range.serialize_field("max", &self.max.map(|max| max.to_string()))?;
+        range.end()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, new)]
 #[serde(tag = "type")]
 pub struct ListElement {
     #[serde(default, rename = "messageid")]
@@ -274,21 +434,61 @@ pub struct ListElement {
     destination_domain: Filter<u32>,
     #[serde(default, rename = "recipientaddress")]
     recipient_address: Filter<H256>,
-    #[serde(default, rename = "bodyregex")]
+    #[serde(default, rename = "bodyregex", skip_serializing_if = "Option::is_none")]
     body_regex: Option<RegexWrapper>,
+    #[new(default)]
+    #[serde(
+        default,
+        rename = "bodyprefix",
+        skip_serializing_if = "Option::is_none"
+    )]
+    body_prefix: Option<BodyPrefix>,
+    #[new(default)]
+    #[serde(
+        default,
+        rename = "bodyselector",
+        skip_serializing_if = "Option::is_none"
+    )]
+    body_selector: Option<FunctionSelector>,
+    #[new(default)]
+    #[serde(
+        default,
+        rename = "tokenamount",
+        skip_serializing_if = "Option::is_none"
+    )]
+    token_amount: Option<TokenAmountRange>,
 }
 
 impl Display for ListElement {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{{messageId: {}, originDomain: {}, senderAddress: {}, destinationDomain: {}, recipientAddress: {}}}",
+            "{{messageId: {}, originDomain: {}, senderAddress: {}, destinationDomain: {}, recipientAddress: {}",
             self.message_id,
             self.origin_domain,
             self.sender_address,
             self.destination_domain,
             self.recipient_address
-        )
+        )?;
+        if let Some(regex) = &self.body_regex {
+            write!(f, ", bodyRegex: {}", regex.0)?;
+        }
+        if let Some(prefix) = &self.body_prefix {
+            write!(f, ", bodyPrefix: 0x{}", hex::encode(&prefix.0))?;
+        }
+        if let Some(selector) = &self.body_selector {
+            write!(f, ", bodySelector: 0x{}", hex::encode(selector.0))?;
+        }
+        if let Some(range) = &self.token_amount {
+            let bound = |b: Option<U256>| b.map_or("*".to_owned(), |b| b.to_string());
+            write!(
+                f,
+                ", tokenAmount: [{}, {}]",
+                bound(range.min),
+                bound(range.max)
+            )?;
+        }
+        write!(f, "}}")
     }
 }
 
@@ -299,7 +499,9 @@ struct MatchInfo<'a> {
     src_addr: &'a H256,
     dst_domain: u32,
     dst_addr: &'a H256,
+    /// Hex encoded body, for `bodyregex` rules
     body: String,
+    body_bytes: &'a [u8],
 }
 
 impl<'a> From<&'a HyperlaneMessage> for MatchInfo<'a> {
@@ -311,6 +513,7 @@ impl<'a> From<&'a HyperlaneMessage> for MatchInfo<'a> {
             dst_domain: msg.destination,
             dst_addr: &msg.recipient,
             body: hex::encode(&msg.body),
+            body_bytes: &msg.body,
         }
     }
 }
@@ -324,6 +527,7 @@ impl<'a> From<&'a QueueOperation> for MatchInfo<'a> {
             dst_domain: op.destination_domain().id(),
             dst_addr: op.recipient_address(),
             body: hex::encode(op.body()),
+            body_bytes: op.body(),
         }
     }
 }
@@ -337,6 +541,9 @@ impl MatchingList {
             destination_domain: Default::default(),
             recipient_address: Default::default(),
             body_regex: Default::default(),
+            body_prefix: Default::default(),
+            body_selector: Default::default(),
+            token_amount: Default::default(),
         }]))
     }
 
@@ -348,6 +555,9 @@ impl MatchingList {
             destination_domain: Filter::Enumerated(vec![destination_domain]),
             recipient_address: Default::default(),
             body_regex: Default::default(),
+            body_prefix: Default::default(),
+            body_selector: Default::default(),
+            token_amount: Default::default(),
         }]))
     }
 
@@ -360,7 +570,13 @@ impl MatchingList {
     /// Check if queue operation matches any of the rules.
     /// If the matching list is empty, we assume the queue operation does not match.
     pub fn op_matches(&self, op: &QueueOperation) -> bool {
-        self.matches(op.into(), false)
+        self.op_matches_or(op, false)
+    }
+
+    /// Check if queue operation matches any of the rules.
+    /// - `default`: What to return if the matching list is empty.
+    pub fn op_matches_or(&self, op: &QueueOperation, default: bool) -> bool {
+        self.matches(op.into(), default)
     }
 
     /// Check if a message matches any of the rules.
@@ -386,6 +602,21 @@ fn matches_any_rule<'a>(mut rules: impl Iterator<Item = &'a ListElement>, info:
                 .as_ref()
                 .map(|regex| regex.0.is_match(&info.body))
                 .unwrap_or(true)
+            && rule
+                .body_prefix
+                .as_ref()
+                .map(|prefix| info.body_bytes.starts_with(&prefix.0))
+                .unwrap_or(true)
+            && rule
+                .body_selector
+                .as_ref()
+                .map(|selector| info.body_bytes.starts_with(&selector.0))
+                .unwrap_or(true)
+            && rule
+                .token_amount
+                .as_ref()
+                .map(|range| range.matches(info.body_bytes))
+                .unwrap_or(true)
     })
 }
 
@@ -411,9 +642,25 @@ fn parse_addr<E: Error>(addr_str: &str) -> Result<H256, E> {
     hex_or_base58_to_h256(addr_str).map_err(to_serde_err)
 }
 
+fn parse_hex_bytes<E: Error>(hex_str: &str) -> Result<Vec<u8>, E> {
+    hex::decode(hex_str.strip_prefix("0x").unwrap_or(hex_str)).map_err(to_serde_err)
+}
+
+/// Amounts are parsed as decimal unless prefixed with `0x`
+fn parse_amount<E: Error>(amount: &StrOrInt) -> Result<U256, E> {
+    match amount {
+        StrOrInt::Str(s) => match s.strip_prefix("0x") {
+            Some(hex_str) => U256::from_str_radix(hex_str, 16).map_err(to_serde_err),
+            None => U256::from_dec_str(s).map_err(to_serde_err),
+        },
+        StrOrInt::Int(i) => u64::try_from(*i).map(U256::from).map_err(to_serde_err),
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use hyperlane_core::{H160, H256};
+    use hyperlane_core::{Encode, HyperlaneMessage, H160, H256, U256};
+    use hyperlane_warp_route::TokenMessage;
 
     use super::{Filter::*, MatchingList};
     use crate::settings::matching_list::MatchInfo;
@@ -445,6 +692,7 @@ mod test {
                 dst_domain: 0,
                 dst_addr: &H256::default(),
                 body: "".into(),
+                body_bytes: &[],
             },
             false
         ));
@@ -460,6 +708,7 @@ mod test {
                 dst_domain: 5456,
                 dst_addr: &H256::default(),
                 body: "".into(),
+                body_bytes: &[],
             },
             false
         ))
@@ -503,6 +752,7 @@ mod test {
                     .unwrap()
                     .into(),
                 body: "".into(),
+                body_bytes: &[],
             },
             false
         ));
@@ -518,6 +768,7 @@ mod test {
                 dst_domain: 5456,
                 dst_addr: &H256::default(),
                 body: "".into(),
+                body_bytes: &[],
             },
             false
         ));
@@ -598,6 +849,7 @@ mod test {
                     .unwrap()
                     .into(),
                 body: "0x123456789".into(),
+                body_bytes: &[],
             },
             false
         ));
@@ -613,8 +865,97 @@ mod test {
                 dst_domain: 5456,
                 dst_addr: &H256::default(),
                 body: "0xdefg".into(),
+                body_bytes: &[],
             },
             false
         ));
     }
+
+    #[test]
+    fn list_element_serialization_roundtrip() {
+        let list: MatchingList = serde_json::from_str(r#"[{"messageid": "*", "origindomain": [1, 2], "senderaddress": "0x6AD4DEBA8A147d000C09de6465267a9047d1c217", "destinationdomain": "*", "recipientaddress": "*", "bodyregex": "^0x12"}, {"destinationdomain": 5, "bodyprefix": "0x0102", "bodyselector": "transfer(address,uint256)", "tokenamount": {"min": "100"}}]"#).unwrap();
+        let elements = list.0.unwrap();
+
+        let serialized = serde_json::to_value(&elements).unwrap();
+        let roundtripped: MatchingList = serde_json::from_value(serialized).unwrap();
+        assert_eq!(roundtripped.0.unwrap(), elements);
+    }
+
+    #[test]
+    fn test_list_element_display_includes_body_filters() {
+        let list: MatchingList = serde_json::from_str(
+            r#"[{"destinationdomain": 5, "bodyprefix": "0x0102", "bodyselector": "0xa9059cbb", "tokenamount": {"min": "100"}}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            list.to_string(),
+            "[{messageId: *, originDomain: *, senderAddress: *, destinationDomain: 5, recipientAddress: *, bodyPrefix: 0x0102, bodySelector: 0xa9059cbb, tokenAmount: [100, *]},]"
+        );
+    }
+
+    fn token_message_body(amount: u64) -> Vec<u8> {
+        TokenMessage::new(H256::random(), U256::from(amount), vec![]).to_vec()
+    }
+
+    fn message_with_body(body: Vec<u8>) -> HyperlaneMessage {
+        HyperlaneMessage {
+            body,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matching_list_body_prefix() {
+        let list: MatchingList = serde_json::from_str(r#"[{"bodyprefix": "0x0102"}]"#).unwrap();
+        assert!(list.msg_matches(&message_with_body(vec![1, 2, 3]), false));
+        assert!(!list.msg_matches(&message_with_body(vec![1, 3, 2]), false));
+        assert!(!list.msg_matches(&message_with_body(vec![1]), false));
+    }
+
+    #[test]
+    fn test_matching_list_body_selector() {
+        // `transfer(address,uint256)` has the selector 0xa9059cbb
+        let mut body = vec![0xa9, 0x05, 0x9c, 0xbb];
+        body.extend_from_slice(&[0; 64]);
+
+        let list: MatchingList =
+            serde_json::from_str(r#"[{"bodyselector": "transfer(address,uint256)"}]"#).unwrap();
+        assert!(list.msg_matches(&message_with_body(body.clone()), false));
+
+        let list: MatchingList =
+            serde_json::from_str(r#"[{"bodyselector": "0xa9059cbb"}]"#).unwrap();
+        assert!(list.msg_matches(&message_with_body(body), false));
+        assert!(!list.msg_matches(&message_with_body(vec![0xa9, 0x05, 0x9c]), false));
+
+        assert!(serde_json::from_str::<MatchingList>(r#"[{"bodyselector": "0xa9059c"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_matching_list_token_amount() {
+        let list: MatchingList =
+            serde_json::from_str(r#"[{"tokenamount": {"min": "100", "max": 1000}}]"#).unwrap();
+        assert!(list.msg_matches(&message_with_body(token_message_body(100)), false));
+        assert!(list.msg_matches(&message_with_body(token_message_body(1000)), false));
+        assert!(!list.msg_matches(&message_with_body(token_message_body(99)), false));
+        assert!(!list.msg_matches(&message_with_body(token_message_body(1001)), false));
+        // bodies without the length of a token message never match
+        assert!(!list.msg_matches(&message_with_body(vec![0; 63]), false));
+        let mut body = token_message_body(100);
+        body.push(0);
+        assert!(!list.msg_matches(&message_with_body(body), false));
+        // the body shape is not validated, so other bodies of that length are read as token messages
+        let mut body = vec![0; 64];
+        body[63] = 100;
+        assert!(list.msg_matches(&message_with_body(body), false));
+
+        let list: MatchingList =
+            serde_json::from_str(r#"[{"tokenamount": {"min": "0x64"}}]"#).unwrap();
+        assert!(list.msg_matches(&message_with_body(token_message_body(100)), false));
+        assert!(!list.msg_matches(&message_with_body(token_message_body(99)), false));
+
+        assert!(serde_json::from_str::<MatchingList>(
+            r#"[{"tokenamount": {"min": "10", "max": "1"}}]"#
+        )
+        .is_err());
+    }
 }
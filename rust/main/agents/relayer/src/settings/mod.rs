@@ -4,7 +4,12 @@
 //! and validations it defines are not applied here, we should mirror them.
 //! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
 
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 
 use convert_case::Case;
 use derive_more::{AsMut, AsRef, Deref, DerefMut};
@@ -16,10 +21,11 @@ use hyperlane_base::{
         parser::{recase_json_value, RawAgentConf, ValueParser},
         Settings,
     },
+    QuorumSelectionStrategy,
 };
 use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, U256};
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
@@ -74,6 +80,20 @@ pub struct RelayerSettings {
     pub tx_id_indexing_enabled: bool,
     /// Whether to enable IGP indexing.
     pub igp_indexing_enabled: bool,
+    /// Token bucket limits on how fast matching operations are prepared
+    pub rate_limits: Vec<RateLimitConf>,
+    /// If set, operations are served with weighted fair queuing across app
+    /// contexts, using these weights.
+    pub app_context_weights: Option<HashMap<String, u32>>,
+    /// If true, messages are prepared and what would have been submitted is
+    /// recorded, but nothing is ever submitted.
+    pub shadow_mode: bool,
+    /// Webhooks notified of message lifecycle events
+    pub webhooks: Vec<WebhookConf>,
+    /// How validators are selected to make up multisig signature quorums
+    pub quorum_selection_strategy: QuorumSelectionStrategy,
+    /// Timeouts of requests to CCIP read gateways, by gateway host
+    pub ccip_read_gateway_timeouts: HashMap<String, Duration>,
 }
 
 /// Config for gas payment enforcement
@@ -107,6 +127,167 @@ pub enum GasPaymentEnforcementPolicy {
         gas_fraction_numerator: u64,
         gas_fraction_denominator: u64,
     },
+    /// The value of the payment on the origin covers the specified fraction
+    /// of the value of the tokens spent on the destination, with both
+    /// converted into a common currency using native token prices from
+    /// `price_source`. TokenPriceQuoting requires a payment to exist on the
+    /// IGP specified in the config.
+    TokenPriceQuoting {
+        price_source: TokenPriceSource,
+        cost_fraction_numerator: u64,
+        cost_fraction_denominator: u64,
+    },
+}
+
+/// Where the `TokenPriceQuoting` gas payment enforcement policy reads native
+/// token prices from. Both serve a JSON object keyed by domain id, e.g.
+/// `{ "1": { "price": 3000.5, "decimals": 18 } }`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub enum TokenPriceSource {
+    /// A JSON file on the local filesystem
+    File(PathBuf),
+    /// A JSON HTTP endpoint
+    Http(String),
+}
+
+impl FromStr for TokenPriceSource {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            let url = reqwest::Url::parse(s).context("Invalid token price URL")?;
+            Ok(Self::Http(url.to_string()))
+        } else if s.is_empty() {
+            Err(eyre!("Token price source cannot be empty"))
+        } else {
+            Ok(Self::File(PathBuf::from(s)))
+        }
+    }
+}
+
+/// Config for a token bucket rate limit on the operations that are prepared
+/// for submission.
+/// Fields are renamed to be all lowercase / without underscores to match
+/// the format expected by the settings parsing.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RateLimitConf {
+    /// An optional matching list, any operation that matches is subject to
+    /// the limit. By default all operations will match.
+    #[serde(default, rename = "matchinglist")]
+    pub matching_list: MatchingList,
+    /// Maximum number of operations that can be prepared in a burst
+    pub capacity: u32,
+    /// Number of operations per second the bucket is refilled with
+    #[serde(rename = "refillrate")]
+    pub refill_rate: f64,
+    /// How matching operations are grouped into buckets
+    #[serde(default)]
+    pub scope: RateLimitScope,
+}
+
+/// Which operations matching a `RateLimitConf` share a token bucket
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitScope {
+    /// A single bucket for all matching operations
+    #[default]
+    Shared,
+    /// A bucket per origin domain and sender address
+    Sender,
+    /// A bucket per destination domain and recipient address
+    Recipient,
+    /// A bucket per origin and destination domain pair
+    Route,
+}
+
+/// Config for a webhook that is notified of message lifecycle events.
+/// Fields are renamed to be all lowercase / without underscores to match
+/// the format expected by the settings parsing.
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct WebhookConf {
+    /// Name identifying the webhook, defaults to its URL. Names must be unique,
+    /// since undelivered events are stored per webhook name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// URL the events are POSTed to
+    pub url: String,
+    /// Secret used to sign the request bodies with HMAC-SHA256
+    pub secret: String,
+    /// An optional matching list, only events of matching messages are sent.
+    /// By default all messages will match.
+    #[serde(default, rename = "matchinglist")]
+    pub matching_list: MatchingList,
+    /// Events the webhook is notified of
+    #[serde(default = "default_webhook_events")]
+    pub events: Vec<WebhookEvent>,
+    /// Number of retries after which a message is considered stuck
+    #[serde(
+        default = "default_webhook_stuck_after_retries",
+        rename = "stuckafterretries"
+    )]
+    pub stuck_after_retries: u32,
+}
+
+impl std::fmt::Debug for WebhookConf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConf")
+            .field("name", &self.name)
+            .field("url", &self.url)
+            .field("secret", &"<redacted>")
+            .field("matching_list", &self.matching_list)
+            .field("events", &self.events)
+            .field("stuck_after_retries", &self.stuck_after_retries)
+            .finish()
+    }
+}
+
+impl WebhookConf {
+    /// The name of the webhook, or its URL if it has none
+    pub fn id(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.url)
+    }
+}
+
+fn default_webhook_events() -> Vec<WebhookEvent> {
+    vec![
+        WebhookEvent::Delivered,
+        WebhookEvent::Dropped,
+        WebhookEvent::Stuck,
+    ]
+}
+
+fn default_webhook_stuck_after_retries() -> u32 {
+    10
+}
+
+/// Message lifecycle events a webhook can be notified of
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    /// The status of the message changed
+    Transition,
+    /// The message was delivered to its destination
+    Delivered,
+    /// The message was dropped and will not be retried
+    Dropped,
+    /// The message reached the configured number of retries
+    Stuck,
+}
+
+/// Weight of an app context for weighted fair queuing
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct AppContextWeightConf {
+    #[serde(rename = "appcontext")]
+    app_context: String,
+    weight: u32,
+}
+
+/// Timeout of requests to a CCIP read gateway
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct CcipReadGatewayTimeoutConf {
+    host: String,
+    /// Timeout in seconds
+    timeout: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -193,51 +374,90 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
                 .take_err(&mut err, || cwp + "gas_payment_enforcement");
         }
 
-        let mut gas_payment_enforcement = gas_payment_enforcement_parser.into_array_iter().map(|itr| {
-            itr.filter_map(|policy| {
-                let policy_type = policy.chain(&mut err).get_opt_key("type").parse_string().end();
-                let minimum_is_defined = matches!(policy.get_opt_key("minimum"), Ok(Some(_)));
-
-                let matching_list = policy.chain(&mut err).get_opt_key("matchingList").and_then(parse_matching_list).unwrap_or_default();
-
-                let parse_minimum = |p| GasPaymentEnforcementPolicy::Minimum { payment: p };
-                match policy_type {
-                    Some("minimum") => policy.chain(&mut err).get_opt_key("payment").parse_u256().end().map(parse_minimum),
-                    None if minimum_is_defined => policy.chain(&mut err).get_opt_key("payment").parse_u256().end().map(parse_minimum),
-                    Some("none") | None => Some(GasPaymentEnforcementPolicy::None),
-                    Some("onChainFeeQuoting") => {
-                        let gas_fraction = policy.chain(&mut err)
-                            .get_opt_key("gasFraction")
-                            .parse_string()
-                            .map(|v| v.replace(' ', ""))
-                            .unwrap_or_else(|| "1/2".to_owned());
-                        let (numerator, denominator) = gas_fraction
-                            .split_once('/')
-                            .ok_or_else(|| eyre!("Invalid `gas_fraction` for OnChainFeeQuoting gas payment enforcement policy; expected `numerator / denominator`"))
-                            .take_err(&mut err, || &policy.cwp + "gas_fraction")
-                            .unwrap_or(("1", "1"));
-
-                        Some(GasPaymentEnforcementPolicy::OnChainFeeQuoting {
-                            gas_fraction_numerator: numerator
-                                .parse()
-                                .context("Error parsing gas fraction numerator")
-                                .take_err(&mut err, || &policy.cwp + "gas_fraction")
-                                .unwrap_or(1),
-                            gas_fraction_denominator: denominator
-                                .parse()
-                                .context("Error parsing gas fraction denominator")
-                                .take_err(&mut err, || &policy.cwp + "gas_fraction")
-                                .unwrap_or(1),
-                        })
+        let mut gas_payment_enforcement = gas_payment_enforcement_parser
+            .into_array_iter()
+            .map(|itr| {
+                itr.filter_map(|policy| {
+                    let policy_type = policy
+                        .chain(&mut err)
+                        .get_opt_key("type")
+                        .parse_string()
+                        .end();
+                    let minimum_is_defined = matches!(policy.get_opt_key("minimum"), Ok(Some(_)));
+
+                    let matching_list = policy
+                        .chain(&mut err)
+                        .get_opt_key("matchingList")
+                        .and_then(parse_matching_list)
+                        .unwrap_or_default();
+
+                    let parse_minimum = |p| GasPaymentEnforcementPolicy::Minimum { payment: p };
+                    match policy_type {
+                        Some("minimum") => policy
+                            .chain(&mut err)
+                            .get_opt_key("payment")
+                            .parse_u256()
+                            .end()
+                            .map(parse_minimum),
+                        None if minimum_is_defined => policy
+                            .chain(&mut err)
+                            .get_opt_key("payment")
+                            .parse_u256()
+                            .end()
+                            .map(parse_minimum),
+                        Some("none") | None => Some(GasPaymentEnforcementPolicy::None),
+                        Some("onChainFeeQuoting") => {
+                            let (gas_fraction_numerator, gas_fraction_denominator) = parse_fraction(
+                                &policy,
+                                "gasFraction",
+                                "gas_fraction",
+                                "1/2",
+                                &mut err,
+                            );
+                            Some(GasPaymentEnforcementPolicy::OnChainFeeQuoting {
+                                gas_fraction_numerator,
+                                gas_fraction_denominator,
+                            })
+                        }
+                        Some("tokenPriceQuoting") => {
+                            let price_source = policy
+                                .chain(&mut err)
+                                .get_key("priceSource")
+                                .parse_string()
+                                .end()
+                                .and_then(|v| {
+                                    v.parse::<TokenPriceSource>()
+                                        .take_err(&mut err, || &policy.cwp + "price_source")
+                                });
+                            let (cost_fraction_numerator, cost_fraction_denominator) =
+                                parse_fraction(
+                                    &policy,
+                                    "costFraction",
+                                    "cost_fraction",
+                                    "1/1",
+                                    &mut err,
+                                );
+                            price_source.map(|price_source| {
+                                GasPaymentEnforcementPolicy::TokenPriceQuoting {
+                                    price_source,
+                                    cost_fraction_numerator,
+                                    cost_fraction_denominator,
+                                }
+                            })
+                        }
+                        Some(pt) => {
+                            Err(eyre!("Unknown gas payment enforcement policy type `{pt}`"))
+                                .take_err(&mut err, || cwp + "type")
+                        }
                     }
-                    Some(pt) => Err(eyre!("Unknown gas payment enforcement policy type `{pt}`"))
-                        .take_err(&mut err, || cwp + "type"),
-                }.map(|policy| GasPaymentEnforcementConf {
-                    policy,
-                    matching_list,
+                    .map(|policy| GasPaymentEnforcementConf {
+                        policy,
+                        matching_list,
+                    })
                 })
-            }).collect_vec()
-        }).unwrap_or_default();
+                .collect_vec()
+            })
+            .unwrap_or_default();
 
         if gas_payment_enforcement.is_empty() {
             gas_payment_enforcement.push(GasPaymentEnforcementConf::default());
@@ -361,6 +581,42 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             .parse_bool()
             .unwrap_or(true);
 
+        let rate_limits = p
+            .chain(&mut err)
+            .get_opt_key("rateLimits")
+            .and_then(parse_rate_limits)
+            .unwrap_or_default();
+
+        let app_context_weights = p
+            .chain(&mut err)
+            .get_opt_key("appContextWeights")
+            .and_then(parse_app_context_weights)
+            .end();
+
+        let shadow_mode = p
+            .chain(&mut err)
+            .get_opt_key("shadowMode")
+            .parse_bool()
+            .unwrap_or(false);
+
+        let webhooks = p
+            .chain(&mut err)
+            .get_opt_key("webhooks")
+            .and_then(parse_webhooks)
+            .unwrap_or_default();
+
+        let quorum_selection_strategy = p
+            .chain(&mut err)
+            .get_opt_key("quorumSelectionStrategy")
+            .and_then(parse_quorum_selection_strategy)
+            .unwrap_or_default();
+
+        let ccip_read_gateway_timeouts = p
+            .chain(&mut err)
+            .get_opt_key("ccipReadGatewayTimeouts")
+            .and_then(parse_ccip_read_gateway_timeouts)
+            .unwrap_or_default();
+
         err.into_result(RelayerSettings {
             base,
             db,
@@ -379,6 +635,12 @@ impl FromRawConf<RawRelayerSettings> for RelayerSettings {
             max_retries: max_message_retries,
             tx_id_indexing_enabled,
             igp_indexing_enabled,
+            rate_limits,
+            app_context_weights,
+            shadow_mode,
+            webhooks,
+            quorum_selection_strategy,
+            ccip_read_gateway_timeouts,
         })
     }
 }
@@ -403,6 +665,79 @@ fn parse_json_array(p: ValueParser) -> Option<(ConfigPath, Value)> {
     }
 }
 
+/// Parses a JSON array of config entries, checking each entry with `check`.
+/// Entries failing the check are skipped, with the error reported at the
+/// field `check` returns.
+fn parse_json_list<T, U, C>(
+    p: ValueParser,
+    expected: &'static str,
+    mut check: impl FnMut(T) -> Result<U, (&'static str, eyre::Report)>,
+) -> ConfigResult<C>
+where
+    T: DeserializeOwned,
+    C: FromIterator<U> + Default,
+{
+    let mut err = ConfigParsingError::default();
+
+    let raw_list = parse_json_array(p.clone()).map(|(_, v)| v);
+    let Some(raw_list) = raw_list else {
+        return err.into_result(C::default());
+    };
+    let p = ValueParser::new(p.cwp.clone(), &raw_list);
+    let list = p
+        .parse_value::<Vec<T>>(expected)
+        .take_config_err(&mut err)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|conf| match check(conf) {
+            Ok(conf) => Some(conf),
+            Err((field, e)) => Err(e).take_err(&mut err, || &p.cwp + field),
+        })
+        .collect();
+
+    err.into_result(list)
+}
+
+/// Parses a `numerator / denominator` fraction of a gas payment enforcement
+/// policy, i.e. the `gasFraction` of OnChainFeeQuoting or the `costFraction` of
+/// TokenPriceQuoting. The denominator must not be zero.
+fn parse_fraction(
+    policy: &ValueParser,
+    key: &str,
+    err_key: &str,
+    default: &str,
+    err: &mut ConfigParsingError,
+) -> (u64, u64) {
+    let fraction = policy
+        .chain(err)
+        .get_opt_key(key)
+        .parse_string()
+        .map(|v| v.replace(' ', ""))
+        .unwrap_or_else(|| default.to_owned());
+    let (numerator, denominator) = fraction
+        .split_once('/')
+        .ok_or_else(|| eyre!("Invalid `{err_key}` for gas payment enforcement policy; expected `numerator / denominator`"))
+        .take_err(err, || &policy.cwp + err_key)
+        .unwrap_or(("1", "1"));
+
+    (
+        numerator
+            .parse()
+            .context("Error parsing fraction numerator")
+            .take_err(err, || &policy.cwp + err_key)
+            .unwrap_or(1),
+        denominator
+            .parse()
+            .context("Error parsing fraction denominator")
+            .and_then(|denominator| match denominator {
+                0 => Err(eyre!("Fraction denominator cannot be zero")),
+                denominator => Ok(denominator),
+            })
+            .take_err(err, || &policy.cwp + err_key)
+            .unwrap_or(1),
+    )
+}
+
 fn parse_matching_list(p: ValueParser) -> ConfigResult<MatchingList> {
     let mut err = ConfigParsingError::default();
 
@@ -435,6 +770,77 @@ fn parse_ism_cache_configs(p: ValueParser) -> ConfigResult<Vec<IsmCacheConfig>>
     err.into_result(ml)
 }
 
+fn parse_rate_limits(p: ValueParser) -> ConfigResult<Vec<RateLimitConf>> {
+    parse_json_list(p, "Expected rate limits", |conf: RateLimitConf| {
+        if conf.capacity == 0 {
+            Err(("capacity", eyre!("Rate limit capacity must be at least 1")))
+        } else if !(conf.refill_rate.is_finite() && conf.refill_rate > 0.) {
+            Err((
+                "refill_rate",
+                eyre!("Rate limit refill rate must be a positive number"),
+            ))
+        } else {
+            Ok(conf)
+        }
+    })
+}
+
+fn parse_app_context_weights(p: ValueParser) -> ConfigResult<HashMap<String, u32>> {
+    parse_json_list(
+        p,
+        "Expected app context weights",
+        |conf: AppContextWeightConf| {
+            if conf.weight == 0 {
+                Err(("weight", eyre!("App context weight must be at least 1")))
+            } else {
+                Ok((conf.app_context, conf.weight))
+            }
+        },
+    )
+}
+
+fn parse_quorum_selection_strategy(p: ValueParser) -> ConfigResult<QuorumSelectionStrategy> {
+    p.parse_value("Invalid quorum selection strategy")
+}
+
+fn parse_ccip_read_gateway_timeouts(p: ValueParser) -> ConfigResult<HashMap<String, Duration>> {
+    parse_json_list(
+        p,
+        "Expected CCIP read gateway timeouts",
+        |conf: CcipReadGatewayTimeoutConf| {
+            if conf.timeout == 0 {
+                Err((
+                    "timeout",
+                    eyre!("CCIP read gateway timeout must be at least 1 second"),
+                ))
+            } else {
+                Ok((conf.host, Duration::from_secs(conf.timeout)))
+            }
+        },
+    )
+}
+
+fn parse_webhooks(p: ValueParser) -> ConfigResult<Vec<WebhookConf>> {
+    let mut ids = HashSet::new();
+    parse_json_list(p, "Expected webhooks", |conf: WebhookConf| {
+        if let Err(e) = reqwest::Url::parse(&conf.url) {
+            Err(("url", eyre!("Invalid webhook URL: {e}")))
+        } else if conf.secret.is_empty() {
+            Err(("secret", eyre!("Webhook secret cannot be empty")))
+        } else if !ids.insert(conf.id().to_owned()) {
+            Err((
+                "name",
+                eyre!(
+                    "Duplicate webhook name `{}`, set a unique `name` for webhooks sharing a URL",
+                    conf.id()
+                ),
+            ))
+        } else {
+            Ok(conf)
+        }
+    })
+}
+
 fn parse_address_list(
     str: &str,
     err: &mut ConfigParsingError,
@@ -484,6 +890,23 @@ mod test {
         assert!(!err.is_ok());
     }
 
+    #[test]
+    fn test_parse_token_price_source() {
+        assert_eq!(
+            "https://localhost:8080/prices"
+                .parse::<TokenPriceSource>()
+                .unwrap(),
+            TokenPriceSource::Http("https://localhost:8080/prices".to_owned())
+        );
+        assert_eq!(
+            "/etc/relayer/prices.json"
+                .parse::<TokenPriceSource>()
+                .unwrap(),
+            TokenPriceSource::File(PathBuf::from("/etc/relayer/prices.json"))
+        );
+        assert!("".parse::<TokenPriceSource>().is_err());
+    }
+
     #[test]
     fn test_parse_ism_cache_configs() {
         let raw = r#"
@@ -513,4 +936,217 @@ mod test {
         let configs = parse_ism_cache_configs(p).expect("Failed to parse ism cache config");
         assert_eq!(configs.len(), 2);
     }
+
+    /// Parses the `raw` JSON value of a setting with `parse`
+    fn parse_raw<T>(parse: fn(ValueParser) -> ConfigResult<T>, raw: &str) -> ConfigResult<T> {
+        let value = serde_json::from_str::<Value>(raw).expect("Failed to parse json");
+        parse(ValueParser::new(ConfigPath::default(), &value))
+    }
+
+    #[test]
+    fn test_parse_rate_limits() {
+        let conf = |matching_list, capacity, refill_rate, scope| RateLimitConf {
+            matching_list,
+            capacity,
+            refill_rate,
+            scope,
+        };
+        let cases = [
+            (
+                r#"[
+                    { "matchinglist": [{ "origindomain": 1 }], "capacity": 10, "refillrate": 0.5, "scope": "sender" },
+                    { "capacity": 100, "refillrate": 20 }
+                ]"#,
+                Some(vec![
+                    conf(
+                        serde_json::from_str(r#"[{ "origindomain": 1 }]"#).unwrap(),
+                        10,
+                        0.5,
+                        RateLimitScope::Sender,
+                    ),
+                    conf(MatchingList::default(), 100, 20., RateLimitScope::Shared),
+                ]),
+            ),
+            ("[]", Some(vec![])),
+            (r#"[{ "capacity": 0, "refillrate": 1 }]"#, None),
+            (r#"[{ "capacity": 1, "refillrate": 0 }]"#, None),
+            (r#"[{ "capacity": 1, "refillrate": -1 }]"#, None),
+            (r#"[{ "capacity": 1 }]"#, None),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(parse_raw(parse_rate_limits, raw).ok(), expected, "{raw}");
+        }
+    }
+
+    #[test]
+    fn test_parse_app_context_weights() {
+        let cases = [
+            (
+                r#"[
+                    { "appcontext": "governance", "weight": 10 },
+                    { "appcontext": "warp_route", "weight": 1 }
+                ]"#,
+                Some(HashMap::from([
+                    ("governance".to_owned(), 10),
+                    ("warp_route".to_owned(), 1),
+                ])),
+            ),
+            ("[]", Some(HashMap::new())),
+            (r#"[{ "appcontext": "governance", "weight": 0 }]"#, None),
+            (r#"[{ "appcontext": "governance" }]"#, None),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(
+                parse_raw(parse_app_context_weights, raw).ok(),
+                expected,
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_fraction() {
+        let value = serde_json::json!({ "costFraction": "3 / 4" });
+        let p = ValueParser::new(ConfigPath::default(), &value);
+        let mut err = ConfigParsingError::default();
+        let fraction = parse_fraction(&p, "costFraction", "cost_fraction", "1/1", &mut err);
+        assert_eq!(fraction, (3, 4));
+        assert!(err.into_result(()).is_ok());
+
+        let value = serde_json::json!({ "costFraction": "1/0" });
+        let p = ValueParser::new(ConfigPath::default(), &value);
+        let mut err = ConfigParsingError::default();
+        parse_fraction(&p, "costFraction", "cost_fraction", "1/1", &mut err);
+        assert!(err.into_result(()).is_err());
+
+        let value = serde_json::json!({});
+        let p = ValueParser::new(ConfigPath::default(), &value);
+        let mut err = ConfigParsingError::default();
+        let fraction = parse_fraction(&p, "gasFraction", "gas_fraction", "1/2", &mut err);
+        assert_eq!(fraction, (1, 2));
+        assert!(err.into_result(()).is_ok());
+
+        let value = serde_json::json!({ "gasFraction": "1/0" });
+        let p = ValueParser::new(ConfigPath::default(), &value);
+        let mut err = ConfigParsingError::default();
+        parse_fraction(&p, "gasFraction", "gas_fraction", "1/2", &mut err);
+        assert!(err.into_result(()).is_err());
+    }
+
+    #[test]
+    fn test_parse_quorum_selection_strategy() {
+        let cases = [
+            (
+                r#""minimizeGas""#,
+                Some(QuorumSelectionStrategy::MinimizeGas),
+            ),
+            (
+                r#""lowestLatency""#,
+                Some(QuorumSelectionStrategy::LowestLatency),
+            ),
+            (
+                r#""freshestCheckpoint""#,
+                Some(QuorumSelectionStrategy::FreshestCheckpoint),
+            ),
+            (r#""fastest""#, None),
+            ("1", None),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(
+                parse_raw(parse_quorum_selection_strategy, raw).ok(),
+                expected,
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_ccip_read_gateway_timeouts() {
+        let cases = [
+            (
+                r#"[
+                    { "host": "gateway.example.com", "timeout": 5 },
+                    { "host": "slow.example.com", "timeout": 120 }
+                ]"#,
+                Some(HashMap::from([
+                    ("gateway.example.com".to_owned(), Duration::from_secs(5)),
+                    ("slow.example.com".to_owned(), Duration::from_secs(120)),
+                ])),
+            ),
+            ("[]", Some(HashMap::new())),
+            (r#"[{ "host": "gateway.example.com", "timeout": 0 }]"#, None),
+            (
+                r#"[{ "host": "gateway.example.com", "timeout": -1 }]"#,
+                None,
+            ),
+            (r#"[{ "timeout": 5 }]"#, None),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(
+                parse_raw(parse_ccip_read_gateway_timeouts, raw).ok(),
+                expected,
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_webhooks() {
+        let raw = r#"
+        [
+            {
+                "url": "https://example.com/hyperlane",
+                "secret": "s3cr3t",
+                "matchinglist": [{ "destinationdomain": 1 }],
+                "events": ["transition", "stuck"],
+                "stuckafterretries": 3
+            },
+            {
+                "url": "https://example.com/all",
+                "secret": "other"
+            }
+        ]
+        "#;
+
+        let webhooks = parse_raw(parse_webhooks, raw).expect("Failed to parse webhooks");
+        assert_eq!(webhooks.len(), 2);
+        assert_eq!(
+            webhooks[0].events,
+            vec![WebhookEvent::Transition, WebhookEvent::Stuck]
+        );
+        assert_eq!(webhooks[0].stuck_after_retries, 3);
+        assert_eq!(webhooks[1].events, default_webhook_events());
+        assert_eq!(webhooks[1].stuck_after_retries, 10);
+        assert!(!format!("{:?}", webhooks[0]).contains("s3cr3t"));
+
+        let cases = [
+            (r#"[{ "url": "not a url", "secret": "s3cr3t" }]"#, None),
+            (
+                r#"[{ "url": "https://example.com/all", "secret": "" }]"#,
+                None,
+            ),
+            (
+                r#"[
+                    { "url": "https://example.com/all", "secret": "s3cr3t" },
+                    { "url": "https://example.com/all", "secret": "other" }
+                ]"#,
+                None,
+            ),
+            (
+                r#"[
+                    { "url": "https://example.com/all", "secret": "s3cr3t" },
+                    { "name": "second", "url": "https://example.com/all", "secret": "other" }
+                ]"#,
+                Some(vec!["https://example.com/all", "second"]),
+            ),
+            ("[]", Some(vec![])),
+        ];
+        for (raw, expected) in cases {
+            let ids = parse_raw(parse_webhooks, raw)
+                .ok()
+                .map(|webhooks| webhooks.iter().map(|w| w.id().to_owned()).collect_vec());
+            let expected = expected.map(|ids| ids.into_iter().map(str::to_owned).collect_vec());
+            assert_eq!(ids, expected, "{raw}");
+        }
+    }
 }
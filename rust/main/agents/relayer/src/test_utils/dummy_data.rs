@@ -88,6 +88,8 @@ pub fn dummy_metadata_builder(
         IsmCachePolicyClassifier::new(default_ism_getter, Default::default()),
         None,
         false,
+        Default::default(),
+        Default::default(),
     )
 }
 
@@ -124,5 +126,8 @@ pub fn dummy_message_context(
         transaction_gas_limit: Default::default(),
         metrics: dummy_submission_metrics(),
         application_operation_verifier: Some(Arc::new(DummyApplicationOperationVerifier {})),
+        shadow_mode: false,
+        webhook_notifier: None,
+        ism_trees: None,
     }
 }
@@ -1,6 +1,7 @@
 use std::{
     collections::{HashMap, VecDeque},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use hyperlane_base::{
@@ -17,8 +18,9 @@ use hyperlane_test::mocks::MockMailboxContract;
 
 use crate::{
     msg::metadata::{
-        BuildsBaseMetadata, DefaultIsmCache, IsmAwareAppContextClassifier, IsmBuildMetricsParams,
-        IsmCachePolicyClassifier,
+        BuildsBaseMetadata, CcipReadGatewayMetricsParams, DefaultIsmCache,
+        IsmAwareAppContextClassifier, IsmBuildMetricsParams, IsmCachePolicyClassifier,
+        QuorumCheckpointCache, CCIP_READ_DEFAULT_TIMEOUT,
     },
     settings::matching_list::{Filter, ListElement, MatchingList},
 };
@@ -100,12 +102,15 @@ impl MockBaseMetadataBuilderResponses {
 #[derive(Debug, Default)]
 pub struct MockBaseMetadataBuilderRequests {
     pub update_ism_metrics: RequestList<IsmBuildMetricsParams>,
+    pub update_ccip_read_gateway_metrics: RequestList<CcipReadGatewayMetricsParams>,
 }
 
 #[derive(Debug, Default)]
 pub struct MockBaseMetadataBuilder {
     pub responses: MockBaseMetadataBuilderResponses,
     pub requests: MockBaseMetadataBuilderRequests,
+    pub quorum_checkpoints: QuorumCheckpointCache,
+    pub ccip_read_gateway_timeouts: HashMap<String, Duration>,
 }
 
 impl MockBaseMetadataBuilder {
@@ -113,6 +118,8 @@ impl MockBaseMetadataBuilder {
         Self {
             responses: MockBaseMetadataBuilderResponses::default(),
             requests: MockBaseMetadataBuilderRequests::default(),
+            quorum_checkpoints: QuorumCheckpointCache::default(),
+            ccip_read_gateway_timeouts: HashMap::new(),
         }
     }
 }
@@ -152,6 +159,9 @@ impl BuildsBaseMetadata for MockBaseMetadataBuilder {
             .as_ref()
             .expect("No mock cache response set")
     }
+    fn quorum_checkpoints(&self) -> &QuorumCheckpointCache {
+        &self.quorum_checkpoints
+    }
     fn update_ism_metric(&self, params: IsmBuildMetricsParams) {
         self.requests
             .update_ism_metrics
@@ -160,6 +170,21 @@ impl BuildsBaseMetadata for MockBaseMetadataBuilder {
             .push(params);
     }
 
+    fn update_ccip_read_gateway_metric(&self, params: CcipReadGatewayMetricsParams) {
+        self.requests
+            .update_ccip_read_gateway_metrics
+            .lock()
+            .unwrap()
+            .push(params);
+    }
+
+    fn ccip_read_gateway_timeout(&self, host: &str) -> Duration {
+        self.ccip_read_gateway_timeouts
+            .get(host)
+            .copied()
+            .unwrap_or(Duration::from_secs(CCIP_READ_DEFAULT_TIMEOUT))
+    }
+
     async fn get_proof(&self, _leaf_index: u32, _checkpoint: Checkpoint) -> eyre::Result<Proof> {
         self.responses
             .get_proof
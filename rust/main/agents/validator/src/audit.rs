@@ -0,0 +1,402 @@
+use std::{sync::Arc, time::Duration};
+
+use derive_new::new;
+use eyre::{Result, WrapErr};
+use futures::{future::try_join_all, stream, StreamExt, TryStreamExt};
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+use hyperlane_base::{db::HyperlaneDb, CheckpointSyncer};
+use hyperlane_core::{
+    accumulator::incremental::IncrementalMerkle, Checkpoint, CheckpointWithMessageId,
+    SignedCheckpointWithMessageId, H160, H256,
+};
+
+/// A problem found with the published checkpoints of a validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointAuditFinding {
+    /// No checkpoint is published at an index below the latest index of the
+    /// storage location
+    Gap { location: String, index: u32 },
+    /// The checkpoint isn't signed by the announced validator
+    InvalidSignature {
+        location: String,
+        index: u32,
+        signer: Option<H160>,
+    },
+    /// The validator signed a checkpoint that doesn't match the locally
+    /// rebuilt merkle tree
+    Mismatch {
+        location: String,
+        expected: CheckpointWithMessageId,
+        signed: CheckpointWithMessageId,
+    },
+    /// The validator signed different checkpoints at the same index
+    Equivocation { index: u32, locations: Vec<String> },
+    /// The merkle tree hook insertion at the index wasn't indexed in time,
+    /// so checkpoints from the index on weren't audited
+    MissingInsertion { index: u32 },
+}
+
+/// The outcome of auditing the published checkpoints of a validator.
+#[derive(Debug, Default)]
+pub struct CheckpointAuditReport {
+    /// The highest latest index across the audited storage locations
+    pub latest_index: Option<u32>,
+    /// The problems found, by increasing index
+    pub findings: Vec<CheckpointAuditFinding>,
+}
+
+/// Walks the checkpoints a validator published to each of its storage
+/// locations and checks them against the merkle tree hook insertions indexed
+/// in the local db.
+#[derive(new)]
+pub(crate) struct CheckpointAuditor {
+    validator: H160,
+    merkle_tree_hook_address: H256,
+    mailbox_domain: u32,
+    db: Arc<dyn HyperlaneDb>,
+    /// Number of indices fetched concurrently
+    concurrency: usize,
+    /// How long to wait for the merkle tree hook sync to index an insertion
+    insertion_timeout: Duration,
+}
+
+impl CheckpointAuditor {
+    /// Audit every checkpoint from index 0 up to the latest index of each of
+    /// the storage locations, given as `(location, syncer)` pairs.
+    pub(crate) async fn audit(
+        &self,
+        syncers: &[(String, Arc<dyn CheckpointSyncer>)],
+    ) -> Result<CheckpointAuditReport> {
+        let latest_indices = try_join_all(syncers.iter().map(|(location, syncer)| async move {
+            syncer
+                .latest_index()
+                .await
+                .wrap_err_with(|| format!("Reading latest index from {location}"))
+        }))
+        .await?;
+        let mut report = CheckpointAuditReport {
+            latest_index: latest_indices.iter().copied().flatten().max(),
+            findings: vec![],
+        };
+        let Some(latest_index) = report.latest_index else {
+            return Ok(report);
+        };
+
+        let mut tree = IncrementalMerkle::default();
+        let mut checkpoints = stream::iter(0..=latest_index)
+            .map(|index| self.fetch_checkpoints(syncers, &latest_indices, index))
+            .buffered(self.concurrency.max(1));
+        while let Some((index, fetched)) = checkpoints.try_next().await? {
+            let Some(message_id) = self.message_id_at(index).await? else {
+                warn!(
+                    index,
+                    timeout = ?self.insertion_timeout,
+                    "Merkle tree hook insertion wasn't indexed in time, stopping the audit"
+                );
+                report
+                    .findings
+                    .push(CheckpointAuditFinding::MissingInsertion { index });
+                break;
+            };
+            tree.ingest(message_id);
+            let expected = CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: self.merkle_tree_hook_address,
+                    mailbox_domain: self.mailbox_domain,
+                    root: tree.root(),
+                    index,
+                },
+                message_id,
+            };
+            self.check_index(index, expected, fetched, &mut report.findings);
+
+            if index % 1000 == 0 {
+                info!(
+                    index,
+                    latest_index,
+                    findings = report.findings.len(),
+                    "Audited checkpoints"
+                );
+            }
+        }
+        Ok(report)
+    }
+
+    /// Fetch the checkpoint at `index` from each location whose latest index
+    /// covers it.
+    async fn fetch_checkpoints<'a>(
+        &self,
+        syncers: &'a [(String, Arc<dyn CheckpointSyncer>)],
+        latest_indices: &[Option<u32>],
+        index: u32,
+    ) -> Result<(u32, Vec<(&'a str, Option<SignedCheckpointWithMessageId>)>)> {
+        let fetched = try_join_all(
+            syncers
+                .iter()
+                .zip(latest_indices)
+                .filter(|(_, latest_index)| latest_index.is_some_and(|latest| latest >= index))
+                .map(|((location, syncer), _)| async move {
+                    let checkpoint = syncer
+                        .fetch_checkpoint(index)
+                        .await
+                        .wrap_err_with(|| format!("Fetching checkpoint {index} from {location}"))?;
+                    Ok::<_, eyre::Report>((location.as_str(), checkpoint))
+                }),
+        )
+        .await?;
+        Ok((index, fetched))
+    }
+
+    /// The message id inserted at `index`, waiting up to the insertion
+    /// timeout for the merkle tree hook sync to index it if needed.
+    async fn message_id_at(&self, index: u32) -> Result<Option<H256>> {
+        let wait_for_insertion = async {
+            loop {
+                if let Some(insertion) = self
+                    .db
+                    .retrieve_merkle_tree_insertion_by_leaf_index(&index)?
+                {
+                    return Ok::<_, eyre::Report>(insertion.message_id());
+                }
+                sleep(Duration::from_millis(100)).await;
+            }
+        };
+        match timeout(self.insertion_timeout, wait_for_insertion).await {
+            Ok(message_id) => message_id.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn check_index(
+        &self,
+        index: u32,
+        expected: CheckpointWithMessageId,
+        fetched: Vec<(&str, Option<SignedCheckpointWithMessageId>)>,
+        findings: &mut Vec<CheckpointAuditFinding>,
+    ) {
+        let mut signed_values = vec![];
+        let mut signed_locations = vec![];
+        for (location, checkpoint) in fetched {
+            let Some(checkpoint) = checkpoint else {
+                findings.push(CheckpointAuditFinding::Gap {
+                    location: location.to_owned(),
+                    index,
+                });
+                continue;
+            };
+            let signer = checkpoint.recover().ok();
+            if signer != Some(self.validator) {
+                findings.push(CheckpointAuditFinding::InvalidSignature {
+                    location: location.to_owned(),
+                    index,
+                    signer,
+                });
+                continue;
+            }
+            if checkpoint.value != expected {
+                findings.push(CheckpointAuditFinding::Mismatch {
+                    location: location.to_owned(),
+                    expected,
+                    signed: checkpoint.value,
+                });
+            }
+            if !signed_values.contains(&checkpoint.value) {
+                signed_values.push(checkpoint.value);
+            }
+            signed_locations.push(location.to_owned());
+        }
+        if signed_values.len() > 1 {
+            findings.push(CheckpointAuditFinding::Equivocation {
+                index,
+                locations: signed_locations,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use hyperlane_base::{
+        db::{HyperlaneRocksDB, DB},
+        LocalStorage,
+    };
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneSigner, HyperlaneSignerExt, KnownHyperlaneDomain,
+        MerkleTreeInsertion,
+    };
+    use hyperlane_ethereum::Signers;
+
+    use super::*;
+
+    const PRIVATE_KEY: &str = "254bf805ec98536bbcfcf7bd88f58aa17bcf2955138237d3d06288d39fabfecb";
+    const OTHER_PRIVATE_KEY: &str =
+        "5c5ec0dd04b7a8b4ea7d204bb8d30159fe33bdf29c0015986b430ff5b952b5fb";
+
+    fn signer(private_key: &str) -> Signers {
+        private_key
+            .parse::<ethers::signers::LocalWallet>()
+            .unwrap()
+            .into()
+    }
+
+    fn local_storage(path: PathBuf) -> (String, Arc<dyn CheckpointSyncer>) {
+        let storage = LocalStorage::new(path, None).unwrap();
+        (storage.announcement_location(), Arc::new(storage))
+    }
+
+    #[tokio::test]
+    async fn test_audit_reports_findings() {
+        let db_dir = tempfile::tempdir().unwrap();
+        let db = HyperlaneRocksDB::new(
+            &HyperlaneDomain::Known(KnownHyperlaneDomain::Test1),
+            DB::from_path(db_dir.path()).unwrap(),
+        );
+        let message_ids = [H256::random(), H256::random(), H256::random()];
+        let mut tree = IncrementalMerkle::default();
+        let mut checkpoints = vec![];
+        for (index, message_id) in message_ids.iter().enumerate() {
+            db.store_merkle_tree_insertion_by_leaf_index(
+                &(index as u32),
+                &MerkleTreeInsertion::new(index as u32, *message_id),
+            )
+            .unwrap();
+            tree.ingest(*message_id);
+            checkpoints.push(CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::zero(),
+                    mailbox_domain: 13371,
+                    root: tree.root(),
+                    index: index as u32,
+                },
+                message_id: *message_id,
+            });
+        }
+
+        let validator = signer(PRIVATE_KEY);
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let primary = local_storage(primary_dir.path().into());
+        let mirror = local_storage(mirror_dir.path().into());
+
+        // The primary location misses index 1, and index 2 is signed by
+        // someone else
+        primary
+            .1
+            .write_checkpoint(&validator.sign(checkpoints[0]).await.unwrap())
+            .await
+            .unwrap();
+        primary
+            .1
+            .write_checkpoint(
+                &signer(OTHER_PRIVATE_KEY)
+                    .sign(checkpoints[2])
+                    .await
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        primary.1.write_latest_index(2).await.unwrap();
+
+        // The mirror has a different root signed at index 0
+        let mut forged = checkpoints[0];
+        forged.checkpoint.root = H256::random();
+        mirror
+            .1
+            .write_checkpoint(&validator.sign(forged).await.unwrap())
+            .await
+            .unwrap();
+        mirror.1.write_latest_index(0).await.unwrap();
+
+        let auditor = CheckpointAuditor::new(
+            validator.eth_address(),
+            H256::zero(),
+            13371,
+            Arc::new(db),
+            2,
+            Duration::from_secs(1),
+        );
+        let report = auditor
+            .audit(&[primary.clone(), mirror.clone()])
+            .await
+            .unwrap();
+
+        assert_eq!(report.latest_index, Some(2));
+        assert_eq!(
+            report.findings,
+            vec![
+                CheckpointAuditFinding::Mismatch {
+                    location: mirror.0.clone(),
+                    expected: checkpoints[0],
+                    signed: forged,
+                },
+                CheckpointAuditFinding::Equivocation {
+                    index: 0,
+                    locations: vec![primary.0.clone(), mirror.0.clone()],
+                },
+                CheckpointAuditFinding::Gap {
+                    location: primary.0.clone(),
+                    index: 1,
+                },
+                CheckpointAuditFinding::InvalidSignature {
+                    location: primary.0.clone(),
+                    index: 2,
+                    signer: Some(signer(OTHER_PRIVATE_KEY).eth_address()),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_stops_at_missing_insertion() {
+        let db_dir = tempfile::tempdir().unwrap();
+        let db = HyperlaneRocksDB::new(
+            &HyperlaneDomain::Known(KnownHyperlaneDomain::Test1),
+            DB::from_path(db_dir.path()).unwrap(),
+        );
+        let message_id = H256::random();
+        db.store_merkle_tree_insertion_by_leaf_index(&0, &MerkleTreeInsertion::new(0, message_id))
+            .unwrap();
+        let mut tree = IncrementalMerkle::default();
+        tree.ingest(message_id);
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::zero(),
+                mailbox_domain: 13371,
+                root: tree.root(),
+                index: 0,
+            },
+            message_id,
+        };
+
+        // The location claims index 1, whose insertion is never indexed
+        let validator = signer(PRIVATE_KEY);
+        let storage_dir = tempfile::tempdir().unwrap();
+        let storage = local_storage(storage_dir.path().into());
+        storage
+            .1
+            .write_checkpoint(&validator.sign(checkpoint).await.unwrap())
+            .await
+            .unwrap();
+        storage.1.write_latest_index(1).await.unwrap();
+
+        let auditor = CheckpointAuditor::new(
+            validator.eth_address(),
+            H256::zero(),
+            13371,
+            Arc::new(db),
+            2,
+            Duration::from_millis(200),
+        );
+        let report = auditor.audit(&[storage]).await.unwrap();
+
+        assert_eq!(report.latest_index, Some(1));
+        assert_eq!(
+            report.findings,
+            vec![CheckpointAuditFinding::MissingInsertion { index: 1 }]
+        );
+    }
+}
@@ -10,6 +10,8 @@ use hyperlane_base::agent_main;
 
 use crate::validator::Validator;
 
+mod audit;
+mod origin;
 mod reorg_reporter;
 mod server;
 mod settings;
@@ -0,0 +1,629 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use eyre::{eyre, Context, Result};
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{error, info, info_span, warn, Instrument};
+
+use hyperlane_base::{
+    db::{HyperlaneDb, HyperlaneRocksDB, SlashingProtectionDb, SlashingProtectionInterchange, DB},
+    settings::{ChainConf, CheckpointSyncerBuildError, CheckpointSyncerConf},
+    CheckpointSyncer, ContractSyncMetrics, ContractSyncer, CoreMetrics, SequencedDataContractSync,
+};
+use hyperlane_core::{
+    Announcement, ChainResult, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneSigner,
+    HyperlaneSignerExt, Mailbox, MerkleTreeHook, MerkleTreeInsertion, ReorgPeriod, TxOutcome,
+    ValidatorAnnounce, H160, H256, U256,
+};
+use hyperlane_ethereum::{Signers, SingletonSignerHandle};
+
+use crate::audit::CheckpointAuditor;
+use crate::reorg_reporter::{LatestCheckpointReorgReporter, ReorgReporter};
+use crate::{
+    settings::{KeyRotationConf, ValidatorSettings},
+    submit::{KeyRotation, ValidatorSubmitter, ValidatorSubmitterMetrics},
+    validator::ValidatorMetadata,
+};
+
+/// How long the checkpoint audit waits for the merkle tree hook sync to index
+/// the next insertion before giving up
+const AUDIT_INSERTION_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Validates the messages of a single origin chain: indexes its merkle tree
+/// hook and signs and submits its checkpoints.
+#[derive(Debug)]
+pub(crate) struct ValidatorOrigin {
+    pub(crate) origin_chain: HyperlaneDomain,
+    pub(crate) origin_chain_conf: ChainConf,
+    pub(crate) db: HyperlaneRocksDB,
+    merkle_tree_hook_sync: Arc<SequencedDataContractSync<MerkleTreeInsertion>>,
+    mailbox: Arc<dyn Mailbox>,
+    merkle_tree_hook: Arc<dyn MerkleTreeHook>,
+    validator_announce: Arc<dyn ValidatorAnnounce>,
+    signer: SingletonSignerHandle,
+    raw_signer: Signers,
+    reorg_period: ReorgPeriod,
+    interval: Duration,
+    checkpoint_syncer: Arc<dyn CheckpointSyncer>,
+    core_metrics: Arc<CoreMetrics>,
+    max_sign_concurrency: usize,
+    reorg_reporter: Arc<dyn ReorgReporter>,
+    key_rotation: Option<KeyRotation>,
+    slashing_protection: SlashingProtectionDb,
+    slashing_protection_export: Option<PathBuf>,
+}
+
+impl ValidatorOrigin {
+    /// Build the validator of `origin_chain`. With several origin chains,
+    /// `None` is returned if the checkpoint syncer of `origin_chain` has a
+    /// reorg flag set, so that the other origins are still validated. The
+    /// origin is then reported as halted, which makes the validator unhealthy.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn from_settings(
+        settings: &ValidatorSettings,
+        origin_chain: &HyperlaneDomain,
+        db: &DB,
+        slashing_protection_db: &DB,
+        signer: &SingletonSignerHandle,
+        raw_signer: &Signers,
+        metrics: &Arc<CoreMetrics>,
+        contract_sync_metrics: &Arc<ContractSyncMetrics>,
+    ) -> Result<Option<Self>> {
+        let msg_db = HyperlaneRocksDB::new(origin_chain, db.clone());
+
+        let slashing_protection =
+            SlashingProtectionDb::new(origin_chain, slashing_protection_db.clone());
+        if let Some(path) = &settings.slashing_protection_import {
+            let path = settings.origin_slashing_protection_interchange(path, origin_chain);
+            Self::import_slashing_protection(&slashing_protection, &path)?;
+        }
+
+        let reorg_reporter =
+            LatestCheckpointReorgReporter::from_settings(settings, origin_chain, metrics).await?;
+        let reorg_reporter = Arc::new(reorg_reporter) as Arc<dyn ReorgReporter>;
+
+        let checkpoint_syncer_result = settings
+            .origin_checkpoint_syncer(origin_chain)
+            .build_and_validate(None, Some(metrics.checkpoint_syncer_mirror_lag()))
+            .await;
+
+        Self::report_latest_checkpoints_from_each_endpoint(
+            &reorg_reporter,
+            &checkpoint_syncer_result,
+        )
+        .await;
+
+        if settings.is_multi_origin() {
+            if let Err(CheckpointSyncerBuildError::ReorgEvent(reorg_event)) =
+                &checkpoint_syncer_result
+            {
+                error!(
+                    origin_chain = %origin_chain,
+                    ?reorg_event,
+                    "Not validating origin chain, its checkpoint syncer has a reorg flag set"
+                );
+                metrics.set_halted(origin_chain.clone());
+                return Ok(None);
+            }
+        }
+
+        // Be extra sure to panic when checkpoint syncer fails, which indicates
+        // a fatal startup error.
+        let checkpoint_syncer: Arc<dyn CheckpointSyncer> = checkpoint_syncer_result
+            .expect("Failed to build checkpoint syncer")
+            .into();
+
+        let key_rotation = match settings.origin_key_rotation(origin_chain) {
+            Some(conf) => Some(
+                Self::build_key_rotation(&conf, raw_signer, &checkpoint_syncer, metrics).await?,
+            ),
+            None => None,
+        };
+
+        let mailbox = settings.build_mailbox(origin_chain, metrics).await?;
+
+        let merkle_tree_hook = settings
+            .build_merkle_tree_hook(origin_chain, metrics)
+            .await?;
+
+        let validator_announce = settings
+            .build_validator_announce(origin_chain, metrics)
+            .await?;
+
+        let origin_chain_conf = settings.chain_setup(origin_chain)?.clone();
+
+        let merkle_tree_hook_sync = settings
+            .sequenced_contract_sync::<MerkleTreeInsertion, _>(
+                origin_chain,
+                metrics,
+                contract_sync_metrics,
+                msg_db.clone().into(),
+                false,
+                false,
+            )
+            .await?;
+
+        let reorg_period = settings
+            .reorg_periods
+            .get(origin_chain)
+            .cloned()
+            .unwrap_or(ReorgPeriod::from_blocks(1));
+
+        let slashing_protection_export = settings
+            .slashing_protection_export
+            .as_ref()
+            .map(|path| settings.origin_slashing_protection_interchange(path, origin_chain));
+
+        Ok(Some(Self {
+            origin_chain: origin_chain.clone(),
+            origin_chain_conf,
+            db: msg_db,
+            merkle_tree_hook_sync,
+            mailbox: mailbox.into(),
+            merkle_tree_hook: merkle_tree_hook.into(),
+            validator_announce: validator_announce.into(),
+            signer: signer.clone(),
+            raw_signer: raw_signer.clone(),
+            reorg_period,
+            interval: settings.interval,
+            checkpoint_syncer,
+            core_metrics: metrics.clone(),
+            max_sign_concurrency: settings.max_sign_concurrency,
+            reorg_reporter,
+            key_rotation,
+            slashing_protection,
+            slashing_protection_export,
+        }))
+    }
+
+    /// Report the agent metadata and announce the validator, then start
+    /// indexing the merkle tree hook and submitting checkpoints once it has
+    /// count > 0.
+    pub(crate) async fn start(
+        &self,
+        agent_metadata: &ValidatorMetadata,
+    ) -> Result<Vec<JoinHandle<()>>> {
+        // report agent metadata
+        self.metadata(agent_metadata)
+            .await
+            .expect("Failed to report agent metadata");
+
+        // announce the validator after spawning the signer task
+        self.announce().await.expect("Failed to announce validator");
+
+        // Ensure that the merkle tree hook has count > 0 before we begin indexing
+        // messages or submitting checkpoints.
+        loop {
+            match self.merkle_tree_hook.count(&self.reorg_period).await {
+                Ok(0) => {
+                    info!("Waiting for first message in merkle tree hook");
+                    sleep(self.interval).await;
+                }
+                Ok(_) => {
+                    let merkle_tree_hook_sync = self
+                        .run_merkle_tree_hook_sync()
+                        .await
+                        .context("Failed to run merkle tree hook sync")?;
+                    let mut tasks = vec![merkle_tree_hook_sync];
+                    tasks.extend(self.run_checkpoint_submitters().await);
+                    return Ok(tasks);
+                }
+                Err(err) => {
+                    error!(?err, "Error getting merkle tree hook count");
+                    sleep(self.interval).await;
+                }
+            }
+        }
+    }
+
+    async fn build_key_rotation(
+        conf: &KeyRotationConf,
+        raw_signer: &Signers,
+        checkpoint_syncer: &Arc<dyn CheckpointSyncer>,
+        metrics: &CoreMetrics,
+    ) -> Result<KeyRotation> {
+        let signer: Signers = conf.validator.build().await?;
+        if signer.eth_address() == raw_signer.eth_address() {
+            return Err(eyre!(
+                "The key rotation validator must differ from the current validator"
+            ));
+        }
+
+        let rotation_checkpoint_syncer: Arc<dyn CheckpointSyncer> = conf
+            .checkpoint_syncer
+            .build_and_validate(None, Some(metrics.checkpoint_syncer_mirror_lag()))
+            .await?
+            .into();
+        let locations = checkpoint_syncer.announcement_locations();
+        if rotation_checkpoint_syncer
+            .announcement_locations()
+            .iter()
+            .any(|location| locations.contains(location))
+        {
+            return Err(eyre!(
+                "The key rotation checkpoint syncer must not share a storage location with the validator checkpoint syncer"
+            ));
+        }
+
+        if chrono::Utc::now().timestamp() as u64 >= conf.ends_at {
+            warn!(
+                ends_at = conf.ends_at,
+                "Key rotation window has ended, only signing checkpoints with the new key"
+            );
+        }
+
+        Ok(KeyRotation {
+            signer,
+            checkpoint_syncer: rotation_checkpoint_syncer,
+            ends_at: conf.ends_at,
+        })
+    }
+
+    fn import_slashing_protection(
+        slashing_protection: &SlashingProtectionDb,
+        path: &Path,
+    ) -> Result<()> {
+        let interchange = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading slashing protection interchange from {path:?}"))?;
+        let interchange: SlashingProtectionInterchange = serde_json::from_str(&interchange)
+            .with_context(|| format!("Parsing slashing protection interchange from {path:?}"))?;
+        let imported = slashing_protection.import(&interchange)?;
+        info!(
+            ?path,
+            imported,
+            total = interchange.signed_checkpoints.len(),
+            "Imported slashing protection interchange"
+        );
+        Ok(())
+    }
+
+    /// Export the slashing protection database to the configured path, if any.
+    pub(crate) fn export_slashing_protection(&self) -> Result<()> {
+        let Some(path) = &self.slashing_protection_export else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Creating slashing protection directory {dir:?}"))?;
+        }
+        let interchange = self.slashing_protection.export()?;
+        std::fs::write(path, serde_json::to_string_pretty(&interchange)?)
+            .with_context(|| format!("Writing slashing protection interchange to {path:?}"))?;
+        info!(?path, "Exported slashing protection database");
+        Ok(())
+    }
+
+    async fn run_merkle_tree_hook_sync(&self) -> eyre::Result<JoinHandle<()>> {
+        let index_settings = self.origin_chain_conf.index_settings();
+        let contract_sync = self.merkle_tree_hook_sync.clone();
+        let cursor = contract_sync.cursor(index_settings).await?;
+        let origin = self.origin_chain.name().to_string();
+
+        let handle = tokio::spawn(
+            async move {
+                let label = "merkle_tree_hook";
+                contract_sync.clone().sync(label, cursor.into()).await;
+                info!(chain = origin, label, "contract sync task exit");
+            }
+            .instrument(info_span!("MerkleTreeHookSyncer")),
+        );
+        Ok(handle)
+    }
+
+    /// Audit the checkpoints published to each announced storage location,
+    /// while indexing the merkle tree hook to rebuild the tree locally.
+    /// Returns whether the audit completed without findings.
+    pub(crate) async fn run_checkpoint_audit(&self) -> bool {
+        let merkle_tree_hook_sync = match self.run_merkle_tree_hook_sync().await {
+            Ok(handle) => handle,
+            Err(err) => {
+                error!(?err, "Failed to run merkle tree hook sync");
+                return false;
+            }
+        };
+
+        let address = self.signer.eth_address();
+        let auditor = CheckpointAuditor::new(
+            address,
+            self.merkle_tree_hook.address(),
+            self.merkle_tree_hook.domain().id(),
+            Arc::new(self.db.clone()),
+            self.max_sign_concurrency,
+            AUDIT_INSERTION_TIMEOUT,
+        );
+        let result = match self.announced_checkpoint_syncers(address).await {
+            Ok(syncers) => auditor.audit(&syncers).await,
+            Err(err) => Err(err),
+        };
+        merkle_tree_hook_sync.abort();
+
+        let report = match result {
+            Ok(report) => report,
+            Err(err) => {
+                error!(?err, "Failed to audit checkpoints");
+                return false;
+            }
+        };
+        for finding in &report.findings {
+            warn!(?finding, "Checkpoint audit finding");
+        }
+        if report.findings.is_empty() {
+            info!(latest_index = ?report.latest_index, "Checkpoint audit found no problems");
+            true
+        } else {
+            error!(
+                latest_index = ?report.latest_index,
+                findings = report.findings.len(),
+                "Checkpoint audit found problems"
+            );
+            false
+        }
+    }
+
+    /// The checkpoint syncers of the storage locations announced by
+    /// `address`, or the configured one if none is announced yet.
+    async fn announced_checkpoint_syncers(
+        &self,
+        address: H160,
+    ) -> Result<Vec<(String, Arc<dyn CheckpointSyncer>)>> {
+        let locations = self
+            .validator_announce
+            .get_announced_storage_locations(&[address.into()])
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if locations.is_empty() {
+            warn!(
+                ?address,
+                "Validator has not announced any storage location, auditing the configured one"
+            );
+            return Ok(vec![(
+                self.checkpoint_syncer.announcement_location(),
+                self.checkpoint_syncer.clone(),
+            )]);
+        }
+
+        let mut syncers = vec![];
+        for location in locations {
+            let conf = match CheckpointSyncerConf::from_str(&location) {
+                Ok(conf) => conf,
+                Err(err) => {
+                    warn!(?err, %location, "Skipping unparseable storage location");
+                    continue;
+                }
+            };
+            match conf.build_and_validate(None, None).await {
+                Ok(syncer) => syncers.push((location, syncer.into())),
+                // Relayers don't read from a location with a reorg flag either
+                Err(CheckpointSyncerBuildError::ReorgEvent(reorg_event)) => warn!(
+                    ?reorg_event,
+                    %location, "Skipping storage location with a reorg flag set"
+                ),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(syncers)
+    }
+
+    async fn run_checkpoint_submitters(&self) -> Vec<JoinHandle<()>> {
+        let submitter = ValidatorSubmitter::new(
+            self.interval,
+            self.reorg_period.clone(),
+            self.merkle_tree_hook.clone(),
+            self.signer.clone(),
+            self.raw_signer.clone(),
+            self.checkpoint_syncer.clone(),
+            Arc::new(self.db.clone()) as Arc<dyn HyperlaneDb>,
+            self.slashing_protection.clone(),
+            ValidatorSubmitterMetrics::new(&self.core_metrics, &self.origin_chain),
+            self.max_sign_concurrency,
+            self.reorg_reporter.clone(),
+        )
+        .with_key_rotation(self.key_rotation.clone());
+
+        let tip_tree = self
+            .merkle_tree_hook
+            .tree(&self.reorg_period)
+            .await
+            .expect("failed to get merkle tree");
+
+        // This function is only called after we have already checked that the
+        // merkle tree hook has count > 0, but we assert to be extra sure this is
+        // the case.
+        assert!(tip_tree.count() > 0, "merkle tree is empty");
+        let backfill_target = submitter.checkpoint_at_block(&tip_tree);
+
+        let backfill_submitter = submitter.clone();
+
+        let mut tasks = vec![];
+        tasks.push(tokio::spawn(
+            async move {
+                backfill_submitter
+                    .backfill_checkpoint_submitter(backfill_target)
+                    .await
+            }
+            .instrument(info_span!("BackfillCheckpointSubmitter")),
+        ));
+
+        tasks.push(tokio::spawn(
+            async move { submitter.checkpoint_submitter(tip_tree.tree).await }
+                .instrument(info_span!("TipCheckpointSubmitter")),
+        ));
+
+        tasks
+    }
+
+    fn log_on_announce_failure(result: ChainResult<TxOutcome>, chain_signer: &String) {
+        match result {
+            Ok(outcome) => {
+                if outcome.executed {
+                    info!(
+                        tx_outcome=?outcome,
+                        ?chain_signer,
+                        "Successfully announced validator",
+                    );
+                } else {
+                    error!(
+                        txid=?outcome.transaction_id,
+                        gas_used=?outcome.gas_used,
+                        gas_price=?outcome.gas_price,
+                        ?chain_signer,
+                        "Transaction attempting to announce validator reverted. Make sure you have enough funds in your account to pay for transaction fees."
+                    );
+                }
+            }
+            Err(err) => {
+                error!(
+                    ?err,
+                    ?chain_signer,
+                    "Failed to announce validator. Make sure you have enough funds in your account to pay for gas."
+                );
+            }
+        }
+    }
+
+    async fn metadata(&self, agent_metadata: &ValidatorMetadata) -> Result<()> {
+        let serialized_metadata = serde_json::to_string_pretty(agent_metadata)?;
+        self.checkpoint_syncer
+            .write_metadata(&serialized_metadata)
+            .await?;
+        if let Some(key_rotation) = &self.key_rotation {
+            key_rotation
+                .checkpoint_syncer
+                .write_metadata(&serialized_metadata)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn announce(&self) -> Result<()> {
+        self.announce_signer(&self.signer, &self.checkpoint_syncer)
+            .await?;
+        // Announce the key being rotated to as well, so relayers can find its
+        // signatures once the ISMs are updated to include it
+        if let Some(key_rotation) = &self.key_rotation {
+            info!(
+                validator = ?key_rotation.signer.eth_address(),
+                "Announcing the validator key being rotated to"
+            );
+            self.announce_signer(&key_rotation.signer, &key_rotation.checkpoint_syncer)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn announce_signer(
+        &self,
+        signer: &impl HyperlaneSigner,
+        checkpoint_syncer: &Arc<dyn CheckpointSyncer>,
+    ) -> Result<()> {
+        let address = signer.eth_address();
+        let announcement_locations = checkpoint_syncer.announcement_locations();
+
+        // Sign and post the validator announcement of each storage location
+        let mut signed_announcements = Vec::with_capacity(announcement_locations.len());
+        for announcement_location in &announcement_locations {
+            let announcement = Announcement {
+                validator: address,
+                mailbox_address: self.mailbox.address(),
+                mailbox_domain: self.mailbox.domain().id(),
+                storage_location: announcement_location.clone(),
+            };
+            let signed_announcement = signer.sign(announcement).await?;
+            checkpoint_syncer
+                .write_announcement(&signed_announcement)
+                .await?;
+            signed_announcements.push(signed_announcement);
+        }
+
+        // Ensure that the validator has announced themselves before we enter
+        // the main validator submit loop. This is to avoid a situation in
+        // which the validator is signing checkpoints but has not announced
+        // their locations, which makes them functionally unusable.
+        let validators: [H256; 1] = [address.into()];
+        loop {
+            info!("Checking for validator announcement");
+            if let Some(locations) = self
+                .validator_announce
+                .get_announced_storage_locations(&validators)
+                .await?
+                .first()
+            {
+                let unannounced = signed_announcements
+                    .iter()
+                    .filter(|signed_announcement| {
+                        !locations.contains(&signed_announcement.value.storage_location)
+                    })
+                    .collect::<Vec<_>>();
+                if unannounced.is_empty() {
+                    info!(
+                        ?locations,
+                        ?announcement_locations,
+                        "Validator has announced signature storage locations"
+                    );
+
+                    self.core_metrics.set_announced(self.origin_chain.clone());
+
+                    break;
+                }
+                info!(
+                    announced_locations=?locations,
+                    "Validator has not announced all signature storage locations"
+                );
+
+                if let Some(chain_signer) = self.origin_chain_conf.chain_signer().await? {
+                    let chain_signer_string = chain_signer.address_string();
+                    let chain_signer_h256 = chain_signer.address_h256();
+                    for signed_announcement in unannounced {
+                        let announcement = &signed_announcement.value;
+                        info!(eth_validator_address=?announcement.validator, storage_location=?announcement.storage_location, ?chain_signer_string, ?chain_signer_h256, "Attempting self announce");
+
+                        let balance_delta = self
+                            .validator_announce
+                            .announce_tokens_needed(signed_announcement.clone(), chain_signer_h256)
+                            .await
+                            .unwrap_or_default();
+                        if balance_delta > U256::zero() {
+                            warn!(
+                                tokens_needed=%balance_delta,
+                                eth_validator_address=?announcement.validator,
+                                ?chain_signer_string,
+                                ?chain_signer_h256,
+                                "Please send tokens to your chain signer address to announce",
+                            );
+                            break;
+                        }
+                        let result = self
+                            .validator_announce
+                            .announce(signed_announcement.clone())
+                            .await;
+                        Self::log_on_announce_failure(result, &chain_signer_string);
+                    }
+                } else {
+                    warn!(origin_chain=%self.origin_chain, "Cannot announce validator without a signer; make sure a signer is set for the origin chain");
+                }
+
+                sleep(self.interval).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn report_latest_checkpoints_from_each_endpoint(
+        reorg_reporter: &Arc<dyn ReorgReporter>,
+        checkpoint_syncer_result: &Result<Box<dyn CheckpointSyncer>, CheckpointSyncerBuildError>,
+    ) {
+        if let Err(CheckpointSyncerBuildError::ReorgEvent(reorg_event)) =
+            checkpoint_syncer_result.as_ref()
+        {
+            reorg_reporter
+                .report_with_reorg_period(&reorg_event.reorg_period)
+                .await;
+        }
+    }
+}
@@ -75,15 +75,12 @@ impl ReorgReporter for LatestCheckpointReorgReporter {
 impl LatestCheckpointReorgReporter {
     pub(crate) async fn from_settings(
         settings: &ValidatorSettings,
+        origin: &HyperlaneDomain,
         metrics: &CoreMetrics,
     ) -> eyre::Result<Self> {
-        let origin = &settings.origin_chain;
-
         let mut merkle_tree_hooks = HashMap::new();
         for (url, settings) in Self::settings_with_single_rpc(settings, origin) {
-            let merkle_tree_hook = settings
-                .build_merkle_tree_hook(&settings.origin_chain, metrics)
-                .await?;
+            let merkle_tree_hook = settings.build_merkle_tree_hook(origin, metrics).await?;
 
             merkle_tree_hooks.insert(url, merkle_tree_hook.into());
         }
@@ -52,19 +52,19 @@ struct Service {
 
 #[derive(new)]
 pub struct EigenNodeApi {
-    origin_chain: HyperlaneDomain,
+    origin_chains: Vec<HyperlaneDomain>,
     core_metrics: Arc<CoreMetrics>,
 }
 
 impl EigenNodeApi {
     pub fn router(&self) -> Router {
         let core_metrics_clone = self.core_metrics.clone();
-        let origin_chain = self.origin_chain.clone();
+        let origin_chains = self.origin_chains.clone();
 
         tracing::info!("Serving the EigenNodeAPI routes...");
 
         let health_route = get(move || {
-            Self::node_health_handler(origin_chain.clone(), core_metrics_clone.clone())
+            Self::node_health_handler(origin_chains.clone(), core_metrics_clone.clone())
         });
 
         let router = Router::new()
@@ -94,11 +94,24 @@ impl EigenNodeApi {
     /// if signed_checkpoint - observed_checkpoint <= 1 return 200 - healthy
     /// else if observed_checkpoint - signed_checkpoint <= 10 return 203 - partially healthy
     /// else return 503 - unhealthy
+    /// With several origin chains, the node is as healthy as its least healthy origin,
+    /// and unhealthy if it stopped validating any of them
     pub async fn node_health_handler(
-        origin_chain: HyperlaneDomain,
+        origin_chains: Vec<HyperlaneDomain>,
         core_metrics: Arc<CoreMetrics>,
     ) -> impl IntoResponse {
-        let checkpoint_delta = core_metrics.get_latest_checkpoint_validator_delta(origin_chain);
+        if origin_chains
+            .iter()
+            .any(|origin_chain| core_metrics.is_validator_halted(origin_chain))
+        {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+
+        let checkpoint_delta = origin_chains
+            .into_iter()
+            .map(|origin_chain| core_metrics.get_latest_checkpoint_validator_delta(origin_chain))
+            .max()
+            .unwrap_or_default();
 
         // logic to check if the node is healthy
         if checkpoint_delta <= 1 {
@@ -172,7 +185,7 @@ mod tests {
             .set(HEALTHY_OBSERVED_CHECKPOINT);
 
         let node_api = EigenNodeApi::new(
-            HyperlaneDomain::new_test_domain("ethereum"),
+            vec![HyperlaneDomain::new_test_domain("ethereum")],
             Arc::clone(&core_metrics),
         );
         let app = node_api.router();
@@ -259,6 +272,18 @@ mod tests {
             .await
             .expect("Failed to send request");
         assert_eq!(response.status(), StatusCode::OK);
+
+        // a halted origin chain is unhealthy however far its checkpoints are
+        core_metrics.set_halted(HyperlaneDomain::new_test_domain("ethereum"));
+
+        let api_url = format!("{EIGEN_NODE_API_BASE}/node/health");
+        let request = Request::builder()
+            .uri(api_url)
+            .method(Method::GET)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Failed to send request");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
     }
 
     #[tokio::test]
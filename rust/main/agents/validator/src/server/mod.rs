@@ -12,8 +12,8 @@ use hyperlane_core::HyperlaneDomain;
 
 /// Returns a vector of validator-specific endpoint routes to be served.
 /// Can be extended with additional routes and feature flags to enable/disable individually.
-pub fn router(origin_chain: HyperlaneDomain, metrics: Arc<CoreMetrics>) -> Router {
-    let eigen_node_api = EigenNodeApi::new(origin_chain, metrics);
+pub fn router(origin_chains: Vec<HyperlaneDomain>, metrics: Arc<CoreMetrics>) -> Router {
+    let eigen_node_api = EigenNodeApi::new(origin_chains, metrics);
 
     eigen_node_api.router()
 }
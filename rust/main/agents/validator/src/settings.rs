@@ -4,7 +4,11 @@
 //! and validations it defines are not applied here, we should mirror them.
 //! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
 
-use std::{collections::HashSet, path::PathBuf, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use aws_config::Region;
 use derive_more::{AsMut, AsRef, Deref, DerefMut};
@@ -41,14 +45,14 @@ pub struct ValidatorSettings {
 
     /// Database path
     pub db: PathBuf,
-    /// Chain to validate messages on
-    pub origin_chain: HyperlaneDomain,
+    /// Chains to validate messages on
+    pub origin_chains: Vec<HyperlaneDomain>,
     /// The validator attestation signer
     pub validator: SignerConf,
     /// The checkpoint syncer configuration
     pub checkpoint_syncer: CheckpointSyncerConf,
-    /// The reorg configuration
-    pub reorg_period: ReorgPeriod,
+    /// The reorg configuration of each origin chain
+    pub reorg_periods: HashMap<HyperlaneDomain, ReorgPeriod>,
     /// How frequently to check for new checkpoints
     pub interval: Duration,
     /// A list of RPCs that the validator uses
@@ -57,6 +61,69 @@ pub struct ValidatorSettings {
     pub allow_public_rpcs: bool,
     /// Max sign concurrency
     pub max_sign_concurrency: usize,
+    /// Audit the published checkpoints and exit, instead of validating
+    pub audit_checkpoints: bool,
+    /// The key the validator is being rotated to, if any
+    pub key_rotation: Option<KeyRotationConf>,
+    /// Slashing protection database path
+    pub slashing_protection_db: PathBuf,
+    /// Slashing protection interchange file to import on startup
+    pub slashing_protection_import: Option<PathBuf>,
+    /// Export the slashing protection database to this file and exit, instead of validating
+    pub slashing_protection_export: Option<PathBuf>,
+}
+
+/// Settings for rotating the validator key
+#[derive(Debug, Clone)]
+pub struct KeyRotationConf {
+    /// The new validator attestation signer
+    pub validator: SignerConf,
+    /// The checkpoint syncer the new key's signatures are written to
+    pub checkpoint_syncer: CheckpointSyncerConf,
+    /// Unix timestamp until which checkpoints are signed with both keys
+    pub ends_at: u64,
+}
+
+impl ValidatorSettings {
+    /// Whether the validator validates more than one origin chain
+    pub fn is_multi_origin(&self) -> bool {
+        self.origin_chains.len() > 1
+    }
+
+    /// The checkpoint syncer configuration of `origin`. With several origin
+    /// chains, the checkpoints of each are written under a folder named after it.
+    pub fn origin_checkpoint_syncer(&self, origin: &HyperlaneDomain) -> CheckpointSyncerConf {
+        if self.is_multi_origin() {
+            self.checkpoint_syncer.with_prefix(origin.name())
+        } else {
+            self.checkpoint_syncer.clone()
+        }
+    }
+
+    /// The key rotation configuration of `origin`, with its checkpoint syncer
+    /// prefixed like `origin_checkpoint_syncer`.
+    pub fn origin_key_rotation(&self, origin: &HyperlaneDomain) -> Option<KeyRotationConf> {
+        let mut key_rotation = self.key_rotation.clone()?;
+        if self.is_multi_origin() {
+            key_rotation.checkpoint_syncer =
+                key_rotation.checkpoint_syncer.with_prefix(origin.name());
+        }
+        Some(key_rotation)
+    }
+
+    /// The slashing protection interchange file of `origin` at `path`. With
+    /// several origin chains, `path` is a directory holding a file per origin.
+    pub fn origin_slashing_protection_interchange(
+        &self,
+        path: &Path,
+        origin: &HyperlaneDomain,
+    ) -> PathBuf {
+        if self.is_multi_origin() {
+            path.join(format!("{}.json", origin.name()))
+        } else {
+            path.to_path_buf()
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,11 +149,22 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
 
         let p = ValueParser::new(cwp.clone(), &raw.0);
 
-        let origin_chain_name = p
+        let origin_chain_names: Option<Vec<&str>> = p
             .chain(&mut err)
             .get_key("originChainName")
             .parse_string()
-            .end();
+            .end()
+            .map(|s| s.split(',').map(str::trim).unique().collect());
+        let origin_chain_names = match origin_chain_names {
+            Some(names) if names.iter().any(|name| name.is_empty()) => {
+                err.push(
+                    cwp + "origin_chain_name",
+                    eyre::eyre!("Expected non-empty origin chain names"),
+                );
+                None
+            }
+            names => names,
+        };
 
         let allow_public_rpcs = p
             .chain(&mut err)
@@ -94,7 +172,9 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             .parse_bool()
             .unwrap_or(false);
 
-        let origin_chain_name_set = origin_chain_name.map(|s| HashSet::from([s]));
+        let origin_chain_name_set: Option<HashSet<&str>> = origin_chain_names
+            .as_ref()
+            .map(|names| names.iter().copied().collect());
 
         let base: Option<Settings> = p
             .parse_from_raw_config::<Settings, RawAgentConf, Option<&HashSet<&str>>>(
@@ -104,14 +184,19 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             )
             .take_config_err(&mut err);
 
-        let origin_chain = if let (Some(base), Some(origin_chain_name)) = (&base, origin_chain_name)
-        {
-            base.lookup_domain(origin_chain_name)
-                .context("Missing configuration for the origin chain")
-                .take_err(&mut err, || cwp + "origin_chain_name")
-        } else {
-            None
-        };
+        let origin_chains: Option<Vec<HyperlaneDomain>> =
+            if let (Some(base), Some(origin_chain_names)) = (&base, &origin_chain_names) {
+                origin_chain_names
+                    .iter()
+                    .map(|origin_chain_name| {
+                        base.lookup_domain(origin_chain_name)
+                            .context("Missing configuration for the origin chain")
+                            .take_err(&mut err, || cwp + "origin_chain_name")
+                    })
+                    .collect()
+            } else {
+                None
+            };
 
         let validator = p
             .chain(&mut err)
@@ -127,10 +212,38 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             .chain(&mut err)
             .get_opt_key("db")
             .parse_from_str("Expected db file path")
+            .unwrap_or_else(|| match origin_chain_names.as_deref() {
+                Some([origin_chain_name]) => {
+                    curr_dir.join(format!("validator_db_{origin_chain_name}"))
+                }
+                Some(_) => curr_dir.join("validator_db"),
+                None => curr_dir.join("validator_db_"),
+            });
+
+        // Next to the message db by default, so that validators sharing a working
+        // directory don't share a slashing protection db
+        let slashing_protection_db = p
+            .chain(&mut err)
+            .get_opt_key("slashingProtectionDb")
+            .parse_from_str("Expected slashing protection db file path")
             .unwrap_or_else(|| {
-                curr_dir.join(format!("validator_db_{}", origin_chain_name.unwrap_or("")))
+                let mut path = db.clone().into_os_string();
+                path.push("_slashing_protection");
+                PathBuf::from(path)
             });
 
+        let slashing_protection_import = p
+            .chain(&mut err)
+            .get_opt_key("slashingProtectionImport")
+            .parse_from_str("Expected slashing protection interchange file path")
+            .end();
+
+        let slashing_protection_export = p
+            .chain(&mut err)
+            .get_opt_key("slashingProtectionExport")
+            .parse_from_str("Expected slashing protection interchange file path")
+            .end();
+
         let checkpoint_syncer = p
             .chain(&mut err)
             .get_key("checkpointSyncer")
@@ -144,27 +257,40 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             .map(Duration::from_secs)
             .unwrap_or(Duration::from_secs(5));
 
-        cfg_unwrap_all!(cwp, err: [origin_chain_name]);
+        cfg_unwrap_all!(cwp, err: [origin_chain_names]);
 
-        let reorg_period = p
-            .chain(&mut err)
-            .get_key("chains")
-            .get_key(origin_chain_name)
-            .get_opt_key("blocks")
-            .get_opt_key("reorgPeriod")
-            .parse_value("Invalid reorgPeriod")
-            .unwrap_or(ReorgPeriod::from_blocks(1));
-
-        let chain = p
-            .chain(&mut err)
-            .get_key("chains")
-            .get_key(origin_chain_name)
-            .end()
-            .ok_or_else(|| {
-                let mut config_err = ConfigParsingError::default();
-                config_err.push(cwp.clone(), eyre::eyre!("chains missing".to_string()));
-                config_err
-            })?;
+        let mut reorg_periods = HashMap::new();
+        let mut rpcs = vec![];
+        for origin_chain_name in &origin_chain_names {
+            let reorg_period = p
+                .chain(&mut err)
+                .get_key("chains")
+                .get_key(origin_chain_name)
+                .get_opt_key("blocks")
+                .get_opt_key("reorgPeriod")
+                .parse_value("Invalid reorgPeriod")
+                .unwrap_or(ReorgPeriod::from_blocks(1));
+            if let Some(base) = &base {
+                if let Ok(origin_chain) = base.lookup_domain(origin_chain_name) {
+                    reorg_periods.insert(origin_chain, reorg_period);
+                }
+            }
+
+            let chain = p
+                .chain(&mut err)
+                .get_key("chains")
+                .get_key(origin_chain_name)
+                .end()
+                .ok_or_else(|| {
+                    let mut config_err = ConfigParsingError::default();
+                    config_err.push(cwp.clone(), eyre::eyre!("chains missing".to_string()));
+                    config_err
+                })?;
+
+            rpcs.extend(get_rpc_urls(&chain, "rpcUrls", "customRpcUrls", &mut err));
+            // this is only relevant for cosmos
+            rpcs.extend(get_rpc_urls(&chain, "grpcUrls", "customGrpcUrls", &mut err));
+        }
 
         let max_sign_concurrency = p
             .chain(&mut err)
@@ -172,35 +298,80 @@ impl FromRawConf<RawValidatorSettings> for ValidatorSettings {
             .parse_u64()
             .unwrap_or(50) as usize;
 
-        let mut rpcs = get_rpc_urls(&chain, "rpcUrls", "customRpcUrls", &mut err);
-        // this is only relevant for cosmos
-        rpcs.extend(get_rpc_urls(&chain, "grpcUrls", "customGrpcUrls", &mut err));
+        let audit_checkpoints = p
+            .chain(&mut err)
+            .get_opt_key("auditCheckpoints")
+            .parse_bool()
+            .unwrap_or(false);
+
+        let key_rotation = p
+            .chain(&mut err)
+            .get_opt_key("keyRotation")
+            .and_then(|rotation| parse_key_rotation(rotation, agent_name))
+            .end();
 
-        cfg_unwrap_all!(cwp, err: [base, origin_chain, validator, checkpoint_syncer]);
+        cfg_unwrap_all!(cwp, err: [base, origin_chains, validator, checkpoint_syncer]);
 
         let mut base: Settings = base;
-        // If the origin chain is an EVM chain, then we can use the validator as the signer if needed.
-        if origin_chain.domain_protocol() == HyperlaneDomainProtocol::Ethereum {
-            if let Some(origin) = base.chains.get_mut(&origin_chain) {
-                origin.signer.get_or_insert_with(|| validator.clone());
+        // If an origin chain is an EVM chain, then we can use the validator as its signer if needed.
+        for origin_chain in &origin_chains {
+            if origin_chain.domain_protocol() == HyperlaneDomainProtocol::Ethereum {
+                if let Some(origin) = base.chains.get_mut(origin_chain) {
+                    origin.signer.get_or_insert_with(|| validator.clone());
+                }
             }
         }
 
         err.into_result(Self {
             base,
             db,
-            origin_chain,
+            origin_chains,
             validator,
             checkpoint_syncer,
-            reorg_period,
+            reorg_periods,
             interval,
             rpcs,
             allow_public_rpcs,
             max_sign_concurrency,
+            audit_checkpoints,
+            key_rotation,
+            slashing_protection_db,
+            slashing_protection_import,
+            slashing_protection_export,
         })
     }
 }
 
+/// Expects ValidatorAgentConfig.keyRotation
+fn parse_key_rotation(rotation: ValueParser, agent_name: &str) -> ConfigResult<KeyRotationConf> {
+    let mut err = ConfigParsingError::default();
+
+    let validator = rotation
+        .chain(&mut err)
+        .get_key("validator")
+        .parse_from_raw_config::<SignerConf, RawAgentSignerConf, NoFilter>(
+            (),
+            "Expected valid key rotation validator configuration",
+            agent_name.to_string(),
+        )
+        .end();
+
+    let checkpoint_syncer = rotation
+        .chain(&mut err)
+        .get_key("checkpointSyncer")
+        .and_then(parse_checkpoint_syncer)
+        .end();
+
+    let ends_at = rotation.chain(&mut err).get_key("endsAt").parse_u64().end();
+
+    cfg_unwrap_all!(&rotation.cwp, err: [validator, checkpoint_syncer, ends_at]);
+    err.into_result(KeyRotationConf {
+        validator,
+        checkpoint_syncer,
+        ends_at,
+    })
+}
+
 /// Extracts all of the rpc urls
 ///
 /// rpcKey is either grpcUrls or rpcUrls
@@ -334,6 +505,64 @@ fn parse_checkpoint_syncer(syncer: ValueParser) -> ConfigResult<CheckpointSyncer
                 user_secrets,
             })
         }
+        Some("azure") => {
+            let account = syncer
+                .chain(&mut err)
+                .get_key("account")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            let container = syncer
+                .chain(&mut err)
+                .get_key("container")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            let folder = syncer
+                .chain(&mut err)
+                .get_opt_key("folder")
+                .parse_string()
+                .end()
+                .map(str::to_owned);
+            let endpoint = syncer
+                .chain(&mut err)
+                .get_opt_key("endpoint")
+                .parse_from_str("Expected Azure Blob service endpoint url")
+                .end();
+
+            cfg_unwrap_all!(&syncer.cwp, err: [account, container]);
+            err.into_result(CheckpointSyncerConf::Azure {
+                account,
+                container,
+                folder,
+                endpoint,
+            })
+        }
+        Some("mirrored") => {
+            let syncers = syncer
+                .chain(&mut err)
+                .get_key("syncers")
+                .into_array_iter()
+                .map(|syncers| {
+                    syncers
+                        .filter_map(|mirrored| {
+                            mirrored
+                                .chain(&mut err)
+                                .and_then(parse_checkpoint_syncer)
+                                .end()
+                        })
+                        .collect_vec()
+                });
+
+            cfg_unwrap_all!(&syncer.cwp, err: [syncers]);
+            if syncers.is_empty() {
+                err.push(
+                    &syncer.cwp + "syncers",
+                    eyre!("Expected at least one mirrored checkpoint syncer"),
+                );
+            }
+            err.into_result(CheckpointSyncerConf::Mirrored { syncers })
+        }
         Some(_) => {
             Err(eyre!("Unknown checkpoint syncer type")).into_config_result(|| &syncer.cwp + "type")
         }
@@ -436,4 +665,43 @@ mod test {
         assert_eq!(parsed[1].url, "http://my-rpc-url-4.com");
         assert!(!parsed[1].public);
     }
+
+    #[test]
+    fn test_parse_mirrored_checkpoint_syncer() {
+        let syncer = r#"
+            {
+                "type": "mirrored",
+                "syncers": [
+                    {
+                        "type": "s3",
+                        "bucket": "my-bucket",
+                        "region": "us-east-1"
+                    },
+                    {
+                        "type": "localStorage",
+                        "path": "/tmp/checkpoints"
+                    }
+                ]
+            }
+        "#;
+        let syncer = serde_json::from_str(syncer).unwrap();
+        let parsed =
+            parse_checkpoint_syncer(ValueParser::new(ConfigPath::default(), &syncer)).unwrap();
+
+        let CheckpointSyncerConf::Mirrored { syncers } = parsed else {
+            panic!("Expected a mirrored checkpoint syncer config");
+        };
+        assert_eq!(syncers.len(), 2);
+        assert!(matches!(
+            &syncers[0],
+            CheckpointSyncerConf::S3 { bucket, folder: None, .. } if bucket == "my-bucket"
+        ));
+        assert!(matches!(
+            &syncers[1],
+            CheckpointSyncerConf::LocalStorage { path } if path == &PathBuf::from("/tmp/checkpoints")
+        ));
+
+        let empty = serde_json::json!({ "type": "mirrored", "syncers": [] });
+        assert!(parse_checkpoint_syncer(ValueParser::new(ConfigPath::default(), &empty)).is_err());
+    }
 }
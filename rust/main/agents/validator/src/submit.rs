@@ -7,7 +7,7 @@ use prometheus::IntGauge;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
-use hyperlane_base::db::HyperlaneDb;
+use hyperlane_base::db::{HyperlaneDb, SlashingProtectionDb, SlashingProtectionError};
 use hyperlane_base::{CheckpointSyncer, CoreMetrics};
 use hyperlane_core::rpc_clients::call_and_retry_indefinitely;
 use hyperlane_core::{
@@ -16,12 +16,32 @@ use hyperlane_core::{
     HyperlaneSignerExt, IncrementalMerkleAtBlock,
 };
 use hyperlane_core::{
-    ChainResult, HyperlaneSigner, MerkleTreeHook, ReorgEvent, ReorgPeriod, SignedType,
+    ChainCommunicationError, ChainResult, HyperlaneSigner, MerkleTreeHook, ReorgEvent, ReorgPeriod,
+    SignedType,
 };
 use hyperlane_ethereum::{Signers, SingletonSignerHandle};
 
 use crate::reorg_reporter::ReorgReporter;
 
+/// A validator key being rotated to. Until the rotation window ends, each
+/// checkpoint is signed with both the validator key and this key; afterwards
+/// only with this key. Its signatures are written to a separate checkpoint
+/// syncer so relayers can read both sets of signatures during the rotation.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyRotation {
+    pub(crate) signer: Signers,
+    pub(crate) checkpoint_syncer: Arc<dyn CheckpointSyncer>,
+    /// Unix timestamp at which the rotation window ends
+    pub(crate) ends_at: u64,
+}
+
+impl KeyRotation {
+    /// Whether the validator key should still sign checkpoints
+    fn window_is_open(&self) -> bool {
+        (chrono::Utc::now().timestamp() as u64) < self.ends_at
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct ValidatorSubmitter {
     interval: Duration,
@@ -32,9 +52,11 @@ pub(crate) struct ValidatorSubmitter {
     merkle_tree_hook: Arc<dyn MerkleTreeHook>,
     checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     db: Arc<dyn HyperlaneDb>,
+    slashing_protection: SlashingProtectionDb,
     metrics: ValidatorSubmitterMetrics,
     max_sign_concurrency: usize,
     reorg_reporter: Arc<dyn ReorgReporter>,
+    key_rotation: Option<KeyRotation>,
 }
 
 impl ValidatorSubmitter {
@@ -47,6 +69,7 @@ impl ValidatorSubmitter {
         signer: Signers,
         checkpoint_syncer: Arc<dyn CheckpointSyncer>,
         db: Arc<dyn HyperlaneDb>,
+        slashing_protection: SlashingProtectionDb,
         metrics: ValidatorSubmitterMetrics,
         max_sign_concurrency: usize,
         reorg_reporter: Arc<dyn ReorgReporter>,
@@ -59,9 +82,30 @@ impl ValidatorSubmitter {
             signer,
             checkpoint_syncer,
             db,
+            slashing_protection,
             metrics,
             max_sign_concurrency,
             reorg_reporter,
+            key_rotation: None,
+        }
+    }
+
+    /// Also sign checkpoints with the key being rotated to.
+    pub(crate) fn with_key_rotation(mut self, key_rotation: Option<KeyRotation>) -> Self {
+        self.key_rotation = key_rotation;
+        self
+    }
+
+    /// The signers checkpoints are currently signed with, along with the
+    /// checkpoint syncer each one's signatures are written to.
+    fn signing_keys(&self) -> Vec<(&Signers, &Arc<dyn CheckpointSyncer>)> {
+        match &self.key_rotation {
+            None => vec![(&self.signer, &self.checkpoint_syncer)],
+            Some(rotation) if rotation.window_is_open() => vec![
+                (&self.signer, &self.checkpoint_syncer),
+                (&rotation.signer, &rotation.checkpoint_syncer),
+            ],
+            Some(rotation) => vec![(&rotation.signer, &rotation.checkpoint_syncer)],
         }
     }
 
@@ -263,15 +307,13 @@ impl ValidatorSubmitter {
             }
 
             let mut panic_message = "Incorrect tree root. Most likely a reorg has occurred. Please reach out for help, this is a potentially serious error impacting signed messages. Do NOT forcefully resume operation of this validator. Keep it crashlooping or shut down until you receive support.".to_owned();
-            if let Err(e) = self
-                .checkpoint_syncer
-                .write_reorg_status(&reorg_event)
-                .await
-            {
-                panic_message.push_str(&format!(
-                    " Reorg troubleshooting details couldn't be written to checkpoint storage: {}",
-                    e
-                ));
+            for (_, checkpoint_syncer) in self.signing_keys() {
+                if let Err(e) = checkpoint_syncer.write_reorg_status(&reorg_event).await {
+                    panic_message.push_str(&format!(
+                        " Reorg troubleshooting details couldn't be written to checkpoint storage: {}",
+                        e
+                    ));
+                }
             }
             panic!("{panic_message}");
         }
@@ -299,12 +341,14 @@ impl ValidatorSubmitter {
 
     async fn sign_checkpoint(
         &self,
+        signer: &Signers,
         checkpoint: CheckpointWithMessageId,
     ) -> ChainResult<SignedType<CheckpointWithMessageId>> {
         let signer_retries = 5;
+        let mut last_err = None;
 
         for i in 0..signer_retries {
-            match self.signer.sign(checkpoint).await {
+            match signer.sign(checkpoint).await {
                 Ok(signed_checkpoint) => return Ok(signed_checkpoint),
                 Err(err) => {
                     tracing::warn!(
@@ -314,11 +358,20 @@ impl ValidatorSubmitter {
                         ?err,
                         "Error signing checkpoint with direct signer"
                     );
+                    last_err = Some(err);
                     sleep(Duration::from_millis(100)).await;
                 }
             }
         }
 
+        // The singleton signer only holds the validator key, so the key being
+        // rotated to has nothing to fall back to
+        if signer.eth_address() != self.singleton_signer.eth_address() {
+            if let Some(err) = last_err {
+                return Err(err.into());
+            }
+        }
+
         tracing::warn!(
             ?checkpoint,
             retries = signer_retries,
@@ -329,15 +382,45 @@ impl ValidatorSubmitter {
         Ok(self.singleton_signer.sign(checkpoint).await?)
     }
 
+    /// Signs and submits the checkpoint with each of the current signing keys.
     async fn sign_and_submit_checkpoint(
         &self,
         checkpoint: CheckpointWithMessageId,
     ) -> ChainResult<()> {
+        for (signer, checkpoint_syncer) in self.signing_keys() {
+            self.sign_and_submit_checkpoint_with(signer, checkpoint_syncer, checkpoint)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn sign_and_submit_checkpoint_with(
+        &self,
+        signer: &Signers,
+        checkpoint_syncer: &Arc<dyn CheckpointSyncer>,
+        checkpoint: CheckpointWithMessageId,
+    ) -> ChainResult<()> {
+        // Refuse to sign a checkpoint conflicting with one signed before, e.g. after
+        // a restart against a faulty RPC, whatever the checkpoint storage contains.
+        // Like an incorrect tree root, a conflict is not worth retrying.
+        match self
+            .slashing_protection
+            .check_and_record(signer.eth_address(), &checkpoint)
+        {
+            Ok(()) => {}
+            Err(err @ SlashingProtectionError::Conflict { .. }) => {
+                error!(
+                    ?checkpoint,
+                    ?err,
+                    "Refusing to sign checkpoint, as it conflicts with a previously signed one. Do NOT remove the slashing protection database, this validator may be connected to a faulty RPC or a reorg may have occurred."
+                );
+                panic!("Refusing to sign checkpoint, as it conflicts with a previously signed one. Do NOT remove the slashing protection database, this validator may be connected to a faulty RPC or a reorg may have occurred. {err}");
+            }
+            Err(err) => return Err(ChainCommunicationError::from_other(err)),
+        }
+
         let start = Instant::now();
-        let existing = self
-            .checkpoint_syncer
-            .fetch_checkpoint(checkpoint.index)
-            .await?;
+        let existing = checkpoint_syncer.fetch_checkpoint(checkpoint.index).await?;
         tracing::trace!(
             elapsed=?start.elapsed(),
             "Fetched checkpoint from checkpoint storage",
@@ -345,7 +428,7 @@ impl ValidatorSubmitter {
 
         if let Some(existing) = existing.as_ref() {
             let existing_signer = existing.recover()?;
-            let signer = self.signer.eth_address();
+            let signer = signer.eth_address();
             if existing_signer == signer && existing.value == checkpoint {
                 debug!(index = checkpoint.index, "Checkpoint already submitted");
                 return Ok(());
@@ -362,14 +445,14 @@ impl ValidatorSubmitter {
         }
 
         let start = Instant::now();
-        let signed_checkpoint = self.sign_checkpoint(checkpoint).await?;
+        let signed_checkpoint = self.sign_checkpoint(signer, checkpoint).await?;
         tracing::trace!(
             elapsed=?start.elapsed(),
             "Signed checkpoint",
         );
 
         let start = Instant::now();
-        self.checkpoint_syncer
+        checkpoint_syncer
             .write_checkpoint(&signed_checkpoint)
             .await?;
         tracing::trace!(
@@ -446,10 +529,11 @@ impl ValidatorSubmitter {
                     let self_clone = self.clone();
                     Box::pin(async move {
                         let start = Instant::now();
-                        self_clone
-                            .checkpoint_syncer
-                            .update_latest_index(last_checkpoint_index)
-                            .await?;
+                        for (_, checkpoint_syncer) in self_clone.signing_keys() {
+                            checkpoint_syncer
+                                .update_latest_index(last_checkpoint_index)
+                                .await?;
+                        }
                         tracing::trace!(
                             elapsed=?start.elapsed(),
                             "Updated latest index",
@@ -504,7 +588,9 @@ mod test {
     use async_trait::async_trait;
     use eyre::Result;
     use hyperlane_base::db::{
-        DbResult, HyperlaneDb, InterchainGasExpenditureData, InterchainGasPaymentData,
+        DbResult, DynamicMessageFilterData, HyperlaneDb, InterchainGasExpenditureData,
+        InterchainGasPaymentData, ProfitabilityLedgerEntry, ProfitabilityRouteAggregate,
+        ShadowSubmission, DB,
     };
     use hyperlane_core::{
         identifiers::UniqueIdentifier, test_utils::dummy_domain, GasPaymentKey, HyperlaneChain,
@@ -515,6 +601,7 @@ mod test {
     };
     use prometheus::Registry;
     use std::{fmt::Debug, sync::Arc, time::Duration};
+    use tempfile::TempDir;
     use tokio::sync::mpsc;
 
     mockall::mock! {
@@ -630,6 +717,14 @@ mod test {
             fn retrieve_highest_seen_message_nonce_number(&self) -> DbResult<Option<u32>>;
             fn store_payload_uuids_by_message_id(&self, message_id: &H256, payload_uuids: Vec<UniqueIdentifier>) -> DbResult<()>;
             fn retrieve_payload_uuids_by_message_id(&self, message_id: &H256) -> DbResult<Option<Vec<UniqueIdentifier>>>;
+            fn store_profitability_ledger_entry_by_message_id(&self, message_id: &H256, entry: &ProfitabilityLedgerEntry) -> DbResult<()>;
+            fn retrieve_profitability_ledger_entry_by_message_id(&self, message_id: &H256) -> DbResult<Option<ProfitabilityLedgerEntry>>;
+            fn store_profitability_route_aggregate_by_destination(&self, destination: &u32, aggregate: &ProfitabilityRouteAggregate) -> DbResult<()>;
+            fn retrieve_profitability_route_aggregate_by_destination(&self, destination: &u32) -> DbResult<Option<ProfitabilityRouteAggregate>>;
+            fn store_dynamic_message_filter(&self, filter: &DynamicMessageFilterData) -> DbResult<()>;
+            fn retrieve_dynamic_message_filter(&self) -> DbResult<Option<DynamicMessageFilterData>>;
+            fn store_shadow_submission_by_message_id(&self, message_id: &H256, submission: &ShadowSubmission) -> DbResult<()>;
+            fn retrieve_shadow_submission_by_message_id(&self, message_id: &H256) -> DbResult<Option<ShadowSubmission>>;
         }
     }
 
@@ -678,6 +773,7 @@ mod test {
             async fn write_metadata(&self, metadata: &str) -> Result<()>;
             async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()>;
             fn announcement_location(&self) -> String;
+            fn announcement_locations(&self) -> Vec<String>;
             async fn write_reorg_status(&self, reorg_event: &ReorgEvent) -> Result<()>;
             async fn reorg_status(&self) -> Result<Option<ReorgEvent>>;
         }
@@ -707,6 +803,14 @@ mod test {
         SingletonSignerHandle::new(H160::from_low_u64_be(0), mpsc::unbounded_channel().0)
     }
 
+    fn dummy_slashing_protection() -> (TempDir, SlashingProtectionDb) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::from_path(dir.path()).unwrap();
+        let slashing_protection =
+            SlashingProtectionDb::new(&dummy_domain(0, "dummy_origin_domain"), db);
+        (dir, slashing_protection)
+    }
+
     fn reorg_event_is_correct(
         reorg_event: &ReorgEvent,
         expected_local_merkle_tree: &IncrementalMerkle,
@@ -814,6 +918,7 @@ mod test {
             .return_once(|_| return ());
 
         // instantiate the validator submitter
+        let (_slashing_protection_dir, slashing_protection) = dummy_slashing_protection();
         let validator_submitter = ValidatorSubmitter::new(
             Duration::from_secs(1),
             ReorgPeriod::from_blocks(expected_reorg_period),
@@ -822,6 +927,7 @@ mod test {
             signer,
             Arc::new(mock_checkpoint_syncer),
             Arc::new(db),
+            slashing_protection,
             dummy_metrics(),
             50,
             Arc::new(mock_reorg_reporter),
@@ -932,6 +1038,7 @@ mod test {
         let mock_reorg_reporter = MockReorgReporter::new();
 
         // instantiate the validator submitter
+        let (_slashing_protection_dir, slashing_protection) = dummy_slashing_protection();
         let validator_submitter = ValidatorSubmitter::new(
             Duration::from_secs(1),
             ReorgPeriod::from_blocks(expected_reorg_period),
@@ -940,6 +1047,7 @@ mod test {
             signer,
             Arc::new(mock_checkpoint_syncer),
             Arc::new(db),
+            slashing_protection,
             dummy_metrics(),
             50,
             Arc::new(mock_reorg_reporter),
@@ -1051,6 +1159,7 @@ mod test {
         let mock_reorg_reporter = MockReorgReporter::new();
 
         // instantiate the validator submitter
+        let (_slashing_protection_dir, slashing_protection) = dummy_slashing_protection();
         let validator_submitter = ValidatorSubmitter::new(
             Duration::from_secs(1),
             ReorgPeriod::from_blocks(expected_reorg_period),
@@ -1059,6 +1168,7 @@ mod test {
             signer,
             Arc::new(mock_checkpoint_syncer),
             Arc::new(db),
+            slashing_protection,
             dummy_metrics(),
             50,
             Arc::new(mock_reorg_reporter),
@@ -1072,4 +1182,154 @@ mod test {
 
         logs_contain("Checkpoint already submitted, but with different signature, overwriting");
     }
+
+    fn checkpoint_syncer_expecting_signature_by(signer: H160) -> MockCheckpointSyncer {
+        let mut mock_checkpoint_syncer = MockCheckpointSyncer::new();
+        mock_checkpoint_syncer
+            .expect_fetch_checkpoint()
+            .once()
+            .returning(|_| Ok(None));
+        mock_checkpoint_syncer
+            .expect_write_checkpoint()
+            .once()
+            .returning(move |signed_checkpoint| {
+                assert_eq!(signed_checkpoint.recover().unwrap(), signer);
+                Ok(())
+            });
+        mock_checkpoint_syncer
+    }
+
+    async fn sign_and_submit_checkpoint_during_rotation(
+        ends_at: u64,
+        validator_checkpoint_syncer: MockCheckpointSyncer,
+        rotation_checkpoint_syncer: MockCheckpointSyncer,
+        validator_signer: Signers,
+        rotation_signer: Signers,
+    ) {
+        let dummy_domain = dummy_domain(0, "dummy_domain");
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                root: H256::random(),
+                index: 0,
+                merkle_tree_hook_address: H256::from_low_u64_be(0),
+                mailbox_domain: dummy_domain.id(),
+            },
+            message_id: H256::random(),
+        };
+
+        let (_slashing_protection_dir, slashing_protection) = dummy_slashing_protection();
+        let validator_submitter = ValidatorSubmitter::new(
+            Duration::from_secs(1),
+            ReorgPeriod::from_blocks(12),
+            Arc::new(MockMerkleTreeHook::new()),
+            dummy_singleton_handle(),
+            validator_signer,
+            Arc::new(validator_checkpoint_syncer),
+            Arc::new(MockDb::new()),
+            slashing_protection,
+            dummy_metrics(),
+            50,
+            Arc::new(MockReorgReporter::new()),
+        )
+        .with_key_rotation(Some(KeyRotation {
+            signer: rotation_signer,
+            checkpoint_syncer: Arc::new(rotation_checkpoint_syncer),
+            ends_at,
+        }));
+
+        validator_submitter
+            .sign_and_submit_checkpoint(checkpoint)
+            .await
+            .unwrap();
+    }
+
+    fn dummy_signers() -> (Signers, Signers) {
+        let validator_signer: Signers =
+            "1111111111111111111111111111111111111111111111111111111111111111"
+                .parse::<ethers::signers::LocalWallet>()
+                .unwrap()
+                .into();
+        let rotation_signer: Signers =
+            "2222222222222222222222222222222222222222222222222222222222222222"
+                .parse::<ethers::signers::LocalWallet>()
+                .unwrap()
+                .into();
+        (validator_signer, rotation_signer)
+    }
+
+    #[tokio::test]
+    async fn sign_and_submit_checkpoint_with_both_keys_during_rotation_window() {
+        let (validator_signer, rotation_signer) = dummy_signers();
+        let ends_at = chrono::Utc::now().timestamp() as u64 + 3600;
+
+        sign_and_submit_checkpoint_during_rotation(
+            ends_at,
+            checkpoint_syncer_expecting_signature_by(validator_signer.eth_address()),
+            checkpoint_syncer_expecting_signature_by(rotation_signer.eth_address()),
+            validator_signer,
+            rotation_signer,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn sign_and_submit_checkpoint_with_rotated_key_after_rotation_window() {
+        let (validator_signer, rotation_signer) = dummy_signers();
+        let ends_at = chrono::Utc::now().timestamp() as u64 - 1;
+
+        // no expectations are set on the validator key's checkpoint syncer,
+        // so any call to it fails the test
+        sign_and_submit_checkpoint_during_rotation(
+            ends_at,
+            MockCheckpointSyncer::new(),
+            checkpoint_syncer_expecting_signature_by(rotation_signer.eth_address()),
+            validator_signer,
+            rotation_signer,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "conflicts with a previously signed one")]
+    async fn sign_and_submit_checkpoint_refuses_conflicting_checkpoint() {
+        let (validator_signer, _) = dummy_signers();
+        let dummy_domain = dummy_domain(0, "dummy_domain");
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                root: H256::random(),
+                index: 0,
+                merkle_tree_hook_address: H256::from_low_u64_be(0),
+                mailbox_domain: dummy_domain.id(),
+            },
+            message_id: H256::random(),
+        };
+
+        // the validator signed a checkpoint with a different root at the same index before
+        let (_slashing_protection_dir, slashing_protection) = dummy_slashing_protection();
+        let mut signed_checkpoint = checkpoint;
+        signed_checkpoint.checkpoint.root = H256::random();
+        slashing_protection
+            .check_and_record(validator_signer.eth_address(), &signed_checkpoint)
+            .unwrap();
+
+        // no expectations are set on the checkpoint syncer, so nothing may be
+        // fetched or written
+        let validator_submitter = ValidatorSubmitter::new(
+            Duration::from_secs(1),
+            ReorgPeriod::from_blocks(12),
+            Arc::new(MockMerkleTreeHook::new()),
+            dummy_singleton_handle(),
+            validator_signer,
+            Arc::new(MockCheckpointSyncer::new()),
+            Arc::new(MockDb::new()),
+            slashing_protection,
+            dummy_metrics(),
+            50,
+            Arc::new(MockReorgReporter::new()),
+        );
+
+        let _ = validator_submitter
+            .sign_and_submit_checkpoint(checkpoint)
+            .await;
+    }
 }
@@ -1,4 +1,4 @@
-use std::{fmt::Debug, sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use axum::Router;
@@ -8,58 +8,35 @@ use eyre::{eyre, Result};
 use futures_util::future::try_join_all;
 use itertools::Itertools;
 use serde::Serialize;
-use tokio::{task::JoinHandle, time::sleep};
-use tracing::{error, info, info_span, warn, Instrument};
+use tracing::{error, info_span, Instrument};
 
 use hyperlane_base::{
-    db::{HyperlaneDb, HyperlaneRocksDB, DB},
-    git_sha,
-    metrics::AgentMetrics,
-    settings::{ChainConf, CheckpointSyncerBuildError},
-    BaseAgent, ChainMetrics, ChainSpecificMetricsUpdater, CheckpointSyncer, ContractSyncMetrics,
-    ContractSyncer, CoreMetrics, HyperlaneAgentCore, MetadataFromSettings, RuntimeMetrics,
-    SequencedDataContractSync,
+    db::DB, git_sha, metrics::AgentMetrics, BaseAgent, ChainMetrics, ChainSpecificMetricsUpdater,
+    ContractSyncMetrics, CoreMetrics, HyperlaneAgentCore, MetadataFromSettings, RuntimeMetrics,
 };
-use hyperlane_core::{
-    Announcement, ChainResult, HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneSigner,
-    HyperlaneSignerExt, Mailbox, MerkleTreeHook, MerkleTreeInsertion, ReorgPeriod, TxOutcome,
-    ValidatorAnnounce, H256, U256,
-};
-use hyperlane_ethereum::{Signers, SingletonSigner, SingletonSignerHandle};
+use hyperlane_core::{HyperlaneDomain, H256};
+use hyperlane_ethereum::{Signers, SingletonSigner};
 
-use crate::reorg_reporter::{LatestCheckpointReorgReporter, ReorgReporter};
+use crate::origin::ValidatorOrigin;
 use crate::server::{self as validator_server, merkle_tree_insertions};
-use crate::{
-    settings::ValidatorSettings,
-    submit::{ValidatorSubmitter, ValidatorSubmitterMetrics},
-};
+use crate::settings::ValidatorSettings;
 
 /// A validator agent
 #[derive(Debug, AsRef)]
 pub struct Validator {
-    origin_chain: HyperlaneDomain,
-    origin_chain_conf: ChainConf,
+    origin_chains: Vec<HyperlaneDomain>,
+    origins: Vec<ValidatorOrigin>,
     #[as_ref]
     core: HyperlaneAgentCore,
-    db: HyperlaneRocksDB,
-    merkle_tree_hook_sync: Arc<SequencedDataContractSync<MerkleTreeInsertion>>,
-    mailbox: Arc<dyn Mailbox>,
-    merkle_tree_hook: Arc<dyn MerkleTreeHook>,
-    validator_announce: Arc<dyn ValidatorAnnounce>,
-    signer: SingletonSignerHandle,
-    raw_signer: Signers,
     // temporary holder until `run` is called
     signer_instance: Option<Box<SingletonSigner>>,
-    reorg_period: ReorgPeriod,
-    interval: Duration,
-    checkpoint_syncer: Arc<dyn CheckpointSyncer>,
     core_metrics: Arc<CoreMetrics>,
     agent_metrics: AgentMetrics,
     chain_metrics: ChainMetrics,
     runtime_metrics: RuntimeMetrics,
     agent_metadata: ValidatorMetadata,
-    max_sign_concurrency: usize,
-    reorg_reporter: Arc<dyn ReorgReporter>,
+    audit_checkpoints: bool,
+    export_slashing_protection: bool,
 }
 
 /// Metadata for `validator`
@@ -116,8 +93,10 @@ impl BaseAgent for Validator {
             );
         }
 
+        // Shared by every origin chain, the keys of both are prefixed by the origin domain
         let db = DB::from_path(&settings.db)?;
-        let msg_db = HyperlaneRocksDB::new(&settings.origin_chain, db);
+        // Kept apart from the message db, so that it survives the message db being wiped
+        let slashing_protection_db = DB::from_path(&settings.slashing_protection_db)?;
 
         let raw_signer: Signers = settings.validator.build().await?;
 
@@ -126,92 +105,98 @@ impl BaseAgent for Validator {
 
         let core = settings.build_hyperlane_core(metrics.clone());
 
-        let reorg_reporter =
-            LatestCheckpointReorgReporter::from_settings(&settings, &metrics).await?;
-        let reorg_reporter = Arc::new(reorg_reporter) as Arc<dyn ReorgReporter>;
-
-        let checkpoint_syncer_result = settings.checkpoint_syncer.build_and_validate(None).await;
-
-        Self::report_latest_checkpoints_from_each_endpoint(
-            &reorg_reporter,
-            &checkpoint_syncer_result,
-        )
-        .await;
-
-        // Be extra sure to panic when checkpoint syncer fails, which indicates
-        // a fatal startup error.
-        let checkpoint_syncer = checkpoint_syncer_result
-            .expect("Failed to build checkpoint syncer")
-            .into();
-
-        let mailbox = settings
-            .build_mailbox(&settings.origin_chain, &metrics)
-            .await?;
-
-        let merkle_tree_hook = settings
-            .build_merkle_tree_hook(&settings.origin_chain, &metrics)
-            .await?;
-
-        let validator_announce = settings
-            .build_validator_announce(&settings.origin_chain, &metrics)
-            .await?;
-
-        let origin_chain_conf = core.settings.chain_setup(&settings.origin_chain)?.clone();
-
         let contract_sync_metrics = Arc::new(ContractSyncMetrics::new(&metrics));
 
-        let merkle_tree_hook_sync = settings
-            .sequenced_contract_sync::<MerkleTreeInsertion, _>(
-                &settings.origin_chain,
+        let mut origins = Vec::with_capacity(settings.origin_chains.len());
+        for origin_chain in &settings.origin_chains {
+            let origin = ValidatorOrigin::from_settings(
+                &settings,
+                origin_chain,
+                &db,
+                &slashing_protection_db,
+                &signer,
+                &raw_signer,
                 &metrics,
                 &contract_sync_metrics,
-                msg_db.clone().into(),
-                false,
-                false,
             )
             .await?;
+            origins.extend(origin);
+        }
 
         Ok(Self {
-            origin_chain: settings.origin_chain,
-            origin_chain_conf,
+            origin_chains: settings.origin_chains.clone(),
+            origins,
             core,
-            db: msg_db,
-            mailbox: mailbox.into(),
-            merkle_tree_hook: merkle_tree_hook.into(),
-            merkle_tree_hook_sync,
-            validator_announce: validator_announce.into(),
-            signer,
-            raw_signer,
             signer_instance: Some(Box::new(signer_instance)),
-            reorg_period: settings.reorg_period,
-            interval: settings.interval,
-            checkpoint_syncer,
+            core_metrics: metrics,
             agent_metrics,
             chain_metrics,
-            core_metrics: metrics,
             runtime_metrics,
             agent_metadata,
-            max_sign_concurrency: settings.max_sign_concurrency,
-            reorg_reporter,
+            audit_checkpoints: settings.audit_checkpoints,
+            export_slashing_protection: settings.slashing_protection_export.is_some(),
         })
     }
 
     #[allow(clippy::async_yields_async)]
     async fn run(mut self) {
+        if self.audit_checkpoints {
+            let mut passed = true;
+            for origin in &self.origins {
+                passed &= origin
+                    .run_checkpoint_audit()
+                    .instrument(info_span!("CheckpointAudit", origin_chain = %origin.origin_chain))
+                    .await;
+            }
+            // Let scripts running the audit tell a failed one apart
+            if !passed {
+                std::process::exit(1);
+            }
+            return;
+        }
+
+        if self.export_slashing_protection {
+            let mut exported = true;
+            for origin in &self.origins {
+                if let Err(err) = origin.export_slashing_protection() {
+                    error!(
+                        ?err,
+                        origin_chain = %origin.origin_chain,
+                        "Failed to export slashing protection database"
+                    );
+                    exported = false;
+                }
+            }
+            if !exported {
+                std::process::exit(1);
+            }
+            return;
+        }
+
+        let multi_origin = self.origin_chains.len() > 1;
         let mut tasks = vec![];
 
         // run server
-        let router = Router::new()
-            .merge(validator_server::router(
-                self.origin_chain.clone(),
-                self.core.metrics.clone(),
-            ))
-            .merge(
+        let mut router = Router::new().merge(validator_server::router(
+            self.origin_chains.clone(),
+            self.core.metrics.clone(),
+        ));
+        for origin in &self.origins {
+            let merkle_tree_insertions_router =
                 merkle_tree_insertions::list_merkle_tree_insertions::ServerState::new(
-                    self.db.clone(),
+                    origin.db.clone(),
                 )
-                .router(),
-            );
+                .router();
+            // With several origin chains, the routes of each are served under its name
+            router = if multi_origin {
+                router.nest(
+                    &format!("/{}", origin.origin_chain.name()),
+                    merkle_tree_insertions_router,
+                )
+            } else {
+                router.merge(merkle_tree_insertions_router)
+            };
+        }
 
         let server = self
             .core
@@ -235,58 +220,76 @@ impl BaseAgent for Validator {
             ));
         }
 
-        let metrics_updater = match ChainSpecificMetricsUpdater::new(
-            &self.origin_chain_conf,
-            self.core_metrics.clone(),
-            self.agent_metrics.clone(),
-            self.chain_metrics.clone(),
-            Self::AGENT_NAME.to_string(),
-        )
-        .await
-        {
-            Ok(task) => task,
-            Err(err) => {
-                tracing::error!(?err, "Failed to build metrics updater");
-                return;
-            }
-        };
-
-        let task = metrics_updater.spawn();
-        tasks.push(task);
-
-        // report agent metadata
-        self.metadata()
+        for origin in &self.origins {
+            let metrics_updater = match ChainSpecificMetricsUpdater::new(
+                &origin.origin_chain_conf,
+                self.core_metrics.clone(),
+                self.agent_metrics.clone(),
+                self.chain_metrics.clone(),
+                Self::AGENT_NAME.to_string(),
+            )
             .await
-            .expect("Failed to report agent metadata");
+            {
+                Ok(task) => task,
+                Err(err) => {
+                    tracing::error!(?err, "Failed to build metrics updater");
+                    return;
+                }
+            };
 
-        // announce the validator after spawning the signer task
-        self.announce().await.expect("Failed to announce validator");
+            let task = metrics_updater.spawn();
+            tasks.push(task);
+        }
 
-        // Ensure that the merkle tree hook has count > 0 before we begin indexing
-        // messages or submitting checkpoints.
-        loop {
-            match self.merkle_tree_hook.count(&self.reorg_period).await {
-                Ok(0) => {
-                    info!("Waiting for first message in merkle tree hook");
-                    sleep(self.interval).await;
-                }
-                Ok(_) => {
-                    let merkle_tree_hook_sync = match self.run_merkle_tree_hook_sync().await {
-                        Ok(handle) => handle,
-                        Err(err) => {
-                            tracing::error!(?err, "Failed to run merkle tree hook sync");
-                            return;
+        if multi_origin {
+            // Each origin chain is started and run on its own, so that one
+            // waiting for its first message or halted by a reorg doesn't hold
+            // up the others
+            let agent_metadata = Arc::new(self.agent_metadata);
+            for origin in self.origins {
+                let agent_metadata = agent_metadata.clone();
+                let core_metrics = self.core_metrics.clone();
+                let span = info_span!("ValidatorOrigin", origin_chain = %origin.origin_chain);
+                tasks.push(tokio::spawn(
+                    async move {
+                        let origin_tasks = match origin.start(&agent_metadata).await {
+                            Ok(origin_tasks) => origin_tasks,
+                            Err(err) => {
+                                error!(?err, "Failed to start validating origin chain");
+                                core_metrics.set_halted(origin.origin_chain.clone());
+                                return;
+                            }
+                        };
+                        let abort_handles = origin_tasks
+                            .iter()
+                            .map(|task| task.abort_handle())
+                            .collect::<Vec<_>>();
+                        // Note that this only returns an error if one of the tasks panics
+                        if let Err(err) = try_join_all(origin_tasks).await {
+                            // Dropping the other tasks wouldn't stop them, and e.g. the
+                            // backfill submitter must not keep signing once the tip
+                            // submitter detected a reorg
+                            for abort_handle in abort_handles {
+                                abort_handle.abort();
+                            }
+                            core_metrics.set_halted(origin.origin_chain.clone());
+                            error!(
+                                ?err,
+                                "One of the origin chain tasks returned an error, no longer validating it"
+                            );
                         }
-                    };
-                    tasks.push(merkle_tree_hook_sync);
-                    for checkpoint_sync_task in self.run_checkpoint_submitters().await {
-                        tasks.push(checkpoint_sync_task);
                     }
-                    break;
-                }
-                Err(err) => {
-                    error!(?err, "Error getting merkle tree hook count");
-                    sleep(self.interval).await;
+                    .instrument(span),
+                ));
+            }
+        } else {
+            for origin in &self.origins {
+                match origin.start(&self.agent_metadata).await {
+                    Ok(origin_tasks) => tasks.extend(origin_tasks),
+                    Err(err) => {
+                        tracing::error!(?err, "Failed to start validating origin chain");
+                        return;
+                    }
                 }
             }
         }
@@ -298,206 +301,3 @@ impl BaseAgent for Validator {
         }
     }
 }
-
-impl Validator {
-    async fn run_merkle_tree_hook_sync(&self) -> eyre::Result<JoinHandle<()>> {
-        let index_settings = self
-            .as_ref()
-            .settings
-            .chains
-            .get(&self.origin_chain)
-            .map(|chain| chain.index_settings())
-            .ok_or_else(|| eyre::eyre!("No index setting found"))?;
-        let contract_sync = self.merkle_tree_hook_sync.clone();
-        let cursor = contract_sync.cursor(index_settings).await?;
-        let origin = self.origin_chain.name().to_string();
-
-        let handle = tokio::spawn(
-            async move {
-                let label = "merkle_tree_hook";
-                contract_sync.clone().sync(label, cursor.into()).await;
-                info!(chain = origin, label, "contract sync task exit");
-            }
-            .instrument(info_span!("MerkleTreeHookSyncer")),
-        );
-        Ok(handle)
-    }
-
-    async fn run_checkpoint_submitters(&self) -> Vec<JoinHandle<()>> {
-        let submitter = ValidatorSubmitter::new(
-            self.interval,
-            self.reorg_period.clone(),
-            self.merkle_tree_hook.clone(),
-            self.signer.clone(),
-            self.raw_signer.clone(),
-            self.checkpoint_syncer.clone(),
-            Arc::new(self.db.clone()) as Arc<dyn HyperlaneDb>,
-            ValidatorSubmitterMetrics::new(&self.core.metrics, &self.origin_chain),
-            self.max_sign_concurrency,
-            self.reorg_reporter.clone(),
-        );
-
-        let tip_tree = self
-            .merkle_tree_hook
-            .tree(&self.reorg_period)
-            .await
-            .expect("failed to get merkle tree");
-
-        // This function is only called after we have already checked that the
-        // merkle tree hook has count > 0, but we assert to be extra sure this is
-        // the case.
-        assert!(tip_tree.count() > 0, "merkle tree is empty");
-        let backfill_target = submitter.checkpoint_at_block(&tip_tree);
-
-        let backfill_submitter = submitter.clone();
-
-        let mut tasks = vec![];
-        tasks.push(tokio::spawn(
-            async move {
-                backfill_submitter
-                    .backfill_checkpoint_submitter(backfill_target)
-                    .await
-            }
-            .instrument(info_span!("BackfillCheckpointSubmitter")),
-        ));
-
-        tasks.push(tokio::spawn(
-            async move { submitter.checkpoint_submitter(tip_tree.tree).await }
-                .instrument(info_span!("TipCheckpointSubmitter")),
-        ));
-
-        tasks
-    }
-
-    fn log_on_announce_failure(result: ChainResult<TxOutcome>, chain_signer: &String) {
-        match result {
-            Ok(outcome) => {
-                if outcome.executed {
-                    info!(
-                        tx_outcome=?outcome,
-                        ?chain_signer,
-                        "Successfully announced validator",
-                    );
-                } else {
-                    error!(
-                        txid=?outcome.transaction_id,
-                        gas_used=?outcome.gas_used,
-                        gas_price=?outcome.gas_price,
-                        ?chain_signer,
-                        "Transaction attempting to announce validator reverted. Make sure you have enough funds in your account to pay for transaction fees."
-                    );
-                }
-            }
-            Err(err) => {
-                error!(
-                    ?err,
-                    ?chain_signer,
-                    "Failed to announce validator. Make sure you have enough funds in your account to pay for gas."
-                );
-            }
-        }
-    }
-
-    async fn metadata(&self) -> Result<()> {
-        let serialized_metadata = serde_json::to_string_pretty(&self.agent_metadata)?;
-        self.checkpoint_syncer
-            .write_metadata(&serialized_metadata)
-            .await
-    }
-
-    async fn announce(&self) -> Result<()> {
-        let address = self.signer.eth_address();
-        let announcement_location = self.checkpoint_syncer.announcement_location();
-
-        // Sign and post the validator announcement
-        let announcement = Announcement {
-            validator: address,
-            mailbox_address: self.mailbox.address(),
-            mailbox_domain: self.mailbox.domain().id(),
-            storage_location: announcement_location.clone(),
-        };
-        let signed_announcement = self.signer.sign(announcement.clone()).await?;
-        self.checkpoint_syncer
-            .write_announcement(&signed_announcement)
-            .await?;
-
-        // Ensure that the validator has announced themselves before we enter
-        // the main validator submit loop. This is to avoid a situation in
-        // which the validator is signing checkpoints but has not announced
-        // their locations, which makes them functionally unusable.
-        let validators: [H256; 1] = [address.into()];
-        loop {
-            info!("Checking for validator announcement");
-            if let Some(locations) = self
-                .validator_announce
-                .get_announced_storage_locations(&validators)
-                .await?
-                .first()
-            {
-                if locations.contains(&announcement_location) {
-                    info!(
-                        ?locations,
-                        ?announcement_location,
-                        "Validator has announced signature storage location"
-                    );
-
-                    self.core_metrics.set_announced(self.origin_chain.clone());
-
-                    break;
-                }
-                info!(
-                    announced_locations=?locations,
-                    "Validator has not announced signature storage location"
-                );
-
-                if let Some(chain_signer) = self.core.settings.chains[&self.origin_chain]
-                    .chain_signer()
-                    .await?
-                {
-                    let chain_signer_string = chain_signer.address_string();
-                    let chain_signer_h256 = chain_signer.address_h256();
-                    info!(eth_validator_address=?announcement.validator, ?chain_signer_string, ?chain_signer_h256, "Attempting self announce");
-
-                    let balance_delta = self
-                        .validator_announce
-                        .announce_tokens_needed(signed_announcement.clone(), chain_signer_h256)
-                        .await
-                        .unwrap_or_default();
-                    if balance_delta > U256::zero() {
-                        warn!(
-                            tokens_needed=%balance_delta,
-                            eth_validator_address=?announcement.validator,
-                            ?chain_signer_string,
-                            ?chain_signer_h256,
-                            "Please send tokens to your chain signer address to announce",
-                        );
-                    } else {
-                        let result = self
-                            .validator_announce
-                            .announce(signed_announcement.clone())
-                            .await;
-                        Self::log_on_announce_failure(result, &chain_signer_string);
-                    }
-                } else {
-                    warn!(origin_chain=%self.origin_chain, "Cannot announce validator without a signer; make sure a signer is set for the origin chain");
-                }
-
-                sleep(self.interval).await;
-            }
-        }
-        Ok(())
-    }
-
-    async fn report_latest_checkpoints_from_each_endpoint(
-        reorg_reporter: &Arc<dyn ReorgReporter>,
-        checkpoint_syncer_result: &Result<Box<dyn CheckpointSyncer>, CheckpointSyncerBuildError>,
-    ) {
-        if let Err(CheckpointSyncerBuildError::ReorgEvent(reorg_event)) =
-            checkpoint_syncer_result.as_ref()
-        {
-            reorg_reporter
-                .report_with_reorg_period(&reorg_event.reorg_period)
-                .await;
-        }
-    }
-}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use eyre::{Context, Result};
+use serde::Serialize;
+
+use hyperlane_core::{CheckpointWithMessageId, SignedCheckpointWithMessageId, H160};
+
+/// The kind of fraud a validator committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FraudKind {
+    /// Signed different checkpoints at the same index
+    Equivocation,
+    /// Signed a checkpoint that doesn't match the merkle tree of the origin
+    InvalidRoot,
+}
+
+impl FraudKind {
+    /// The label used in metrics and evidence file names
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FraudKind::Equivocation => "equivocation",
+            FraudKind::InvalidRoot => "invalid_root",
+        }
+    }
+}
+
+/// A signed checkpoint, and the storage location it was published to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedCheckpoint {
+    pub location: String,
+    pub signed_checkpoint: SignedCheckpointWithMessageId,
+}
+
+/// Everything needed to prove a validator committed fraud, independently of
+/// the watchtower.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FraudEvidence {
+    pub kind: FraudKind,
+    pub origin: String,
+    pub validator: H160,
+    pub index: u32,
+    /// The signed checkpoints proving the fraud: both conflicting ones for
+    /// an equivocation, the one with the wrong root otherwise
+    pub signed_checkpoints: Vec<PublishedCheckpoint>,
+    /// The checkpoint matching the merkle tree of the origin, if known
+    pub expected: Option<CheckpointWithMessageId>,
+    /// Unix timestamp of the detection
+    pub detected_at: i64,
+}
+
+/// Persists fraud evidence as JSON files, one per detection.
+#[derive(Debug, Clone)]
+pub struct EvidenceStore {
+    dir: PathBuf,
+}
+
+impl EvidenceStore {
+    /// Store evidence under `dir`, which is created if needed.
+    pub fn new(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create evidence directory at {dir:?}"))?;
+        Ok(Self { dir })
+    }
+
+    /// Write `evidence` to `<dir>/<origin>/<kind>_<validator>_<index>.json`,
+    /// returning the path.
+    pub async fn write(&self, evidence: &FraudEvidence) -> Result<PathBuf> {
+        let dir = self.dir.join(&evidence.origin);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Creating evidence directory {dir:?}"))?;
+        let path = dir.join(format!(
+            "{}_{:?}_{}.json",
+            evidence.kind.as_str(),
+            evidence.validator,
+            evidence.index
+        ));
+        tokio::fs::write(&path, serde_json::to_string_pretty(evidence)?)
+            .await
+            .with_context(|| format!("Writing fraud evidence to {path:?}"))?;
+        Ok(path)
+    }
+}
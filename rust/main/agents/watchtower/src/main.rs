@@ -0,0 +1,30 @@
+//! The watchtower is responsible for catching validators committing fraud.
+//!
+//! It indexes the merkle tree hook insertions of each watched origin chain,
+//! reads the checkpoints each watched validator publishes to its announced
+//! storage locations, and reports any validator that signs a checkpoint that
+//! doesn't match the merkle tree, or different checkpoints at the same index.
+//! Each detection is exported as a metric and written to disk as an evidence
+//! bundle containing the offending signed checkpoints.
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+#![deny(clippy::unwrap_used, clippy::panic)]
+
+use eyre::Result;
+use hyperlane_base::agent_main;
+
+use crate::watchtower::Watchtower;
+
+mod evidence;
+mod settings;
+mod watcher;
+mod watchtower;
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() -> Result<()> {
+    // Logging is not initialised at this point, so, using `println!`
+    println!("Watchtower starting up...");
+
+    agent_main::<Watchtower>().await
+}
@@ -0,0 +1,183 @@
+//! Watchtower configuration.
+//!
+//! The correct settings shape is defined in the TypeScript SDK metadata. While the exact shape
+//! and validations it defines are not applied here, we should mirror them.
+//! ANY CHANGES HERE NEED TO BE REFLECTED IN THE TYPESCRIPT SDK.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+
+use derive_more::{AsMut, AsRef, Deref, DerefMut};
+use eyre::{eyre, Context};
+use hyperlane_base::{
+    impl_loadable_from_settings,
+    settings::{
+        parser::{RawAgentConf, ValueParser},
+        Settings,
+    },
+};
+use hyperlane_core::{cfg_unwrap_all, config::*, HyperlaneDomain, H160};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Settings for `Watchtower`
+#[derive(Debug, AsRef, AsMut, Deref, DerefMut)]
+pub struct WatchtowerSettings {
+    #[as_ref]
+    #[as_mut]
+    #[deref]
+    #[deref_mut]
+    pub base: Settings,
+
+    /// Database path
+    pub db: PathBuf,
+    /// The origin chains to watch
+    pub chains_to_watch: Vec<HyperlaneDomain>,
+    /// The validators to watch, by origin chain
+    pub validators: HashMap<HyperlaneDomain, Vec<H160>>,
+    /// How often to check the published checkpoints
+    pub interval: Duration,
+    /// How often to refresh the announced storage locations of the validators
+    pub announcement_refresh_interval: Duration,
+    /// Directory the fraud evidence is written to
+    pub evidence_dir: PathBuf,
+    /// Whether to read checkpoints from announced local storage locations
+    pub allow_local_checkpoint_syncers: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+struct RawWatchtowerSettings(Value);
+
+impl_loadable_from_settings!(Watchtower, RawWatchtowerSettings -> WatchtowerSettings);
+
+impl FromRawConf<RawWatchtowerSettings> for WatchtowerSettings {
+    fn from_config_filtered(
+        raw: RawWatchtowerSettings,
+        cwp: &ConfigPath,
+        _filter: (),
+        agent_name: &str,
+    ) -> ConfigResult<Self> {
+        let mut err = ConfigParsingError::default();
+
+        let p = ValueParser::new(cwp.clone(), &raw.0);
+
+        let chains_names_to_watch: Option<HashSet<&str>> = p
+            .chain(&mut err)
+            .get_key("chainsToWatch")
+            .parse_string()
+            .end()
+            .map(|s| s.split(',').collect());
+
+        let base = p
+            .parse_from_raw_config::<Settings, RawAgentConf, Option<&HashSet<&str>>>(
+                chains_names_to_watch.as_ref(),
+                "Parsing base config",
+                agent_name.to_string(),
+            )
+            .take_config_err(&mut err);
+
+        let db = p
+            .chain(&mut err)
+            .get_opt_key("db")
+            .parse_from_str("Expected db file path")
+            .unwrap_or_else(|| PathBuf::from("watchtower_db"));
+
+        let chains_to_watch: Vec<HyperlaneDomain> =
+            if let (Some(base), Some(chains)) = (&base, chains_names_to_watch) {
+                chains
+                    .into_iter()
+                    .filter_map(|chain| {
+                        base.lookup_domain(chain)
+                            .context("Missing configuration for a chain in `chainsToWatch`")
+                            .into_config_result(|| cwp + "chains_to_watch")
+                            .take_config_err(&mut err)
+                    })
+                    .collect()
+            } else {
+                Default::default()
+            };
+
+        let mut validators = HashMap::new();
+        if let Some(origins) = p.chain(&mut err).get_key("validators").into_obj_iter() {
+            for (origin_name, addresses) in origins {
+                let Some(origin) = chains_to_watch
+                    .iter()
+                    .find(|domain| domain.name() == origin_name)
+                else {
+                    err.push(
+                        addresses.cwp.clone(),
+                        eyre!("Validators configured for a chain not in `chainsToWatch`"),
+                    );
+                    continue;
+                };
+                let addresses: Vec<H160> = addresses
+                    .chain(&mut err)
+                    .into_array_iter()
+                    .map(|addresses| {
+                        addresses
+                            .filter_map(|address| {
+                                address
+                                    .chain(&mut err)
+                                    .parse_address_hash()
+                                    .end()
+                                    .map(Into::into)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                validators.insert(origin.clone(), addresses);
+            }
+        }
+        for origin in &chains_to_watch {
+            if !validators.contains_key(origin) {
+                err.push(
+                    cwp + "validators",
+                    eyre!("No validators configured for {}", origin.name()),
+                );
+            }
+        }
+
+        let interval = p
+            .chain(&mut err)
+            .get_opt_key("interval")
+            .parse_u64()
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(5));
+
+        let announcement_refresh_interval = p
+            .chain(&mut err)
+            .get_opt_key("announcementRefreshInterval")
+            .parse_u64()
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(600));
+
+        let evidence_dir = p
+            .chain(&mut err)
+            .get_opt_key("evidenceDir")
+            .parse_from_str("Expected evidence directory path")
+            .unwrap_or_else(|| PathBuf::from("watchtower_evidence"));
+
+        let allow_local_checkpoint_syncers = p
+            .chain(&mut err)
+            .get_opt_key("allowLocalCheckpointSyncers")
+            .parse_bool()
+            .unwrap_or(false);
+
+        cfg_unwrap_all!(&p.cwp, err: [base]);
+
+        err.into_result(Self {
+            base,
+            db,
+            chains_to_watch,
+            validators,
+            interval,
+            announcement_refresh_interval,
+            evidence_dir,
+            allow_local_checkpoint_syncers,
+        })
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use eyre::Result;
+use tracing::{debug, warn};
+
+use hyperlane_base::{db::HyperlaneDb, CheckpointSyncer};
+use hyperlane_core::{
+    accumulator::incremental::IncrementalMerkle, Checkpoint, CheckpointWithMessageId, H160, H256,
+};
+
+use crate::evidence::{FraudEvidence, FraudKind, PublishedCheckpoint};
+
+/// Maximum number of checkpoints read from a single storage location per tick,
+/// so that a location far behind doesn't starve the others.
+const MAX_CHECKPOINTS_PER_TICK: u32 = 100;
+
+/// A storage location announced by a validator.
+#[derive(Debug)]
+struct WatchedLocation {
+    location: String,
+    syncer: Arc<dyn CheckpointSyncer>,
+    /// The next index to check, set to the latest index of the location the
+    /// first time it is read
+    next_index: Option<u32>,
+}
+
+#[derive(Debug)]
+struct WatchedValidator {
+    address: H160,
+    locations: Vec<WatchedLocation>,
+    /// The first validly signed checkpoint found at each index not yet
+    /// checked by every location
+    seen: HashMap<u32, PublishedCheckpoint>,
+    /// Indices an equivocation was already reported for
+    equivocations: HashSet<u32>,
+}
+
+impl WatchedValidator {
+    /// The lowest index still to be checked by one of the locations.
+    fn min_next_index(&self) -> Option<u32> {
+        self.locations.iter().filter_map(|l| l.next_index).min()
+    }
+
+    async fn check(
+        &mut self,
+        origin: &str,
+        expected: &BTreeMap<u32, CheckpointWithMessageId>,
+        tree_count: u32,
+    ) -> Vec<FraudEvidence> {
+        let mut evidence = vec![];
+        let Some(last_known_index) = tree_count.checked_sub(1) else {
+            return evidence;
+        };
+        for location in &mut self.locations {
+            let latest_index = match location.syncer.latest_index().await {
+                Ok(Some(latest_index)) => latest_index,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!(?err, location = %location.location, "Failed to read latest index");
+                    continue;
+                }
+            };
+            let start = *location.next_index.get_or_insert(latest_index);
+            // Only check indices whose expected checkpoint is known locally
+            let end = latest_index
+                .min(last_known_index)
+                .min(start.saturating_add(MAX_CHECKPOINTS_PER_TICK - 1));
+
+            for index in start..=end {
+                let signed_checkpoint = match location.syncer.fetch_checkpoint(index).await {
+                    Ok(Some(signed_checkpoint)) => signed_checkpoint,
+                    Ok(None) => {
+                        location.next_index = Some(index + 1);
+                        continue;
+                    }
+                    Err(err) => {
+                        // Retried on the next tick
+                        warn!(
+                            ?err,
+                            location = %location.location,
+                            index,
+                            "Failed to fetch checkpoint"
+                        );
+                        break;
+                    }
+                };
+                location.next_index = Some(index + 1);
+
+                match signed_checkpoint.recover() {
+                    Ok(signer) if signer == self.address => {}
+                    // Only checkpoints signed by the validator prove anything
+                    signer => {
+                        debug!(
+                            ?signer,
+                            location = %location.location,
+                            index,
+                            "Ignoring checkpoint not signed by the validator"
+                        );
+                        continue;
+                    }
+                }
+                let published = PublishedCheckpoint {
+                    location: location.location.clone(),
+                    signed_checkpoint,
+                };
+
+                let expected = expected.get(&index).copied();
+                if expected.is_some_and(|expected| expected != published.signed_checkpoint.value) {
+                    evidence.push(FraudEvidence {
+                        kind: FraudKind::InvalidRoot,
+                        origin: origin.to_owned(),
+                        validator: self.address,
+                        index,
+                        signed_checkpoints: vec![published.clone()],
+                        expected,
+                        detected_at: chrono::Utc::now().timestamp(),
+                    });
+                }
+
+                match self.seen.get(&index) {
+                    Some(first)
+                        if first.signed_checkpoint.value != published.signed_checkpoint.value
+                            && self.equivocations.insert(index) =>
+                    {
+                        evidence.push(FraudEvidence {
+                            kind: FraudKind::Equivocation,
+                            origin: origin.to_owned(),
+                            validator: self.address,
+                            index,
+                            signed_checkpoints: vec![first.clone(), published],
+                            expected,
+                            detected_at: chrono::Utc::now().timestamp(),
+                        });
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.seen.insert(index, published);
+                    }
+                }
+            }
+        }
+
+        if let Some(min_next_index) = self.min_next_index() {
+            self.seen.retain(|index, _| *index >= min_next_index);
+            self.equivocations.retain(|index| *index >= min_next_index);
+        }
+        evidence
+    }
+}
+
+/// Checks the checkpoints published by the validators of an origin chain
+/// against each other, and against the merkle tree rebuilt from the
+/// insertions indexed in the local db.
+#[derive(Debug)]
+pub(crate) struct OriginWatcher {
+    origin: String,
+    merkle_tree_hook_address: H256,
+    mailbox_domain: u32,
+    db: Arc<dyn HyperlaneDb>,
+    tree: IncrementalMerkle,
+    /// The checkpoint matching the local tree at each index, from the lowest
+    /// index still to be checked
+    expected: BTreeMap<u32, CheckpointWithMessageId>,
+    validators: Vec<WatchedValidator>,
+}
+
+impl OriginWatcher {
+    pub(crate) fn new(
+        origin: String,
+        merkle_tree_hook_address: H256,
+        mailbox_domain: u32,
+        db: Arc<dyn HyperlaneDb>,
+        validators: &[H160],
+    ) -> Self {
+        Self {
+            origin,
+            merkle_tree_hook_address,
+            mailbox_domain,
+            db,
+            tree: IncrementalMerkle::default(),
+            expected: BTreeMap::new(),
+            validators: validators
+                .iter()
+                .map(|address| WatchedValidator {
+                    address: *address,
+                    locations: vec![],
+                    seen: HashMap::new(),
+                    equivocations: HashSet::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The addresses of the watched validators.
+    pub(crate) fn validators(&self) -> impl Iterator<Item = H160> + '_ {
+        self.validators.iter().map(|validator| validator.address)
+    }
+
+    /// Replace the storage locations read for `validator`, given as
+    /// `(location, syncer)` pairs. Locations already watched keep their
+    /// progress.
+    pub(crate) fn set_locations(
+        &mut self,
+        validator: H160,
+        syncers: Vec<(String, Arc<dyn CheckpointSyncer>)>,
+    ) {
+        let Some(validator) = self.validators.iter_mut().find(|v| v.address == validator) else {
+            return;
+        };
+        let mut previous = std::mem::take(&mut validator.locations);
+        validator.locations = syncers
+            .into_iter()
+            .map(|(location, syncer)| {
+                let next_index = previous
+                    .iter()
+                    .position(|l| l.location == location)
+                    .and_then(|i| previous.swap_remove(i).next_index);
+                WatchedLocation {
+                    location,
+                    syncer,
+                    next_index,
+                }
+            })
+            .collect();
+    }
+
+    /// The highest index checked for each validator, if any.
+    pub(crate) fn latest_checked_indices(&self) -> impl Iterator<Item = (H160, u32)> + '_ {
+        self.validators.iter().filter_map(|validator| {
+            let latest_checked_index = validator
+                .locations
+                .iter()
+                .filter_map(|l| l.next_index)
+                .max()?
+                .checked_sub(1)?;
+            Some((validator.address, latest_checked_index))
+        })
+    }
+
+    /// Check the checkpoints published since the last tick, returning the
+    /// evidence of any fraud found.
+    pub(crate) async fn tick(&mut self) -> Result<Vec<FraudEvidence>> {
+        self.ingest_insertions()?;
+
+        let tree_count = self.tree.count() as u32;
+        let mut evidence = vec![];
+        for validator in &mut self.validators {
+            evidence.extend(
+                validator
+                    .check(&self.origin, &self.expected, tree_count)
+                    .await,
+            );
+        }
+
+        // Locations read for the first time start at their latest index, so
+        // nothing below the lowest index still to be checked is needed
+        let min_next_index = self
+            .validators
+            .iter()
+            .filter_map(WatchedValidator::min_next_index)
+            .min()
+            .unwrap_or(tree_count);
+        self.expected = self.expected.split_off(&min_next_index);
+        Ok(evidence)
+    }
+
+    /// Add the insertions indexed since the last tick to the local tree.
+    fn ingest_insertions(&mut self) -> Result<()> {
+        loop {
+            let index = self.tree.count() as u32;
+            let Some(insertion) = self
+                .db
+                .retrieve_merkle_tree_insertion_by_leaf_index(&index)?
+            else {
+                return Ok(());
+            };
+            self.tree.ingest(insertion.message_id());
+            self.expected.insert(
+                index,
+                CheckpointWithMessageId {
+                    checkpoint: Checkpoint {
+                        merkle_tree_hook_address: self.merkle_tree_hook_address,
+                        mailbox_domain: self.mailbox_domain,
+                        root: self.tree.root(),
+                        index,
+                    },
+                    message_id: insertion.message_id(),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use hyperlane_base::{
+        db::{HyperlaneRocksDB, DB},
+        LocalStorage,
+    };
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneSigner, HyperlaneSignerExt, KnownHyperlaneDomain,
+        MerkleTreeInsertion,
+    };
+    use hyperlane_ethereum::Signers;
+
+    use super::*;
+
+    const PRIVATE_KEY: &str = "254bf805ec98536bbcfcf7bd88f58aa17bcf2955138237d3d06288d39fabfecb";
+
+    fn local_storage(path: PathBuf) -> (String, Arc<dyn CheckpointSyncer>) {
+        let storage = LocalStorage::new(path, None).unwrap();
+        (storage.announcement_location(), Arc::new(storage))
+    }
+
+    #[tokio::test]
+    async fn test_tick_detects_fraud() {
+        let db_dir = tempfile::tempdir().unwrap();
+        let db = HyperlaneRocksDB::new(
+            &HyperlaneDomain::Known(KnownHyperlaneDomain::Test1),
+            DB::from_path(db_dir.path()).unwrap(),
+        );
+        let message_ids = [H256::random(), H256::random()];
+        let mut tree = IncrementalMerkle::default();
+        let mut checkpoints = vec![];
+        for (index, message_id) in message_ids.iter().enumerate() {
+            db.store_merkle_tree_insertion_by_leaf_index(
+                &(index as u32),
+                &MerkleTreeInsertion::new(index as u32, *message_id),
+            )
+            .unwrap();
+            tree.ingest(*message_id);
+            checkpoints.push(CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::zero(),
+                    mailbox_domain: 13371,
+                    root: tree.root(),
+                    index: index as u32,
+                },
+                message_id: *message_id,
+            });
+        }
+
+        let validator: Signers = PRIVATE_KEY
+            .parse::<ethers::signers::LocalWallet>()
+            .unwrap()
+            .into();
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let primary = local_storage(primary_dir.path().into());
+        let mirror = local_storage(mirror_dir.path().into());
+        primary.1.write_latest_index(0).await.unwrap();
+        mirror.1.write_latest_index(0).await.unwrap();
+
+        let mut watcher = OriginWatcher::new(
+            "test1".to_owned(),
+            H256::zero(),
+            13371,
+            Arc::new(db),
+            &[validator.eth_address()],
+        );
+        watcher.set_locations(
+            validator.eth_address(),
+            vec![primary.clone(), mirror.clone()],
+        );
+
+        // Both locations agree on index 0
+        let signed = validator.sign(checkpoints[0]).await.unwrap();
+        primary.1.write_checkpoint(&signed).await.unwrap();
+        mirror.1.write_checkpoint(&signed).await.unwrap();
+        assert_eq!(watcher.tick().await.unwrap(), vec![]);
+
+        // The mirror gets a checkpoint with a forged root at index 1
+        let honest = validator.sign(checkpoints[1]).await.unwrap();
+        let mut forged = checkpoints[1];
+        forged.checkpoint.root = H256::random();
+        let forged = validator.sign(forged).await.unwrap();
+        primary.1.write_checkpoint(&honest).await.unwrap();
+        primary.1.write_latest_index(1).await.unwrap();
+        mirror.1.write_checkpoint(&forged).await.unwrap();
+        mirror.1.write_latest_index(1).await.unwrap();
+
+        let evidence = watcher.tick().await.unwrap();
+        let kinds: Vec<_> = evidence.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![FraudKind::InvalidRoot, FraudKind::Equivocation]);
+        assert_eq!(evidence[0].signed_checkpoints[0].signed_checkpoint, forged);
+        assert_eq!(evidence[0].expected, Some(checkpoints[1]));
+        let signed_checkpoints: Vec<_> = evidence[1]
+            .signed_checkpoints
+            .iter()
+            .map(|c| (c.location.clone(), c.signed_checkpoint.clone()))
+            .collect();
+        assert_eq!(
+            signed_checkpoints,
+            vec![(primary.0.clone(), honest), (mirror.0.clone(), forged)]
+        );
+        assert_eq!(
+            watcher.latest_checked_indices().collect::<Vec<_>>(),
+            vec![(validator.eth_address(), 1)]
+        );
+
+        // Nothing new is reported on the next tick
+        assert_eq!(watcher.tick().await.unwrap(), vec![]);
+    }
+}
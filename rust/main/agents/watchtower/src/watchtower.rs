@@ -0,0 +1,415 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use derive_more::AsRef;
+use eyre::Result;
+use futures::future::try_join_all;
+use prometheus::{IntCounterVec, IntGaugeVec};
+use tokio::{
+    task::JoinHandle,
+    time::{sleep, Instant},
+};
+use tracing::{error, info, info_span, warn, Instrument};
+
+use hyperlane_base::{
+    db::{HyperlaneRocksDB, DB},
+    metrics::AgentMetrics,
+    settings::{CheckpointSyncerBuildError, CheckpointSyncerConf, IndexSettings},
+    AgentMetadata, BaseAgent, ChainMetrics, ChainSpecificMetricsUpdater, CheckpointSyncer,
+    ContractSyncMetrics, ContractSyncer, CoreMetrics, HyperlaneAgentCore, RuntimeMetrics,
+    SequencedDataContractSync,
+};
+use hyperlane_core::{
+    HyperlaneContract, HyperlaneDomain, MerkleTreeInsertion, ValidatorAnnounce, H160,
+};
+
+use crate::{
+    evidence::{EvidenceStore, FraudEvidence},
+    settings::WatchtowerSettings,
+    watcher::OriginWatcher,
+};
+
+/// An agent watching the checkpoints published by validators for
+/// equivocations and invalid roots
+#[derive(Debug, AsRef)]
+pub struct Watchtower {
+    #[as_ref]
+    core: HyperlaneAgentCore,
+    origins: Vec<WatchedOrigin>,
+    interval: Duration,
+    announcement_refresh_interval: Duration,
+    allow_local_checkpoint_syncers: bool,
+    evidence_store: EvidenceStore,
+    metrics: WatchtowerMetrics,
+    core_metrics: Arc<CoreMetrics>,
+    agent_metrics: AgentMetrics,
+    chain_metrics: ChainMetrics,
+    runtime_metrics: RuntimeMetrics,
+}
+
+#[derive(Debug)]
+struct WatchedOrigin {
+    domain: HyperlaneDomain,
+    watcher: OriginWatcher,
+    validator_announce: Arc<dyn ValidatorAnnounce>,
+    merkle_tree_hook_sync: Arc<SequencedDataContractSync<MerkleTreeInsertion>>,
+}
+
+#[derive(Debug, Clone)]
+struct WatchtowerMetrics {
+    /// Fraud detected, by origin, validator and kind
+    fraud_detected: IntCounterVec,
+    /// Highest checkpoint index checked, by origin and validator
+    latest_checked_index: IntGaugeVec,
+}
+
+impl WatchtowerMetrics {
+    fn new(metrics: &CoreMetrics) -> Result<Self> {
+        Ok(Self {
+            fraud_detected: metrics.new_int_counter(
+                "watchtower_fraud_detected",
+                "Number of frauds detected in the checkpoints published by validators",
+                &["origin", "validator", "kind"],
+            )?,
+            latest_checked_index: metrics.new_int_gauge(
+                "watchtower_latest_checked_index",
+                "Highest checkpoint index checked for a validator",
+                &["origin", "validator"],
+            )?,
+        })
+    }
+}
+
+#[async_trait]
+impl BaseAgent for Watchtower {
+    const AGENT_NAME: &'static str = "watchtower";
+    type Settings = WatchtowerSettings;
+    type Metadata = AgentMetadata;
+
+    async fn from_settings(
+        _agent_metadata: Self::Metadata,
+        settings: Self::Settings,
+        metrics: Arc<CoreMetrics>,
+        agent_metrics: AgentMetrics,
+        chain_metrics: ChainMetrics,
+        runtime_metrics: RuntimeMetrics,
+        _tokio_console_server: console_subscriber::Server,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let db = DB::from_path(&settings.db)?;
+        let core = settings.build_hyperlane_core(metrics.clone());
+        let contract_sync_metrics = Arc::new(ContractSyncMetrics::new(&metrics));
+
+        let mut origins = Vec::with_capacity(settings.chains_to_watch.len());
+        for domain in &settings.chains_to_watch {
+            let origin_db = HyperlaneRocksDB::new(domain, db.clone());
+            let merkle_tree_hook = settings.build_merkle_tree_hook(domain, &metrics).await?;
+            let validator_announce = settings.build_validator_announce(domain, &metrics).await?;
+            let merkle_tree_hook_sync = settings
+                .sequenced_contract_sync::<MerkleTreeInsertion, _>(
+                    domain,
+                    &metrics,
+                    &contract_sync_metrics,
+                    origin_db.clone().into(),
+                    false,
+                    false,
+                )
+                .await?;
+            let validators = settings
+                .validators
+                .get(domain)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            origins.push(WatchedOrigin {
+                domain: domain.clone(),
+                watcher: OriginWatcher::new(
+                    domain.name().to_owned(),
+                    merkle_tree_hook.address(),
+                    domain.id(),
+                    Arc::new(origin_db),
+                    validators,
+                ),
+                validator_announce: validator_announce.into(),
+                merkle_tree_hook_sync,
+            });
+        }
+
+        Ok(Self {
+            core,
+            origins,
+            interval: settings.interval,
+            announcement_refresh_interval: settings.announcement_refresh_interval,
+            allow_local_checkpoint_syncers: settings.allow_local_checkpoint_syncers,
+            evidence_store: EvidenceStore::new(settings.evidence_dir.clone())?,
+            metrics: WatchtowerMetrics::new(&metrics)?,
+            core_metrics: metrics,
+            agent_metrics,
+            chain_metrics,
+            runtime_metrics,
+        })
+    }
+
+    #[allow(clippy::async_yields_async)]
+    async fn run(self) {
+        let mut tasks = vec![];
+
+        let server = self
+            .core
+            .settings
+            .server(self.core_metrics.clone())
+            .expect("Failed to create server");
+        let server_task = tokio::spawn(
+            async move {
+                server.run();
+            }
+            .instrument(info_span!("Watchtower server")),
+        );
+        tasks.push(server_task);
+
+        for origin in self.origins {
+            let chain_conf = match self.core.settings.chain_setup(&origin.domain) {
+                Ok(chain_conf) => chain_conf,
+                Err(err) => {
+                    error!(
+                        ?err,
+                        domain = origin.domain.name(),
+                        "Failed to get chain config"
+                    );
+                    self.chain_metrics
+                        .set_critical_error(origin.domain.name(), true);
+                    continue;
+                }
+            };
+            match ChainSpecificMetricsUpdater::new(
+                chain_conf,
+                self.core_metrics.clone(),
+                self.agent_metrics.clone(),
+                self.chain_metrics.clone(),
+                Self::AGENT_NAME.to_string(),
+            )
+            .await
+            {
+                Ok(metrics_updater) => tasks.push(metrics_updater.spawn()),
+                Err(err) => {
+                    error!(
+                        ?err,
+                        domain = origin.domain.name(),
+                        "Failed to build metrics updater"
+                    );
+                    self.chain_metrics
+                        .set_critical_error(origin.domain.name(), true);
+                    continue;
+                }
+            }
+
+            let Some(index_settings) = self
+                .core
+                .settings
+                .chains
+                .get(&origin.domain)
+                .map(|chain| chain.index_settings())
+            else {
+                error!(domain = origin.domain.name(), "No index setting found");
+                self.chain_metrics
+                    .set_critical_error(origin.domain.name(), true);
+                continue;
+            };
+            match origin.run_merkle_tree_hook_sync(index_settings).await {
+                Ok(task) => tasks.push(task),
+                Err(err) => {
+                    error!(
+                        ?err,
+                        domain = origin.domain.name(),
+                        "Failed to run merkle tree hook sync"
+                    );
+                    self.chain_metrics
+                        .set_critical_error(origin.domain.name(), true);
+                    continue;
+                }
+            }
+
+            let span = info_span!("OriginWatcher", origin = origin.domain.name());
+            let task = OriginWatcherTask {
+                origin,
+                interval: self.interval,
+                announcement_refresh_interval: self.announcement_refresh_interval,
+                allow_local_checkpoint_syncers: self.allow_local_checkpoint_syncers,
+                evidence_store: self.evidence_store.clone(),
+                metrics: self.metrics.clone(),
+            };
+            tasks.push(tokio::spawn(task.run().instrument(span)));
+        }
+        tasks.push(self.runtime_metrics.spawn());
+
+        if let Err(err) = try_join_all(tasks).await {
+            error!(error = ?err, "Watchtower task panicked");
+        }
+    }
+}
+
+impl WatchedOrigin {
+    async fn run_merkle_tree_hook_sync(
+        &self,
+        index_settings: IndexSettings,
+    ) -> Result<JoinHandle<()>> {
+        let contract_sync = self.merkle_tree_hook_sync.clone();
+        let cursor = contract_sync.cursor(index_settings).await?;
+        let origin = self.domain.name().to_string();
+
+        Ok(tokio::spawn(
+            async move {
+                let label = "merkle_tree_hook";
+                contract_sync.clone().sync(label, cursor.into()).await;
+                info!(chain = origin, label, "contract sync task exit");
+            }
+            .instrument(info_span!("MerkleTreeHookSyncer")),
+        ))
+    }
+}
+
+struct OriginWatcherTask {
+    origin: WatchedOrigin,
+    interval: Duration,
+    announcement_refresh_interval: Duration,
+    allow_local_checkpoint_syncers: bool,
+    evidence_store: EvidenceStore,
+    metrics: WatchtowerMetrics,
+}
+
+impl OriginWatcherTask {
+    async fn run(mut self) {
+        let mut last_refresh: Option<Instant> = None;
+        loop {
+            if last_refresh.map_or(true, |at| {
+                at.elapsed() >= self.announcement_refresh_interval
+            }) {
+                match self.refresh_locations().await {
+                    Ok(()) => last_refresh = Some(Instant::now()),
+                    Err(err) => warn!(?err, "Failed to refresh announced storage locations"),
+                }
+            }
+
+            match self.origin.watcher.tick().await {
+                Ok(evidence) => {
+                    for evidence in evidence {
+                        self.report(evidence).await;
+                    }
+                }
+                Err(err) => warn!(?err, "Failed to check published checkpoints"),
+            }
+
+            let origin = self.origin.domain.name();
+            for (validator, index) in self.origin.watcher.latest_checked_indices() {
+                self.metrics
+                    .latest_checked_index
+                    .with_label_values(&[origin, &format!("{validator:?}")])
+                    .set(index as i64);
+            }
+
+            sleep(self.interval).await;
+        }
+    }
+
+    /// Read the storage locations announced by each watched validator.
+    async fn refresh_locations(&mut self) -> Result<()> {
+        let validators: Vec<H160> = self.origin.watcher.validators().collect();
+        let addresses: Vec<_> = validators.iter().map(|v| (*v).into()).collect();
+        let announced = self
+            .origin
+            .validator_announce
+            .get_announced_storage_locations(&addresses)
+            .await?;
+
+        for (validator, locations) in validators.into_iter().zip(announced) {
+            let mut syncers = vec![];
+            for location in locations {
+                if let Some(syncer) = self.build_syncer(validator, &location).await {
+                    syncers.push((location, syncer));
+                }
+            }
+            if syncers.is_empty() {
+                warn!(
+                    ?validator,
+                    "Validator has no readable announced storage location"
+                );
+            }
+            self.origin.watcher.set_locations(validator, syncers);
+        }
+        Ok(())
+    }
+
+    async fn build_syncer(
+        &self,
+        validator: H160,
+        location: &str,
+    ) -> Option<Arc<dyn CheckpointSyncer>> {
+        let conf = match CheckpointSyncerConf::from_str(location) {
+            Ok(conf) => conf,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?validator,
+                    location,
+                    "Skipping unparseable storage location"
+                );
+                return None;
+            }
+        };
+        if matches!(conf, CheckpointSyncerConf::LocalStorage { .. })
+            && !self.allow_local_checkpoint_syncers
+        {
+            warn!(?validator, location, "Skipping local storage location");
+            return None;
+        }
+        match conf.build_and_validate(None, None).await {
+            Ok(syncer) => Some(syncer.into()),
+            // Relayers don't read from a location with a reorg flag either
+            Err(CheckpointSyncerBuildError::ReorgEvent(reorg_event)) => {
+                warn!(
+                    ?reorg_event,
+                    ?validator,
+                    location,
+                    "Skipping storage location with a reorg flag set"
+                );
+                None
+            }
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?validator,
+                    location,
+                    "Failed to build checkpoint syncer"
+                );
+                None
+            }
+        }
+    }
+
+    async fn report(&self, evidence: FraudEvidence) {
+        let validator = format!("{:?}", evidence.validator);
+        self.metrics
+            .fraud_detected
+            .with_label_values(&[&evidence.origin, &validator, evidence.kind.as_str()])
+            .inc();
+        match self.evidence_store.write(&evidence).await {
+            Ok(path) => error!(
+                kind = evidence.kind.as_str(),
+                validator,
+                index = evidence.index,
+                ?path,
+                "Fraud detected"
+            ),
+            Err(err) => error!(
+                kind = evidence.kind.as_str(),
+                validator,
+                index = evidence.index,
+                ?evidence,
+                ?err,
+                "Fraud detected, but failed to write the evidence"
+            ),
+        }
+    }
+}
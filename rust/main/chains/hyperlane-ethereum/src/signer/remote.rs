@@ -0,0 +1,406 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use ethers::prelude::{Address, Signature};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::H256;
+use ethers::utils::{keccak256, public_key_to_address};
+use ethers_core::k256::ecdsa::VerifyingKey;
+use ethers_signers::{to_eip155_v, Signer};
+use reqwest::{Certificate, Client, Identity};
+use serde::Serialize;
+use url::Url;
+
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// TLS settings used to connect to a remote signer.
+#[derive(Debug, Clone, Default)]
+pub struct RemoteSignerTls {
+    /// PEM encoded certificate of the CA the signing service certificate is
+    /// issued by, trusted on top of the system ones
+    pub ca_cert: Option<PathBuf>,
+    /// PEM encoded client certificate, for mutual TLS
+    pub client_cert: Option<PathBuf>,
+    /// PEM encoded PKCS #8 private key of the client certificate
+    pub client_key: Option<PathBuf>,
+}
+
+/// A signer delegating signing to an HTTP signing service implementing the
+/// eth1 signing API of Web3Signer, typically backed by an HSM.
+///
+/// The service signs the keccak256 hash of the data it's given, so messages,
+/// transactions and typed data are sent as the preimage of the hash ethers
+/// would sign for them.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: Client,
+    sign_url: Url,
+    address: Address,
+    chain_id: u64,
+}
+
+#[derive(Serialize)]
+struct SignRequest {
+    data: String,
+}
+
+impl RemoteSigner {
+    /// Create a signer for the key identified by `public_key`, a hex encoded
+    /// secp256k1 public key, of the signing service at base url `url`.
+    pub fn new(
+        url: &Url,
+        public_key: &str,
+        tls: Option<&RemoteSignerTls>,
+    ) -> Result<Self, RemoteSignerError> {
+        let address = public_key_address(public_key)?;
+        // Keep the path of `url`, if any, when joining the endpoint to it
+        let mut base_url = url.clone();
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        let sign_url = base_url
+            .join(&format!("api/v1/eth1/sign/{public_key}"))
+            .map_err(|err| RemoteSignerError::InvalidUrl(err.to_string()))?;
+
+        let mut builder = Client::builder().timeout(REMOTE_SIGNER_TIMEOUT);
+        if let Some(tls) = tls {
+            if let Some(ca_cert) = &tls.ca_cert {
+                builder = builder.add_root_certificate(Certificate::from_pem(&read(ca_cert)?)?);
+            }
+            match (&tls.client_cert, &tls.client_key) {
+                (Some(cert), Some(key)) => {
+                    builder = builder.identity(Identity::from_pkcs8_pem(&read(cert)?, &read(key)?)?)
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(RemoteSignerError::InvalidTls(
+                        "client certificate and key must be set together".to_owned(),
+                    ))
+                }
+            }
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            sign_url,
+            address,
+            chain_id: 1,
+        })
+    }
+
+    /// Have the service sign the keccak256 hash of `data`, and check the
+    /// signature is from the expected key.
+    async fn sign_data(&self, data: &[u8]) -> Result<Signature, RemoteSignerError> {
+        let response = self
+            .client
+            .post(self.sign_url.clone())
+            .json(&SignRequest {
+                data: format!("0x{}", hex::encode(data)),
+            })
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(RemoteSignerError::Status { status, body });
+        }
+
+        let mut signature = Signature::from_str(body.trim().trim_matches('"'))
+            .map_err(|err| RemoteSignerError::InvalidSignature(err.to_string()))?;
+        signature.v = 27 + u64::from(recovery_id(signature.v)?);
+        let signer = signature
+            .recover(H256::from(keccak256(data)))
+            .map_err(|err| RemoteSignerError::InvalidSignature(err.to_string()))?;
+        if signer != self.address {
+            return Err(RemoteSignerError::UnexpectedSigner {
+                expected: self.address,
+                actual: signer,
+            });
+        }
+        Ok(signature)
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for RemoteSigner {
+    type Error = RemoteSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let message = message.as_ref();
+        let mut data = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        data.extend_from_slice(message);
+        self.sign_data(&data).await
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        let chain_id = match tx.chain_id() {
+            Some(chain_id) => chain_id.as_u64(),
+            None => {
+                tx.set_chain_id(self.chain_id);
+                self.chain_id
+            }
+        };
+        let mut signature = self.sign_data(&tx.rlp()).await?;
+        signature.v = to_eip155_v(recovery_id(signature.v)?, chain_id);
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        let eip712_err = |err: T::Error| RemoteSignerError::Eip712(err.to_string());
+        let mut data = vec![0x19, 0x01];
+        data.extend_from_slice(&payload.domain_separator().map_err(eip712_err)?);
+        data.extend_from_slice(&payload.struct_hash().map_err(eip712_err)?);
+        self.sign_data(&data).await
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+/// The recovery id of a signature, whether the service returned its `v` as
+/// the recovery id itself, as 27 or 28, or as an EIP-155 `v`.
+fn recovery_id(v: u64) -> Result<u8, RemoteSignerError> {
+    match v {
+        0 | 1 => Ok(v as u8),
+        27 | 28 => Ok((v - 27) as u8),
+        v if v >= 35 => Ok(((v - 35) % 2) as u8),
+        v => Err(RemoteSignerError::InvalidSignature(format!(
+            "invalid recovery id `v` of {v}"
+        ))),
+    }
+}
+
+fn public_key_address(public_key: &str) -> Result<Address, RemoteSignerError> {
+    let mut bytes = hex::decode(public_key.trim_start_matches("0x"))
+        .map_err(|err| RemoteSignerError::InvalidPublicKey(err.to_string()))?;
+    // Web3Signer identifies keys by their uncompressed public key, without
+    // the SEC1 tag
+    if bytes.len() == 64 {
+        bytes.insert(0, 0x04);
+    }
+    let key = VerifyingKey::from_sec1_bytes(&bytes)
+        .map_err(|err| RemoteSignerError::InvalidPublicKey(err.to_string()))?;
+    Ok(public_key_to_address(&key))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, RemoteSignerError> {
+    std::fs::read(path)
+        .map_err(|err| RemoteSignerError::InvalidTls(format!("reading {path:?}: {err}")))
+}
+
+/// Error types for RemoteSigner
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteSignerError {
+    /// The public key isn't a valid secp256k1 public key
+    #[error("Invalid remote signer public key: {0}")]
+    InvalidPublicKey(String),
+    /// The signing service url can't be built
+    #[error("Invalid remote signer url: {0}")]
+    InvalidUrl(String),
+    /// The TLS settings can't be loaded
+    #[error("Invalid remote signer TLS settings: {0}")]
+    InvalidTls(String),
+    /// The request to the signing service failed
+    #[error("{0}")]
+    Http(#[from] reqwest::Error),
+    /// The signing service rejected the request
+    #[error("Remote signer responded with {status}: {body}")]
+    Status {
+        /// The status of the response
+        status: reqwest::StatusCode,
+        /// The body of the response
+        body: String,
+    },
+    /// The signing service returned something that isn't a signature
+    #[error("Invalid signature from remote signer: {0}")]
+    InvalidSignature(String),
+    /// The signing service signed with another key than the configured one
+    #[error("Remote signer signed with {actual:?} instead of {expected:?}")]
+    UnexpectedSigner {
+        /// The address of the configured key
+        expected: Address,
+        /// The address of the key that signed
+        actual: Address,
+    },
+    /// The typed data can't be encoded
+    #[error("Failed to encode typed data: {0}")]
+    Eip712(String),
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use ethers::signers::LocalWallet;
+    use ethers::types::TransactionRequest;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const PRIVATE_KEY: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+    #[derive(serde::Deserialize)]
+    struct Request {
+        data: String,
+    }
+
+    /// Signs like Web3Signer does, with the key of `wallet`, adding `v_offset`
+    /// to the recovery id
+    async fn sign(
+        State((wallet, v_offset)): State<(Arc<LocalWallet>, u8)>,
+        Json(request): Json<Request>,
+    ) -> (StatusCode, String) {
+        let Ok(data) = hex::decode(request.data.trim_start_matches("0x")) else {
+            return (StatusCode::BAD_REQUEST, "invalid data".to_owned());
+        };
+        let (signature, recovery_id) = wallet
+            .signer()
+            .sign_prehash_recoverable(&keccak256(data))
+            .unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(v_offset + u8::from(recovery_id));
+        (StatusCode::OK, format!("0x{}", hex::encode(bytes)))
+    }
+
+    /// Serves the signing API for `wallet`, returning its base url and the
+    /// public key identifying it.
+    async fn serve(wallet: LocalWallet) -> (Url, String) {
+        serve_with_v_offset(wallet, 27).await
+    }
+
+    async fn serve_with_v_offset(wallet: LocalWallet, v_offset: u8) -> (Url, String) {
+        let public_key = hex::encode(
+            &wallet
+                .signer()
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()[1..],
+        );
+        let app = Router::new()
+            .route(&format!("/api/v1/eth1/sign/0x{public_key}"), post(sign))
+            .with_state((Arc::new(wallet), v_offset));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, format!("0x{public_key}"))
+    }
+
+    fn wallet(private_key: &str) -> LocalWallet {
+        private_key.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_signs_like_a_local_wallet() {
+        let local = wallet(PRIVATE_KEY).with_chain_id(5u64);
+        let (url, public_key) = serve(local.clone()).await;
+        let remote = RemoteSigner::new(&url, &public_key, None)
+            .unwrap()
+            .with_chain_id(5u64);
+        assert_eq!(remote.address(), local.address());
+
+        let message = b"hello";
+        assert_eq!(
+            remote.sign_message(message).await.unwrap(),
+            local.sign_message(message).await.unwrap()
+        );
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .value(100)
+            .nonce(3)
+            .gas(21000)
+            .gas_price(1)
+            .into();
+        let signature = remote.sign_transaction(&tx).await.unwrap();
+        assert_eq!(signature, local.sign_transaction(&tx).await.unwrap());
+        assert!(signature.v == 45 || signature.v == 46);
+    }
+
+    #[tokio::test]
+    async fn test_normalizes_signature_v() {
+        let local = wallet(PRIVATE_KEY).with_chain_id(5u64);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .value(100)
+            .nonce(3)
+            .gas(21000)
+            .gas_price(1)
+            .into();
+
+        // Services may return the bare recovery id or an EIP-155 `v` for chain 5
+        for v_offset in [0, 45] {
+            let (url, public_key) = serve_with_v_offset(local.clone(), v_offset).await;
+            let remote = RemoteSigner::new(&url, &public_key, None)
+                .unwrap()
+                .with_chain_id(5u64);
+
+            assert_eq!(
+                remote.sign_message(b"hello").await.unwrap(),
+                local.sign_message(b"hello").await.unwrap()
+            );
+            assert_eq!(
+                remote.sign_transaction(&tx).await.unwrap(),
+                local.sign_transaction(&tx).await.unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_signature_from_another_key() {
+        let (url, public_key) = serve(wallet(PRIVATE_KEY)).await;
+        let other = wallet("2222222222222222222222222222222222222222222222222222222222222222");
+
+        // The service signs with another key than the one it's identified by
+        let remote = RemoteSigner::new(&url, &public_key, None).unwrap();
+        let misconfigured = RemoteSigner {
+            address: other.address(),
+            ..remote
+        };
+        assert!(matches!(
+            misconfigured.sign_message(b"hello").await,
+            Err(RemoteSignerError::UnexpectedSigner { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_key_is_an_error() {
+        let (url, _) = serve(wallet(PRIVATE_KEY)).await;
+        let other = wallet("2222222222222222222222222222222222222222222222222222222222222222");
+        let other_public_key = hex::encode(
+            other
+                .signer()
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes(),
+        );
+
+        let remote = RemoteSigner::new(&url, &other_public_key, None).unwrap();
+        assert_eq!(remote.address(), other.address());
+        assert!(matches!(
+            remote.sign_message(b"hello").await,
+            Err(RemoteSignerError::Status { .. })
+        ));
+    }
+}
@@ -104,4 +104,15 @@ where
 
         result
     }
+
+    async fn remove_cached_call_result(
+        &self,
+        domain_name: &str,
+        method: &str,
+        fn_params: &(impl Serialize + Send + Sync),
+    ) -> CacheResult<()> {
+        self.inner
+            .remove_cached_call_result(domain_name, method, fn_params)
+            .await
+    }
 }
@@ -11,7 +11,7 @@ pub use metered_cache::{
     MeteredCache, MeteredCacheConfig, MeteredCacheMetrics, MeteredCacheMetricsBuilder,
     HIT_COUNT_HELP, HIT_COUNT_LABELS, MISS_COUNT_HELP, MISS_COUNT_LABELS,
 };
-pub use moka::{CacheResult, Expiration, LocalCache};
+pub use moka::{default_expiration, CacheResult, Expiration, LocalCache};
 pub use optional_cache::OptionalCache;
 
 /// Should be used as the `fn_params` when the function has no parameters
@@ -39,4 +39,12 @@ pub trait FunctionCallCache: Send + Sync {
     ) -> CacheResult<Option<T>>
     where
         T: DeserializeOwned;
+
+    /// Remove a cached call result with the given parameters
+    async fn remove_cached_call_result(
+        &self,
+        domain_name: &str,
+        method: &str,
+        fn_params: &(impl Serialize + Send + Sync),
+    ) -> CacheResult<()>;
 }
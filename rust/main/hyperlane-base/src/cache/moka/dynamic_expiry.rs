@@ -10,6 +10,8 @@ use serde::{Deserialize, Serialize};
 /// Default expiration time for cache entries.
 static DEFAULT_EXPIRATION: OnceLock<Duration> = OnceLock::new();
 
+/// Default expiration time for cache entries, configurable through the
+/// `HYP_CACHEDEFAULTEXPIRATIONSECONDS` environment variable.
 pub fn default_expiration() -> Duration {
     *DEFAULT_EXPIRATION.get_or_init(|| {
         let secs = std::env::var("HYP_CACHEDEFAULTEXPIRATIONSECONDS")
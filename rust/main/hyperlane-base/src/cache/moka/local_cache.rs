@@ -53,4 +53,15 @@ impl FunctionCallCache for LocalCache {
             None => Ok(None),
         }
     }
+
+    /// Remove a cached call result with the given parameters
+    async fn remove_cached_call_result(
+        &self,
+        domain_name: &str,
+        method: &str,
+        fn_params: &(impl Serialize + Send + Sync),
+    ) -> CacheResult<()> {
+        let key = (domain_name, method, fn_params);
+        self.0.remove(&key).await
+    }
 }
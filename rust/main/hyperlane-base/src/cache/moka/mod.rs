@@ -7,7 +7,7 @@ use std::hash::RandomState;
 use moka::{future::Cache, policy::EvictionPolicy};
 use serde::{de::DeserializeOwned, Serialize};
 
-pub use dynamic_expiry::{DynamicExpiry, Expiration, ExpirationType};
+pub use dynamic_expiry::{default_expiration, DynamicExpiry, Expiration, ExpirationType};
 pub use local_cache::LocalCache;
 
 use crate::cache::CacheError;
@@ -74,6 +74,13 @@ impl BaseCache {
         Ok(ttl)
     }
 
+    /// Remove the value for the given key
+    pub async fn remove(&self, key: &impl Serialize) -> CacheResult<()> {
+        let key = self.serialize(key)?;
+        self.cache.invalidate(&key).await;
+        Ok(())
+    }
+
     fn serialize(&self, value: &impl Serialize) -> CacheResult<String> {
         serde_json::to_string(value).map_err(CacheError::FailedToSerializeInput)
     }
@@ -98,13 +105,6 @@ impl BaseCache {
         let key = self.serialize(key)?;
         Ok(self.cache.contains_key(&key))
     }
-
-    /// Remove the value for the given key
-    pub async fn remove(&self, key: &impl Serialize) -> CacheResult<()> {
-        let key = self.serialize(key)?;
-        self.cache.invalidate(&key).await;
-        Ok(())
-    }
 }
 
 #[cfg(test)]
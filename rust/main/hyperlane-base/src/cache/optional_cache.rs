@@ -52,4 +52,18 @@ where
         }
         Ok(None)
     }
+    /// Calls the inner cache if it exists, otherwise returns Ok(())
+    async fn remove_cached_call_result(
+        &self,
+        domain_name: &str,
+        method: &str,
+        fn_params: &(impl Serialize + Send + Sync),
+    ) -> CacheResult<()> {
+        if let Some(inner) = &self.inner {
+            return inner
+                .remove_cached_call_result(domain_name, method, fn_params)
+                .await;
+        }
+        Ok(())
+    }
 }
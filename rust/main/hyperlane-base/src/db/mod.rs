@@ -1,4 +1,8 @@
-pub use self::storage_types::{InterchainGasExpenditureData, InterchainGasPaymentData};
+pub use self::storage_types::{
+    DynamicMessageFilterData, InterchainGasExpenditureData, InterchainGasPaymentData,
+    ProfitabilityLedgerEntry, ProfitabilityRouteAggregate, ShadowSubmission, ValidatorReliability,
+    WebhookOutboxEntry,
+};
 pub use error::*;
 pub use rocks::*;
 
@@ -173,4 +177,49 @@ pub trait HyperlaneDb: Send + Sync {
         &self,
         message_id: &H256,
     ) -> DbResult<Option<Vec<UniqueIdentifier>>>;
+
+    /// Store the profitability ledger entry of a message by its message id
+    fn store_profitability_ledger_entry_by_message_id(
+        &self,
+        message_id: &H256,
+        entry: &ProfitabilityLedgerEntry,
+    ) -> DbResult<()>;
+
+    /// Retrieve the profitability ledger entry of a message by its message id
+    fn retrieve_profitability_ledger_entry_by_message_id(
+        &self,
+        message_id: &H256,
+    ) -> DbResult<Option<ProfitabilityLedgerEntry>>;
+
+    /// Store the profitability totals of messages sent to a destination
+    fn store_profitability_route_aggregate_by_destination(
+        &self,
+        destination: &u32,
+        aggregate: &ProfitabilityRouteAggregate,
+    ) -> DbResult<()>;
+
+    /// Retrieve the profitability totals of messages sent to a destination
+    fn retrieve_profitability_route_aggregate_by_destination(
+        &self,
+        destination: &u32,
+    ) -> DbResult<Option<ProfitabilityRouteAggregate>>;
+
+    /// Store the message whitelist and blacklist entries added at runtime
+    fn store_dynamic_message_filter(&self, filter: &DynamicMessageFilterData) -> DbResult<()>;
+
+    /// Retrieve the message whitelist and blacklist entries added at runtime
+    fn retrieve_dynamic_message_filter(&self) -> DbResult<Option<DynamicMessageFilterData>>;
+
+    /// Store what would have been submitted for a message in shadow mode
+    fn store_shadow_submission_by_message_id(
+        &self,
+        message_id: &H256,
+        submission: &ShadowSubmission,
+    ) -> DbResult<()>;
+
+    /// Retrieve what would have been submitted for a message in shadow mode
+    fn retrieve_shadow_submission_by_message_id(
+        &self,
+        message_id: &H256,
+    ) -> DbResult<Option<ShadowSubmission>>;
 }
@@ -1,16 +1,21 @@
 use async_trait::async_trait;
 use eyre::{bail, Result};
+use rocksdb::WriteBatch;
 use tracing::{debug, instrument, trace};
 
 use hyperlane_core::{
     identifiers::UniqueIdentifier, Decode, Encode, GasPaymentKey, HyperlaneDomain,
     HyperlaneLogStore, HyperlaneMessage, HyperlaneSequenceAwareIndexerStoreReader,
     HyperlaneWatermarkedLogStore, Indexed, InterchainGasExpenditure, InterchainGasPayment,
-    InterchainGasPaymentMeta, LogMeta, MerkleTreeInsertion, PendingOperationStatus, H256,
+    InterchainGasPaymentMeta, LogMeta, MerkleTreeInsertion, PendingOperationStatus, H160, H256,
 };
 
 use crate::db::{
-    storage_types::{InterchainGasExpenditureData, InterchainGasPaymentData},
+    storage_types::{
+        DynamicMessageFilterData, InterchainGasExpenditureData, InterchainGasPaymentData, Json,
+        ProfitabilityLedgerEntry, ProfitabilityRouteAggregate, ShadowSubmission,
+        ValidatorReliability, WebhookOutboxEntry,
+    },
     HyperlaneDb,
 };
 
@@ -38,6 +43,16 @@ const MERKLE_TREE_INSERTION_BLOCK_NUMBER_BY_LEAF_INDEX: &str =
     "merkle_tree_insertion_block_number_by_leaf_index_";
 const LATEST_INDEXED_GAS_PAYMENT_BLOCK: &str = "latest_indexed_gas_payment_block";
 const PAYLOAD_UUIDS_BY_MESSAGE_ID: &str = "payload_uuids_by_message_id_";
+const PROFITABILITY_LEDGER_ENTRY_BY_MESSAGE_ID: &str = "profitability_ledger_entry_by_message_id_";
+const PROFITABILITY_ROUTE_AGGREGATE_BY_DESTINATION: &str =
+    "profitability_route_aggregate_by_destination_";
+const PROFITABILITY_ROUTE_DESTINATIONS: &str = "profitability_route_destinations";
+const DYNAMIC_MESSAGE_FILTER: &str = "dynamic_message_filter";
+const SHADOW_SUBMISSION_BY_MESSAGE_ID: &str = "shadow_submission_by_message_id_";
+const WEBHOOK_OUTBOX_HEAD: &str = "webhook_outbox_head_";
+const WEBHOOK_OUTBOX_TAIL: &str = "webhook_outbox_tail_";
+const WEBHOOK_OUTBOX_ENTRY_BY_SEQUENCE: &str = "webhook_outbox_entry_by_sequence_";
+const VALIDATOR_RELIABILITY_BY_ADDRESS: &str = "validator_reliability_by_address_";
 
 /// Rocks DB result type
 pub type DbResult<T> = std::result::Result<T, DbError>;
@@ -294,6 +309,171 @@ impl HyperlaneRocksDB {
             }))
     }
 
+    /// Store the profitability ledger entry of a message, and update the
+    /// totals of the route the message was sent on.
+    ///
+    /// All writes are applied in one batch. Callers must serialize updates,
+    /// as the route totals are read before they are written.
+    pub fn process_profitability_ledger_entry(
+        &self,
+        message_id: &H256,
+        entry: &ProfitabilityLedgerEntry,
+    ) -> DbResult<()> {
+        let previous = self.retrieve_profitability_ledger_entry_by_message_id(message_id)?;
+        let existing_aggregate =
+            self.retrieve_profitability_route_aggregate_by_destination(&entry.destination)?;
+        let mut batch = WriteBatch::default();
+        if existing_aggregate.is_none() {
+            let mut destinations = self.retrieve_profitability_route_destinations()?;
+            destinations.push(entry.destination);
+            self.batch_store_encodable(
+                &mut batch,
+                "",
+                PROFITABILITY_ROUTE_DESTINATIONS,
+                &destinations,
+            );
+        }
+        let mut aggregate = existing_aggregate.unwrap_or_default();
+        aggregate.apply(previous.as_ref(), entry);
+
+        debug!(
+            ?message_id,
+            ?entry,
+            ?aggregate,
+            "Storing profitability ledger entry"
+        );
+        self.batch_store_encodable(
+            &mut batch,
+            PROFITABILITY_LEDGER_ENTRY_BY_MESSAGE_ID,
+            message_id.to_vec(),
+            &Json(entry),
+        );
+        self.batch_store_encodable(
+            &mut batch,
+            PROFITABILITY_ROUTE_AGGREGATE_BY_DESTINATION,
+            entry.destination.to_vec(),
+            &Json(aggregate),
+        );
+        self.write_batch(batch)?;
+        Ok(())
+    }
+
+    /// Retrieve the destinations of every route with profitability totals
+    pub fn retrieve_profitability_route_destinations(&self) -> DbResult<Vec<u32>> {
+        Ok(self
+            .retrieve_decodable("", PROFITABILITY_ROUTE_DESTINATIONS)?
+            .unwrap_or_default())
+    }
+
+    /// Store the checkpoint fetch reliability of a validator
+    pub fn store_validator_reliability(
+        &self,
+        validator: &H160,
+        reliability: &ValidatorReliability,
+    ) -> DbResult<()> {
+        self.store_value_by_key(
+            VALIDATOR_RELIABILITY_BY_ADDRESS,
+            validator,
+            &Json(reliability),
+        )
+    }
+
+    /// Retrieve the checkpoint fetch reliability of a validator
+    pub fn retrieve_validator_reliability(
+        &self,
+        validator: &H160,
+    ) -> DbResult<Option<ValidatorReliability>> {
+        self.retrieve_value_by_key(VALIDATOR_RELIABILITY_BY_ADDRESS, validator)
+            .map(|reliability| reliability.map(|Json(reliability)| reliability))
+    }
+
+    /// Append an entry to the webhook outbox of `sink`, returning its sequence.
+    ///
+    /// Every sink has its own outbox, a FIFO queue of sequences in
+    /// `[head, tail)`. Callers must serialize access to it.
+    pub fn push_webhook_outbox_entry(
+        &self,
+        sink: &str,
+        entry: &WebhookOutboxEntry,
+    ) -> DbResult<u64> {
+        let (_, tail) = self.webhook_outbox_bounds(sink)?;
+        let mut batch = WriteBatch::default();
+        self.batch_store_encodable(
+            &mut batch,
+            WEBHOOK_OUTBOX_ENTRY_BY_SEQUENCE,
+            webhook_outbox_entry_key(sink, tail),
+            &Json(entry),
+        );
+        self.batch_store_encodable(&mut batch, WEBHOOK_OUTBOX_TAIL, sink, &(tail + 1));
+        self.write_batch(batch)?;
+        Ok(tail)
+    }
+
+    /// Retrieve the oldest entry of the webhook outbox of `sink`, along with
+    /// its sequence
+    pub fn peek_webhook_outbox_entry(
+        &self,
+        sink: &str,
+    ) -> DbResult<Option<(u64, WebhookOutboxEntry)>> {
+        let (mut head, tail) = self.webhook_outbox_bounds(sink)?;
+        while head < tail {
+            if let Some(Json(entry)) = self.retrieve_decodable(
+                WEBHOOK_OUTBOX_ENTRY_BY_SEQUENCE,
+                webhook_outbox_entry_key(sink, head),
+            )? {
+                return Ok(Some((head, entry)));
+            }
+            head += 1;
+        }
+        Ok(None)
+    }
+
+    /// Replace the entry with `sequence` in the webhook outbox of `sink`
+    pub fn update_webhook_outbox_entry(
+        &self,
+        sink: &str,
+        sequence: u64,
+        entry: &WebhookOutboxEntry,
+    ) -> DbResult<()> {
+        self.store_encodable(
+            WEBHOOK_OUTBOX_ENTRY_BY_SEQUENCE,
+            webhook_outbox_entry_key(sink, sequence),
+            &Json(entry),
+        )
+    }
+
+    /// Remove the oldest entry of the webhook outbox of `sink`
+    pub fn pop_webhook_outbox_entry(&self, sink: &str) -> DbResult<Option<WebhookOutboxEntry>> {
+        let Some((sequence, entry)) = self.peek_webhook_outbox_entry(sink)? else {
+            return Ok(None);
+        };
+        let mut batch = WriteBatch::default();
+        self.batch_delete(
+            &mut batch,
+            WEBHOOK_OUTBOX_ENTRY_BY_SEQUENCE,
+            webhook_outbox_entry_key(sink, sequence),
+        );
+        self.batch_store_encodable(&mut batch, WEBHOOK_OUTBOX_HEAD, sink, &(sequence + 1));
+        self.write_batch(batch)?;
+        Ok(Some(entry))
+    }
+
+    /// Number of entries in the webhook outbox of `sink`
+    pub fn webhook_outbox_len(&self, sink: &str) -> DbResult<u64> {
+        let (head, tail) = self.webhook_outbox_bounds(sink)?;
+        Ok(tail.saturating_sub(head))
+    }
+
+    fn webhook_outbox_bounds(&self, sink: &str) -> DbResult<(u64, u64)> {
+        let head: u64 = self
+            .retrieve_decodable(WEBHOOK_OUTBOX_HEAD, sink)?
+            .unwrap_or_default();
+        let tail: u64 = self
+            .retrieve_decodable(WEBHOOK_OUTBOX_TAIL, sink)?
+            .unwrap_or_default();
+        Ok((head, tail))
+    }
+
     /// Retrieve the total gas payment for a message
     pub fn retrieve_gas_expenditure_by_message_id(
         &self,
@@ -696,6 +876,75 @@ impl HyperlaneDb for HyperlaneRocksDB {
     ) -> DbResult<Option<Vec<UniqueIdentifier>>> {
         self.retrieve_value_by_key(PAYLOAD_UUIDS_BY_MESSAGE_ID, message_id)
     }
+
+    fn store_profitability_ledger_entry_by_message_id(
+        &self,
+        message_id: &H256,
+        entry: &ProfitabilityLedgerEntry,
+    ) -> DbResult<()> {
+        self.store_value_by_key(
+            PROFITABILITY_LEDGER_ENTRY_BY_MESSAGE_ID,
+            message_id,
+            &Json(entry),
+        )
+    }
+
+    fn retrieve_profitability_ledger_entry_by_message_id(
+        &self,
+        message_id: &H256,
+    ) -> DbResult<Option<ProfitabilityLedgerEntry>> {
+        self.retrieve_value_by_key(PROFITABILITY_LEDGER_ENTRY_BY_MESSAGE_ID, message_id)
+            .map(|entry| entry.map(|Json(entry)| entry))
+    }
+
+    fn store_profitability_route_aggregate_by_destination(
+        &self,
+        destination: &u32,
+        aggregate: &ProfitabilityRouteAggregate,
+    ) -> DbResult<()> {
+        self.store_value_by_key(
+            PROFITABILITY_ROUTE_AGGREGATE_BY_DESTINATION,
+            destination,
+            &Json(aggregate),
+        )
+    }
+
+    fn retrieve_profitability_route_aggregate_by_destination(
+        &self,
+        destination: &u32,
+    ) -> DbResult<Option<ProfitabilityRouteAggregate>> {
+        self.retrieve_value_by_key(PROFITABILITY_ROUTE_AGGREGATE_BY_DESTINATION, destination)
+            .map(|aggregate| aggregate.map(|Json(aggregate)| aggregate))
+    }
+
+    fn store_dynamic_message_filter(&self, filter: &DynamicMessageFilterData) -> DbResult<()> {
+        self.store_encodable("", DYNAMIC_MESSAGE_FILTER, &Json(filter))
+    }
+
+    fn retrieve_dynamic_message_filter(&self) -> DbResult<Option<DynamicMessageFilterData>> {
+        self.retrieve_decodable("", DYNAMIC_MESSAGE_FILTER)
+            .map(|filter| filter.map(|Json(filter)| filter))
+    }
+
+    fn store_shadow_submission_by_message_id(
+        &self,
+        message_id: &H256,
+        submission: &ShadowSubmission,
+    ) -> DbResult<()> {
+        self.store_value_by_key(
+            SHADOW_SUBMISSION_BY_MESSAGE_ID,
+            message_id,
+            &Json(submission),
+        )
+    }
+
+    fn retrieve_shadow_submission_by_message_id(
+        &self,
+        message_id: &H256,
+    ) -> DbResult<Option<ShadowSubmission>> {
+        self.retrieve_value_by_key(SHADOW_SUBMISSION_BY_MESSAGE_ID, message_id)
+            .map(|submission| submission.map(|Json(submission)| submission))
+    }
 }
 
 impl HyperlaneRocksDB {
@@ -718,3 +967,9 @@ impl HyperlaneRocksDB {
         self.retrieve_decodable(prefix, key.to_vec())
     }
 }
+
+/// Key of an entry in the webhook outbox of `sink`. Sequences are fixed size, so
+/// the keys of different sinks cannot collide.
+fn webhook_outbox_entry_key(sink: &str, sequence: u64) -> Vec<u8> {
+    [sink.as_bytes(), &sequence.to_vec()[..]].concat()
+}
@@ -1,10 +1,11 @@
 use std::{path::Path, sync::Arc};
 
 use super::error::DbError;
-use rocksdb::{Options, DB as Rocks};
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB as Rocks};
 use tracing::info;
 
 pub use hyperlane_db::*;
+pub use slashing_protection_db::*;
 pub use typed_db::*;
 
 /// Shared functionality surrounding use of rocksdb
@@ -12,6 +13,8 @@ pub mod iterator;
 
 /// DB operations tied to specific Mailbox
 mod hyperlane_db;
+/// Validator slashing protection
+mod slashing_protection_db;
 /// Type-specific db operations
 mod typed_db;
 
@@ -74,4 +77,30 @@ impl DB {
     pub fn retrieve(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         Ok(self.0.get(key)?)
     }
+
+    /// Delete a value from the DB
+    pub fn delete(&self, key: &[u8]) -> Result<()> {
+        Ok(self.0.delete(key)?)
+    }
+
+    /// Apply all writes of a batch atomically
+    pub fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        Ok(self.0.write(batch)?)
+    }
+
+    /// Retrieve the values of all keys starting with `prefix`, in key order
+    pub fn retrieve_by_prefix(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut values = vec![];
+        for item in self
+            .0
+            .iterator(IteratorMode::From(prefix, Direction::Forward))
+        {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            values.push(value.into_vec());
+        }
+        Ok(values)
+    }
 }
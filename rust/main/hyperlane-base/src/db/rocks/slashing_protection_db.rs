@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use hyperlane_core::{
+    CheckpointWithMessageId, Decode, Encode, HyperlaneDomain, HyperlaneProtocolError, H160, H256,
+};
+
+use super::{DbError, DbResult, TypedDB, DB};
+
+const SIGNED_CHECKPOINT: &str = "slashing_protection_signed_checkpoint_";
+
+/// The version of the slashing protection interchange format written by
+/// `SlashingProtectionDb::export`
+pub const SLASHING_PROTECTION_INTERCHANGE_VERSION: u32 = 1;
+
+/// A checkpoint signed by a validator, as recorded for slashing protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedCheckpointRecord {
+    /// The validator that signed the checkpoint
+    pub validator: H160,
+    /// The merkle tree hook the checkpoint is for
+    pub merkle_tree_hook_address: H256,
+    /// The index of the checkpoint
+    pub index: u32,
+    /// The root of the checkpoint
+    pub root: H256,
+    /// The id of the message at the index of the checkpoint
+    pub message_id: H256,
+}
+
+impl SignedCheckpointRecord {
+    /// The record of `validator` signing `checkpoint`
+    pub fn new(validator: H160, checkpoint: &CheckpointWithMessageId) -> Self {
+        Self {
+            validator,
+            merkle_tree_hook_address: checkpoint.merkle_tree_hook_address,
+            index: checkpoint.index,
+            root: checkpoint.root,
+            message_id: checkpoint.message_id,
+        }
+    }
+
+    fn key(&self) -> Vec<u8> {
+        [
+            self.merkle_tree_hook_address.as_bytes(),
+            self.validator.as_bytes(),
+            &self.index.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Whether both records are for the same validator, merkle tree hook and
+    /// index, but with a different root or message id
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        self.key() == other.key() && self != other
+    }
+}
+
+impl Encode for SignedCheckpointRecord {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        Ok(self.validator.write_to(writer)?
+            + self.merkle_tree_hook_address.write_to(writer)?
+            + self.index.write_to(writer)?
+            + self.root.write_to(writer)?
+            + self.message_id.write_to(writer)?)
+    }
+}
+
+impl Decode for SignedCheckpointRecord {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        Ok(Self {
+            validator: H160::read_from(reader)?,
+            merkle_tree_hook_address: H256::read_from(reader)?,
+            index: u32::read_from(reader)?,
+            root: H256::read_from(reader)?,
+            message_id: H256::read_from(reader)?,
+        })
+    }
+}
+
+/// The signed checkpoints of a slashing protection database, in a format that
+/// can be moved to another host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlashingProtectionInterchange {
+    /// The version of the format
+    pub interchange_format_version: u32,
+    /// The domain of the origin mailbox the checkpoints were signed for
+    pub mailbox_domain: u32,
+    /// The signed checkpoints
+    pub signed_checkpoints: Vec<SignedCheckpointRecord>,
+}
+
+/// Errors of the slashing protection database
+#[derive(thiserror::Error, Debug)]
+pub enum SlashingProtectionError {
+    /// Signing the checkpoint would conflict with a previously signed one
+    #[error(
+        "Refusing to sign {attempted:?}, which conflicts with the previously signed {signed:?}"
+    )]
+    Conflict {
+        /// The previously signed checkpoint
+        signed: SignedCheckpointRecord,
+        /// The checkpoint that was attempted to be signed
+        attempted: SignedCheckpointRecord,
+    },
+    /// The interchange data can't be imported
+    #[error("Invalid slashing protection interchange: {0}")]
+    InvalidInterchange(String),
+    /// DB Error
+    #[error("{0}")]
+    DbError(#[from] DbError),
+}
+
+/// Records every checkpoint signed by a validator, so that it never signs
+/// a conflicting checkpoint at the same index, e.g. after being restarted
+/// against a faulty RPC or a reorged chain.
+///
+/// Keys --> Values:
+/// - `merkle tree hook address`, `validator`, `index` --> `signed checkpoint record`
+#[derive(Debug, Clone)]
+pub struct SlashingProtectionDb {
+    domain: HyperlaneDomain,
+    db: TypedDB,
+    // Makes checking and recording a signed checkpoint atomic
+    lock: Arc<Mutex<()>>,
+}
+
+impl SlashingProtectionDb {
+    /// Instantiate a new `SlashingProtectionDb`
+    pub fn new(domain: &HyperlaneDomain, db: DB) -> Self {
+        Self {
+            domain: domain.clone(),
+            db: TypedDB::new(domain, db),
+            lock: Default::default(),
+        }
+    }
+
+    /// Record that `validator` signs `checkpoint`, unless it already signed a
+    /// conflicting checkpoint. Must be called before signing.
+    pub fn check_and_record(
+        &self,
+        validator: H160,
+        checkpoint: &CheckpointWithMessageId,
+    ) -> Result<(), SlashingProtectionError> {
+        let attempted = SignedCheckpointRecord::new(validator, checkpoint);
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        match self.retrieve(&attempted)? {
+            Some(signed) if signed.conflicts_with(&attempted) => {
+                Err(SlashingProtectionError::Conflict { signed, attempted })
+            }
+            Some(_) => Ok(()),
+            None => Ok(self.store(&attempted)?),
+        }
+    }
+
+    /// Export every signed checkpoint
+    pub fn export(&self) -> DbResult<SlashingProtectionInterchange> {
+        Ok(SlashingProtectionInterchange {
+            interchange_format_version: SLASHING_PROTECTION_INTERCHANGE_VERSION,
+            mailbox_domain: self.domain.id(),
+            signed_checkpoints: self.db.retrieve_decodables_by_prefix(SIGNED_CHECKPOINT)?,
+        })
+    }
+
+    /// Import the signed checkpoints of `interchange`, returning how many were
+    /// not recorded yet. Nothing is imported if any of them conflicts with a
+    /// recorded one.
+    pub fn import(
+        &self,
+        interchange: &SlashingProtectionInterchange,
+    ) -> Result<usize, SlashingProtectionError> {
+        if interchange.interchange_format_version != SLASHING_PROTECTION_INTERCHANGE_VERSION {
+            return Err(SlashingProtectionError::InvalidInterchange(format!(
+                "unsupported interchange format version {}",
+                interchange.interchange_format_version
+            )));
+        }
+        if interchange.mailbox_domain != self.domain.id() {
+            return Err(SlashingProtectionError::InvalidInterchange(format!(
+                "expected checkpoints for domain {}, got domain {}",
+                self.domain.id(),
+                interchange.mailbox_domain
+            )));
+        }
+
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        let mut new_records: HashMap<Vec<u8>, SignedCheckpointRecord> = HashMap::new();
+        for record in &interchange.signed_checkpoints {
+            let key = record.key();
+            let signed = match new_records.get(&key) {
+                Some(signed) => Some(*signed),
+                None => self.retrieve(record)?,
+            };
+            match signed {
+                Some(signed) if signed.conflicts_with(record) => {
+                    return Err(SlashingProtectionError::Conflict {
+                        signed,
+                        attempted: *record,
+                    })
+                }
+                Some(_) => {}
+                None => {
+                    new_records.insert(key, *record);
+                }
+            }
+        }
+        for record in new_records.values() {
+            self.store(record)?;
+        }
+        Ok(new_records.len())
+    }
+
+    fn retrieve(
+        &self,
+        record: &SignedCheckpointRecord,
+    ) -> DbResult<Option<SignedCheckpointRecord>> {
+        self.db.retrieve_decodable(SIGNED_CHECKPOINT, record.key())
+    }
+
+    fn store(&self, record: &SignedCheckpointRecord) -> DbResult<()> {
+        self.db
+            .store_encodable(SIGNED_CHECKPOINT, record.key(), record)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::Checkpoint;
+
+    use super::*;
+    use crate::db::test_utils;
+
+    fn checkpoint(index: u32, root: H256) -> CheckpointWithMessageId {
+        CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::from_low_u64_be(1),
+                mailbox_domain: 1,
+                root,
+                index,
+            },
+            message_id: H256::from_low_u64_be(index as u64),
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_conflicting_checkpoints() {
+        test_utils::run_test_db(|db| async move {
+            let domain = HyperlaneDomain::new_test_domain("test_slashing_protection");
+            let slashing_protection = SlashingProtectionDb::new(&domain, db);
+            let validator = H160::from_low_u64_be(1);
+            let root = H256::from_low_u64_be(2);
+
+            slashing_protection
+                .check_and_record(validator, &checkpoint(0, root))
+                .unwrap();
+            // signing the same checkpoint again is fine
+            slashing_protection
+                .check_and_record(validator, &checkpoint(0, root))
+                .unwrap();
+            // as is another validator signing a different one at the same index
+            slashing_protection
+                .check_and_record(H160::from_low_u64_be(2), &checkpoint(0, H256::zero()))
+                .unwrap();
+
+            let err = slashing_protection
+                .check_and_record(validator, &checkpoint(0, H256::zero()))
+                .unwrap_err();
+            assert!(matches!(err, SlashingProtectionError::Conflict { .. }));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn exports_and_imports_signed_checkpoints() {
+        let domain = HyperlaneDomain::new_test_domain("test_slashing_protection");
+        let validator = H160::from_low_u64_be(1);
+        let root = H256::from_low_u64_be(2);
+
+        let mut exported = None;
+        test_utils::run_test_db(|db| async {
+            let slashing_protection = SlashingProtectionDb::new(&domain, db);
+            for index in 0..3 {
+                slashing_protection
+                    .check_and_record(validator, &checkpoint(index, root))
+                    .unwrap();
+            }
+            exported = Some(slashing_protection.export().unwrap());
+        })
+        .await;
+        let exported = exported.unwrap();
+        assert_eq!(exported.signed_checkpoints.len(), 3);
+
+        // round trip through the JSON interchange format
+        let exported: SlashingProtectionInterchange =
+            serde_json::from_str(&serde_json::to_string(&exported).unwrap()).unwrap();
+
+        test_utils::run_test_db(|db| async {
+            let slashing_protection = SlashingProtectionDb::new(&domain, db);
+            slashing_protection
+                .check_and_record(validator, &checkpoint(0, root))
+                .unwrap();
+            assert_eq!(slashing_protection.import(&exported).unwrap(), 2);
+            assert_eq!(slashing_protection.export().unwrap(), exported);
+
+            let err = slashing_protection
+                .check_and_record(validator, &checkpoint(2, H256::zero()))
+                .unwrap_err();
+            assert!(matches!(err, SlashingProtectionError::Conflict { .. }));
+
+            let mut conflicting = exported.clone();
+            conflicting.signed_checkpoints[1].root = H256::zero();
+            assert!(matches!(
+                slashing_protection.import(&conflicting),
+                Err(SlashingProtectionError::Conflict { .. })
+            ));
+        })
+        .await;
+    }
+}
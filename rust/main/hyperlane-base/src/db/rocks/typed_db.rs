@@ -1,4 +1,5 @@
 use hyperlane_core::{Decode, Encode, HyperlaneDomain};
+use rocksdb::WriteBatch;
 
 use crate::db::{error::DbError, DB};
 
@@ -67,6 +68,18 @@ impl TypedDB {
             .map_err(Into::into)
     }
 
+    /// Retrieve all decodable values under a prefix, in key order
+    pub fn retrieve_decodables_by_prefix<V: Decode>(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<Vec<V>> {
+        self.db
+            .retrieve_by_prefix(&self.prefixed_key(prefix.as_ref(), &[]))?
+            .into_iter()
+            .map(|v| V::read_from(&mut v.as_slice()).map_err(Into::into))
+            .collect()
+    }
+
     /// Store encodable kv pair
     pub fn store_keyed_encodable<K: Encode, V: Encode>(
         &self,
@@ -85,4 +98,39 @@ impl TypedDB {
     ) -> Result<Option<V>> {
         self.retrieve_decodable(prefix, key.to_vec())
     }
+
+    /// Delete value given encodable key
+    pub fn delete_keyed<K: Encode>(&self, prefix: impl AsRef<[u8]>, key: &K) -> Result<()> {
+        self.db
+            .delete(&self.prefixed_key(prefix.as_ref(), &key.to_vec()))
+    }
+
+    /// Add storing an encodable value to `batch`
+    pub fn batch_store_encodable<V: Encode>(
+        &self,
+        batch: &mut WriteBatch,
+        prefix: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+        value: &V,
+    ) {
+        batch.put(
+            self.prefixed_key(prefix.as_ref(), key.as_ref()),
+            value.to_vec(),
+        );
+    }
+
+    /// Add deleting a value to `batch`
+    pub fn batch_delete(
+        &self,
+        batch: &mut WriteBatch,
+        prefix: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+    ) {
+        batch.delete(self.prefixed_key(prefix.as_ref(), key.as_ref()));
+    }
+
+    /// Apply all writes of `batch` atomically
+    pub fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        self.db.write_batch(batch)
+    }
 }
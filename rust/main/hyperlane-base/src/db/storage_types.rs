@@ -2,8 +2,9 @@ use std::io::{Read, Write};
 
 use hyperlane_core::{
     Decode, Encode, HyperlaneProtocolError, InterchainGasExpenditure, InterchainGasPayment, H256,
-    U256,
+    H512, U256,
 };
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// Subset of `InterchainGasPayment` excluding the message id which is stored in
 /// the key.
@@ -126,3 +127,179 @@ impl Decode for InterchainGasExpenditureData {
         })
     }
 }
+
+/// What was paid on the origin and spent on the destination to relay a
+/// message, used to reconcile relaying costs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProfitabilityLedgerEntry {
+    /// Origin domain of the message
+    pub origin: u32,
+    /// Destination domain of the message
+    pub destination: u32,
+    /// Total amount of origin native tokens paid to the IGP
+    pub payment: U256,
+    /// Total amount of destination gas paid for
+    pub gas_amount: U256,
+    /// Total amount of destination gas used
+    pub gas_used: U256,
+    /// Total amount of destination native tokens spent
+    pub tokens_used: U256,
+    /// Ids of the destination transactions submitted for the message
+    pub transaction_ids: Vec<H512>,
+    /// Price of the origin native token used to value the payment, if any
+    pub origin_token_price: Option<String>,
+    /// Price of the destination native token used to value the expenditure,
+    /// if any
+    pub destination_token_price: Option<String>,
+    /// Unix timestamp of the last update, in seconds
+    pub updated_at: u64,
+}
+
+/// Totals of the `ProfitabilityLedgerEntry`s of all messages relayed
+/// between an origin and a destination.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProfitabilityRouteAggregate {
+    /// Number of messages with a ledger entry
+    pub messages: u64,
+    /// Total amount of origin native tokens paid to the IGP
+    pub payment: U256,
+    /// Total amount of destination gas used
+    pub gas_used: U256,
+    /// Total amount of destination native tokens spent
+    pub tokens_used: U256,
+}
+
+impl ProfitabilityRouteAggregate {
+    /// Replace the contribution of `previous` (if any) with that of `entry`.
+    pub fn apply(
+        &mut self,
+        previous: Option<&ProfitabilityLedgerEntry>,
+        entry: &ProfitabilityLedgerEntry,
+    ) {
+        match previous {
+            Some(previous) => {
+                self.payment = self.payment.saturating_sub(previous.payment);
+                self.gas_used = self.gas_used.saturating_sub(previous.gas_used);
+                self.tokens_used = self.tokens_used.saturating_sub(previous.tokens_used);
+            }
+            None => self.messages += 1,
+        }
+        self.payment = self.payment.saturating_add(entry.payment);
+        self.gas_used = self.gas_used.saturating_add(entry.gas_used);
+        self.tokens_used = self.tokens_used.saturating_add(entry.tokens_used);
+    }
+}
+
+/// Message whitelist and blacklist entries that were added at runtime, on top
+/// of the ones in the agent config. Entries are kept as raw JSON since their
+/// shape is owned by the agent using them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DynamicMessageFilterData {
+    /// Entries added to the message whitelist
+    pub whitelist: Vec<serde_json::Value>,
+    /// Entries added to the message blacklist
+    pub blacklist: Vec<serde_json::Value>,
+}
+
+/// What a relayer running in shadow mode would have submitted to deliver a
+/// message.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ShadowSubmission {
+    /// Destination domain of the message
+    pub destination: u32,
+    /// ISM metadata that would have been submitted
+    pub metadata: Vec<u8>,
+    /// Calldata of the `process` transaction, if it could be built
+    pub calldata: Option<Vec<u8>>,
+    /// Gas limit the transaction would have been submitted with
+    pub gas_limit: U256,
+    /// Estimated gas price of the transaction
+    pub gas_price: String,
+    /// Estimated L2 gas limit of the transaction, for chains that have one
+    pub l2_gas_limit: Option<U256>,
+    /// Unix timestamp of when the message was prepared, in seconds
+    pub prepared_at: u64,
+}
+
+/// A webhook request waiting to be delivered
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WebhookOutboxEntry {
+    /// Name of the webhook the request is sent to
+    pub sink: String,
+    /// JSON body of the request
+    pub body: String,
+    /// Number of failed delivery attempts so far
+    pub attempts: u32,
+    /// Unix timestamp before which delivery should not be attempted, in seconds
+    pub next_attempt_at: u64,
+}
+
+/// How reliably a validator served signed checkpoints to the relayer
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ValidatorReliability {
+    /// Number of checkpoint fetches that returned a valid signed checkpoint
+    pub successes: u64,
+    /// Number of checkpoint fetches that failed or returned nothing usable
+    pub failures: u64,
+    /// Exponentially weighted moving average of the fetch latency, in milliseconds
+    pub avg_latency_ms: f64,
+    /// Latest checkpoint index the validator reported
+    pub latest_index: Option<u32>,
+}
+
+impl ValidatorReliability {
+    /// Weight given to the newest latency sample in the moving average
+    const LATENCY_SMOOTHING: f64 = 0.2;
+
+    /// Record the outcome of a checkpoint fetch
+    pub fn record_fetch(&mut self, success: bool, latency_ms: f64) {
+        if self.successes + self.failures == 0 {
+            self.avg_latency_ms = latency_ms;
+        } else {
+            self.avg_latency_ms += Self::LATENCY_SMOOTHING * (latency_ms - self.avg_latency_ms);
+        }
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    /// Ratio of successful checkpoint fetches, or `None` if nothing was fetched yet
+    pub fn success_ratio(&self) -> Option<f64> {
+        let total = self.successes + self.failures;
+        (total > 0).then(|| self.successes as f64 / total as f64)
+    }
+}
+
+/// Stores the wrapped value as JSON, for types that don't implement the
+/// encoding manually
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Json<T>(pub T);
+
+impl<T: Serialize> Encode for Json<T> {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: Write,
+    {
+        let serialized = serde_json::to_vec(&self.0)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Failed to serialize"))?;
+        writer.write_all(&serialized)?;
+        Ok(serialized.len())
+    }
+}
+
+impl<T: DeserializeOwned> Decode for Json<T> {
+    fn read_from<R>(reader: &mut R) -> Result<Self, HyperlaneProtocolError>
+    where
+        R: Read,
+        Self: Sized,
+    {
+        serde_json::from_reader(reader).map(Self).map_err(|err| {
+            HyperlaneProtocolError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to deserialize. Error: {}", err),
+            ))
+        })
+    }
+}
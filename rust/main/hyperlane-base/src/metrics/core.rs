@@ -17,6 +17,7 @@ use hyperlane_core::{HyperlaneDomain, H160};
 use hyperlane_metric::prometheus_metric::PrometheusClientMetrics;
 
 use crate::cache::MeteredCacheMetrics;
+use crate::db::ValidatorReliability;
 use crate::metrics::{
     cache::create_cache_metrics, json_rpc_client::create_json_rpc_client_metrics,
     provider::create_provider_metrics,
@@ -54,10 +55,12 @@ pub struct CoreMetrics {
     messages_processed_count: IntCounterVec,
 
     latest_checkpoint: IntGaugeVec,
+    checkpoint_syncer_mirror_lag: IntGaugeVec,
 
     announced: IntGaugeVec,
     backfill_complete: IntGaugeVec,
     reached_initial_consistency: IntGaugeVec,
+    halted: IntGaugeVec,
 
     // metadata building metrics
     metadata_build_count: IntCounterVec,
@@ -66,6 +69,10 @@ pub struct CoreMetrics {
     // ism building metrics
     ism_build_count: IntCounterVec,
 
+    // ccip read gateway metrics
+    ccip_read_gateway_request_count: IntCounterVec,
+    ccip_read_gateway_request_duration: CounterVec,
+
     /// Chain initialization metrics
     chain_init_latency: IntGaugeVec,
 
@@ -202,6 +209,26 @@ impl CoreMetrics {
             registry
         )?;
 
+        let validator_checkpoint_fetch_success_ratio = register_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("validator_checkpoint_fetch_success_ratio"),
+                "Ratio of signed checkpoint fetches per validator that returned a valid checkpoint, from the perspective of the relayer",
+                const_labels_ref
+            ),
+            &["origin", "validator"],
+            registry
+        )?;
+
+        let validator_checkpoint_fetch_latency_ms = register_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("validator_checkpoint_fetch_latency_ms"),
+                "Moving average of the signed checkpoint fetch latency per validator, in milliseconds",
+                const_labels_ref
+            ),
+            &["origin", "validator"],
+            registry
+        )?;
+
         let submitter_queue_length = register_int_gauge_vec_with_registry!(
             opts!(
                 namespaced!("submitter_queue_length"),
@@ -222,6 +249,16 @@ impl CoreMetrics {
             registry
         )?;
 
+        let checkpoint_syncer_mirror_lag = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("checkpoint_syncer_mirror_lag"),
+                "Number of checkpoints a mirrored checkpoint syncer backend is missing",
+                const_labels_ref
+            ),
+            &["location"],
+            registry
+        )?;
+
         let announced = register_int_gauge_vec_with_registry!(
             opts!(
                 namespaced!("announced"),
@@ -252,6 +289,16 @@ impl CoreMetrics {
             registry
         )?;
 
+        let halted = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("halted"),
+                "Whether the validator stopped validating the chain, e.g. after a reorg",
+                const_labels_ref
+            ),
+            &["chain"],
+            registry
+        )?;
+
         let operations_processed_count = register_int_counter_vec_with_registry!(
             opts!(
                 namespaced!("operations_processed_count"),
@@ -302,6 +349,26 @@ impl CoreMetrics {
             registry
         )?;
 
+        let ccip_read_gateway_request_count = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("ccip_read_gateway_request_count"),
+                "Total number of requests made to CCIP read gateways",
+                const_labels_ref
+            ),
+            &["host", "status"],
+            registry
+        )?;
+
+        let ccip_read_gateway_request_duration = register_counter_vec_with_registry!(
+            opts!(
+                namespaced!("ccip_read_gateway_request_duration"),
+                "Duration of requests made to CCIP read gateways, in seconds",
+                const_labels_ref
+            ),
+            &["host", "status"],
+            registry
+        )?;
+
         let chain_init_latency = register_int_gauge_vec_with_registry!(
             opts!(
                 namespaced!("chain_init_latency"),
@@ -335,16 +402,21 @@ impl CoreMetrics {
             messages_processed_count,
 
             latest_checkpoint,
+            checkpoint_syncer_mirror_lag,
 
             announced,
             backfill_complete,
             reached_initial_consistency,
+            halted,
 
             metadata_build_count,
             metadata_build_duration,
 
             ism_build_count,
 
+            ccip_read_gateway_request_count,
+            ccip_read_gateway_request_duration,
+
             chain_init_latency,
 
             client_metrics: OnceLock::new(),
@@ -353,6 +425,8 @@ impl CoreMetrics {
 
             validator_metrics: ValidatorObservabilityMetricManager::new(
                 observed_validator_latest_index.clone(),
+                validator_checkpoint_fetch_success_ratio,
+                validator_checkpoint_fetch_latency_ms,
             ),
         })
     }
@@ -538,6 +612,15 @@ impl CoreMetrics {
         self.latest_checkpoint.clone()
     }
 
+    /// Number of checkpoints a backend of a mirrored checkpoint syncer is
+    /// missing, until it is backfilled.
+    ///
+    /// Labels:
+    /// - `location`: Announcement location of the backend.
+    pub fn checkpoint_syncer_mirror_lag(&self) -> IntGaugeVec {
+        self.checkpoint_syncer_mirror_lag.clone()
+    }
+
     /// Set the validator to be announced
     ///
     /// Labels:
@@ -573,6 +656,25 @@ impl CoreMetrics {
         self.reached_initial_consistency.clone()
     }
 
+    /// Set the validator to have stopped validating a chain
+    ///
+    /// Labels:
+    /// - `chain`: Chain the validator stopped validating.
+    pub fn set_halted(&self, origin_chain: HyperlaneDomain) {
+        self.halted
+            .clone()
+            .with_label_values(&[origin_chain.name()])
+            .set(1);
+    }
+
+    /// Whether the validator stopped validating a chain.
+    ///
+    /// Labels:
+    /// - `chain`: Chain the validator stopped validating.
+    pub fn halted(&self) -> IntGaugeVec {
+        self.halted.clone()
+    }
+
     /// Measure of the queue lengths in Submitter instances
     ///
     /// Labels:
@@ -683,6 +785,26 @@ impl CoreMetrics {
         self.ism_build_count.clone()
     }
 
+    /// The number of requests made to CCIP read gateways by this process
+    /// during its lifetime.
+    ///
+    /// Labels:
+    /// - `host`: Host of the gateway URL.
+    /// - `status`: `success`, `client_error`, `invalid_response` or `unavailable`
+    pub fn ccip_read_gateway_request_count(&self) -> IntCounterVec {
+        self.ccip_read_gateway_request_count.clone()
+    }
+
+    /// The durations of requests made to CCIP read gateways by this process
+    /// during its lifetime, in seconds.
+    ///
+    /// Labels:
+    /// - `host`: Host of the gateway URL.
+    /// - `status`: `success`, `client_error`, `invalid_response` or `unavailable`
+    pub fn ccip_read_gateway_request_duration(&self) -> CounterVec {
+        self.ccip_read_gateway_request_duration.clone()
+    }
+
     /// The latency of chain initialization in milliseconds.
     ///
     /// Labels:
@@ -741,6 +863,14 @@ impl CoreMetrics {
             .get();
         observed_checkpoint - signed_checkpoint
     }
+
+    /// Whether the validator stopped validating `origin_chain`
+    pub fn is_validator_halted(&self, origin_chain: &HyperlaneDomain) -> bool {
+        self.halted()
+            .with_label_values(&[origin_chain.name()])
+            .get()
+            > 0
+    }
 }
 
 impl Debug for CoreMetrics {
@@ -767,6 +897,8 @@ const MIN_VALIDATOR_METRIC_RESET_PERIOD: time::Duration = time::Duration::from_s
 /// Manages metrics for observing sets of validators.
 pub struct ValidatorObservabilityMetricManager {
     observed_validator_latest_index: IntGaugeVec,
+    validator_checkpoint_fetch_success_ratio: GaugeVec,
+    validator_checkpoint_fetch_latency_ms: GaugeVec,
 
     // AppContextKey -> Validator -> Last updated at
     // Used to track the last time a validator was updated in the metrics, allowing
@@ -776,9 +908,15 @@ pub struct ValidatorObservabilityMetricManager {
 }
 
 impl ValidatorObservabilityMetricManager {
-    fn new(observed_validator_latest_index: IntGaugeVec) -> Self {
+    fn new(
+        observed_validator_latest_index: IntGaugeVec,
+        validator_checkpoint_fetch_success_ratio: GaugeVec,
+        validator_checkpoint_fetch_latency_ms: GaugeVec,
+    ) -> Self {
         Self {
             observed_validator_latest_index,
+            validator_checkpoint_fetch_success_ratio,
+            validator_checkpoint_fetch_latency_ms,
             app_context_validators: RwLock::new(HashMap::new()),
         }
     }
@@ -848,6 +986,25 @@ impl ValidatorObservabilityMetricManager {
         Ok(())
     }
 
+    /// Updates the checkpoint fetch reliability metrics of a validator.
+    pub fn set_validator_reliability(
+        &self,
+        origin: &HyperlaneDomain,
+        validator: &H160,
+        reliability: &ValidatorReliability,
+    ) {
+        let validator = format!("0x{:x}", validator).to_lowercase();
+        let labels = [origin.as_ref(), validator.as_str()];
+        if let Some(success_ratio) = reliability.success_ratio() {
+            self.validator_checkpoint_fetch_success_ratio
+                .with_label_values(&labels)
+                .set(success_ratio);
+        }
+        self.validator_checkpoint_fetch_latency_ms
+            .with_label_values(&labels)
+            .set(reliability.avg_latency_ms);
+    }
+
     /// Gauge for reporting recently observed latest checkpoint indices for validator sets.
     /// The entire set for an app context should be updated at once, and it should be updated
     /// in a way that is robust to validator set changes.
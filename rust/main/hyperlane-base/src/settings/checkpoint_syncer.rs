@@ -1,14 +1,15 @@
 use crate::{
-    CheckpointSyncer, GcsStorageClientBuilder, LocalStorage, S3Storage, GCS_SERVICE_ACCOUNT_KEY,
-    GCS_USER_SECRET,
+    AzureStorage, CheckpointSyncer, GcsStorageClientBuilder, HttpStorage, LocalStorage,
+    MirroredStorage, S3Storage, GCS_SERVICE_ACCOUNT_KEY, GCS_USER_SECRET,
 };
 use aws_config::Region;
 use core::str::FromStr;
 use eyre::{eyre, Context, Report, Result};
 use hyperlane_core::{ChainCommunicationError, ReorgEvent};
-use prometheus::IntGauge;
+use prometheus::{IntGauge, IntGaugeVec};
 use std::{env, path::PathBuf};
 use tracing::error;
+use url::{Host, Url};
 use ya_gcp::{AuthFlow, ServiceAccountAuth};
 
 /// Checkpoint Syncer types
@@ -40,6 +41,29 @@ pub enum CheckpointSyncerConf {
         /// `gcloud auth application-default login`
         user_secrets: Option<String>,
     },
+    /// A checkpoint syncer on Azure Blob Storage
+    Azure {
+        /// Storage account name
+        account: String,
+        /// Container name
+        container: String,
+        /// Folder name inside container - defaults to the root of the container
+        folder: Option<String>,
+        /// Blob service endpoint, e.g. of a local emulator - defaults to the
+        /// `AZURE_STORAGE_BLOB_ENDPOINT` env var, or the public endpoint of the account
+        endpoint: Option<Url>,
+    },
+    /// A read-only checkpoint syncer over HTTP(S)
+    Http {
+        /// URL the checkpoints are published under
+        base_url: Url,
+    },
+    /// A checkpoint syncer writing to several others, so that checkpoints
+    /// stay available while one of them is down
+    Mirrored {
+        /// The mirrored syncers, reads prefer them in this order
+        syncers: Vec<CheckpointSyncerConf>,
+    },
 }
 
 /// Checkpoint Syncer errors
@@ -70,7 +94,7 @@ impl FromStr for CheckpointSyncerConf {
                 let url_components = suffix.split('/').collect::<Vec<&str>>();
                 let (bucket, region, folder): (&str, &str, Option<String>) = match url_components.len() {
                     2 => Ok((url_components[0], url_components[1], None)),
-                    3 .. => Ok((url_components[0], url_components[1], Some(url_components[2..].join("/")))),
+                    3.. => Ok((url_components[0], url_components[1], Some(url_components[2..].join("/")))),
                     _ => Err(eyre!("Error parsing storage location; could not split bucket, region and folder ({suffix})"))
                 }?;
                 Ok(CheckpointSyncerConf::S3 {
@@ -98,7 +122,7 @@ impl FromStr for CheckpointSyncerConf {
                 let url_components = suffix.split('/').collect::<Vec<&str>>();
                 let (bucket, folder): (&str, Option<String>) = match url_components.len() {
                     2 => Ok((url_components[0], None)),
-                    3 => Ok((url_components[0], Some(url_components[1].to_owned()))),
+                    3.. => Ok((url_components[0], Some(url_components[1..url_components.len() - 1].join("/")))),
                     _ => Err(eyre!("Error parsing storage location; could not split bucket and folder ({suffix})"))
                 }?;
                 match folder {
@@ -116,18 +140,139 @@ impl FromStr for CheckpointSyncerConf {
                     }),
                 }
             }
+            // the access key for writes is read from the environment when building the syncer
+            "azure" => {
+                let url_components = suffix.split('/').collect::<Vec<&str>>();
+                let (account, container, folder): (&str, &str, Option<String>) = match url_components.len() {
+                    2 => Ok((url_components[0], url_components[1], None)),
+                    3.. => Ok((url_components[0], url_components[1], Some(url_components[2..].join("/")))),
+                    _ => Err(eyre!("Error parsing storage location; could not split account, container and folder ({suffix})"))
+                }?;
+                Ok(CheckpointSyncerConf::Azure {
+                    account: account.into(),
+                    container: container.into(),
+                    folder,
+                    endpoint: None,
+                })
+            }
+            "http" | "https" => {
+                let base_url =
+                    Url::parse(s).context("Invalid url when parsing storage location")?;
+                // Like CCIP read gateways, announced locations must not point agents at local hosts
+                if is_local_host(&base_url) {
+                    return Err(eyre!(
+                        "Storage location points at a local or private host ({base_url})"
+                    ));
+                }
+                Ok(CheckpointSyncerConf::Http { base_url })
+            }
             _ => Err(eyre!("Unknown storage location prefix `{prefix}`")),
         }
     }
 }
 
+/// Whether `url` points at the local machine or a private network
+fn is_local_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain == "localhost"
+                || [".localhost", ".local", ".internal"]
+                    .iter()
+                    .any(|suffix| domain.ends_with(suffix))
+        }
+        Some(Host::Ipv4(ip)) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+        }
+        Some(Host::Ipv6(ip)) => {
+            let first_segment = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local and link local addresses
+                || (first_segment & 0xfe00) == 0xfc00
+                || (first_segment & 0xffc0) == 0xfe80
+                || ip.to_ipv4_mapped().is_some_and(|ip| {
+                    ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+                })
+        }
+        None => true,
+    }
+}
+
 impl CheckpointSyncerConf {
+    /// The conf of a syncer writing under the `prefix` folder of this one's
+    /// storage, e.g. to keep the checkpoints of each origin of a validator apart
+    pub fn with_prefix(&self, prefix: &str) -> Self {
+        let prefixed_folder = |folder: &Option<String>| match folder.as_deref() {
+            None | Some("") => Some(prefix.to_owned()),
+            Some(folder) => Some(format!("{}/{prefix}", folder.trim_end_matches('/'))),
+        };
+        match self {
+            CheckpointSyncerConf::LocalStorage { path } => CheckpointSyncerConf::LocalStorage {
+                path: path.join(prefix),
+            },
+            CheckpointSyncerConf::S3 {
+                bucket,
+                folder,
+                region,
+            } => CheckpointSyncerConf::S3 {
+                bucket: bucket.clone(),
+                folder: prefixed_folder(folder),
+                region: region.clone(),
+            },
+            CheckpointSyncerConf::Gcs {
+                bucket,
+                folder,
+                service_account_key,
+                user_secrets,
+            } => CheckpointSyncerConf::Gcs {
+                bucket: bucket.clone(),
+                folder: prefixed_folder(folder),
+                service_account_key: service_account_key.clone(),
+                user_secrets: user_secrets.clone(),
+            },
+            CheckpointSyncerConf::Azure {
+                account,
+                container,
+                folder,
+                endpoint,
+            } => CheckpointSyncerConf::Azure {
+                account: account.clone(),
+                container: container.clone(),
+                folder: prefixed_folder(folder),
+                endpoint: endpoint.clone(),
+            },
+            CheckpointSyncerConf::Http { base_url } => {
+                let mut base_url = base_url.clone();
+                if let Ok(mut segments) = base_url.path_segments_mut() {
+                    segments.pop_if_empty().push(prefix);
+                }
+                CheckpointSyncerConf::Http { base_url }
+            }
+            CheckpointSyncerConf::Mirrored { syncers } => CheckpointSyncerConf::Mirrored {
+                syncers: syncers
+                    .iter()
+                    .map(|conf| conf.with_prefix(prefix))
+                    .collect(),
+            },
+        }
+    }
+
     /// Turn conf info a Checkpoint Syncer
+    ///
+    /// `mirror_lag_gauge` tracks how many checkpoints each backend of a
+    /// mirrored syncer is missing, labelled by the backend's location.
     pub async fn build_and_validate(
         &self,
         latest_index_gauge: Option<IntGauge>,
+        mirror_lag_gauge: Option<IntGaugeVec>,
     ) -> Result<Box<dyn CheckpointSyncer>, CheckpointSyncerBuildError> {
-        let syncer: Box<dyn CheckpointSyncer> = self.build(latest_index_gauge).await?;
+        let syncer: Box<dyn CheckpointSyncer> =
+            self.build(latest_index_gauge, mirror_lag_gauge).await?;
 
         match syncer.reorg_status().await {
             Ok(Some(reorg_event)) => {
@@ -148,6 +293,7 @@ impl CheckpointSyncerConf {
     async fn build(
         &self,
         latest_index_gauge: Option<IntGauge>,
+        mirror_lag_gauge: Option<IntGaugeVec>,
     ) -> Result<Box<dyn CheckpointSyncer>, Report> {
         Ok(match self {
             CheckpointSyncerConf::LocalStorage { path } => {
@@ -184,6 +330,30 @@ impl CheckpointSyncerConf {
                         .await?,
                 )
             }
+            CheckpointSyncerConf::Azure {
+                account,
+                container,
+                folder,
+                endpoint,
+            } => Box::new(AzureStorage::from_env(
+                account.clone(),
+                container.clone(),
+                folder.clone(),
+                endpoint.clone(),
+                latest_index_gauge,
+            )?),
+            CheckpointSyncerConf::Http { base_url } => {
+                Box::new(HttpStorage::new(base_url.clone(), latest_index_gauge)?)
+            }
+            CheckpointSyncerConf::Mirrored { syncers } => {
+                let mut backends = Vec::with_capacity(syncers.len());
+                for conf in syncers {
+                    backends.push(Box::pin(conf.build(None, mirror_lag_gauge.clone())).await?);
+                }
+                let storage = MirroredStorage::new(backends, latest_index_gauge, mirror_lag_gauge)?;
+                storage.detect_lag().await;
+                Box::new(storage)
+            }
         })
     }
 }
@@ -222,7 +392,7 @@ mod test {
         // then `drop` it, to simulate a restart
         {
             let checkpoint_syncer = checkpoint_syncer_conf
-                .build_and_validate(None)
+                .build_and_validate(None, None)
                 .await
                 .unwrap();
 
@@ -233,7 +403,7 @@ mod test {
         }
 
         // Initialize a new checkpoint syncer and expect it to panic due to the reorg event.
-        let result = checkpoint_syncer_conf.build_and_validate(None).await;
+        let result = checkpoint_syncer_conf.build_and_validate(None, None).await;
         match result {
             Err(CheckpointSyncerBuildError::ReorgEvent(e)) => {
                 assert_eq!(e, dummy_reorg_event, "Reported reorg event doesn't match");
@@ -241,4 +411,115 @@ mod test {
             _ => panic!("Expected a reorg event error"),
         }
     }
+
+    #[test]
+    fn test_parse_azure_storage_location() {
+        use super::*;
+
+        let conf = CheckpointSyncerConf::from_str("azure://account/container/a/b").unwrap();
+        match conf {
+            CheckpointSyncerConf::Azure {
+                account,
+                container,
+                folder,
+                endpoint,
+            } => {
+                assert_eq!(account, "account");
+                assert_eq!(container, "container");
+                assert_eq!(folder.as_deref(), Some("a/b"));
+                assert_eq!(endpoint, None);
+            }
+            _ => panic!("Expected an Azure checkpoint syncer config"),
+        }
+        assert!(matches!(
+            CheckpointSyncerConf::from_str("azure://account/container").unwrap(),
+            CheckpointSyncerConf::Azure { folder: None, .. }
+        ));
+        assert!(CheckpointSyncerConf::from_str("azure://account").is_err());
+    }
+
+    #[test]
+    fn test_parse_http_storage_location() {
+        use super::*;
+
+        let conf =
+            CheckpointSyncerConf::from_str("https://checkpoints.example.com/validator").unwrap();
+        match conf {
+            CheckpointSyncerConf::Http { base_url } => {
+                assert_eq!(
+                    base_url.as_str(),
+                    "https://checkpoints.example.com/validator"
+                );
+            }
+            _ => panic!("Expected an HTTP checkpoint syncer config"),
+        }
+        assert!(CheckpointSyncerConf::from_str("https://").is_err());
+
+        for local in [
+            "http://localhost:8080/validator",
+            "https://checkpoints.internal/validator",
+            "http://printer.local",
+            "http://127.0.0.1/validator",
+            "http://10.0.0.1",
+            "http://192.168.1.1",
+            "http://169.254.169.254/latest/meta-data",
+            "http://0.0.0.0",
+            "http://[::1]:8080",
+            "http://[fd00::1]",
+            "http://[::ffff:127.0.0.1]",
+        ] {
+            assert!(
+                CheckpointSyncerConf::from_str(local).is_err(),
+                "Expected {local} to be rejected"
+            );
+        }
+        assert!(CheckpointSyncerConf::from_str("https://1.1.1.1/validator").is_ok());
+    }
+
+    #[test]
+    fn test_parse_nested_gcs_storage_location() {
+        use super::*;
+
+        let conf = CheckpointSyncerConf::from_str("gs://bucket/a/b/announcement.json").unwrap();
+        assert!(matches!(
+            conf,
+            CheckpointSyncerConf::Gcs { bucket, folder: Some(folder), .. }
+                if bucket == "bucket" && folder == "a/b"
+        ));
+    }
+
+    #[test]
+    fn test_with_prefix() {
+        use super::*;
+
+        let conf = CheckpointSyncerConf::Mirrored {
+            syncers: vec![
+                CheckpointSyncerConf::from_str("s3://bucket/us-east-1").unwrap(),
+                CheckpointSyncerConf::from_str("azure://account/container/folder").unwrap(),
+                CheckpointSyncerConf::from_str("file:///tmp/checkpoints").unwrap(),
+                CheckpointSyncerConf::from_str("https://checkpoints.example.com/validator/")
+                    .unwrap(),
+            ],
+        };
+        let CheckpointSyncerConf::Mirrored { syncers } = conf.with_prefix("ethereum") else {
+            panic!("Expected a mirrored checkpoint syncer config");
+        };
+        assert!(matches!(
+            &syncers[0],
+            CheckpointSyncerConf::S3 { folder: Some(folder), .. } if folder == "ethereum"
+        ));
+        assert!(matches!(
+            &syncers[1],
+            CheckpointSyncerConf::Azure { folder: Some(folder), .. } if folder == "folder/ethereum"
+        ));
+        assert!(matches!(
+            &syncers[2],
+            CheckpointSyncerConf::LocalStorage { path } if path == &PathBuf::from("/tmp/checkpoints/ethereum")
+        ));
+        assert!(matches!(
+            &syncers[3],
+            CheckpointSyncerConf::Http { base_url }
+                if base_url.as_str() == "https://checkpoints.example.com/validator/ethereum"
+        ));
+    }
 }
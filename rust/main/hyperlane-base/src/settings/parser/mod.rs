@@ -22,6 +22,7 @@ use hyperlane_core::{
     cfg_unwrap_all, config::*, HyperlaneDomain, HyperlaneDomainProtocol,
     HyperlaneDomainTechnicalStack, IndexMode, ReorgPeriod, SubmitterType,
 };
+use hyperlane_ethereum::RemoteSignerTls;
 
 use crate::settings::{
     chains::IndexSettings, parser::connection_parser::build_connection_conf, trace::TracingConfig,
@@ -427,6 +428,63 @@ fn parse_signer(signer: ValueParser) -> ConfigResult<SignerConf> {
                 is_legacy,
             })
         }};
+        (remote) => {{
+            let url = signer
+                .chain(&mut err)
+                .get_key("url")
+                .parse_from_str("Expected remote signer url")
+                .end();
+            let public_key = signer
+                .chain(&mut err)
+                .get_key("publicKey")
+                .parse_string()
+                .unwrap_or_default()
+                .to_owned();
+            let tls = signer
+                .chain(&mut err)
+                .get_opt_key("tls")
+                .end()
+                .map(|tls| RemoteSignerTls {
+                    ca_cert: tls
+                        .chain(&mut err)
+                        .get_opt_key("caCert")
+                        .parse_from_str("Expected CA certificate path")
+                        .end(),
+                    client_cert: tls
+                        .chain(&mut err)
+                        .get_opt_key("clientCert")
+                        .parse_from_str("Expected client certificate path")
+                        .end(),
+                    client_key: tls
+                        .chain(&mut err)
+                        .get_opt_key("clientKey")
+                        .parse_from_str("Expected client key path")
+                        .end(),
+                });
+            cfg_unwrap_all!(&signer.cwp, err: [url]);
+            err.into_result(SignerConf::Remote {
+                url,
+                public_key,
+                tls,
+            })
+        }};
+        (keystore) => {{
+            let path = signer
+                .chain(&mut err)
+                .get_key("path")
+                .parse_from_str("Expected keystore path")
+                .end();
+            let password_file = signer
+                .chain(&mut err)
+                .get_key("passwordFile")
+                .parse_from_str("Expected keystore password file path")
+                .end();
+            cfg_unwrap_all!(&signer.cwp, err: [path, password_file]);
+            err.into_result(SignerConf::Keystore {
+                path,
+                password_file,
+            })
+        }};
     }
 
     match signer_type {
@@ -434,6 +492,8 @@ fn parse_signer(signer: ValueParser) -> ConfigResult<SignerConf> {
         Some("aws") => parse_signer!(aws),
         Some("cosmosKey") => parse_signer!(cosmosKey),
         Some("starkKey") => parse_signer!(starkKey),
+        Some("remote") => parse_signer!(remote),
+        Some("keystore") => parse_signer!(keystore),
         Some(t) => {
             Err(eyre!("Unknown signer type `{t}`")).into_config_result(|| &signer.cwp + "type")
         }
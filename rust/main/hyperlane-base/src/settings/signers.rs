@@ -1,14 +1,21 @@
-use std::time::Duration;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use ethers::prelude::{AwsSigner, LocalWallet};
 use ethers::utils::hex::ToHex;
-use eyre::{bail, Context, Report};
+use eyre::{bail, eyre, Context, Report};
 use rusoto_core::Region;
 use rusoto_kms::KmsClient;
+use serde::Deserialize;
 use tracing::instrument;
+use url::Url;
 
 use hyperlane_core::{AccountAddressType, H256};
+use hyperlane_ethereum::{RemoteSigner, RemoteSignerTls};
 
 use super::aws_credentials::AwsChainCredentialsProvider;
 use crate::types::utils;
@@ -49,6 +56,26 @@ pub enum SignerConf {
         /// Whether the Starknet signer is legacy
         is_legacy: bool,
     },
+    /// A remote signing service implementing the eth1 signing API of
+    /// Web3Signer, e.g. in front of an HSM
+    Remote {
+        /// Base url of the signing service
+        url: Url,
+        /// Hex encoded secp256k1 public key identifying the key to sign with
+        public_key: String,
+        /// TLS settings used to connect to the signing service
+        tls: Option<RemoteSignerTls>,
+    },
+    /// A key in an encrypted JSON keystore, as written by geth, decrypted at
+    /// startup. Keys other than a `hexKey` list the fields of their signer
+    /// type next to the `crypto` section, e.g. `"keyType": "cosmosKey"` and
+    /// `"prefix"`.
+    Keystore {
+        /// Path to the keystore file
+        path: PathBuf,
+        /// Path to a file holding the password of the keystore
+        password_file: PathBuf,
+    },
     /// Assume node will sign on RPC calls
     #[default]
     Node,
@@ -60,6 +87,92 @@ impl SignerConf {
     pub async fn build<S: BuildableWithSignerConf>(&self) -> Result<S, Report> {
         S::build(self).await
     }
+
+    /// Decrypt a keystore signer into the signer conf of the key it holds.
+    /// Other signers are returned as is.
+    pub fn decrypt_keystore(&self) -> Result<SignerConf, Report> {
+        let SignerConf::Keystore {
+            path,
+            password_file,
+        } = self
+        else {
+            return Ok(self.clone());
+        };
+        let password = std::fs::read_to_string(password_file)
+            .with_context(|| format!("Reading keystore password from {password_file:?}"))?;
+        // Don't count the newline ending the file as part of the password
+        let password = password
+            .strip_suffix('\n')
+            .map(|p| p.strip_suffix('\r').unwrap_or(p))
+            .unwrap_or(&password);
+        let secret = eth_keystore::decrypt_key(path, password)
+            .with_context(|| format!("Decrypting keystore {path:?}"))?;
+        let metadata: KeystoreMetadata = serde_json::from_slice(&std::fs::read(path)?)
+            .with_context(|| format!("Parsing keystore {path:?}"))?;
+
+        match metadata.key_type.as_deref() {
+            None | Some("hexKey") => Ok(SignerConf::HexKey {
+                key: keystore_hex_key(&secret)?,
+            }),
+            Some("cosmosKey") => Ok(SignerConf::CosmosKey {
+                key: keystore_key(&secret)?,
+                prefix: metadata
+                    .prefix
+                    .ok_or_else(|| eyre!("Missing `prefix` in cosmosKey keystore"))?,
+                account_address_type: metadata
+                    .account_address_type
+                    .as_deref()
+                    .map(AccountAddressType::from_str)
+                    .transpose()
+                    .context("Invalid `accountAddressType` in cosmosKey keystore")?
+                    .unwrap_or_default(),
+            }),
+            Some("starkKey") => Ok(SignerConf::StarkKey {
+                key: keystore_key(&secret)?,
+                address: metadata
+                    .address
+                    .ok_or_else(|| eyre!("Missing `address` in starkKey keystore"))?,
+                is_legacy: metadata.legacy.unwrap_or(false),
+            }),
+            Some(key_type) => bail!("Unsupported keystore key type `{key_type}`"),
+        }
+    }
+}
+
+/// The fields of a keystore describing its key, next to the encrypted key.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeystoreMetadata {
+    /// The signer type of the key, `hexKey` if unset
+    key_type: Option<String>,
+    prefix: Option<String>,
+    account_address_type: Option<String>,
+    address: Option<H256>,
+    legacy: Option<bool>,
+}
+
+fn keystore_key(secret: &[u8]) -> Result<H256, Report> {
+    if secret.len() != 32 {
+        bail!(
+            "Expected a 32 byte key in keystore, found {} bytes",
+            secret.len()
+        );
+    }
+    Ok(H256::from_slice(secret))
+}
+
+/// The key of a `hexKey` keystore, which may also hold a 64 byte sealevel
+/// keypair as written by the solana cli, whose first half is the secret key.
+fn keystore_hex_key(secret: &[u8]) -> Result<H256, Report> {
+    if secret.len() != 64 {
+        return keystore_key(secret);
+    }
+    let key = H256::from_slice(&secret[..32]);
+    let keypair = hyperlane_sealevel::create_keypair(&key)?;
+    if keypair.to_bytes()[32..] != secret[32..] {
+        bail!("Invalid sealevel keypair in keystore");
+    }
+    Ok(key)
 }
 
 /// A signer for a chain.
@@ -98,12 +211,22 @@ impl BuildableWithSignerConf for hyperlane_ethereum::Signers {
                 let signer = AwsSigner::new(client, id, 0, Some(AWS_SIGNER_TIMEOUT)).await?;
                 hyperlane_ethereum::Signers::Aws(signer)
             }
+            SignerConf::Remote {
+                url,
+                public_key,
+                tls,
+            } => hyperlane_ethereum::Signers::Remote(RemoteSigner::new(
+                url,
+                public_key,
+                tls.as_ref(),
+            )?),
             SignerConf::CosmosKey { .. } => {
                 bail!("cosmosKey signer is not supported by Ethereum")
             }
             SignerConf::StarkKey { .. } => {
                 bail!("starkKey signer is not supported by Ethereum")
             }
+            SignerConf::Keystore { .. } => return Self::build(&conf.decrypt_keystore()?).await,
             SignerConf::Node => bail!("Node signer"),
         })
     }
@@ -121,6 +244,12 @@ impl ChainSigner for hyperlane_ethereum::Signers {
 #[async_trait]
 impl BuildableWithSignerConf for fuels::prelude::WalletUnlocked {
     async fn build(conf: &SignerConf) -> Result<Self, Report> {
+        if let SignerConf::Keystore { .. } = conf {
+            return Self::build(&conf.decrypt_keystore()?).await;
+        }
+        if let SignerConf::Remote { .. } = conf {
+            bail!("Remote signers are only supported by Ethereum chains, not by Fuel chains");
+        }
         if let SignerConf::HexKey { key } = conf {
             let key = fuels::crypto::SecretKey::try_from(key.as_bytes())
                 .context("Invalid fuel signer key")?;
@@ -145,6 +274,12 @@ impl ChainSigner for fuels::prelude::WalletUnlocked {
 #[async_trait]
 impl BuildableWithSignerConf for hyperlane_sealevel::Keypair {
     async fn build(conf: &SignerConf) -> Result<Self, Report> {
+        if let SignerConf::Keystore { .. } = conf {
+            return Self::build(&conf.decrypt_keystore()?).await;
+        }
+        if let SignerConf::Remote { .. } = conf {
+            bail!("Remote signers are only supported by Ethereum chains, not by Sealevel chains");
+        }
         if let SignerConf::HexKey { key } = conf {
             hyperlane_sealevel::create_keypair(key)
         } else {
@@ -165,6 +300,12 @@ impl ChainSigner for hyperlane_sealevel::Keypair {
 #[async_trait]
 impl BuildableWithSignerConf for hyperlane_cosmos::Signer {
     async fn build(conf: &SignerConf) -> Result<Self, Report> {
+        if let SignerConf::Keystore { .. } = conf {
+            return Self::build(&conf.decrypt_keystore()?).await;
+        }
+        if let SignerConf::Remote { .. } = conf {
+            bail!("Remote signers are only supported by Ethereum chains, not by Cosmos chains");
+        }
         if let SignerConf::CosmosKey {
             key,
             prefix,
@@ -194,6 +335,12 @@ impl ChainSigner for hyperlane_cosmos::Signer {
 #[async_trait]
 impl BuildableWithSignerConf for hyperlane_starknet::Signer {
     async fn build(conf: &SignerConf) -> Result<Self, Report> {
+        if let SignerConf::Keystore { .. } = conf {
+            return Self::build(&conf.decrypt_keystore()?).await;
+        }
+        if let SignerConf::Remote { .. } = conf {
+            bail!("Remote signers are only supported by Ethereum chains, not by Starknet chains");
+        }
         if let SignerConf::StarkKey {
             key,
             address,
@@ -210,6 +357,14 @@ impl BuildableWithSignerConf for hyperlane_starknet::Signer {
 #[async_trait]
 impl BuildableWithSignerConf for hyperlane_cosmos_native::Signer {
     async fn build(conf: &SignerConf) -> Result<Self, Report> {
+        if let SignerConf::Keystore { .. } = conf {
+            return Self::build(&conf.decrypt_keystore()?).await;
+        }
+        if let SignerConf::Remote { .. } = conf {
+            bail!(
+                "Remote signers are only supported by Ethereum chains, not by Cosmos native chains"
+            );
+        }
         if let SignerConf::CosmosKey {
             key,
             prefix,
@@ -248,10 +403,49 @@ impl ChainSigner for hyperlane_cosmos_native::Signer {
 
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
     use ethers::{signers::LocalWallet, utils::hex};
     use hyperlane_core::{AccountAddressType, Encode, H256};
-
-    use crate::settings::ChainSigner;
+    use serde_json::json;
+
+    use crate::settings::{ChainSigner, SignerConf};
+
+    /// Encrypts `secret` with `encryption_password` into a keystore in `dir`,
+    /// adding the `metadata` fields next to the encrypted key, and writes
+    /// `password_file_password` to its password file
+    fn write_keystore(
+        dir: &Path,
+        secret: &[u8],
+        encryption_password: &str,
+        password_file_password: &str,
+        metadata: serde_json::Value,
+    ) -> SignerConf {
+        eth_keystore::encrypt_key(
+            dir,
+            &mut rand::thread_rng(),
+            secret,
+            encryption_password,
+            Some("keystore.json"),
+        )
+        .expect("Failed to encrypt keystore");
+        let path = dir.join("keystore.json");
+        let mut keystore: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).expect("Failed to read keystore"))
+                .expect("Failed to parse keystore");
+        if let (Some(keystore), Some(metadata)) = (keystore.as_object_mut(), metadata.as_object()) {
+            keystore.extend(metadata.clone());
+        }
+        std::fs::write(&path, keystore.to_string()).expect("Failed to write keystore");
+
+        let password_file = dir.join("password.txt");
+        std::fs::write(&password_file, format!("{password_file_password}\n"))
+            .expect("Failed to write password");
+        SignerConf::Keystore {
+            path,
+            password_file,
+        }
+    }
 
     #[test]
     fn address_h256_ethereum() {
@@ -368,4 +562,106 @@ mod tests {
         );
         assert_eq!(chain_signer.address_h256(), address_h256);
     }
+
+    #[tokio::test]
+    async fn keystore_ethereum() {
+        const PRIVATE_KEY: &str =
+            "2bcd4cb33dc9b879d74aebb847b0fdd27868ade2b3a999988debcaae763283c6";
+        const ADDRESS: &str = "0000000000000000000000000bec35c9af305b1b8849d652f4b542d19ef7e8f9";
+
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let conf = write_keystore(
+            dir.path(),
+            &hex::decode(PRIVATE_KEY).expect("Failed to decode private key"),
+            "hunter2",
+            "hunter2",
+            json!({}),
+        );
+
+        let chain_signer: hyperlane_ethereum::Signers =
+            conf.build().await.expect("Failed to build signer");
+        assert_eq!(
+            chain_signer.address_h256(),
+            H256::from_slice(&hex::decode(ADDRESS).expect("Failed to decode address"))
+        );
+    }
+
+    #[tokio::test]
+    async fn keystore_cosmos() {
+        const PRIVATE_KEY: &str =
+            "5486418967eabc770b0fcb995f7ef6d9a72f7fc195531ef76c5109f44f51af26";
+        const ADDRESS: &str = "000000000000000000000000b5a79b48c87e7a37bdb625096140ee7054816942";
+
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let conf = write_keystore(
+            dir.path(),
+            &hex::decode(PRIVATE_KEY).expect("Failed to decode private key"),
+            "hunter2",
+            "hunter2",
+            json!({ "keyType": "cosmosKey", "prefix": "neutron" }),
+        );
+
+        let chain_signer: hyperlane_cosmos::Signer =
+            conf.build().await.expect("Failed to build signer");
+        assert_eq!(
+            chain_signer.address_h256(),
+            H256::from_slice(&hex::decode(ADDRESS).expect("Failed to decode address"))
+        );
+
+        // A cosmos keystore doesn't hold a plain hex key
+        assert!(conf.build::<hyperlane_ethereum::Signers>().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn keystore_sealevel_keypair() {
+        const KEYPAIR: &str =
+            "0d861aa9ee7b09fe0305a649ec9aa0dfede421817dbe995b48964e5a79fc89e50f8ac473c042cdd96a1fc81eac32221188807572521429fb871a856a668502a5";
+        const ADDRESS: &str = "0f8ac473c042cdd96a1fc81eac32221188807572521429fb871a856a668502a5";
+
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut keypair = hex::decode(KEYPAIR).expect("Failed to decode keypair");
+        let conf = write_keystore(dir.path(), &keypair, "hunter2", "hunter2", json!({}));
+
+        let chain_signer: hyperlane_sealevel::Keypair =
+            conf.build().await.expect("Failed to build signer");
+        assert_eq!(
+            chain_signer.address_h256(),
+            H256::from_slice(&hex::decode(ADDRESS).expect("Failed to decode address"))
+        );
+
+        // The public half must match the secret half
+        keypair[63] ^= 1;
+        let conf = write_keystore(dir.path(), &keypair, "hunter2", "hunter2", json!({}));
+        assert!(conf.build::<hyperlane_sealevel::Keypair>().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn keystore_wrong_password() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let conf = write_keystore(dir.path(), &[1; 32], "hunter2", "not hunter2", json!({}));
+
+        assert!(conf.build::<hyperlane_ethereum::Signers>().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn remote_signer_is_rejected_by_other_chains() {
+        let conf = SignerConf::Remote {
+            url: "https://signer.example.com"
+                .parse()
+                .expect("Failed to parse url"),
+            public_key: "0x02".to_owned(),
+            tls: None,
+        };
+
+        let err = conf
+            .build::<hyperlane_cosmos::Signer>()
+            .await
+            .expect_err("Remote cosmos signer must be rejected");
+        assert!(err.to_string().contains("not by Cosmos chains"));
+        let err = conf
+            .build::<hyperlane_sealevel::Keypair>()
+            .await
+            .expect_err("Remote sealevel signer must be rejected");
+        assert!(err.to_string().contains("not by Sealevel chains"));
+    }
 }
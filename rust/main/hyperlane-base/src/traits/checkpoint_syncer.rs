@@ -39,6 +39,11 @@ pub trait CheckpointSyncer: Debug + Send + Sync {
     async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()>;
     /// Return the announcement storage location for this syncer
     fn announcement_location(&self) -> String;
+    /// Return all the storage locations this syncer publishes to, each of
+    /// which is announced
+    fn announcement_locations(&self) -> Vec<String> {
+        vec![self.announcement_location()]
+    }
     /// If a bigger than expected reorg was detected on the validated chain, this flag can be set to inform
     /// the validator agent to stop publishing checkpoints. Once any remediation is done, this flag can be reset
     /// to resume operation.
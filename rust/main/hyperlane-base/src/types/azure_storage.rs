@@ -0,0 +1,504 @@
+use std::{env, fmt, time::Duration};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use eyre::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use hyperlane_core::{ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId};
+use prometheus::IntGauge;
+use reqwest::{
+    header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Client, Request, StatusCode,
+};
+use sha2::Sha256;
+use url::Url;
+
+use crate::{types::utils::read_capped_body, CheckpointSyncer};
+
+/// The timeout for all Azure Blob Storage requests.
+const AZURE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const AZURE_MAX_OBJECT_SIZE: u64 = 50 * 1024; // 50KiB
+/// Version of the Blob service REST API requests are made with.
+const AZURE_API_VERSION: &str = "2021-08-06";
+
+/// Env var holding the base64 encoded access key of the storage account
+/// named by [`AZURE_STORAGE_ACCOUNT`], required to write checkpoints
+pub const AZURE_STORAGE_KEY: &str = "AZURE_STORAGE_KEY";
+/// Env var naming the storage account the [`AZURE_STORAGE_KEY`] belongs to
+pub const AZURE_STORAGE_ACCOUNT: &str = "AZURE_STORAGE_ACCOUNT";
+/// Env var overriding the Blob service endpoint of storage accounts, e.g.
+/// `http://127.0.0.1:10000/devstoreaccount1` for a local emulator
+pub const AZURE_STORAGE_BLOB_ENDPOINT: &str = "AZURE_STORAGE_BLOB_ENDPOINT";
+
+/// Type for reading/writing to Azure Blob Storage
+///
+/// Requests are authorized with the storage account's Shared Key when an
+/// access key is provided. Otherwise only publicly readable containers can
+/// be read, which is all a relayer needs.
+#[derive(Clone)]
+pub struct AzureStorage {
+    /// The name of the storage account.
+    account: String,
+    /// The name of the container.
+    container: String,
+    /// A specific folder inside the container - set to empty string to use the root of the container
+    folder: Option<String>,
+    /// The decoded access key of the storage account.
+    access_key: Option<Vec<u8>>,
+    /// The Blob service endpoint of the storage account.
+    endpoint: Url,
+    client: Client,
+    /// The latest seen signed checkpoint index.
+    latest_index: Option<IntGauge>,
+}
+
+impl fmt::Debug for AzureStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureStorage")
+            .field("account", &self.account)
+            .field("container", &self.container)
+            .field("folder", &self.folder)
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
+
+impl AzureStorage {
+    /// Create a storage for `container` of the storage `account`.
+    ///
+    /// `access_key` is the base64 encoded key of the account, and `endpoint`
+    /// defaults to `https://{account}.blob.core.windows.net`.
+    pub fn new(
+        account: String,
+        container: String,
+        folder: Option<String>,
+        access_key: Option<&str>,
+        endpoint: Option<Url>,
+        latest_index: Option<IntGauge>,
+    ) -> Result<Self> {
+        let access_key = access_key
+            .map(|key| BASE64.decode(key))
+            .transpose()
+            .context("Invalid Azure storage account access key")?;
+        let mut endpoint = match endpoint {
+            Some(endpoint) => endpoint,
+            None => Url::parse(&format!("https://{account}.blob.core.windows.net"))?,
+        };
+        // Without a trailing slash, joining a path would replace the last path segment
+        if !endpoint.path().ends_with('/') {
+            endpoint.set_path(&format!("{}/", endpoint.path()));
+        }
+        let client = Client::builder().timeout(AZURE_REQUEST_TIMEOUT).build()?;
+        Ok(Self {
+            account,
+            container,
+            folder,
+            access_key,
+            endpoint,
+            client,
+            latest_index,
+        })
+    }
+
+    /// Create a storage with the access key and endpoint from the
+    /// environment, see [`AZURE_STORAGE_KEY`] and [`AZURE_STORAGE_BLOB_ENDPOINT`].
+    ///
+    /// The access key is only used if `account` is the [`AZURE_STORAGE_ACCOUNT`],
+    /// so that e.g. the announced locations of other validators are read anonymously.
+    pub fn from_env(
+        account: String,
+        container: String,
+        folder: Option<String>,
+        endpoint: Option<Url>,
+        latest_index: Option<IntGauge>,
+    ) -> Result<Self> {
+        let access_key = match env::var(AZURE_STORAGE_ACCOUNT) {
+            Ok(key_account) if key_account == account => env::var(AZURE_STORAGE_KEY).ok(),
+            _ => None,
+        };
+        let endpoint = match endpoint {
+            Some(endpoint) => Some(endpoint),
+            None => env::var(AZURE_STORAGE_BLOB_ENDPOINT)
+                .ok()
+                .map(|endpoint| Url::parse(&endpoint))
+                .transpose()
+                .context("Invalid Azure Blob service endpoint")?,
+        };
+        Self::new(
+            account,
+            container,
+            folder,
+            access_key.as_deref(),
+            endpoint,
+            latest_index,
+        )
+    }
+
+    fn get_composite_key(&self, key: String) -> String {
+        match self.folder.as_deref() {
+            None | Some("") => key,
+            Some(folder_str) => format!("{}/{}", folder_str, key),
+        }
+    }
+
+    fn blob_url(&self, key: String) -> Result<Url> {
+        let path = format!("{}/{}", self.container, self.get_composite_key(key));
+        Ok(self.endpoint.join(&path)?)
+    }
+
+    /// Sign the request with the Shared Key of the storage account, if there is one.
+    /// See https://learn.microsoft.com/en-us/rest/api/storageservices/authorize-with-shared-key
+    fn authorize(&self, request: &mut Request) -> Result<()> {
+        let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let headers = request.headers_mut();
+        headers.insert("x-ms-date", HeaderValue::from_str(&date)?);
+        headers.insert("x-ms-version", HeaderValue::from_static(AZURE_API_VERSION));
+
+        let Some(access_key) = &self.access_key else {
+            return Ok(());
+        };
+
+        let string_to_sign = self.string_to_sign(request);
+        let mut mac = Hmac::<Sha256>::new_from_slice(access_key)?;
+        mac.update(string_to_sign.as_bytes());
+        let signature = BASE64.encode(mac.finalize().into_bytes());
+        request.headers_mut().insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("SharedKey {}:{}", self.account, signature))?,
+        );
+        Ok(())
+    }
+
+    fn string_to_sign(&self, request: &Request) -> String {
+        fn header<'a>(request: &'a Request, name: &str) -> &'a str {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+        }
+        // An empty body is signed with an empty content length
+        let content_length = match request.body().and_then(|body| body.as_bytes()) {
+            Some(body) if !body.is_empty() => body.len().to_string(),
+            _ => String::new(),
+        };
+
+        let mut ms_headers = request
+            .headers()
+            .iter()
+            .filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+            .map(|(name, value)| {
+                format!(
+                    "{}:{}\n",
+                    name.as_str(),
+                    value.to_str().unwrap_or_default().trim()
+                )
+            })
+            .collect::<Vec<_>>();
+        ms_headers.sort();
+
+        let url = request.url();
+        let mut resource = format!("/{}{}", self.account, url.path());
+        let mut query = url
+            .query_pairs()
+            .map(|(name, value)| format!("\n{}:{}", name.to_lowercase(), value))
+            .collect::<Vec<_>>();
+        query.sort();
+        resource.extend(query);
+
+        [
+            request.method().as_str(),
+            header(request, "content-encoding"),
+            header(request, "content-language"),
+            content_length.as_str(),
+            header(request, "content-md5"),
+            header(request, "content-type"),
+            // The date is provided with `x-ms-date`
+            "",
+            header(request, "if-modified-since"),
+            header(request, "if-match"),
+            header(request, "if-none-match"),
+            header(request, "if-unmodified-since"),
+            header(request, "range"),
+        ]
+        .join("\n")
+            + "\n"
+            + &ms_headers.concat()
+            + &resource
+    }
+
+    async fn write_to_container(&self, key: String, body: &str) -> Result<()> {
+        let mut request = self
+            .client
+            .put(self.blob_url(key)?)
+            .header("x-ms-blob-type", "BlockBlob")
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_owned())
+            .build()?;
+        self.authorize(&mut request)?;
+
+        let response = self.client.execute(request).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error = response.text().await.unwrap_or_default();
+            bail!("Failed to write to Azure Blob Storage: {status}: {error}");
+        }
+        Ok(())
+    }
+
+    async fn read_from_container(&self, key: String) -> Result<Option<Vec<u8>>> {
+        let url = self.blob_url(key)?;
+        let mut request = self.client.get(url.clone()).build()?;
+        self.authorize(&mut request)?;
+
+        let response = self.client.execute(request).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let body = read_capped_body(&url, response, AZURE_MAX_OBJECT_SIZE).await?;
+                Ok(Some(body))
+            }
+            status => {
+                let error = response.text().await.unwrap_or_default();
+                bail!("Failed to read from Azure Blob Storage: {status}: {error}")
+            }
+        }
+    }
+
+    fn checkpoint_key(index: u32) -> String {
+        format!("checkpoint_{index}_with_id.json")
+    }
+
+    fn latest_index_key() -> String {
+        "checkpoint_latest_index.json".to_owned()
+    }
+
+    fn metadata_key() -> String {
+        "metadata_latest.json".to_owned()
+    }
+
+    fn announcement_key() -> String {
+        "announcement.json".to_owned()
+    }
+
+    fn reorg_flag_key() -> String {
+        "reorg_flag.json".to_owned()
+    }
+}
+
+#[async_trait]
+impl CheckpointSyncer for AzureStorage {
+    async fn latest_index(&self) -> Result<Option<u32>> {
+        let ret = self
+            .read_from_container(AzureStorage::latest_index_key())
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into);
+
+        if let Ok(Some(latest_index)) = ret {
+            if let Some(gauge) = &self.latest_index {
+                gauge.set(latest_index as i64);
+            }
+        }
+
+        ret
+    }
+
+    async fn write_latest_index(&self, index: u32) -> Result<()> {
+        let serialized_index = serde_json::to_string(&index)?;
+        self.write_to_container(AzureStorage::latest_index_key(), &serialized_index)
+            .await
+    }
+
+    async fn fetch_checkpoint(&self, index: u32) -> Result<Option<SignedCheckpointWithMessageId>> {
+        self.read_from_container(AzureStorage::checkpoint_key(index))
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    async fn write_checkpoint(
+        &self,
+        signed_checkpoint: &SignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        let serialized_checkpoint = serde_json::to_string_pretty(signed_checkpoint)?;
+        self.write_to_container(
+            AzureStorage::checkpoint_key(signed_checkpoint.value.index),
+            &serialized_checkpoint,
+        )
+        .await
+    }
+
+    async fn write_metadata(&self, serialized_metadata: &str) -> Result<()> {
+        self.write_to_container(AzureStorage::metadata_key(), serialized_metadata)
+            .await
+    }
+
+    async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
+        let serialized_announcement = serde_json::to_string_pretty(signed_announcement)?;
+        self.write_to_container(AzureStorage::announcement_key(), &serialized_announcement)
+            .await
+    }
+
+    fn announcement_location(&self) -> String {
+        match self.folder.as_deref() {
+            None | Some("") => format!("azure://{}/{}", self.account, self.container),
+            Some(folder_str) => {
+                format!("azure://{}/{}/{}", self.account, self.container, folder_str)
+            }
+        }
+    }
+
+    async fn write_reorg_status(&self, reorged_event: &ReorgEvent) -> Result<()> {
+        let serialized_reorg = serde_json::to_string(reorged_event)?;
+        self.write_to_container(AzureStorage::reorg_flag_key(), &serialized_reorg)
+            .await
+    }
+
+    async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+        self.read_from_container(AzureStorage::reorg_flag_key())
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperlane_core::{Checkpoint, CheckpointWithMessageId, HyperlaneSignerExt, H256};
+    use hyperlane_ethereum::Signers;
+
+    use super::*;
+
+    /// Well known account of the Azurite emulator
+    const AZURITE_ACCOUNT: &str = "devstoreaccount1";
+    const AZURITE_KEY: &str =
+        "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==";
+
+    #[test]
+    fn test_announcement_location() {
+        let storage = AzureStorage::new(
+            "account".to_owned(),
+            "container".to_owned(),
+            Some("folder".to_owned()),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            storage.announcement_location(),
+            "azure://account/container/folder"
+        );
+        assert_eq!(
+            storage
+                .blob_url(AzureStorage::checkpoint_key(1))
+                .unwrap()
+                .as_str(),
+            "https://account.blob.core.windows.net/container/folder/checkpoint_1_with_id.json"
+        );
+
+        let storage = AzureStorage::new(
+            "account".to_owned(),
+            "container".to_owned(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(storage.announcement_location(), "azure://account/container");
+    }
+
+    #[test]
+    fn test_string_to_sign() {
+        let endpoint = Url::parse("http://127.0.0.1:10000/devstoreaccount1").unwrap();
+        let storage = AzureStorage::new(
+            AZURITE_ACCOUNT.to_owned(),
+            "checkpoints".to_owned(),
+            None,
+            Some(AZURITE_KEY),
+            Some(endpoint),
+            None,
+        )
+        .unwrap();
+        let request = storage
+            .client
+            .put(storage.blob_url(AzureStorage::latest_index_key()).unwrap())
+            .header("x-ms-date", "Mon, 01 Jan 2024 00:00:00 GMT")
+            .header("x-ms-blob-type", "BlockBlob")
+            .header(CONTENT_TYPE, "application/json")
+            .body("42")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            storage.string_to_sign(&request),
+            "PUT\n\n\n2\n\napplication/json\n\n\n\n\n\n\n\
+             x-ms-blob-type:BlockBlob\n\
+             x-ms-date:Mon, 01 Jan 2024 00:00:00 GMT\n\
+             /devstoreaccount1/devstoreaccount1/checkpoints/checkpoint_latest_index.json"
+        );
+    }
+
+    /// Requires an Azurite emulator listening on the default port, e.g.
+    /// `docker run -p 10000:10000 mcr.microsoft.com/azure-storage/azurite azurite-blob --blobHost 0.0.0.0`
+    #[tokio::test]
+    #[ignore]
+    async fn test_azurite_checkpoint_syncer() {
+        let endpoint = Url::parse("http://127.0.0.1:10000/devstoreaccount1").unwrap();
+        let storage = AzureStorage::new(
+            AZURITE_ACCOUNT.to_owned(),
+            "checkpoints".to_owned(),
+            Some("validator".to_owned()),
+            Some(AZURITE_KEY),
+            Some(endpoint.clone()),
+            None,
+        )
+        .unwrap();
+
+        // Create the container, which may already exist
+        let mut request = storage
+            .client
+            .put(
+                endpoint
+                    .join("devstoreaccount1/checkpoints?restype=container")
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        storage.authorize(&mut request).unwrap();
+        storage.client.execute(request).await.unwrap();
+
+        let signer: Signers = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            .parse::<ethers::signers::LocalWallet>()
+            .unwrap()
+            .into();
+        let signed_checkpoint = signer
+            .sign(CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    merkle_tree_hook_address: H256::zero(),
+                    mailbox_domain: 1,
+                    root: H256::zero(),
+                    index: 7,
+                },
+                message_id: H256::zero(),
+            })
+            .await
+            .unwrap();
+
+        storage.write_checkpoint(&signed_checkpoint).await.unwrap();
+        storage.update_latest_index(7).await.unwrap();
+
+        assert_eq!(storage.latest_index().await.unwrap(), Some(7));
+        assert_eq!(
+            storage.fetch_checkpoint(7).await.unwrap(),
+            Some(signed_checkpoint)
+        );
+        assert_eq!(storage.fetch_checkpoint(8).await.unwrap(), None);
+    }
+}
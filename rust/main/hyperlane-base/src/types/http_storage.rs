@@ -0,0 +1,282 @@
+use std::{fmt, time::Duration};
+
+use async_trait::async_trait;
+use eyre::{bail, Result};
+use hyperlane_core::{ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId};
+use moka::future::Cache;
+use prometheus::IntGauge;
+use reqwest::{
+    header::{ETAG, IF_NONE_MATCH},
+    Client, StatusCode,
+};
+use url::Url;
+
+use crate::{types::utils::read_capped_body, CheckpointSyncer};
+
+/// The timeout for all HTTP requests.
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const HTTP_MAX_OBJECT_SIZE: u64 = 50 * 1024; // 50KiB
+/// Maximum number of objects whose ETag and body are kept for revalidation.
+const HTTP_ETAG_CACHE_CAPACITY: u64 = 1_000;
+
+/// Read-only access to checkpoints published over plain HTTP(S), e.g. by a
+/// web server or behind a CDN.
+///
+/// Objects are looked up by the same keys as in the other storages, relative
+/// to `base_url`. Fetched objects are revalidated with their `ETag`, so that
+/// unchanged objects such as the latest index aren't downloaded again.
+#[derive(Clone)]
+pub struct HttpStorage {
+    /// The URL the objects are stored under.
+    base_url: Url,
+    client: Client,
+    /// The ETag and body of recently fetched objects, by URL.
+    etag_cache: Cache<Url, (String, Vec<u8>)>,
+    /// The latest seen signed checkpoint index.
+    latest_index: Option<IntGauge>,
+}
+
+impl fmt::Debug for HttpStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpStorage")
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+impl HttpStorage {
+    /// Create a storage reading objects under `base_url`.
+    pub fn new(mut base_url: Url, latest_index: Option<IntGauge>) -> Result<Self> {
+        // Without a trailing slash, joining a key would replace the last path segment
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        let client = Client::builder().timeout(HTTP_REQUEST_TIMEOUT).build()?;
+        Ok(Self {
+            base_url,
+            client,
+            etag_cache: Cache::new(HTTP_ETAG_CACHE_CAPACITY),
+            latest_index,
+        })
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let url = self.base_url.join(key)?;
+        let cached = self.etag_cache.get(&url).await;
+
+        let mut request = self.client.get(url.clone());
+        if let Some((etag, _)) = &cached {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+
+        match response.status() {
+            StatusCode::NOT_MODIFIED => match cached {
+                Some((_, body)) => Ok(Some(body)),
+                None => bail!("Unexpected {} response for {url}", StatusCode::NOT_MODIFIED),
+            },
+            StatusCode::NOT_FOUND => {
+                self.etag_cache.invalidate(&url).await;
+                Ok(None)
+            }
+            status if status.is_success() => {
+                let etag = response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(str::to_owned);
+                let body = read_capped_body(&url, response, HTTP_MAX_OBJECT_SIZE).await?;
+                if let Some(etag) = etag {
+                    self.etag_cache.insert(url, (etag, body.clone())).await;
+                }
+                Ok(Some(body))
+            }
+            status => bail!("Failed to read {url}: {status}"),
+        }
+    }
+
+    fn checkpoint_key(index: u32) -> String {
+        format!("checkpoint_{index}_with_id.json")
+    }
+
+    fn latest_index_key() -> &'static str {
+        "checkpoint_latest_index.json"
+    }
+
+    fn announcement_key() -> &'static str {
+        "announcement.json"
+    }
+
+    fn reorg_flag_key() -> &'static str {
+        "reorg_flag.json"
+    }
+
+    /// Read the signed announcement published by the validator.
+    pub async fn announcement(&self) -> Result<Option<SignedAnnouncement>> {
+        self.read(Self::announcement_key())
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+#[async_trait]
+impl CheckpointSyncer for HttpStorage {
+    async fn latest_index(&self) -> Result<Option<u32>> {
+        let ret = self
+            .read(Self::latest_index_key())
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into);
+
+        if let Ok(Some(latest_index)) = ret {
+            if let Some(gauge) = &self.latest_index {
+                gauge.set(latest_index as i64);
+            }
+        }
+
+        ret
+    }
+
+    async fn write_latest_index(&self, _index: u32) -> Result<()> {
+        bail!("HTTP checkpoint storage is read-only")
+    }
+
+    async fn fetch_checkpoint(&self, index: u32) -> Result<Option<SignedCheckpointWithMessageId>> {
+        self.read(&Self::checkpoint_key(index))
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    async fn write_checkpoint(
+        &self,
+        _signed_checkpoint: &SignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        bail!("HTTP checkpoint storage is read-only")
+    }
+
+    async fn write_metadata(&self, _serialized_metadata: &str) -> Result<()> {
+        bail!("HTTP checkpoint storage is read-only")
+    }
+
+    async fn write_announcement(&self, _signed_announcement: &SignedAnnouncement) -> Result<()> {
+        bail!("HTTP checkpoint storage is read-only")
+    }
+
+    fn announcement_location(&self) -> String {
+        self.base_url.to_string()
+    }
+
+    async fn write_reorg_status(&self, _reorg_event: &ReorgEvent) -> Result<()> {
+        bail!("HTTP checkpoint storage is read-only")
+    }
+
+    async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+        self.read(Self::reorg_flag_key())
+            .await?
+            .map(|data| serde_json::from_slice(&data))
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use axum::{
+        extract::State,
+        http::{HeaderMap, StatusCode as HttpStatusCode},
+        routing::get,
+        Router,
+    };
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    async fn serve(app: Router) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Url::parse(&format!("http://{address}/validator")).unwrap()
+    }
+
+    /// Serves a latest index of 42 under `/validator`, and counts how many
+    /// times the full body was sent
+    async fn serve_latest_index() -> (Url, Arc<AtomicUsize>) {
+        async fn latest_index(
+            State(downloads): State<Arc<AtomicUsize>>,
+            headers: HeaderMap,
+        ) -> (
+            HttpStatusCode,
+            [(&'static str, &'static str); 1],
+            &'static str,
+        ) {
+            let etag = [("etag", "\"42\"")];
+            if headers.get("if-none-match").and_then(|v| v.to_str().ok()) == Some("\"42\"") {
+                return (HttpStatusCode::NOT_MODIFIED, etag, "");
+            }
+            downloads.fetch_add(1, Ordering::SeqCst);
+            (HttpStatusCode::OK, etag, "42")
+        }
+
+        let downloads = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/validator/checkpoint_latest_index.json", get(latest_index))
+            .with_state(downloads.clone());
+        (serve(app).await, downloads)
+    }
+
+    #[tokio::test]
+    async fn test_read_revalidates_with_etag() {
+        let (url, downloads) = serve_latest_index().await;
+        let storage = HttpStorage::new(url, None).unwrap();
+
+        assert_eq!(storage.latest_index().await.unwrap(), Some(42));
+        assert_eq!(storage.latest_index().await.unwrap(), Some(42));
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+        assert_eq!(storage.fetch_checkpoint(1).await.unwrap(), None);
+        assert!(storage.write_latest_index(43).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_rejects_oversized_objects() {
+        let oversized = "1".repeat(HTTP_MAX_OBJECT_SIZE as usize + 1);
+        let app = Router::new()
+            .route(
+                "/validator/checkpoint_latest_index.json",
+                get(move || async move { oversized }),
+            )
+            // Streamed, so without a content length
+            .route(
+                "/validator/checkpoint_0_with_id.json",
+                get(|| async {
+                    let chunks = (0..=HTTP_MAX_OBJECT_SIZE / 1024)
+                        .map(|_| Ok::<_, std::io::Error>(vec![b'1'; 1024]));
+                    axum::body::Body::from_stream(futures::stream::iter(chunks))
+                }),
+            );
+        let storage = HttpStorage::new(serve(app).await, None).unwrap();
+
+        assert!(storage.latest_index().await.is_err());
+        assert!(storage.fetch_checkpoint(0).await.is_err());
+    }
+
+    #[test]
+    fn test_announcement_location() {
+        let url = Url::parse("https://example.com/validator").unwrap();
+        let storage = HttpStorage::new(url, None).unwrap();
+        assert_eq!(
+            storage.announcement_location(),
+            "https://example.com/validator/"
+        );
+    }
+}
@@ -0,0 +1,916 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use eyre::{bail, Result, WrapErr};
+use futures::future::join_all;
+use hyperlane_core::{ReorgEvent, SignedAnnouncement, SignedCheckpointWithMessageId};
+use prometheus::{IntGauge, IntGaugeVec};
+use tracing::{debug, warn};
+
+use crate::CheckpointSyncer;
+
+/// Maximum number of missed checkpoints copied to a recovered backend before
+/// checking again what it misses, so that the newest ones are copied first.
+const MIRRORED_MAX_BACKFILL_BATCH: usize = 100;
+
+/// Writes checkpoints to several checkpoint syncers, so that they stay
+/// available to relayers while one of the backends is down.
+///
+/// A write succeeds as long as one backend accepts it. Whatever a backend
+/// missed is remembered, and copied over from the other backends in the
+/// background once it accepts writes again. A backend only advertises a latest
+/// index once it holds the checkpoints up to it. Reads are served by the first
+/// backend that answers.
+pub struct MirroredStorage {
+    backends: Arc<Vec<MirroredBackend>>,
+    /// The latest seen signed checkpoint index, across all backends.
+    latest_index: Option<IntGauge>,
+}
+
+struct MirroredBackend {
+    syncer: Box<dyn CheckpointSyncer>,
+    location: String,
+    /// The writes this backend missed
+    pending: Mutex<PendingWrites>,
+    /// Held while backfilling, so that concurrent writes don't backfill the
+    /// same checkpoints twice
+    backfill_lock: tokio::sync::Mutex<()>,
+    /// The number of checkpoints this backend is missing
+    lag: Option<IntGauge>,
+}
+
+#[derive(Debug, Default)]
+struct PendingWrites {
+    checkpoints: BTreeSet<u32>,
+    latest_index: Option<u32>,
+    metadata: Option<String>,
+    announcement: Option<SignedAnnouncement>,
+    reorg_event: Option<ReorgEvent>,
+}
+
+impl PendingWrites {
+    fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
+            && self.latest_index.is_none()
+            && self.metadata.is_none()
+            && self.announcement.is_none()
+            && self.reorg_event.is_none()
+    }
+}
+
+impl MirroredBackend {
+    fn update_pending(&self, update: impl FnOnce(&mut PendingWrites)) {
+        let mut pending = self
+            .pending
+            .lock()
+            .expect("Mirrored checkpoint syncer lock poisoned");
+        update(&mut pending);
+        if let Some(lag) = &self.lag {
+            lag.set(pending.checkpoints.len() as i64);
+        }
+    }
+
+    fn take_pending<T>(&self, take: impl FnOnce(&mut PendingWrites) -> Option<T>) -> Option<T> {
+        let mut taken = None;
+        self.update_pending(|pending| taken = take(pending));
+        taken
+    }
+
+    fn is_missing_checkpoint(&self, index: u32) -> bool {
+        self.pending
+            .lock()
+            .expect("Mirrored checkpoint syncer lock poisoned")
+            .checkpoints
+            .contains(&index)
+    }
+
+    fn is_missing_checkpoint_up_to(&self, index: u32) -> bool {
+        self.pending
+            .lock()
+            .expect("Mirrored checkpoint syncer lock poisoned")
+            .checkpoints
+            .range(..=index)
+            .next()
+            .is_some()
+    }
+
+    fn has_pending(&self) -> bool {
+        !self
+            .pending
+            .lock()
+            .expect("Mirrored checkpoint syncer lock poisoned")
+            .is_empty()
+    }
+}
+
+impl fmt::Debug for MirroredStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirroredStorage")
+            .field(
+                "backends",
+                &self
+                    .backends
+                    .iter()
+                    .map(|backend| &backend.location)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl MirroredStorage {
+    /// Mirror writes to `syncers`. Reads prefer the backends in the given
+    /// order.
+    ///
+    /// `lag` is labelled by the announcement location of each backend.
+    pub fn new(
+        syncers: Vec<Box<dyn CheckpointSyncer>>,
+        latest_index: Option<IntGauge>,
+        lag: Option<IntGaugeVec>,
+    ) -> Result<Self> {
+        if syncers.is_empty() {
+            bail!("A mirrored checkpoint syncer needs at least one backend");
+        }
+        let backends = syncers
+            .into_iter()
+            .map(|syncer| {
+                let location = syncer.announcement_location();
+                let lag = lag.as_ref().map(|lag| {
+                    let gauge = lag.with_label_values(&[&location]);
+                    gauge.set(0);
+                    gauge
+                });
+                MirroredBackend {
+                    syncer,
+                    location,
+                    pending: Default::default(),
+                    backfill_lock: Default::default(),
+                    lag,
+                }
+            })
+            .collect();
+        Ok(Self {
+            backends: Arc::new(backends),
+            latest_index,
+        })
+    }
+
+    /// Compare the latest index of all backends, and mark the checkpoints a
+    /// backend is behind the others as missing, e.g. after a backend was added
+    /// or the validator restarted during an outage.
+    pub async fn detect_lag(&self) {
+        let latest_indices = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.latest_index()),
+        )
+        .await;
+        let Some(max_index) = latest_indices
+            .iter()
+            .filter_map(|index| index.as_ref().ok().copied().flatten())
+            .max()
+        else {
+            return;
+        };
+
+        for (backend, latest_index) in self.backends.iter().zip(latest_indices) {
+            match latest_index {
+                Ok(Some(index)) if index >= max_index => {}
+                Ok(latest_index) => {
+                    let first_missing = latest_index.map_or(0, |index| index + 1);
+                    warn!(
+                        location = %backend.location,
+                        ?latest_index,
+                        max_index,
+                        "Mirrored checkpoint syncer backend is behind, it will be backfilled"
+                    );
+                    backend.update_pending(|pending| {
+                        pending.checkpoints.extend(first_missing..=max_index);
+                        pending.latest_index = Some(max_index);
+                    });
+                }
+                Err(err) => warn!(
+                    location = %backend.location,
+                    ?err,
+                    "Failed to read latest index of mirrored checkpoint syncer backend"
+                ),
+            }
+        }
+    }
+
+    /// Run `write` against the backends at `backends`, and record what each of
+    /// them missed or caught up on with `record`. Succeeds if any backend
+    /// accepted the write.
+    async fn write_to<'a, F, Fut>(
+        &'a self,
+        backends: Vec<usize>,
+        write: F,
+        record: impl Fn(&mut PendingWrites, bool),
+    ) -> Result<()>
+    where
+        F: Fn(&'a dyn CheckpointSyncer) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let results = join_all(
+            backends
+                .iter()
+                .map(|&index| write(self.backends[index].syncer.as_ref())),
+        )
+        .await;
+
+        let mut first_err = None;
+        let mut written = false;
+        for (index, result) in backends.into_iter().zip(results) {
+            let backend = &self.backends[index];
+            match result {
+                Ok(()) => {
+                    written = true;
+                    backend.update_pending(|pending| record(pending, true));
+                    self.spawn_backfill(index);
+                }
+                Err(err) => {
+                    warn!(
+                        location = %backend.location,
+                        ?err,
+                        "Failed to write to mirrored checkpoint syncer backend, it will be backfilled once it recovers"
+                    );
+                    backend.update_pending(|pending| record(pending, false));
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+
+        match first_err {
+            Some(err) if !written => {
+                Err(err).wrap_err("Failed to write to any mirrored checkpoint syncer backend")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn write_to_all<'a, F, Fut>(
+        &'a self,
+        write: F,
+        record: impl Fn(&mut PendingWrites, bool),
+    ) -> Result<()>
+    where
+        F: Fn(&'a dyn CheckpointSyncer) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        self.write_to((0..self.backends.len()).collect(), write, record)
+            .await
+    }
+
+    /// Write the latest index to the backends holding every checkpoint up to
+    /// it. The others get it once they're backfilled, so that they never
+    /// advertise checkpoints they don't have.
+    async fn write_latest_index_to_caught_up<'a, F, Fut>(
+        &'a self,
+        index: u32,
+        write: F,
+    ) -> Result<()>
+    where
+        F: Fn(&'a dyn CheckpointSyncer) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut caught_up = vec![];
+        for (i, backend) in self.backends.iter().enumerate() {
+            if backend.is_missing_checkpoint_up_to(index) {
+                debug!(
+                    location = %backend.location,
+                    index,
+                    "Mirrored checkpoint syncer backend is being backfilled, deferring its latest index"
+                );
+                backend.update_pending(|pending| {
+                    pending.latest_index = pending.latest_index.max(Some(index));
+                });
+            } else {
+                caught_up.push(i);
+            }
+        }
+        self.write_to(caught_up, write, |pending, written| {
+            if !written {
+                pending.latest_index = pending.latest_index.max(Some(index));
+            } else if pending.latest_index <= Some(index) {
+                pending.latest_index = None;
+            }
+        })
+        .await
+    }
+
+    /// Replay the writes the backend at `index` missed in the background, so
+    /// that catching up after a long outage doesn't hold up new writes.
+    fn spawn_backfill(&self, index: usize) {
+        if !self.backends[index].has_pending() {
+            return;
+        }
+        tokio::spawn(Self::backfill(self.backends.clone(), index));
+    }
+
+    /// Fetch a checkpoint `lagging` is missing from the other backends.
+    async fn fetch_missed_checkpoint(
+        backends: &[MirroredBackend],
+        lagging: &MirroredBackend,
+        index: u32,
+    ) -> Result<Option<SignedCheckpointWithMessageId>> {
+        let mut first_err = None;
+        for source in backends.iter().filter(|source| {
+            !std::ptr::eq(*source, lagging) && !source.is_missing_checkpoint(index)
+        }) {
+            match source.syncer.fetch_checkpoint(index).await {
+                Ok(Some(checkpoint)) => return Ok(Some(checkpoint)),
+                Ok(None) => {}
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        first_err.map_or(Ok(None), Err)
+    }
+
+    /// Replay the writes the backend at `lagging` missed, now that it accepts
+    /// writes again. Stops at the first failure, the rest is retried after a
+    /// later write.
+    async fn backfill(backends: Arc<Vec<MirroredBackend>>, lagging: usize) {
+        let lagging = &backends[lagging];
+        let Ok(_guard) = lagging.backfill_lock.try_lock() else {
+            return;
+        };
+
+        if let Some(reorg_event) = lagging.take_pending(|pending| pending.reorg_event.take()) {
+            if let Err(err) = lagging.syncer.write_reorg_status(&reorg_event).await {
+                warn!(
+                    location = %lagging.location,
+                    ?err,
+                    "Failed to backfill reorg status"
+                );
+                lagging.update_pending(|pending| {
+                    pending.reorg_event.get_or_insert(reorg_event);
+                });
+                return;
+            }
+        }
+
+        loop {
+            let missing = lagging
+                .pending
+                .lock()
+                .expect("Mirrored checkpoint syncer lock poisoned")
+                .checkpoints
+                .iter()
+                .rev()
+                .take(MIRRORED_MAX_BACKFILL_BATCH)
+                .copied()
+                .collect::<Vec<_>>();
+            if missing.is_empty() {
+                break;
+            }
+
+            // Newest first, like the checkpoint submitter, since those are the
+            // most likely to be needed by relayers
+            for index in missing {
+                let checkpoint =
+                    match Self::fetch_missed_checkpoint(&backends, lagging, index).await {
+                        Ok(checkpoint) => checkpoint,
+                        Err(err) => {
+                            warn!(
+                                location = %lagging.location,
+                                index,
+                                ?err,
+                                "Failed to fetch checkpoint to backfill"
+                            );
+                            return;
+                        }
+                    };
+                match checkpoint {
+                    Some(checkpoint) => {
+                        if let Err(err) = lagging.syncer.write_checkpoint(&checkpoint).await {
+                            warn!(
+                                location = %lagging.location,
+                                index,
+                                ?err,
+                                "Failed to backfill checkpoint"
+                            );
+                            return;
+                        }
+                    }
+                    None => debug!(
+                        location = %lagging.location,
+                        index, "No backend has the checkpoint to backfill, skipping it"
+                    ),
+                }
+                lagging.update_pending(|pending| {
+                    pending.checkpoints.remove(&index);
+                });
+            }
+        }
+
+        if let Some(metadata) = lagging.take_pending(|pending| pending.metadata.take()) {
+            if let Err(err) = lagging.syncer.write_metadata(&metadata).await {
+                warn!(
+                    location = %lagging.location,
+                    ?err,
+                    "Failed to backfill metadata"
+                );
+                lagging.update_pending(|pending| {
+                    pending.metadata.get_or_insert(metadata);
+                });
+                return;
+            }
+        }
+        if let Some(announcement) = lagging.take_pending(|pending| pending.announcement.take()) {
+            if let Err(err) = lagging.syncer.write_announcement(&announcement).await {
+                warn!(
+                    location = %lagging.location,
+                    ?err,
+                    "Failed to backfill announcement"
+                );
+                lagging.update_pending(|pending| {
+                    pending.announcement.get_or_insert(announcement);
+                });
+                return;
+            }
+        }
+
+        // Only publish the latest index once the checkpoints up to it are in
+        let Some(index) = lagging.take_pending(|pending| {
+            let index = pending.latest_index?;
+            if pending.checkpoints.range(..=index).next().is_some() {
+                return None;
+            }
+            pending.latest_index.take()
+        }) else {
+            return;
+        };
+        if let Err(err) = lagging.syncer.update_latest_index(index).await {
+            warn!(
+                location = %lagging.location,
+                ?err,
+                "Failed to backfill latest index"
+            );
+            lagging.update_pending(|pending| {
+                pending.latest_index = pending.latest_index.max(Some(index));
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl CheckpointSyncer for MirroredStorage {
+    async fn latest_index(&self) -> Result<Option<u32>> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.latest_index()),
+        )
+        .await;
+
+        let mut first_err = None;
+        let mut latest_index = None;
+        let mut read = false;
+        for result in results {
+            match result {
+                Ok(index) => {
+                    read = true;
+                    latest_index = latest_index.max(index);
+                }
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        if let Some(err) = first_err.filter(|_| !read) {
+            return Err(err);
+        }
+        if let (Some(gauge), Some(index)) = (&self.latest_index, latest_index) {
+            gauge.set(index as i64);
+        }
+        Ok(latest_index)
+    }
+
+    async fn write_latest_index(&self, index: u32) -> Result<()> {
+        self.write_latest_index_to_caught_up(index, |syncer| syncer.write_latest_index(index))
+            .await
+    }
+
+    async fn update_latest_index(&self, index: u32) -> Result<()> {
+        // Each backend compares against its own latest index, since a
+        // lagging backend is behind the highest one
+        self.write_latest_index_to_caught_up(index, |syncer| syncer.update_latest_index(index))
+            .await
+    }
+
+    async fn fetch_checkpoint(&self, index: u32) -> Result<Option<SignedCheckpointWithMessageId>> {
+        let mut first_err = None;
+        let mut read = false;
+        for backend in self
+            .backends
+            .iter()
+            .filter(|backend| !backend.is_missing_checkpoint(index))
+        {
+            match backend.syncer.fetch_checkpoint(index).await {
+                Ok(Some(checkpoint)) => return Ok(Some(checkpoint)),
+                Ok(None) => read = true,
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        match first_err {
+            Some(err) if !read => Err(err),
+            _ => Ok(None),
+        }
+    }
+
+    async fn write_checkpoint(
+        &self,
+        signed_checkpoint: &SignedCheckpointWithMessageId,
+    ) -> Result<()> {
+        let index = signed_checkpoint.value.index;
+        self.write_to_all(
+            |syncer| syncer.write_checkpoint(signed_checkpoint),
+            |pending, written| {
+                if written {
+                    pending.checkpoints.remove(&index);
+                } else {
+                    pending.checkpoints.insert(index);
+                }
+            },
+        )
+        .await
+    }
+
+    async fn write_metadata(&self, serialized_metadata: &str) -> Result<()> {
+        self.write_to_all(
+            |syncer| syncer.write_metadata(serialized_metadata),
+            |pending, written| {
+                pending.metadata = (!written).then(|| serialized_metadata.to_owned());
+            },
+        )
+        .await
+    }
+
+    async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
+        // An announcement names a single location, so it only goes to the
+        // backend it's for
+        let location = &signed_announcement.value.storage_location;
+        let mut backends = self
+            .backends
+            .iter()
+            .enumerate()
+            .filter(|(_, backend)| backend.syncer.announcement_locations().contains(location))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if backends.is_empty() {
+            backends = (0..self.backends.len()).collect();
+        }
+        let result = self
+            .write_to(
+                backends,
+                |syncer| syncer.write_announcement(signed_announcement),
+                |pending, written| {
+                    pending.announcement = (!written).then(|| signed_announcement.clone());
+                },
+            )
+            .await;
+        // The announcement is backfilled once the backend recovers, which
+        // mustn't keep the validator from starting during an outage
+        if let Err(err) = result {
+            warn!(
+                %location,
+                ?err,
+                "Failed to write announcement, it will be backfilled once the backend recovers"
+            );
+        }
+        Ok(())
+    }
+
+    fn announcement_location(&self) -> String {
+        self.backends[0].location.clone()
+    }
+
+    fn announcement_locations(&self) -> Vec<String> {
+        self.backends
+            .iter()
+            .flat_map(|backend| backend.syncer.announcement_locations())
+            .collect()
+    }
+
+    async fn write_reorg_status(&self, reorg_event: &ReorgEvent) -> Result<()> {
+        self.write_to_all(
+            |syncer| syncer.write_reorg_status(reorg_event),
+            |pending, written| {
+                pending.reorg_event = (!written).then(|| reorg_event.clone());
+            },
+        )
+        .await
+    }
+
+    async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+        let results = join_all(
+            self.backends
+                .iter()
+                .map(|backend| backend.syncer.reorg_status()),
+        )
+        .await;
+
+        // A reorg flagged on any backend stops the validator
+        let mut first_err = None;
+        let mut read = false;
+        for result in results {
+            match result {
+                Ok(Some(reorg_event)) => return Ok(Some(reorg_event)),
+                Ok(None) => read = true,
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        match first_err {
+            Some(err) if !read => Err(err),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+
+    use eyre::eyre;
+    use hyperlane_core::{
+        Announcement, Checkpoint, CheckpointWithMessageId, HyperlaneSigner, HyperlaneSignerExt,
+        H256,
+    };
+    use hyperlane_ethereum::Signers;
+    use prometheus::{opts, Registry};
+
+    use crate::{tests::dummy_validators, LocalStorage};
+
+    use super::*;
+
+    /// A local storage that fails every request while `down` is set
+    #[derive(Debug)]
+    struct FlakyStorage {
+        storage: LocalStorage,
+        down: Arc<AtomicBool>,
+    }
+
+    impl FlakyStorage {
+        fn check(&self) -> Result<()> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(eyre!("Storage is down"));
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl CheckpointSyncer for FlakyStorage {
+        async fn latest_index(&self) -> Result<Option<u32>> {
+            self.check()?;
+            self.storage.latest_index().await
+        }
+
+        async fn write_latest_index(&self, index: u32) -> Result<()> {
+            self.check()?;
+            self.storage.write_latest_index(index).await
+        }
+
+        async fn fetch_checkpoint(
+            &self,
+            index: u32,
+        ) -> Result<Option<SignedCheckpointWithMessageId>> {
+            self.check()?;
+            self.storage.fetch_checkpoint(index).await
+        }
+
+        async fn write_checkpoint(
+            &self,
+            signed_checkpoint: &SignedCheckpointWithMessageId,
+        ) -> Result<()> {
+            self.check()?;
+            self.storage.write_checkpoint(signed_checkpoint).await
+        }
+
+        async fn write_metadata(&self, serialized_metadata: &str) -> Result<()> {
+            self.check()?;
+            self.storage.write_metadata(serialized_metadata).await
+        }
+
+        async fn write_announcement(&self, signed_announcement: &SignedAnnouncement) -> Result<()> {
+            self.check()?;
+            self.storage.write_announcement(signed_announcement).await
+        }
+
+        fn announcement_location(&self) -> String {
+            self.storage.announcement_location()
+        }
+
+        async fn write_reorg_status(&self, reorg_event: &ReorgEvent) -> Result<()> {
+            self.check()?;
+            self.storage.write_reorg_status(reorg_event).await
+        }
+
+        async fn reorg_status(&self) -> Result<Option<ReorgEvent>> {
+            self.check()?;
+            self.storage.reorg_status().await
+        }
+    }
+
+    async fn signed_checkpoint(index: u32) -> SignedCheckpointWithMessageId {
+        let signer: Signers = dummy_validators()[0]
+            .private_key
+            .parse::<ethers::signers::LocalWallet>()
+            .unwrap()
+            .into();
+        let checkpoint = CheckpointWithMessageId {
+            checkpoint: Checkpoint {
+                merkle_tree_hook_address: H256::zero(),
+                mailbox_domain: 1,
+                root: H256::zero(),
+                index,
+            },
+            message_id: H256::from_low_u64_be(index as u64),
+        };
+        signer.sign(checkpoint).await.unwrap()
+    }
+
+    /// Wait for the background backfill to make `condition` true
+    async fn eventually<F, Fut>(condition: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Backfill did not complete in time");
+    }
+
+    #[tokio::test]
+    async fn test_backfills_recovered_backend() {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let primary = LocalStorage::new(primary_dir.path().into(), None).unwrap();
+        let mirror = LocalStorage::new(mirror_dir.path().into(), None).unwrap();
+        let mirror_location = mirror.announcement_location();
+        let down = Arc::new(AtomicBool::new(true));
+        let lag = IntGaugeVec::new(opts!("lag", "lag"), &["location"]).unwrap();
+        Registry::new().register(Box::new(lag.clone())).unwrap();
+
+        let storage = MirroredStorage::new(
+            vec![
+                Box::new(primary.clone()),
+                Box::new(FlakyStorage {
+                    storage: mirror.clone(),
+                    down: down.clone(),
+                }),
+            ],
+            None,
+            Some(lag.clone()),
+        )
+        .unwrap();
+
+        // The mirror is down, writes still succeed on the primary
+        for index in 0..2 {
+            storage
+                .write_checkpoint(&signed_checkpoint(index).await)
+                .await
+                .unwrap();
+        }
+        storage.update_latest_index(1).await.unwrap();
+        assert_eq!(storage.latest_index().await.unwrap(), Some(1));
+        assert_eq!(lag.with_label_values(&[&mirror_location]).get(), 2);
+        assert_eq!(mirror.fetch_checkpoint(0).await.unwrap(), None);
+
+        // Once it recovers, the latest index isn't advertised before the
+        // checkpoints up to it are in
+        down.store(false, Ordering::SeqCst);
+        storage.update_latest_index(1).await.unwrap();
+        assert_eq!(mirror.latest_index().await.unwrap(), None);
+
+        // and the next write backfills what it missed in the background
+        storage
+            .write_checkpoint(&signed_checkpoint(2).await)
+            .await
+            .unwrap();
+        let mirror_ref = &mirror;
+        eventually(|| async move { mirror_ref.latest_index().await.unwrap() == Some(1) }).await;
+        for index in 0..3 {
+            assert_eq!(
+                mirror.fetch_checkpoint(index).await.unwrap(),
+                Some(signed_checkpoint(index).await)
+            );
+        }
+        assert_eq!(lag.with_label_values(&[&mirror_location]).get(), 0);
+
+        // Writes fail only once every backend fails
+        down.store(true, Ordering::SeqCst);
+        drop(primary_dir);
+        assert!(storage
+            .write_checkpoint(&signed_checkpoint(3).await)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detect_lag_and_announcement_locations() {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let primary = LocalStorage::new(primary_dir.path().into(), None).unwrap();
+        let mirror = LocalStorage::new(mirror_dir.path().into(), None).unwrap();
+        primary
+            .write_checkpoint(&signed_checkpoint(0).await)
+            .await
+            .unwrap();
+        primary.write_latest_index(0).await.unwrap();
+
+        // A backend added to an existing validator starts out empty
+        let storage = MirroredStorage::new(
+            vec![Box::new(primary.clone()), Box::new(mirror.clone())],
+            None,
+            None,
+        )
+        .unwrap();
+        storage.detect_lag().await;
+        storage
+            .write_checkpoint(&signed_checkpoint(1).await)
+            .await
+            .unwrap();
+        let mirror_ref = &mirror;
+        eventually(|| async move { mirror_ref.latest_index().await.unwrap() == Some(0) }).await;
+        assert_eq!(
+            mirror.fetch_checkpoint(0).await.unwrap(),
+            Some(signed_checkpoint(0).await)
+        );
+
+        assert_eq!(
+            storage.announcement_locations(),
+            vec![
+                primary.announcement_location(),
+                mirror.announcement_location()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_announcement_to_down_backend_is_deferred() {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let primary = LocalStorage::new(primary_dir.path().into(), None).unwrap();
+        let mirror = LocalStorage::new(mirror_dir.path().into(), None).unwrap();
+        let down = Arc::new(AtomicBool::new(true));
+        let storage = MirroredStorage::new(
+            vec![
+                Box::new(primary.clone()),
+                Box::new(FlakyStorage {
+                    storage: mirror.clone(),
+                    down: down.clone(),
+                }),
+            ],
+            None,
+            None,
+        )
+        .unwrap();
+
+        let signer: Signers = dummy_validators()[0]
+            .private_key
+            .parse::<ethers::signers::LocalWallet>()
+            .unwrap()
+            .into();
+        let announcement = Announcement {
+            validator: signer.eth_address(),
+            mailbox_address: H256::zero(),
+            mailbox_domain: 1,
+            storage_location: mirror.announcement_location(),
+        };
+        let signed_announcement = signer.sign(announcement).await.unwrap();
+
+        // The backend of the announced location being down doesn't fail the write
+        storage
+            .write_announcement(&signed_announcement)
+            .await
+            .unwrap();
+
+        // and it gets the announcement once it recovers
+        down.store(false, Ordering::SeqCst);
+        storage
+            .write_checkpoint(&signed_checkpoint(0).await)
+            .await
+            .unwrap();
+        let announcement_path = &mirror_dir.path().join("announcement.json");
+        eventually(|| async move { announcement_path.exists() }).await;
+    }
+}
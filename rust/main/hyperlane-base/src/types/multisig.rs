@@ -1,17 +1,100 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use derive_new::new;
 use eyre::Result;
 use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, instrument, warn};
 
 use hyperlane_core::{
     HyperlaneDomain, MultisigSignedCheckpoint, SignedCheckpointWithMessageId, H160, H256,
 };
 
+use crate::db::{HyperlaneRocksDB, ValidatorReliability};
 use crate::{CheckpointSyncer, CoreMetrics};
 
+/// Validators whose checkpoint fetches succeed less often than this are
+/// only asked for signatures after every other validator.
+const MIN_RELIABLE_SUCCESS_RATIO: f64 = 0.5;
+
+/// How the validators whose signatures make up a quorum are selected.
+///
+/// Signatures are fetched in batches, in the order given by the strategy,
+/// until a quorum is found. Whichever validators are chosen, the signatures
+/// are always returned in validator set order, as the multisig ISMs expect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuorumSelectionStrategy {
+    /// Ask validators in validator set order. The quorum is then made of the
+    /// lowest indexed validators, which the multisig ISMs verify with the
+    /// least gas as they scan the validator set in order.
+    #[default]
+    MinimizeGas,
+    /// Ask the validators that historically served their signed checkpoints
+    /// the fastest first. Validators that often fail are asked last.
+    LowestLatency,
+    /// Ask the validators that reported the highest latest checkpoint index
+    /// first, as they are the most likely to have signed recent checkpoints.
+    FreshestCheckpoint,
+}
+
+/// Keeps track of how reliably each validator of an origin serves signed
+/// checkpoints, persisting it in the origin's database and reporting it as
+/// metrics.
+///
+/// Updates are a read-modify-write of the stored record, so concurrent
+/// fetches from the same validator may occasionally overwrite each other.
+/// This is acceptable for a moving estimate.
+#[derive(Clone, Debug, new)]
+pub struct ValidatorReliabilityTracker {
+    db: HyperlaneRocksDB,
+    metrics: Arc<CoreMetrics>,
+}
+
+impl ValidatorReliabilityTracker {
+    /// The recorded reliability of a validator
+    pub fn get(&self, validator: &H160) -> ValidatorReliability {
+        self.db
+            .retrieve_validator_reliability(validator)
+            .unwrap_or_else(|err| {
+                warn!(?validator, ?err, "Failed to retrieve validator reliability");
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    /// Record the outcome of fetching a signed checkpoint from a validator
+    pub fn record_fetch(&self, validator: &H160, success: bool, latency: Duration) {
+        let mut reliability = self.get(validator);
+        reliability.record_fetch(success, latency.as_secs_f64() * 1000.0);
+        self.store(validator, &reliability);
+    }
+
+    /// Record the latest checkpoint index reported by a validator
+    pub fn record_latest_index(&self, validator: &H160, latest_index: u32) {
+        let mut reliability = self.get(validator);
+        if reliability.latest_index == Some(latest_index) {
+            return;
+        }
+        reliability.latest_index = Some(latest_index);
+        self.store(validator, &reliability);
+    }
+
+    fn store(&self, validator: &H160, reliability: &ValidatorReliability) {
+        if let Err(err) = self.db.store_validator_reliability(validator, reliability) {
+            warn!(?validator, ?err, "Failed to store validator reliability");
+        }
+        self.metrics.validator_metrics.set_validator_reliability(
+            self.db.domain(),
+            validator,
+            reliability,
+        );
+    }
+}
+
 /// For a particular validator set, fetches signed checkpoints from multiple
 /// validators to create MultisigSignedCheckpoints.
 #[derive(Clone, Debug, new)]
@@ -19,9 +102,27 @@ pub struct MultisigCheckpointSyncer {
     /// The checkpoint syncer for each valid validator signer address
     checkpoint_syncers: HashMap<H160, Arc<dyn CheckpointSyncer>>,
     metrics: Option<(Arc<CoreMetrics>, String)>, // first arg is the metrics, second is the app context
+    /// The order in which validators are asked for signed checkpoints
+    #[new(default)]
+    strategy: QuorumSelectionStrategy,
+    /// Validator reliability, used to order validators and updated on every fetch
+    #[new(default)]
+    reliability: Option<ValidatorReliabilityTracker>,
 }
 
 impl MultisigCheckpointSyncer {
+    /// Sets the quorum selection strategy, and the tracker of validator
+    /// reliability it is based on.
+    pub fn with_quorum_selection(
+        mut self,
+        strategy: QuorumSelectionStrategy,
+        reliability: Option<ValidatorReliabilityTracker>,
+    ) -> Self {
+        self.strategy = strategy;
+        self.reliability = reliability;
+        self
+    }
+
     /// Gets the latest checkpoint index from each validator's checkpoint syncer.
     /// Returns a vector of the latest indices, in an unspecified order, and does
     /// not contain indices for validators that did not provide a latest index.
@@ -66,6 +167,9 @@ impl MultisigCheckpointSyncer {
             match latest_index {
                 Ok(Some(index)) => {
                     debug!(?validator, ?index, "Validator returned latest index");
+                    if let Some(reliability) = &self.reliability {
+                        reliability.record_latest_index(validator, index);
+                    }
                     latest_indices.insert(*validator, Some(index));
                 }
                 result => {
@@ -148,8 +252,12 @@ impl MultisigCheckpointSyncer {
                 return Ok(None);
             }
 
+            let latest_indices = latest_indices.into_iter().collect::<HashMap<_, _>>();
+            let fetch_order = self.fetch_order(validators, Some(&latest_indices));
             for index in (minimum_index..=start_index).rev() {
-                let checkpoint_res = self.fetch_checkpoint(validators, threshold, index).await;
+                let checkpoint_res = self
+                    .fetch_checkpoint_in_order(validators, &fetch_order, threshold, index)
+                    .await;
                 if let Ok(Some(checkpoint)) = checkpoint_res {
                     return Ok(Some(checkpoint));
                 }
@@ -169,11 +277,86 @@ impl MultisigCheckpointSyncer {
         threshold: usize,
         index: u32,
     ) -> Result<Option<MultisigSignedCheckpoint>> {
+        let fetch_order = self.fetch_order(validators, None);
+        self.fetch_checkpoint_in_order(validators, &fetch_order, threshold, index)
+            .await
+    }
+
+    /// The order in which validators are asked for signed checkpoints,
+    /// according to the quorum selection strategy.
+    ///
+    /// `latest_indices` are the latest indices just reported by validators,
+    /// otherwise the last recorded ones are used.
+    fn fetch_order(
+        &self,
+        validators: &[H256],
+        latest_indices: Option<&HashMap<H160, u32>>,
+    ) -> Vec<H256> {
+        let mut fetch_order = validators.to_vec();
+        // Sorts are stable, so validators that compare equal are still asked
+        // in validator set order.
+        match (self.strategy, &self.reliability) {
+            (QuorumSelectionStrategy::MinimizeGas, _) => {}
+            (QuorumSelectionStrategy::LowestLatency, Some(reliability)) => {
+                fetch_order.sort_by_cached_key(|validator| {
+                    let reliability = reliability.get(&H160::from(*validator));
+                    let unreliable = reliability
+                        .success_ratio()
+                        .is_some_and(|ratio| ratio < MIN_RELIABLE_SUCCESS_RATIO);
+                    (unreliable, reliability.avg_latency_ms as u64)
+                });
+            }
+            (QuorumSelectionStrategy::FreshestCheckpoint, reliability) => {
+                fetch_order.sort_by_cached_key(|validator| {
+                    let validator = H160::from(*validator);
+                    let latest_index = match latest_indices {
+                        Some(latest_indices) => latest_indices.get(&validator).copied(),
+                        None => reliability
+                            .as_ref()
+                            .and_then(|reliability| reliability.get(&validator).latest_index),
+                    };
+                    Reverse(latest_index)
+                });
+            }
+            (QuorumSelectionStrategy::LowestLatency, None) => {
+                debug!("No validator reliability tracked, fetching in validator set order");
+            }
+        }
+        fetch_order
+    }
+
+    /// Records the outcome of fetching a signed checkpoint from a validator
+    fn record_fetch(&self, validator: &H256, success: bool, latency: Duration) {
+        if let Some(reliability) = &self.reliability {
+            reliability.record_fetch(&H160::from(*validator), success, latency);
+        }
+    }
+
+    /// Fetches a MultisigSignedCheckpointWithMessageId if there is a quorum,
+    /// asking validators in `fetch_order`.
+    /// Validators must reflect the onchain ordering of the set
+    async fn fetch_checkpoint_in_order(
+        &self,
+        validators: &[H256],
+        fetch_order: &[H256],
+        threshold: usize,
+        index: u32,
+    ) -> Result<Option<MultisigSignedCheckpoint>> {
+        // Position of each validator in the set, to return the signatures
+        // in the onchain order regardless of the order they were fetched in
+        let positions: HashMap<H256, usize> = validators
+            .iter()
+            .enumerate()
+            .map(|(position, validator)| (*validator, position))
+            .collect();
+
         // Keeps track of signed validator checkpoints for a particular root.
         // In practice, it's likely that validators will all sign the same root for a
         // particular index, but we'd like to be robust to this not being the case
-        let mut signed_checkpoints_per_root: HashMap<H256, Vec<SignedCheckpointWithMessageId>> =
-            HashMap::new();
+        let mut signed_checkpoints_per_root: HashMap<
+            H256,
+            Vec<(usize, SignedCheckpointWithMessageId)>,
+        > = HashMap::new();
 
         // we iterate in batches of N=threshold*1.5 to avoid waiting for all validators.
         // This reaches a quorum faster without having to fetch all the signatures.
@@ -182,7 +365,7 @@ impl MultisigCheckpointSyncer {
         let batch_size = (threshold as f64 * 1.5) as usize;
         let batch_size = batch_size.clamp(1, 10);
 
-        for validators in validators.chunks(batch_size) {
+        for validators in fetch_order.chunks(batch_size) {
             // Go through each validator and get the checkpoint syncer.
             // Create a future for each validator that fetches its signed checkpoint
             let futures = validators
@@ -197,13 +380,17 @@ impl MultisigCheckpointSyncer {
                 })
                 .map(|(address, syncer)| {
                     let checkpoint_syncer = syncer.clone();
-                    async move { (address, checkpoint_syncer.fetch_checkpoint(index).await) }
+                    async move {
+                        let start = Instant::now();
+                        let checkpoint = checkpoint_syncer.fetch_checkpoint(index).await;
+                        (address, checkpoint, start.elapsed())
+                    }
                 })
                 .collect::<Vec<_>>();
 
             let checkpoints = futures::future::join_all(futures).await;
 
-            for (validator, checkpoint) in checkpoints {
+            for (validator, checkpoint, latency) in checkpoints {
                 // Gracefully ignore an error fetching the checkpoint from a validator's
                 // checkpoint syncer, which can happen if the validator has not
                 // signed the checkpoint at `index`.
@@ -216,6 +403,7 @@ impl MultisigCheckpointSyncer {
                             checkpoint_index = signed_checkpoint.value.index,
                             "Checkpoint index mismatch"
                         );
+                        self.record_fetch(validator, false, latency);
                         continue;
                     }
 
@@ -228,13 +416,15 @@ impl MultisigCheckpointSyncer {
                             index = index,
                             "Checkpoint signature mismatch"
                         );
+                        self.record_fetch(validator, false, latency);
                         continue;
                     }
+                    self.record_fetch(validator, true, latency);
 
                     // Push the signed checkpoint into the hashmap
                     let root = signed_checkpoint.value.root;
                     let signed_checkpoints = signed_checkpoints_per_root.entry(root).or_default();
-                    signed_checkpoints.push(signed_checkpoint);
+                    signed_checkpoints.push((positions[validator], signed_checkpoint));
 
                     // Count the number of signatures for this signed checkpoint
                     let signature_count = signed_checkpoints.len();
@@ -248,7 +438,13 @@ impl MultisigCheckpointSyncer {
 
                     // If we've hit a quorum, create a MultisigSignedCheckpoint
                     if signature_count >= threshold {
-                        let checkpoint: MultisigSignedCheckpoint = signed_checkpoints.try_into()?;
+                        signed_checkpoints.sort_by_key(|(position, _)| *position);
+                        let mut signed_checkpoints = signed_checkpoints
+                            .drain(..)
+                            .map(|(_, signed_checkpoint)| signed_checkpoint)
+                            .collect::<Vec<_>>();
+                        let checkpoint: MultisigSignedCheckpoint =
+                            (&mut signed_checkpoints).try_into()?;
                         debug!(checkpoint=?checkpoint, "Fetched multisig checkpoint");
                         return Ok(Some(checkpoint));
                     }
@@ -258,6 +454,7 @@ impl MultisigCheckpointSyncer {
                         index = index,
                         "Unable to find signed checkpoint"
                     );
+                    self.record_fetch(validator, false, latency);
                 }
             }
         }
@@ -277,6 +474,7 @@ pub mod test {
     use hyperlane_ethereum::Signers;
 
     use crate::{
+        db::test_utils::run_test_db,
         tests::{dummy_validators, mock_checkpoint_syncer::MockCheckpointSyncer, TestValidator},
         S3Storage,
     };
@@ -591,4 +789,76 @@ pub mod test {
         let expected = Some(generate_multisig_signed_checkpoint(&validators, checkpoint).await);
         assert_eq!(result, expected);
     }
+
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn test_fetch_checkpoint_lowest_latency() {
+        run_test_db(|db| async move {
+            let checkpoint = CheckpointWithMessageId {
+                checkpoint: Checkpoint {
+                    mailbox_domain: 100,
+                    merkle_tree_hook_address: H256::zero(),
+                    root: H256::zero(),
+                    index: 1000,
+                },
+                message_id: H256::zero(),
+            };
+
+            let mut validators: Vec<_> = dummy_validators().drain(..).take(5).collect();
+            for validator in validators.iter_mut() {
+                validator.fetch_checkpoint = Some(checkpoint.clone());
+            }
+
+            let syncers = build_mock_checkpoint_syncs(&validators).await;
+            let validator_addresses = validators
+                .iter()
+                .map(|validator| {
+                    let address: H256 = H160::from_str(&validator.public_key).unwrap().into();
+                    address
+                })
+                .collect::<Vec<_>>();
+
+            let db = HyperlaneRocksDB::new(
+                &HyperlaneDomain::new_test_domain("test_fetch_checkpoint_lowest_latency"),
+                db,
+            );
+            let metrics = Arc::new(CoreMetrics::new("test", 8080, Default::default()).unwrap());
+            let tracker = ValidatorReliabilityTracker::new(db, metrics);
+
+            // The first validator is the slowest, so it's asked last
+            for (validator, latency_ms) in validator_addresses.iter().zip([1000, 10, 20, 30, 40]) {
+                tracker.record_fetch(
+                    &H160::from(*validator),
+                    true,
+                    Duration::from_millis(latency_ms),
+                );
+            }
+
+            let multisig_syncer = MultisigCheckpointSyncer::new(syncers, None)
+                .with_quorum_selection(
+                    QuorumSelectionStrategy::LowestLatency,
+                    Some(tracker.clone()),
+                );
+
+            let threshold = 2;
+            let index = 1000;
+            let result = multisig_syncer
+                .fetch_checkpoint(validator_addresses.as_slice(), threshold, index)
+                .await
+                .unwrap();
+
+            let expected =
+                Some(generate_multisig_signed_checkpoint(&validators[1..3], checkpoint).await);
+            assert_eq!(result, expected);
+
+            // The quorum was found in the first batch, before the third validator
+            // of the batch was looked at
+            let successes = validator_addresses
+                .iter()
+                .map(|validator| tracker.get(&H160::from(*validator)).successes)
+                .collect::<Vec<_>>();
+            assert_eq!(successes, vec![1, 2, 2, 1, 1]);
+        })
+        .await;
+    }
 }
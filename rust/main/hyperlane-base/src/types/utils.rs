@@ -1,7 +1,9 @@
 use std::time::Duration;
 
-use eyre::Result;
+use eyre::{bail, Result};
+use reqwest::Response;
 use rusoto_core::{HttpClient, HttpConfig};
+use url::Url;
 
 /// See https://github.com/hyperium/hyper/issues/2136#issuecomment-589488526
 pub const HYPER_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(15);
@@ -13,3 +15,25 @@ pub fn http_client_with_timeout() -> Result<HttpClient> {
     config.pool_idle_timeout(HYPER_POOL_IDLE_TIMEOUT);
     Ok(HttpClient::new_with_config(config)?)
 }
+
+/// Read the body of the `response` to a request for `url`, without
+/// downloading more than `max_size` bytes.
+pub async fn read_capped_body(url: &Url, mut response: Response, max_size: u64) -> Result<Vec<u8>> {
+    if let Some(length) = response.content_length() {
+        if length > max_size {
+            bail!("Object size for {url} is too big: {}KiB", length / 1024);
+        }
+    }
+    // The content length may be missing or wrong, so the body is capped as it streams in
+    let mut body = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        if (body.len() + chunk.len()) as u64 > max_size {
+            bail!(
+                "Object size for {url} is too big: over {}KiB",
+                max_size / 1024
+            );
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
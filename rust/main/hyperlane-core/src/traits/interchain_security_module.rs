@@ -42,6 +42,14 @@ pub enum ModuleType {
     Null,
     /// Ccip Read ISM (accepts offchain signature information)
     CcipRead,
+    /// Arbitrum L2 to L1 ISM (verified via the Arbitrum native bridge)
+    ArbL2ToL1,
+    /// Weighted Merkle Proof ISM (validators with individual signing weights)
+    WeightedMerkleRootMultisig,
+    /// Weighted Message ID ISM (validators with individual signing weights)
+    WeightedMessageIdMultisig,
+    /// OP Stack L2 to L1 ISM (verified via the OP Stack native bridge)
+    OpL2ToL1,
 }
 
 impl ModuleType {
@@ -56,6 +64,10 @@ impl ModuleType {
             Self::MessageIdMultisig => "message_id_multisig",
             Self::Null => "null",
             Self::CcipRead => "ccip_read",
+            Self::ArbL2ToL1 => "arb_l2_to_l1",
+            Self::WeightedMerkleRootMultisig => "weighted_merkle_root_multisig",
+            Self::WeightedMessageIdMultisig => "weighted_message_id_multisig",
+            Self::OpL2ToL1 => "op_l2_to_l1",
         }
     }
 }
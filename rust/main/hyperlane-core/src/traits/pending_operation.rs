@@ -165,6 +165,10 @@ pub trait PendingOperation: Send + Sync + Debug + TryBatchAs<HyperlaneMessage> {
     /// Get the number of times this operation has been retried.
     fn get_retries(&self) -> u32;
 
+    /// Drop this operation at an operator's request, so that it is not
+    /// attempted again, even after a restart.
+    fn drop_on_request(&mut self);
+
     /// If this operation points to a mailbox contract, return it
     fn try_get_mailbox(&self) -> Option<Arc<dyn Mailbox>> {
         None
@@ -293,6 +297,9 @@ pub enum ReprepareReason {
     #[strum(to_string = "Failed to create payload success criteria")]
     /// Failed to create payload success criteria
     ErrorCreatingPayloadSuccessCriteria,
+    #[strum(to_string = "Rate limit exceeded")]
+    /// The operation exceeded a rate limit and was deferred
+    RateLimited,
 }
 
 #[derive(Display, Debug, Clone, Serialize, Deserialize, PartialEq)]
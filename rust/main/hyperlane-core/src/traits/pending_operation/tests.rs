@@ -77,6 +77,7 @@ impl PendingOperation for MockQueueOperation {
     fn get_retries(&self) -> u32 {
         0
     }
+    fn drop_on_request(&mut self) {}
     async fn payload(&self) -> ChainResult<Vec<u8>> {
         unimplemented!()
     }
@@ -454,6 +454,12 @@ impl FromStr for FixedPointNumber {
     }
 }
 
+impl std::fmt::Display for FixedPointNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -12,8 +12,12 @@ pub fn get_connection_conf(conf: &ChainConf) -> &ConnectionConf {
 }
 
 pub fn create_keypair(conf: &ChainConf) -> eyre::Result<SealevelKeypair> {
-    let signer = conf.signer.as_ref().wrap_err("Signer is missing")?;
-    let key = match signer {
+    let signer = conf
+        .signer
+        .as_ref()
+        .wrap_err("Signer is missing")?
+        .decrypt_keystore()?;
+    let key = match &signer {
         SignerConf::HexKey { key } => key,
         _ => bail!("Sealevel supports only hex key"),
     };
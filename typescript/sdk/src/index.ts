@@ -253,7 +253,9 @@ export {
   AgentSignerAwsKey,
   AgentSignerHexKey,
   AgentSignerKeyType,
+  AgentSignerKeystore,
   AgentSignerNode,
+  AgentSignerRemote,
   buildAgentConfig,
   GasPaymentEnforcement,
   GasPaymentEnforcementPolicyType,
@@ -264,6 +266,7 @@ export {
   RpcConsensusType,
   ScraperConfig,
   ValidatorConfig,
+  WatchtowerConfig,
 } from './metadata/agentConfig.js';
 export {
   ChainMetadataManager,
@@ -51,6 +51,8 @@ export enum AgentSignerKeyType {
   Node = 'node',
   Cosmos = 'cosmosKey',
   Starknet = 'starkKey',
+  Remote = 'remote',
+  Keystore = 'keystore',
 }
 
 export enum AgentSealevelPriorityFeeOracleType {
@@ -73,6 +75,12 @@ export enum AgentSealevelTransactionSubmitterType {
   Jito = 'jito',
 }
 
+export enum RelayerQuorumSelectionStrategy {
+  MinimizeGas = 'minimizeGas',
+  LowestLatency = 'lowestLatency',
+  FreshestCheckpoint = 'freshestCheckpoint',
+}
+
 const AgentSignerHexKeySchema = z
   .object({
     type: z.literal(AgentSignerKeyType.Hex).optional(),
@@ -100,18 +108,66 @@ const AgentSignerNodeSchema = z
     type: z.literal(AgentSignerKeyType.Node),
   })
   .describe('Assume the local node will sign on RPC calls automatically');
+const AgentSignerRemoteSchema = z
+  .object({
+    type: z.literal(AgentSignerKeyType.Remote),
+    url: z.string().url().describe('The base url of the signing service'),
+    publicKey: z
+      .string()
+      .regex(/^(0x)?[0-9a-fA-F]+$/)
+      .describe('The hex encoded secp256k1 public key to sign with'),
+    tls: z
+      .object({
+        caCert: z
+          .string()
+          .min(1)
+          .optional()
+          .describe('Path to the PEM encoded CA certificate to trust'),
+        clientCert: z
+          .string()
+          .min(1)
+          .optional()
+          .describe('Path to the PEM encoded client certificate'),
+        clientKey: z
+          .string()
+          .min(1)
+          .optional()
+          .describe('Path to the PEM encoded PKCS #8 client key'),
+      })
+      .optional(),
+  })
+  .describe(
+    'A remote signing service implementing the Web3Signer eth1 signing API, e.g. in front of an HSM',
+  );
+
+const AgentSignerKeystoreSchema = z
+  .object({
+    type: z.literal(AgentSignerKeyType.Keystore),
+    path: z.string().min(1).describe('Path to the encrypted JSON keystore'),
+    passwordFile: z
+      .string()
+      .min(1)
+      .describe('Path to a file holding the password of the keystore'),
+  })
+  .describe(
+    'A key in an encrypted JSON keystore, as written by geth, decrypted at startup. Keys other than hex keys list the fields of their signer type, e.g. `"keyType": "cosmosKey"` and `"prefix"`, next to the `crypto` section.',
+  );
 
 const AgentSignerSchema = z.union([
   AgentSignerHexKeySchema,
   AgentSignerAwsKeySchema,
   AgentSignerCosmosKeySchema,
   AgentSignerNodeSchema,
+  AgentSignerRemoteSchema,
+  AgentSignerKeystoreSchema,
 ]);
 
 export type AgentSignerHexKey = z.infer<typeof AgentSignerHexKeySchema>;
 export type AgentSignerAwsKey = z.infer<typeof AgentSignerAwsKeySchema>;
 export type AgentSignerCosmosKey = z.infer<typeof AgentSignerNodeSchema>;
 export type AgentSignerNode = z.infer<typeof AgentSignerNodeSchema>;
+export type AgentSignerRemote = z.infer<typeof AgentSignerRemoteSchema>;
+export type AgentSignerKeystore = z.infer<typeof AgentSignerKeystoreSchema>;
 export type AgentSigner = z.infer<typeof AgentSignerSchema>;
 
 // Additional chain metadata for Cosmos chains required by the agents.
@@ -317,6 +373,7 @@ export enum GasPaymentEnforcementPolicyType {
   None = 'none',
   Minimum = 'minimum',
   OnChainFeeQuoting = 'onChainFeeQuoting',
+  TokenPriceQuoting = 'tokenPriceQuoting',
 }
 
 const GasPaymentEnforcementBaseSchema = z.object({
@@ -339,6 +396,22 @@ const GasPaymentEnforcementSchema = z.union([
       .regex(/^\d+ ?\/ ?[1-9]\d*$/)
       .optional(),
   }),
+  GasPaymentEnforcementBaseSchema.extend({
+    type: z.literal(GasPaymentEnforcementPolicyType.TokenPriceQuoting),
+    priceSource: z
+      .string()
+      .min(1)
+      .describe(
+        'Path to a JSON file or http(s) URL serving native token prices keyed by domain id, e.g. `{ "1": { "price": 3000.5, "decimals": 18 } }`.',
+      ),
+    costFraction: z
+      .string()
+      .regex(/^\d+ ?\/ ?[1-9]\d*$/)
+      .optional()
+      .describe(
+        'The fraction of the value spent on the destination that the origin payment must cover. Defaults to 1/1.',
+      ),
+  }),
 ]);
 export type GasPaymentEnforcement = z.infer<typeof GasPaymentEnforcementSchema>;
 
@@ -349,6 +422,102 @@ const MetricAppContextSchema = z.object({
   ),
 });
 
+export enum RateLimitScope {
+  Shared = 'shared',
+  Sender = 'sender',
+  Recipient = 'recipient',
+  Route = 'route',
+}
+
+const RateLimitSchema = z.object({
+  matchingList: MatchingListSchema.optional().describe(
+    'An optional matching list, any message that matches is subject to this limit. By default all messages will match.',
+  ),
+  capacity: z
+    .number()
+    .int()
+    .positive()
+    .describe(
+      'The maximum number of messages that can be prepared in a burst.',
+    ),
+  refillRate: z
+    .number()
+    .positive()
+    .describe(
+      'The number of messages per second the limit is refilled with.',
+    ),
+  scope: z
+    .nativeEnum(RateLimitScope)
+    .optional()
+    .describe(
+      'How matching messages are grouped: `shared` (default) uses one limit for all of them, `sender` one per origin and sender, `recipient` one per destination and recipient, and `route` one per origin and destination pair.',
+    ),
+});
+export type RateLimitConfig = z.infer<typeof RateLimitSchema>;
+
+export enum WebhookEvent {
+  Transition = 'transition',
+  Delivered = 'delivered',
+  Dropped = 'dropped',
+  Stuck = 'stuck',
+}
+
+const WebhookSchema = z.object({
+  name: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'A unique name identifying the webhook. Defaults to its URL, so webhooks sharing a URL must be given names.',
+    ),
+  url: z.string().url().describe('The URL the events are POSTed to.'),
+  secret: z
+    .string()
+    .min(1)
+    .describe(
+      'The secret used to sign request bodies. The `X-Hyperlane-Signature` header holds `sha256=` followed by the hex encoded HMAC-SHA256 of the body.',
+    ),
+  matchingList: MatchingListSchema.optional().describe(
+    'An optional matching list, only events of matching messages are sent. By default all messages will match.',
+  ),
+  events: z
+    .array(z.nativeEnum(WebhookEvent))
+    .optional()
+    .describe(
+      'The events the webhook is notified of. Defaults to `delivered`, `dropped` and `stuck`.',
+    ),
+  stuckAfterRetries: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe(
+      'The number of retries after which a message is reported as stuck. Defaults to 10.',
+    ),
+});
+
+export type WebhookConfig = z.infer<typeof WebhookSchema>;
+
+const CcipReadGatewayTimeoutSchema = z.object({
+  host: z.string().min(1).describe('The host name of the gateway.'),
+  timeout: z
+    .number()
+    .int()
+    .positive()
+    .describe('The timeout of requests to the gateway, in seconds.'),
+});
+
+const AppContextWeightSchema = z.object({
+  appContext: z.string().min(1),
+  weight: z
+    .number()
+    .int()
+    .positive()
+    .describe(
+      'The relative share of the delivery capacity given to this app context.',
+    ),
+});
+
 export enum IsmCachePolicy {
   MessageSpecific = 'messageSpecific',
   IsmSpecific = 'ismSpecific',
@@ -459,6 +628,42 @@ export const RelayerAgentConfigSchema = AgentConfigSchema.extend({
     .boolean()
     .optional()
     .describe('Whether to enable IGP indexing'),
+  rateLimits: z
+    .union([z.array(RateLimitSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'Token bucket rate limits on how fast matching messages are prepared for delivery. Messages exceeding a limit are deferred until it has been refilled.',
+    ),
+  appContextWeights: z
+    .union([z.array(AppContextWeightSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'If set, messages are queued per app context (see `metricAppContexts`) and the app contexts are served according to their weights, so that a backlog in one does not delay the others. App contexts that are not listed have a weight of 1.',
+    ),
+  shadowMode: z
+    .boolean()
+    .optional()
+    .describe(
+      'If true, messages are fully prepared and what would have been submitted is recorded, but nothing is ever submitted to the destination chains.',
+    ),
+  webhooks: z
+    .union([z.array(WebhookSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'Webhooks notified of message lifecycle events. Events are stored in the relayer database until delivered, and retried with exponential backoff.',
+    ),
+  quorumSelectionStrategy: z
+    .nativeEnum(RelayerQuorumSelectionStrategy)
+    .optional()
+    .describe(
+      'The order in which validators are asked for signed checkpoints when building multisig metadata. `minimizeGas` (the default) asks them in validator set order, `lowestLatency` asks the historically fastest and most reliable validators first, and `freshestCheckpoint` asks the validators with the highest latest checkpoint index first.',
+    ),
+  ccipReadGatewayTimeouts: z
+    .union([z.array(CcipReadGatewayTimeoutSchema), z.string().min(1)])
+    .optional()
+    .describe(
+      'Timeouts of requests to CCIP read gateways, by gateway host. Gateways that are not listed time out after 30 seconds.',
+    ),
 });
 
 export type RelayerConfig = z.infer<typeof RelayerAgentConfigSchema>;
@@ -472,6 +677,123 @@ export const ScraperAgentConfigSchema = AgentConfigSchema.extend({
 
 export type ScraperConfig = z.infer<typeof ScraperAgentConfigSchema>;
 
+export const WatchtowerAgentConfigSchema = AgentConfigSchema.extend({
+  db: z
+    .string()
+    .min(1)
+    .optional()
+    .describe('The path to the watchtower database.'),
+  chainsToWatch: CommaSeparatedChainList.describe(
+    'Comma separated list of origin chain names to watch',
+  ),
+  validators: z
+    .record(z.array(ZHash))
+    .describe(
+      'The addresses of the validators to watch, by origin chain name. Checkpoints are read from the storage locations each validator announced.',
+    ),
+  interval: ZUint.optional().describe(
+    'How long to wait between checking for new checkpoints in seconds.',
+  ),
+  announcementRefreshInterval: ZUint.optional().describe(
+    'How long to wait between refreshing the announced storage locations in seconds.',
+  ),
+  evidenceDir: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'The directory the evidence of each fraud detected is written to, as JSON.',
+    ),
+  allowLocalCheckpointSyncers: z
+    .boolean()
+    .optional()
+    .describe(
+      'Whether to read checkpoints from announced local storage locations.',
+    ),
+});
+
+export type WatchtowerConfig = z.infer<typeof WatchtowerAgentConfigSchema>;
+
+const CheckpointSyncerSchema = z.discriminatedUnion('type', [
+  z
+    .object({
+      type: z.literal('localStorage'),
+      path: z.string().min(1).describe('Path to the local storage location'),
+    })
+    .describe('A local checkpoint syncer'),
+  z
+    .object({
+      type: z.literal('s3'),
+      bucket: z.string().min(1),
+      region: z.string().min(1),
+      folder: z
+        .string()
+        .min(1)
+        .optional()
+        .describe(
+          'The folder/key-prefix to use, defaults to the root of the bucket',
+        ),
+    })
+    .describe('A checkpoint syncer that uses S3'),
+  z
+    .object({
+      type: z.literal('gcs'),
+      bucket: z.string().min(1),
+      folder: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The folder to use, defaults to the root of the bucket'),
+      service_account_key: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The path to GCS service account key file'),
+      user_secrets: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The path to GCS user secret file'),
+    })
+    .describe('A checkpoint syncer that uses Google Cloud Storage'),
+  z
+    .object({
+      type: z.literal('azure'),
+      account: z.string().min(1).describe('The storage account name'),
+      container: z.string().min(1),
+      folder: z
+        .string()
+        .min(1)
+        .optional()
+        .describe('The folder to use, defaults to the root of the container'),
+      endpoint: z
+        .string()
+        .url()
+        .optional()
+        .describe(
+          'The Blob service endpoint, e.g. of a local emulator. Defaults to the public endpoint of the account. The account access key is read from the AZURE_STORAGE_KEY env var, and only used if AZURE_STORAGE_ACCOUNT names this account.',
+        ),
+    })
+    .describe('A checkpoint syncer that uses Azure Blob Storage'),
+]);
+
+const ValidatorCheckpointSyncerSchema = z.union([
+  CheckpointSyncerSchema,
+  z
+    .object({
+      type: z.literal('mirrored'),
+      syncers: z
+        .array(CheckpointSyncerSchema)
+        .min(1)
+        .describe(
+          'The checkpoint syncers to write to, reads prefer them in this order',
+        ),
+    })
+    .describe(
+      'A checkpoint syncer that writes to several others, so that checkpoints stay available while one of them is down. Each of them is announced.',
+    ),
+]);
+
 export const ValidatorAgentConfigSchema = AgentConfigSchema.extend({
   db: z
     .string()
@@ -481,54 +803,57 @@ export const ValidatorAgentConfigSchema = AgentConfigSchema.extend({
   originChainName: z
     .string()
     .min(1)
-    .describe('Name of the chain to validate messages on'),
+    .describe(
+      'Name of the chain to validate messages on, or a comma separated list of names to validate several chains from one process. With several chains, the checkpoints of each are written under a folder named after it.',
+    ),
   validator: AgentSignerSchema.describe('The validator attestation signer'),
-  checkpointSyncer: z.discriminatedUnion('type', [
-    z
-      .object({
-        type: z.literal('localStorage'),
-        path: z.string().min(1).describe('Path to the local storage location'),
-      })
-      .describe('A local checkpoint syncer'),
-    z
-      .object({
-        type: z.literal('s3'),
-        bucket: z.string().min(1),
-        region: z.string().min(1),
-        folder: z
-          .string()
-          .min(1)
-          .optional()
-          .describe(
-            'The folder/key-prefix to use, defaults to the root of the bucket',
-          ),
-      })
-      .describe('A checkpoint syncer that uses S3'),
-    z
-      .object({
-        type: z.literal('gcs'),
-        bucket: z.string().min(1),
-        folder: z
-          .string()
-          .min(1)
-          .optional()
-          .describe('The folder to use, defaults to the root of the bucket'),
-        service_account_key: z
-          .string()
-          .min(1)
-          .optional()
-          .describe('The path to GCS service account key file'),
-        user_secrets: z
-          .string()
-          .min(1)
-          .optional()
-          .describe('The path to GCS user secret file'),
-      })
-      .describe('A checkpoint syncer that uses Google Cloud Storage'),
-  ]),
+  checkpointSyncer: ValidatorCheckpointSyncerSchema,
   interval: ZUint.optional().describe(
     'How long to wait between checking for new checkpoints in seconds.',
   ),
+  auditCheckpoints: z
+    .boolean()
+    .optional()
+    .describe(
+      'Instead of validating, audit the checkpoints published to each announced storage location against the locally indexed merkle tree and exit.',
+    ),
+  keyRotation: z
+    .object({
+      validator: AgentSignerSchema.describe(
+        'The validator attestation signer being rotated to',
+      ),
+      checkpointSyncer: ValidatorCheckpointSyncerSchema.describe(
+        'Where the signatures of the new key are written, which must differ from the validator checkpoint syncer',
+      ),
+      endsAt: ZUint.describe(
+        'Unix timestamp in seconds until which checkpoints are signed with both keys. Afterwards only the new key signs.',
+      ),
+    })
+    .optional()
+    .describe(
+      'Rotate the validator key by also signing checkpoints with, and announcing, a new key.',
+    ),
+  slashingProtectionDb: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'The path to the slashing protection database, which records every signed checkpoint. Defaults to the validator database path suffixed with `_slashing_protection`.',
+    ),
+  slashingProtectionImport: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'The path to a slashing protection interchange file to import on startup, e.g. when migrating the validator to another host. With several origin chains, a directory holding a `<chain name>.json` file per chain.',
+    ),
+  slashingProtectionExport: z
+    .string()
+    .min(1)
+    .optional()
+    .describe(
+      'Instead of validating, export the slashing protection database to an interchange file at this path and exit, with a non-zero exit code if the export fails. With several origin chains, a directory that a `<chain name>.json` file per chain is written to.',
+    ),
 });
 
 export type ValidatorConfig = z.infer<typeof ValidatorAgentConfigSchema>;
@@ -4,7 +4,7 @@
  */
 import { z } from 'zod';
 
-import { ZHash, ZNzUint } from './customZodTypes.js';
+import { ZHash, ZNzUint, ZUint } from './customZodTypes.js';
 
 const DomainSchema = z.union([
   z.literal('*'),
@@ -14,12 +14,38 @@ const DomainSchema = z.union([
 
 const AddressSchema = z.union([z.literal('*'), ZHash, z.array(ZHash)]);
 
+const AmountSchema = z.union([
+  ZUint,
+  z.string().regex(/^(\d+|0x[0-9a-fA-F]+)$/),
+]);
+
 const MatchingListElementSchema = z.object({
   messageId: AddressSchema.optional(),
   originDomain: DomainSchema.optional(),
   senderAddress: AddressSchema.optional(),
   destinationDomain: DomainSchema.optional(),
   recipientAddress: AddressSchema.optional(),
+  bodyPrefix: z
+    .string()
+    .regex(/^(0x)?([0-9a-fA-F]{2})+$/)
+    .optional()
+    .describe('Hex encoded bytes the message body must start with'),
+  bodySelector: z
+    .string()
+    .regex(/^((0x)?[0-9a-fA-F]{8}|\w+\(.*\))$/)
+    .optional()
+    .describe(
+      'Function selector the message body must start with, either as 4 hex encoded bytes or as a function signature like `transfer(address,uint256)`',
+    ),
+  tokenAmount: z
+    .object({
+      min: AmountSchema.optional(),
+      max: AmountSchema.optional(),
+    })
+    .optional()
+    .describe(
+      'Inclusive range the amount of a warp route token message body must be in. Only bodies with the length of a token message without metadata (64 bytes) are read as one, bodies of any other length never match',
+    ),
 });
 
 export const MatchingListSchema = z.array(MatchingListElementSchema);